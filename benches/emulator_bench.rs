@@ -1,16 +1,9 @@
 const TARGET_FRAMES: u32 = 128;
 
 fn build_headless_emulator() -> yagber::Emulator {
-    // Headless setup with all core functionality
-    yagber::Emulator::new()
-        .with_plugin(yagber_memory::MemoryPlugin::default())
-        .with_plugin(yagber_cpu::CpuPlugin)
-        .with_plugin(yagber_ppu::PpuPlugin)
-        .with_plugin(yagber_apu::ApuPlugin)
-        .with_plugin(yagber_dma::DmaPlugin)
-        .with_plugin(yagber_link_cable::LinkCablePlugin::default().with_serial_output_stdout())
-        .with_plugin(yagber_input::InputPlugin)
-        .with_plugin(yagber_timer::TimerPlugin)
+    yagber::EmulatorBuilder::headless()
+        .build()
+        .expect("Failed to build emulator")
 }
 
 fn run_frames(emulator: &mut yagber::Emulator, frames: u32) {
@@ -33,5 +26,115 @@ fn bench_emulate_frames(c: &mut criterion::Criterion) {
     });
 }
 
-criterion::criterion_group!(benches, bench_emulate_frames);
+/// Logo the boot ROM compares against the cartridge header.
+const NINTENDO_LOGO: [u8; 48] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
+];
+
+/// Turns the timer and a square channel on, then halts between interrupts like most games
+/// waiting for the next frame.
+const IDLE_PROGRAM: [u8; 28] = [
+    0x3E, 0x05, 0xE0, 0x07, // ld a, $05 ; ldh [TAC], a
+    0x3E, 0x80, 0xE0, 0x26, // ld a, $80 ; ldh [NR52], a
+    0x3E, 0xFF, 0xE0, 0x25, // ld a, $FF ; ldh [NR51], a
+    0x3E, 0xF0, 0xE0, 0x12, // ld a, $F0 ; ldh [NR12], a
+    0x3E, 0x87, 0xE0, 0x14, // ld a, $87 ; ldh [NR14], a
+    0x3E, 0x05, 0xE0, 0xFF, // ld a, $05 ; ldh [IE], a
+    0xFB, // ei
+    0x76, // halt
+    0x18, 0xFD, // jr -3
+];
+/// Frames the boot ROM needs before handing over to the cartridge.
+const BOOT_FRAMES: u32 = 300;
+
+fn idle_rom() -> Vec<u8> {
+    let mut rom = vec![0x00; 0x8000];
+    // The VBlank and timer handlers return straight away.
+    rom[0x40] = 0xD9;
+    rom[0x50] = 0xD9;
+    rom[0x100..0x104].copy_from_slice(&[0x00, 0xC3, 0x50, 0x01]);
+    rom[0x104..0x134].copy_from_slice(&NINTENDO_LOGO);
+    rom[0x134..0x13C].copy_from_slice(b"BENCHIDL");
+    rom[0x14D] = yagber::ram::CartridgeHeader::computed_checksum(&rom);
+    rom[0x150..0x150 + IDLE_PROGRAM.len()].copy_from_slice(&IDLE_PROGRAM);
+    rom
+}
+
+/// Frames of a booted game with the PPU, timer and APU running, which is where sparse work
+/// such as mode changes, timer overflows and frame sequencer steps shows up.
+fn bench_game_frames(c: &mut criterion::Criterion) {
+    let mut emulator = yagber::EmulatorBuilder::headless()
+        .with_rom(&idle_rom())
+        .build()
+        .expect("Failed to build emulator");
+    run_frames(&mut emulator, BOOT_FRAMES);
+    c.bench_function("game_frames", |b| {
+        b.iter(|| run_frames(&mut emulator, 1));
+    });
+}
+
+/// A loop of loads, ALU, CB prefix, stack and branch instructions, run from WRAM.
+const CPU_PROGRAM: [u8; 20] = [
+    0x21, 0x00, 0xD0, // ld hl, $D000
+    0x06, 0x10, // ld b, $10
+    0x2A, // ld a, [hl+]
+    0x80, // add a, b
+    0xA9, // xor a, c
+    0x77, // ld [hl], a
+    0x13, // inc de
+    0xCB, 0x5F, // bit 3, a
+    0xC5, // push bc
+    0xC1, // pop bc
+    0x05, // dec b
+    0x20, 0xF4, // jr nz, $C005
+    0xC3, 0x00, 0xC0, // jp $C000
+];
+const CPU_INSTRUCTIONS: u64 = 100_000;
+
+fn build_cpu() -> (yagber::cpu::Cpu, yagber::ram::Bus) {
+    let mut bus = yagber::ram::Bus::new();
+    for address in 0xD000..0xD100 {
+        bus.write(address, 0x00);
+    }
+    for (address, byte) in (0xC000..).zip(CPU_PROGRAM.iter().chain(&[0x00, 0x00])) {
+        bus.write(address, *byte);
+    }
+    let mut cpu = yagber::cpu::Cpu::new();
+    cpu.set_pc(0xC000);
+    cpu.set_sp(0xDFF0);
+    (cpu, bus)
+}
+
+fn run_instructions(cpu: &mut yagber::cpu::Cpu, bus: &mut yagber::ram::Bus, instructions: u64) {
+    let mut executed = 0;
+    while executed < instructions {
+        cpu.step(bus);
+        if cpu.at_instruction_boundary() {
+            executed += 1;
+        }
+    }
+}
+
+/// Instructions per second of the CPU alone, without the rest of the machine.
+fn bench_cpu_instructions(c: &mut criterion::Criterion) {
+    let mut group = c.benchmark_group("cpu");
+    group.throughput(criterion::Throughput::Elements(CPU_INSTRUCTIONS));
+    group.bench_function("instructions", |b| {
+        b.iter_batched(
+            build_cpu,
+            |(mut cpu, mut bus)| run_instructions(&mut cpu, &mut bus, CPU_INSTRUCTIONS),
+            criterion::BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion::criterion_group!(
+    benches,
+    bench_emulate_frames,
+    bench_game_frames,
+    bench_cpu_instructions
+);
 criterion::criterion_main!(benches);
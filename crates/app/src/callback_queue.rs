@@ -23,6 +23,12 @@ impl CallbackQueue {
     pub fn callbacks(&self) -> &[BoxedHandler] {
         &self.callbacks
     }
+
+    /// Puts `queue` back in place, keeping callbacks added while it was taken out after it.
+    pub fn restore(&mut self, queue: CallbackQueue) {
+        let added = std::mem::replace(&mut self.callbacks, queue.callbacks);
+        self.callbacks.extend(added);
+    }
 }
 
 impl Default for CallbackQueue {
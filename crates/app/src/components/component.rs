@@ -1,3 +1,3 @@
 use crate::downcast::Downcastable;
 
-pub trait Component: Downcastable + 'static {}
+pub trait Component: Downcastable + Send + 'static {}
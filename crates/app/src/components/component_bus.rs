@@ -21,6 +21,10 @@ impl ComponentBus {
         self.components.contains_key(&type_id)
     }
 
+    pub fn has_component_id(&self, type_id: std::any::TypeId) -> bool {
+        self.components.contains_key(&type_id)
+    }
+
     pub fn get_component<T: Component>(&self) -> Option<&T> {
         let type_id = std::any::TypeId::of::<T>();
         self.components
@@ -80,69 +84,27 @@ impl ComponentBus {
         }
     }
 
-    fn downcast_box_mut<T: Component>(boxed_component: &mut Box<dyn Component>) -> &mut T {
+    /// Takes the `T` component out, until [`ComponentBus::restore_component`] puts it back.
+    pub fn take_component<T: Component>(&mut self) -> Option<Box<dyn Component>> {
+        self.components.remove(&std::any::TypeId::of::<T>())
+    }
+
+    pub fn restore_component<T: Component>(&mut self, component: Box<dyn Component>) {
+        debug_assert!(
+            component.as_any_ref().is::<T>(),
+            "Restored another component"
+        );
+        self.components
+            .insert(std::any::TypeId::of::<T>(), component);
+    }
+
+    pub fn downcast_box_mut<T: Component>(boxed_component: &mut Box<dyn Component>) -> &mut T {
         boxed_component
             .as_mut()
             .as_any_mut()
             .downcast_mut::<T>()
             .expect("Failed to downcast component")
     }
-
-    pub fn attach_component<C, F, A, R>(&mut self, f: F) -> impl Fn(A) -> R + use<C, F, A, R>
-    where
-        C: Component,
-        F: Fn(&mut C, A) -> R,
-    {
-        let component = self.get_component_mut::<C>().expect("Component not found");
-        // SAFETY: Component stays alive inside the emulator
-        let component = component as *mut C;
-        move |a| f(unsafe { &mut *component }, a)
-    }
-
-    pub fn attach_components2<C0, C1, F, A, R>(
-        &mut self,
-        f: F,
-    ) -> impl Fn(A) -> R + use<C0, C1, F, A, R>
-    where
-        C0: Component,
-        C1: Component,
-        F: Fn(&mut C0, &mut C1, A) -> R,
-    {
-        let (component0, component1) = self
-            .get_components_mut2::<C0, C1>()
-            .expect("Components not found");
-        // SAFETY: Component stays alive inside the emulator
-        let component0 = component0 as *mut C0;
-        let component1 = component1 as *mut C1;
-        move |a| f(unsafe { &mut *component0 }, unsafe { &mut *component1 }, a)
-    }
-
-    pub fn attach_components3<C0, C1, C2, F, A, R>(
-        &mut self,
-        f: F,
-    ) -> impl Fn(A) -> R + use<C0, C1, C2, F, A, R>
-    where
-        C0: Component,
-        C1: Component,
-        C2: Component,
-        F: Fn(&mut C0, &mut C1, &mut C2, A) -> R,
-    {
-        let (component0, component1, component2) = self
-            .get_components_mut3::<C0, C1, C2>()
-            .expect("Components not found");
-        // SAFETY: Component stays alive inside the emulator
-        let component0 = component0 as *mut C0;
-        let component1 = component1 as *mut C1;
-        let component2 = component2 as *mut C2;
-        move |a| {
-            f(
-                unsafe { &mut *component0 },
-                unsafe { &mut *component1 },
-                unsafe { &mut *component2 },
-                a,
-            )
-        }
-    }
 }
 
 impl Default for ComponentBus {
@@ -169,8 +131,13 @@ mod tests {
         pub value: u8,
     }
 
+    struct TestComponent3 {
+        pub value: u8,
+    }
+
     impl Component for TestComponent {}
     impl Component for TestComponent2 {}
+    impl Component for TestComponent3 {}
 
     #[test]
     fn test_get_component() {
@@ -220,4 +187,113 @@ mod tests {
         let ret = bus.get_components_mut2::<TestComponent, TestComponent>();
         assert!(ret.is_none());
     }
+
+    #[test]
+    fn test_get_components_mut2_missing() {
+        let mut bus = ComponentBus::new();
+        bus.add_component(TestComponent { value: 42 });
+        let ret = bus.get_components_mut2::<TestComponent, TestComponent2>();
+        assert!(ret.is_none());
+    }
+
+    #[test]
+    fn test_get_components_mut3_writes_each_component() {
+        let mut bus = ComponentBus::new();
+        bus.add_component(TestComponent { value: 1 });
+        bus.add_component(TestComponent2 { value: 2 });
+        bus.add_component(TestComponent3 { value: 3 });
+        let (component1, component2, component3) = bus
+            .get_components_mut3::<TestComponent, TestComponent2, TestComponent3>()
+            .expect("Components not found");
+        std::mem::swap(&mut component1.value, &mut component3.value);
+        component2.value += 10;
+
+        assert_eq!(bus.get_component::<TestComponent>().unwrap().value, 3);
+        assert_eq!(bus.get_component::<TestComponent2>().unwrap().value, 12);
+        assert_eq!(bus.get_component::<TestComponent3>().unwrap().value, 1);
+    }
+
+    #[test]
+    fn test_get_components_mut3_same_type() {
+        let mut bus = ComponentBus::new();
+        bus.add_component(TestComponent { value: 42 });
+        bus.add_component(TestComponent2 { value: 43 });
+        let ret = bus.get_components_mut3::<TestComponent, TestComponent2, TestComponent>();
+        assert!(ret.is_none());
+    }
+
+    #[test]
+    fn test_take_component_until_restored() {
+        let mut bus = ComponentBus::new();
+        bus.add_component(TestComponent { value: 42 });
+        bus.add_component(TestComponent2 { value: 43 });
+
+        let mut taken = bus
+            .take_component::<TestComponent>()
+            .expect("Component not found");
+        assert!(!bus.has_component::<TestComponent>());
+        assert!(
+            bus.get_components_mut2::<TestComponent, TestComponent2>()
+                .is_none()
+        );
+        let component2 = bus
+            .get_component_mut::<TestComponent2>()
+            .expect("Component not found");
+        let component = ComponentBus::downcast_box_mut::<TestComponent>(&mut taken);
+        component.value += component2.value;
+        component2.value = 0;
+
+        bus.restore_component::<TestComponent>(taken);
+        assert_eq!(bus.get_component::<TestComponent>().unwrap().value, 85);
+        assert_eq!(bus.get_component::<TestComponent2>().unwrap().value, 0);
+        assert!(bus.take_component::<TestComponent3>().is_none());
+    }
+
+    #[test]
+    fn test_split_component_hides_it_from_the_emulator() {
+        let mut emulator = crate::Emulator::new();
+        emulator
+            .with_component(TestComponent { value: 1 })
+            .with_component(TestComponent2 { value: 2 });
+
+        let sum = emulator.split_component(|component: &mut TestComponent, emulator| {
+            assert!(!emulator.has_component::<TestComponent>());
+            let component2 = emulator
+                .get_component_mut::<TestComponent2>()
+                .expect("Component not found");
+            component2.value += 1;
+            component.value + component2.value
+        });
+
+        assert_eq!(sum, Some(4));
+        assert_eq!(emulator.get_component::<TestComponent>().unwrap().value, 1);
+        assert!(
+            emulator
+                .split_component(|_: &mut TestComponent3, _| ())
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_emulator_is_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<crate::Emulator>();
+    }
+
+    #[test]
+    fn test_add_component_replaces_existing() {
+        let mut bus = ComponentBus::new();
+        bus.add_component(TestComponent { value: 42 });
+        bus.add_component(TestComponent { value: 43 });
+        assert_eq!(bus.get_component::<TestComponent>().unwrap().value, 43);
+    }
+
+    #[test]
+    fn test_has_component() {
+        let mut bus = ComponentBus::new();
+        bus.add_component(TestComponent { value: 42 });
+        assert!(bus.has_component::<TestComponent>());
+        assert!(bus.has_component_id(std::any::TypeId::of::<TestComponent>()));
+        assert!(!bus.has_component::<TestComponent2>());
+    }
 }
@@ -1,6 +1,23 @@
+use crate::{EmulationSpeed, ResetKind};
+
+/// Requests to control emulation, sent through the event bus.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EmulationControlEvent {
     Pause,
     Resume,
     TogglePause,
+    /// Rewind while `true`, sent on both press and release.
+    Rewind(bool),
+    /// Run to the next VBlank, then pause.
+    FrameAdvance,
+    /// Run until the CPU finishes its current instruction, then pause.
+    StepInstruction,
+    /// Run to the next scan line, then pause.
+    StepScanline,
+    SpeedUp,
+    SlowDown,
+    SetSpeed(EmulationSpeed),
+    /// Run uncapped while `true`, sent on both press and release.
+    FastForward(bool),
+    Reset(ResetKind),
 }
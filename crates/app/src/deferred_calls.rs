@@ -0,0 +1,94 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use crate::Emulator;
+
+type DeferredCall = Box<dyn FnOnce(&mut Emulator) + Send>;
+
+#[derive(Default)]
+struct Inner {
+    pending: AtomicBool,
+    calls: Mutex<VecDeque<DeferredCall>>,
+}
+
+/// Calls queued by code that has no access to the emulator, such as other threads.
+///
+/// The emulator runs them, in queue order, at the end of [`Emulator::step`].
+/// Cloning gives another handle to the same queue.
+#[derive(Clone, Default)]
+pub struct DeferredCalls {
+    inner: Arc<Inner>,
+}
+
+impl DeferredCalls {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push<F>(&self, call: F)
+    where
+        F: FnOnce(&mut Emulator) + Send + 'static,
+    {
+        self.inner
+            .calls
+            .lock()
+            .expect("Deferred calls poisoned")
+            .push_back(Box::new(call));
+        self.inner.pending.store(true, Ordering::Release);
+    }
+
+    pub fn has_pending(&self) -> bool {
+        self.inner.pending.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn pop(&self) -> Option<DeferredCall> {
+        let mut calls = self.inner.calls.lock().expect("Deferred calls poisoned");
+        let call = calls.pop_front();
+        if calls.is_empty() {
+            self.inner.pending.store(false, Ordering::Release);
+        }
+        call
+    }
+}
+
+impl std::fmt::Debug for DeferredCalls {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeferredCalls")
+            .field("pending", &self.has_pending())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_calls_in_queue_order() {
+        let mut emulator = Emulator::new();
+        let calls = emulator.deferred_calls();
+        calls.push(|emulator| emulator.set_content_id(1));
+        calls.push(|emulator| emulator.set_content_id(emulator.content_id() * 10 + 2));
+        assert!(calls.has_pending());
+
+        emulator.run_deferred_calls();
+        assert!(!calls.has_pending());
+        assert_eq!(emulator.content_id(), 12);
+    }
+
+    #[test]
+    fn runs_calls_queued_by_other_calls() {
+        let mut emulator = Emulator::new();
+        let calls = emulator.deferred_calls();
+        let inner = calls.clone();
+        calls.push(move |_| inner.push(|emulator| emulator.set_content_id(7)));
+
+        emulator.run_deferred_calls();
+        assert_eq!(emulator.content_id(), 7);
+    }
+}
@@ -24,6 +24,10 @@ impl EdgeDetector {
         result
     }
 
+    pub fn value(&self) -> bool {
+        self.value
+    }
+
     pub fn set_value(&mut self, value: bool) {
         self.value = value;
     }
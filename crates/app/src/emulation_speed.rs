@@ -0,0 +1,75 @@
+/// How fast emulated time runs against wall clock time.
+///
+/// Unrelated to the CGB [`SpeedMode`](crate::SpeedMode), which is part of the emulated machine.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum EmulationSpeed {
+    Quarter,
+    Half,
+    #[default]
+    Normal,
+    Double,
+    Quadruple,
+    Octuple,
+    /// As fast as the host allows, audio is muted.
+    Uncapped,
+}
+
+impl EmulationSpeed {
+    /// Emulated seconds per wall clock second, in quarters, `None` when uncapped.
+    pub fn quarters(&self) -> Option<u32> {
+        match self {
+            Self::Quarter => Some(1),
+            Self::Half => Some(2),
+            Self::Normal => Some(4),
+            Self::Double => Some(8),
+            Self::Quadruple => Some(16),
+            Self::Octuple => Some(32),
+            Self::Uncapped => None,
+        }
+    }
+
+    /// Emulated seconds per wall clock second, `None` when uncapped.
+    pub fn multiplier(&self) -> Option<f64> {
+        self.quarters().map(|quarters| quarters as f64 / 4.0)
+    }
+
+    pub fn faster(&self) -> Self {
+        match self {
+            Self::Quarter => Self::Half,
+            Self::Half => Self::Normal,
+            Self::Normal => Self::Double,
+            Self::Double => Self::Quadruple,
+            Self::Quadruple => Self::Octuple,
+            Self::Octuple | Self::Uncapped => Self::Uncapped,
+        }
+    }
+
+    pub fn slower(&self) -> Self {
+        match self {
+            Self::Quarter | Self::Half => Self::Quarter,
+            Self::Normal => Self::Half,
+            Self::Double => Self::Normal,
+            Self::Quadruple => Self::Double,
+            Self::Octuple => Self::Quadruple,
+            Self::Uncapped => Self::Octuple,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn faster_and_slower_stay_in_range() {
+        assert_eq!(EmulationSpeed::Quarter.slower(), EmulationSpeed::Quarter);
+        assert_eq!(EmulationSpeed::Octuple.faster(), EmulationSpeed::Uncapped);
+        assert_eq!(EmulationSpeed::Uncapped.faster(), EmulationSpeed::Uncapped);
+        assert_eq!(
+            EmulationSpeed::Normal.faster().slower(),
+            EmulationSpeed::Normal
+        );
+        assert_eq!(EmulationSpeed::Double.multiplier(), Some(2.0));
+        assert_eq!(EmulationSpeed::Uncapped.multiplier(), None);
+    }
+}
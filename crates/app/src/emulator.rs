@@ -1,14 +1,31 @@
 use crate::{
-    Component, Plugin, SpeedMode, callback_queue::CallbackQueue, components::ComponentBus,
+    Component, EmulationControlEvent, EmulationSpeed, EmulatorState, Plugin, ResetEvent, ResetKind,
+    SpeedChangedEvent, SpeedMode, StateTransitionEvent, StepBoundary,
+    callback_queue::CallbackQueue,
+    components::ComponentBus,
+    deferred_calls::DeferredCalls,
+    events::{Event, EventBus},
+    plugins::{PluginError, PluginRegistry},
     runners::Runner,
+    scheduler::{Clock, Delay, ScheduledTask, Scheduler, TaskCallback},
+    snapshot::{Snapshot, SnapshotError, SnapshotReader, SnapshotRegistry, SnapshotWriter},
+    step_boundary::Stepping,
 };
 
+/// The whole machine: components, the callbacks stepping them and the plugins wiring them.
+///
+/// `Send`, so a frontend may run it on a thread of its own: components, callbacks, event
+/// handlers and plugins all have to be `Send`.
 pub struct Emulator {
     /// Total number of cycles since the start of emulation.
     /// emulation cycles continue to increment even when the emulator is paused.
     emulation_cycles: u64,
     /// Total number of dot cycles since the start of emulation.
     dot_cycles: u64,
+    /// Total number of M-cycles since the start of emulation, twice as many per dot in double speed.
+    mcycles: u64,
+    /// M-cycles whose callbacks all ran, one behind [`Emulator::get_mcycles`] during an M-cycle.
+    completed_mcycles: u64,
     /// State
     components: ComponentBus,
     /// Callback that are called every t cycle.
@@ -24,43 +41,169 @@ pub struct Emulator {
     fixed_step_queue: CallbackQueue,
     /// CGB speed mode.
     speed_mode: SpeedMode,
-    /// Whether the emulator is paused.
-    paused: bool,
+    /// Lifecycle state, the machine only advances while running.
+    state: EmulatorState,
+    /// Whether the emulator is rewinding.
+    /// Forward emulation is suspended, rewind plugins restore past states on the fixed step.
+    rewinding: bool,
+    /// Components taking part in save states.
+    snapshots: SnapshotRegistry,
+    /// Typed events emitted by plugins.
+    events: EventBus,
+    /// Work due at a given dot cycle or M-cycle.
+    scheduler: Scheduler,
+    /// Plugins registered but not yet initialized.
+    plugins: PluginRegistry,
+    /// Calls queued from outside the emulator, run at the end of each step.
+    deferred_calls: DeferredCalls,
+    /// Boundary to pause at, set while advancing a paused emulator.
+    stepping: Option<Stepping>,
+    /// Pace requested from runners, emulated time does not depend on it.
+    speed: EmulationSpeed,
+    /// Whether the speed is overridden to uncapped, while a fast forward key is held.
+    fast_forward: bool,
+    /// State captured by [`Emulator::build`], restored by hard resets.
+    power_on_state: Option<Vec<u8>>,
 }
 
 impl Emulator {
     pub const TARGET_DOT_FREQ_HZ: u32 = 4_194_304;
     pub const NANOS_PER_DOT: u64 = 1_000_000_000 / Emulator::TARGET_DOT_FREQ_HZ as u64;
+    /// Dots the PPU takes to draw a frame, 154 lines of 456 dots.
+    pub const DOTS_PER_FRAME: u64 = 70_224;
 
     pub fn new() -> Self {
-        Self {
+        let mut emulator = Self {
             emulation_cycles: 0,
             dot_cycles: 0,
+            mcycles: 0,
+            completed_mcycles: 0,
             components: ComponentBus::default(),
             tcycle_queue: CallbackQueue::default(),
             mcycle_queue: CallbackQueue::default(),
             dot_cycle_queue: CallbackQueue::default(),
             fixed_step_queue: CallbackQueue::default(),
             speed_mode: SpeedMode::default(),
-            paused: false,
-        }
+            state: EmulatorState::Init,
+            rewinding: false,
+            snapshots: SnapshotRegistry::new(),
+            events: EventBus::default(),
+            scheduler: Scheduler::new(),
+            plugins: PluginRegistry::default(),
+            deferred_calls: DeferredCalls::new(),
+            stepping: None,
+            speed: EmulationSpeed::default(),
+            fast_forward: false,
+            power_on_state: None,
+        };
+        emulator.on_event(|emulator, event: &EmulationControlEvent| {
+            emulator.handle_control_event(*event)
+        });
+        emulator
     }
 
     /// Step the emulator a single dot.
     pub fn step(&mut self) {
         self.emulation_cycles = self.emulation_cycles.wrapping_add(1);
-        if self.emulation_cycles % (Emulator::TARGET_DOT_FREQ_HZ as u64 / 60) == 0 {
+        if self
+            .emulation_cycles
+            .is_multiple_of(Emulator::TARGET_DOT_FREQ_HZ as u64 / 60)
+        {
             self.step_fixed_step();
         }
 
-        if self.paused {
-            return;
+        if self.state == EmulatorState::Init {
+            self.set_state(EmulatorState::Running);
+        }
+
+        if self.state == EmulatorState::Running && !self.rewinding {
+            self.step_machine();
+            if let Some(stepping) = self.stepping
+                && self.dot_cycles >= stepping.deadline
+            {
+                self.pause();
+            }
+        }
+
+        if self.deferred_calls.has_pending() {
+            self.run_deferred_calls();
+        }
+        if self.events.has_pending() {
+            self.flush_events();
+        }
+    }
+
+    /// Runs up to `max_dots` dots and returns how many ran, at least one unless `max_dots` is 0.
+    ///
+    /// The CPU and the other per cycle callbacks run freely until the nearest scheduled task,
+    /// fixed step or stepping deadline, which then goes through [`Emulator::step`]. The run
+    /// ends early once a callback changes the state, emits an event or reschedules a task.
+    pub fn step_until_due(&mut self, max_dots: u64) -> u64 {
+        if max_dots == 0 {
+            return 0;
+        }
+        let free_dots = if self.state == EmulatorState::Running && !self.rewinding {
+            self.dots_until_due().min(max_dots) - 1
+        } else {
+            0
+        };
+        let next_due = Clock::ALL.map(|clock| self.scheduler.next_due(clock));
+        let (speed_mode, deadline) = (self.speed_mode, self.stepping.map(|s| s.deadline));
+        let mut ran = 0;
+        while ran < free_dots {
+            self.emulation_cycles = self.emulation_cycles.wrapping_add(1);
+            self.step_machine();
+            ran += 1;
+            if self.state != EmulatorState::Running
+                || self.events.has_pending()
+                || Clock::ALL.map(|clock| self.scheduler.next_due(clock)) != next_due
+                || self.speed_mode != speed_mode
+                || self.stepping.map(|s| s.deadline) != deadline
+            {
+                break;
+            }
+        }
+        self.step();
+        ran + 1
+    }
+
+    /// Dots until the next one running more than the per cycle callbacks, at least 1.
+    fn dots_until_due(&self) -> u64 {
+        let fixed_step = Emulator::TARGET_DOT_FREQ_HZ as u64 / 60;
+        let mut dots = fixed_step - self.emulation_cycles % fixed_step;
+        dots = dots.min(
+            self.scheduler
+                .next_due(Clock::Dots)
+                .saturating_sub(self.dot_cycles),
+        );
+        let mcycles = self
+            .scheduler
+            .next_due(Clock::MCycles)
+            .saturating_sub(self.mcycles);
+        let per_boundary = match self.speed_mode {
+            SpeedMode::Single => 1,
+            SpeedMode::Double => 2,
+        };
+        let boundaries = mcycles.div_ceil(per_boundary);
+        if boundaries > 0 {
+            let first = 4 - self.dot_cycles % 4;
+            dots = dots.min(first.saturating_add((boundaries - 1).saturating_mul(4)));
+        }
+        if let Some(stepping) = self.stepping {
+            dots = dots.min(stepping.deadline.saturating_sub(self.dot_cycles));
         }
+        dots.max(1)
+    }
 
+    fn step_machine(&mut self) {
         #[cfg(feature = "trace-span")]
         let _step_span = tracing::info_span!("step").entered();
         self.dot_cycles = self.dot_cycles.wrapping_add(1);
 
+        // Dot tasks run first, where per dot callbacks registered before them used to run.
+        if self.dot_cycles >= self.scheduler.next_due(Clock::Dots) {
+            self.step_scheduled(Clock::Dots, self.dot_cycles);
+        }
         self.step_dot_cycle();
         self.step_tcycle();
         if self.is_m_cycle() {
@@ -71,36 +214,41 @@ impl Emulator {
         }
     }
 
-    fn step_tcycle(&mut self) {
-        let emulator_ptr = self as *mut Emulator;
-        let callbacks = self.tcycle_queue.callbacks();
-        for callback in callbacks {
-            callback(unsafe { &mut *emulator_ptr });
+    fn step_scheduled(&mut self, clock: Clock, now: u64) {
+        while let Some(callback) = self.scheduler.pop_due(clock, now) {
+            callback(self);
         }
     }
 
+    fn step_tcycle(&mut self) {
+        self.run_callbacks(|emulator| &mut emulator.tcycle_queue);
+    }
+
+    /// M-cycle tasks run once every M-cycle callback is done.
     fn step_mcycle(&mut self) {
-        let emulator_ptr = self as *mut Emulator;
-        let callbacks = self.mcycle_queue.callbacks();
-        for callback in callbacks {
-            callback(unsafe { &mut *emulator_ptr });
+        self.mcycles = self.mcycles.wrapping_add(1);
+        self.run_callbacks(|emulator| &mut emulator.mcycle_queue);
+        self.completed_mcycles = self.mcycles;
+        if self.mcycles >= self.scheduler.next_due(Clock::MCycles) {
+            self.step_scheduled(Clock::MCycles, self.mcycles);
         }
     }
 
     fn step_dot_cycle(&mut self) {
-        let emulator_ptr = self as *mut Emulator;
-        let callbacks = self.dot_cycle_queue.callbacks();
-        for callback in callbacks {
-            callback(unsafe { &mut *emulator_ptr });
-        }
+        self.run_callbacks(|emulator| &mut emulator.dot_cycle_queue);
     }
 
     fn step_fixed_step(&mut self) {
-        let emulator_ptr = self as *mut Emulator;
-        let callbacks = self.fixed_step_queue.callbacks();
-        for callback in callbacks {
-            callback(unsafe { &mut *emulator_ptr });
+        self.run_callbacks(|emulator| &mut emulator.fixed_step_queue);
+    }
+
+    /// The queue is moved out while its callbacks run, so each gets the emulator exclusively.
+    fn run_callbacks(&mut self, queue: fn(&mut Emulator) -> &mut CallbackQueue) {
+        let callbacks = std::mem::take(queue(self));
+        for callback in callbacks.callbacks() {
+            callback(self);
         }
+        queue(self).restore(callbacks);
     }
 
     pub fn on_tcycle<F>(&mut self, callback: F) -> &mut Self
@@ -163,26 +311,163 @@ impl Emulator {
         self
     }
 
-    pub fn run<T: Runner>(self) -> T::Result {
-        let runner = T::new(self);
-        runner.run()
+    /// Registers a task that runs once its scheduled cycle is reached.
+    /// Names identify pending tasks in save states and must be unique.
+    pub fn add_scheduled_task(
+        &mut self,
+        name: &'static str,
+        callback: TaskCallback,
+    ) -> ScheduledTask {
+        self.scheduler.add_task(name, callback, None)
+    }
+
+    /// Registers a task that runs every `period`, starting one period from now.
+    pub fn add_periodic_task(
+        &mut self,
+        name: &'static str,
+        period: Delay,
+        callback: TaskCallback,
+    ) -> ScheduledTask {
+        let task = self.scheduler.add_task(name, callback, Some(period));
+        self.schedule(task, period);
+        task
+    }
+
+    /// Schedules `task` after `delay`, replacing its pending run if any.
+    ///
+    /// Dot tasks run at the start of their dot, M-cycle tasks after the callbacks of their M-cycle.
+    /// A delay of zero M-cycles from an M-cycle callback runs at the end of the current M-cycle.
+    pub fn schedule(&mut self, task: ScheduledTask, delay: Delay) {
+        let (dot_cycles, mcycles) = (self.dot_cycles, self.mcycles);
+        self.scheduler.schedule(task, delay, |clock| match clock {
+            Clock::Dots => dot_cycles,
+            Clock::MCycles => mcycles,
+        });
     }
 
+    pub fn cancel_scheduled(&mut self, task: ScheduledTask) {
+        self.scheduler.cancel(task);
+    }
+
+    /// Cycle at which `task` runs next, if scheduled.
+    /// Counted in dot cycles or M-cycles, following the delay it was scheduled with.
+    pub fn scheduled_at(&self, task: ScheduledTask) -> Option<u64> {
+        self.scheduler.due_cycle(task)
+    }
+
+    /// Handle for code without access to the emulator to queue calls on it.
+    pub fn deferred_calls(&self) -> DeferredCalls {
+        self.deferred_calls.clone()
+    }
+
+    /// Runs every queued call, including the ones queued meanwhile.
+    pub fn run_deferred_calls(&mut self) {
+        while let Some(call) = self.deferred_calls.pop() {
+            call(self);
+        }
+    }
+
+    /// Subscribes to events of type `E`.
+    /// Handlers run when the bus is flushed, at the end of [`Emulator::step`].
+    pub fn on_event<E, F>(&mut self, handler: F) -> &mut Self
+    where
+        E: Event,
+        F: Fn(&mut Emulator, &E) + Send + Sync + 'static,
+    {
+        self.events.subscribe(handler);
+        self
+    }
+
+    pub fn emit<E: Event>(&mut self, event: E) {
+        self.events.emit(event);
+    }
+
+    /// Hands every buffered event to its subscribers, including the ones emitted meanwhile.
+    pub fn flush_events(&mut self) {
+        while let Some(dispatch) = self.events.next_pending() {
+            dispatch(self);
+        }
+    }
+
+    pub(crate) fn event_bus_mut(&mut self) -> &mut EventBus {
+        &mut self.events
+    }
+
+    /// Runs the emulator, building it first if plugins are pending.
+    /// Fails if the plugins cannot be initialized, see [`Emulator::build`].
+    pub fn run<T: Runner>(self) -> Result<T::Result, PluginError> {
+        let emulator = self.build()?;
+        let runner = T::new(emulator);
+        Ok(runner.run())
+    }
+
+    /// Registers a plugin, it is initialized by [`Emulator::build`].
     pub fn with_plugin<P: Plugin>(mut self, plugin: P) -> Self {
-        #[cfg(feature = "trace-span")]
-        let _span = {
-            let type_name = std::any::type_name::<P>();
-            tracing::info_span!("plugin init", %type_name).entered()
-        };
-        plugin.init(&mut self);
+        self.plugins.add(plugin);
         self
     }
 
+    /// Initializes the registered plugins in an order satisfying their
+    /// [`dependencies`](Plugin::dependencies).
+    /// The state right after is kept as the power on state for hard resets.
+    pub fn build(mut self) -> Result<Self, PluginError> {
+        if !self.plugins.is_empty() {
+            let plugins = std::mem::take(&mut self.plugins);
+            let inits = plugins.sort(|type_id| self.components.has_component_id(type_id))?;
+            for init in inits {
+                init(&mut self)?;
+            }
+        }
+        self.power_on_state = Some(self.save_state());
+        Ok(self)
+    }
+
     pub fn with_component<C: Component>(&mut self, component: C) -> &mut Self {
         self.components.add_component(component);
         self
     }
 
+    /// Registers a component to be included in save states under `key`.
+    /// Keys must be unique and stable across releases.
+    pub fn with_snapshot<C: Component + Snapshot>(&mut self, key: &'static str) -> &mut Self {
+        self.snapshots.register::<C>(key);
+        self
+    }
+
+    /// Identifies the loaded content, save states from other content are rejected.
+    pub fn set_content_id(&mut self, content_id: u64) {
+        self.snapshots.set_content_id(content_id);
+    }
+
+    pub fn content_id(&self) -> u64 {
+        self.snapshots.content_id()
+    }
+
+    /// Serializes the whole machine into a single save-state blob.
+    pub fn save_state(&self) -> Vec<u8> {
+        self.snapshots.save(self)
+    }
+
+    /// Restores the machine from a blob produced by [`Emulator::save_state`].
+    /// On error the emulator is left in the state it had before the call,
+    /// unless [`SnapshotError::RollbackFailed`] is returned.
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), SnapshotError> {
+        let snapshots = std::mem::take(&mut self.snapshots);
+        let backup = snapshots.save(self);
+        let mut result = snapshots.load(self, data);
+        if let Err(error) = result {
+            result = match snapshots.load(self, &backup) {
+                Ok(()) => Err(error),
+                Err(rollback) => Err(SnapshotError::RollbackFailed {
+                    error: Box::new(error),
+                    rollback: Box::new(rollback),
+                }),
+            };
+        }
+        self.snapshots = snapshots;
+        result
+    }
+
     pub fn has_component<C: Component>(&self) -> bool {
         self.components.has_component::<C>()
     }
@@ -201,59 +486,329 @@ impl Emulator {
         self.components.get_component::<C>()
     }
 
-    pub fn attach_component<C, F, A, R>(&mut self, f: F) -> impl Fn(A) -> R + use<C, F, A, R>
+    /// Splits the `C` component off while `f` runs, `f` gets it along with the rest of the
+    /// emulator. Anything reaching for `C` meanwhile, such as an IO hook, finds it missing.
+    /// Returns `None` if there is no such component.
+    pub fn split_component<C, F, R>(&mut self, f: F) -> Option<R>
+    where
+        C: Component,
+        F: FnOnce(&mut C, &mut Emulator) -> R,
+    {
+        let mut component = self.components.take_component::<C>()?;
+        let result = f(ComponentBus::downcast_box_mut(&mut component), self);
+        self.components.restore_component::<C>(component);
+        Some(result)
+    }
+
+    /// Adapts `f` to run on the `C` component of the emulator it is called with.
+    /// Panics at call time if the component is missing.
+    pub fn attach_component<C, F, A, R>(
+        f: F,
+    ) -> impl Fn(&mut Emulator, A) -> R + Send + Sync + 'static + use<C, F, A, R>
     where
         C: Component,
-        F: Fn(&mut C, A) -> R,
+        F: Fn(&mut C, A) -> R + Send + Sync + 'static,
     {
-        self.components.attach_component(f)
+        move |emulator, a| {
+            let component = emulator
+                .get_component_mut::<C>()
+                .unwrap_or_else(|| panic!("{} component missing", std::any::type_name::<C>()));
+            f(component, a)
+        }
     }
 
+    /// Like [`Emulator::attach_component`] for two distinct components.
     pub fn attach_components2<C0, C1, F, A, R>(
-        &mut self,
         f: F,
-    ) -> impl Fn(A) -> R + use<C0, C1, F, A, R>
+    ) -> impl Fn(&mut Emulator, A) -> R + Send + Sync + 'static + use<C0, C1, F, A, R>
     where
         C0: Component,
         C1: Component,
-        F: Fn(&mut C0, &mut C1, A) -> R,
+        F: Fn(&mut C0, &mut C1, A) -> R + Send + Sync + 'static,
     {
-        self.components.attach_components2(f)
+        move |emulator, a| {
+            let (component0, component1) = emulator
+                .get_components_mut2::<C0, C1>()
+                .expect("Hook components missing");
+            f(component0, component1, a)
+        }
     }
 
+    /// Like [`Emulator::attach_component`] for three distinct components.
     pub fn attach_components3<C0, C1, C2, F, A, R>(
-        &mut self,
         f: F,
-    ) -> impl Fn(A) -> R + use<C0, C1, C2, F, A, R>
+    ) -> impl Fn(&mut Emulator, A) -> R + Send + Sync + 'static + use<C0, C1, C2, F, A, R>
     where
         C0: Component,
         C1: Component,
         C2: Component,
-        F: Fn(&mut C0, &mut C1, &mut C2, A) -> R,
+        F: Fn(&mut C0, &mut C1, &mut C2, A) -> R + Send + Sync + 'static,
     {
-        self.components.attach_components3(f)
+        move |emulator, a| {
+            let (component0, component1, component2) = emulator
+                .get_components_mut3::<C0, C1, C2>()
+                .expect("Hook components missing");
+            f(component0, component1, component2, a)
+        }
+    }
+
+    pub fn get_components_mut3<C0: Component, C1: Component, C2: Component>(
+        &mut self,
+    ) -> Option<(&mut C0, &mut C1, &mut C2)> {
+        self.components.get_components_mut3::<C0, C1, C2>()
     }
 
     pub fn get_cycles(&self) -> u64 {
         self.dot_cycles
     }
 
+    /// M-cycles since the start of emulation, including the one running.
+    pub fn get_mcycles(&self) -> u64 {
+        self.mcycles
+    }
+
+    /// M-cycles whose callbacks all ran.
+    /// Hooks triggered by an M-cycle callback see the M-cycle before the one running.
+    pub fn completed_mcycles(&self) -> u64 {
+        self.completed_mcycles
+    }
+
     fn is_m_cycle(&self) -> bool {
-        self.dot_cycles % 4 == 0
+        self.dot_cycles.is_multiple_of(4)
+    }
+
+    pub fn speed_mode(&self) -> SpeedMode {
+        self.speed_mode
     }
 
     pub fn set_speed_mode(&mut self, speed_mode: SpeedMode) {
         self.speed_mode = speed_mode;
     }
 
-    pub fn handle_control_event(&mut self, event: crate::EmulationControlEvent) {
+    pub fn handle_control_event(&mut self, event: EmulationControlEvent) {
         #[cfg(feature = "trace")]
         tracing::trace!("Emulation Control Event: {:?}", event);
         match event {
-            crate::EmulationControlEvent::Pause => self.paused = true,
-            crate::EmulationControlEvent::Resume => self.paused = false,
-            crate::EmulationControlEvent::TogglePause => self.paused = !self.paused,
+            EmulationControlEvent::Pause => self.pause(),
+            EmulationControlEvent::Resume => self.resume(),
+            EmulationControlEvent::TogglePause => {
+                if self.is_paused() {
+                    self.resume()
+                } else {
+                    self.pause()
+                }
+            }
+            EmulationControlEvent::Rewind(rewinding) => self.rewinding = rewinding,
+            EmulationControlEvent::FrameAdvance => self.advance_to(StepBoundary::Frame),
+            EmulationControlEvent::StepInstruction => self.advance_to(StepBoundary::Instruction),
+            EmulationControlEvent::StepScanline => self.advance_to(StepBoundary::Scanline),
+            EmulationControlEvent::SpeedUp => self.set_speed(self.speed.faster()),
+            EmulationControlEvent::SlowDown => self.set_speed(self.speed.slower()),
+            EmulationControlEvent::SetSpeed(speed) => self.set_speed(speed),
+            EmulationControlEvent::FastForward(fast_forward) => {
+                let previous = self.speed();
+                self.fast_forward = fast_forward;
+                self.emit_speed_change(previous);
+            }
+            EmulationControlEvent::Reset(kind) => self.reset(kind),
+        }
+    }
+
+    /// Resets the machine, the lifecycle state is kept, a paused emulator stays paused.
+    ///
+    /// Subscribers see [`ResetEvent::Starting`] before anything is touched and
+    /// [`ResetEvent::Finished`] once the emulator is reset.
+    /// Pending scheduled tasks are cancelled, plugins reschedule the ones they always keep pending.
+    /// Hard resets restore the state captured by [`Emulator::build`],
+    /// an emulator that was never built only gets the events.
+    pub fn reset(&mut self, kind: ResetKind) {
+        if self.state.is_finished() {
+            return;
+        }
+        #[cfg(feature = "trace")]
+        tracing::info!("{kind:?} reset");
+        self.stepping = None;
+        self.emit(ResetEvent::Starting(kind));
+        self.flush_events();
+
+        // Work scheduled before the reset is dropped, plugins schedule theirs again on
+        // `Finished`, periodic tasks start over
+        let (dot_cycles, mcycles) = (self.dot_cycles, self.mcycles);
+        self.scheduler.restart(|clock| match clock {
+            Clock::Dots => dot_cycles,
+            Clock::MCycles => mcycles,
+        });
+
+        if kind == ResetKind::Hard
+            && let Some(power_on_state) = self.power_on_state.take()
+        {
+            let _result = self.load_state(&power_on_state);
+            #[cfg(feature = "trace")]
+            if let Err(error) = &_result {
+                tracing::error!("Failed to restore the power on state: {error}");
+            }
+            self.power_on_state = Some(power_on_state);
+        }
+
+        self.emit(ResetEvent::Finished(kind));
+        self.flush_events();
+    }
+
+    /// Pace runners should emulate at, uncapped while fast forwarding.
+    pub fn speed(&self) -> EmulationSpeed {
+        if self.fast_forward {
+            EmulationSpeed::Uncapped
+        } else {
+            self.speed
+        }
+    }
+
+    /// Sets the pace and emits a [`SpeedChangedEvent`] if the effective speed changed.
+    pub fn set_speed(&mut self, speed: EmulationSpeed) {
+        let previous = self.speed();
+        self.speed = speed;
+        self.emit_speed_change(previous);
+    }
+
+    fn emit_speed_change(&mut self, previous: EmulationSpeed) {
+        let speed = self.speed();
+        if speed != previous {
+            #[cfg(feature = "trace")]
+            tracing::debug!("Emulation speed: {previous:?} -> {speed:?}");
+            self.emit(SpeedChangedEvent { speed });
+        }
+    }
+
+    /// Runs the machine until a plugin reports `boundary`, then pauses.
+    ///
+    /// Stepping goes through the regular [`Emulator::step`], so the fixed step clock keeps
+    /// ticking and input is processed while advancing.
+    pub fn advance_to(&mut self, boundary: StepBoundary) {
+        if self.state.is_finished() {
+            return;
+        }
+        self.stepping = Some(Stepping {
+            boundary,
+            deadline: self.dot_cycles + boundary.max_dots(),
+        });
+        if self.state == EmulatorState::Paused {
+            self.set_state(EmulatorState::Running);
+        }
+    }
+
+    /// Reports that the machine reached `boundary`, pausing if an advance was waiting for it.
+    pub fn reached_boundary(&mut self, boundary: StepBoundary) {
+        if self
+            .stepping
+            .is_some_and(|stepping| stepping.boundary == boundary)
+        {
+            self.pause();
+        }
+    }
+
+    pub fn state(&self) -> EmulatorState {
+        self.state
+    }
+
+    /// Moves to `state` and emits a [`StateTransitionEvent`].
+    /// Finished states are terminal, transitions out of them are ignored.
+    pub fn set_state(&mut self, state: EmulatorState) {
+        if self.state == state || self.state.is_finished() {
+            return;
+        }
+        let from = self.state;
+        self.state = state;
+        #[cfg(feature = "trace")]
+        tracing::debug!("Emulator state: {from:?} -> {state:?}");
+        self.emit(StateTransitionEvent { from, to: state });
+    }
+
+    pub fn pause(&mut self) {
+        self.stepping = None;
+        if self.state == EmulatorState::Running {
+            self.set_state(EmulatorState::Paused);
+        }
+    }
+
+    pub fn resume(&mut self) {
+        self.stepping = None;
+        if self.state == EmulatorState::Paused {
+            self.set_state(EmulatorState::Running);
+        }
+    }
+
+    /// Halts emulation for good, the emulator keeps its state until it exits.
+    pub fn stop(&mut self) {
+        self.set_state(EmulatorState::Stopped);
+    }
+
+    /// Shuts the emulator down.
+    /// Subscribers see `Ending` while every component is still alive, then `Ended`.
+    pub fn exit(&mut self) {
+        self.set_state(EmulatorState::Ending);
+        self.flush_events();
+        self.set_state(EmulatorState::Ended);
+        self.flush_events();
+    }
+
+    /// Shuts the emulator down after an unrecoverable error.
+    pub fn fail(&mut self) {
+        self.set_state(EmulatorState::Error);
+        self.flush_events();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state == EmulatorState::Paused
+    }
+
+    pub fn is_rewinding(&self) -> bool {
+        self.rewinding
+    }
+}
+
+impl Emulator {
+    pub(crate) const SNAPSHOT_KEY: &'static str = "emulator";
+}
+
+impl Snapshot for Emulator {
+    const VERSION: u16 = 4;
+
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_u64(self.emulation_cycles);
+        writer.write_u64(self.dot_cycles);
+        writer.write_u64(self.mcycles);
+        writer.write(&self.speed_mode);
+        let pending = self.scheduler.pending().collect::<Vec<_>>();
+        writer.write_u32(pending.len() as u32);
+        for (name, clock, due) in pending {
+            writer.write_bytes(name.as_bytes());
+            writer.write_u8(clock.to_u8());
+            writer.write_u64(due);
+        }
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.emulation_cycles = reader.read_u64()?;
+        self.dot_cycles = reader.read_u64()?;
+        self.mcycles = reader.read_u64()?;
+        self.completed_mcycles = self.mcycles;
+        reader.read(&mut self.speed_mode)?;
+        let count = reader.read_u32()?;
+        let mut pending = Vec::new();
+        for _ in 0..count {
+            let name = String::from_utf8(reader.read_bytes()?.to_vec())
+                .map_err(|_| SnapshotError::InvalidData("Scheduled task name".to_string()))?;
+            let clock = Clock::from_u8(reader.read_u8()?)
+                .ok_or_else(|| SnapshotError::InvalidData("Scheduled task clock".to_string()))?;
+            pending.push((name, clock, reader.read_u64()?));
         }
+        self.scheduler
+            .restore(
+                pending
+                    .iter()
+                    .map(|(name, clock, due)| (name.as_str(), *clock, *due)),
+            )
+            .map_err(SnapshotError::InvalidData)
     }
 }
 
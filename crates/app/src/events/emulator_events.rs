@@ -0,0 +1,44 @@
+use crate::{EmulationSpeed, ResetKind, events::Event};
+
+/// The PPU finished drawing a frame and entered VBlank.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameCompletedEvent;
+
+impl Event for FrameCompletedEvent {}
+
+/// A byte was shifted out through the serial port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SerialByteSentEvent {
+    pub byte: u8,
+}
+
+impl Event for SerialByteSentEvent {}
+
+/// The cartridge rumble motor was turned on or off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RumbleChangedEvent {
+    pub enabled: bool,
+}
+
+impl Event for RumbleChangedEvent {}
+
+/// The emulation speed changed, see [`Emulator::speed`](crate::Emulator::speed).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpeedChangedEvent {
+    pub speed: EmulationSpeed,
+}
+
+impl Event for SpeedChangedEvent {}
+
+/// Sent around a reset, both are handed to subscribers before [`Emulator::reset`] returns.
+///
+/// [`Emulator::reset`]: crate::Emulator::reset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetEvent {
+    /// Components still hold their pre-reset state, saves should be flushed here.
+    Starting(ResetKind),
+    /// Hard resets have restored the power on state by now.
+    Finished(ResetKind),
+}
+
+impl Event for ResetEvent {}
@@ -0,0 +1,146 @@
+use std::{any::Any, any::TypeId, collections::VecDeque, sync::Arc};
+
+use crate::{Emulator, events::Event};
+
+type Handler<E> = Arc<dyn Fn(&mut Emulator, &E) + Send + Sync>;
+type Dispatcher = fn(&mut Emulator);
+
+struct EventQueue<E: Event> {
+    events: Vec<E>,
+    handlers: Vec<Handler<E>>,
+}
+
+impl<E: Event> Default for EventQueue<E> {
+    fn default() -> Self {
+        Self {
+            events: Vec::new(),
+            handlers: Vec::new(),
+        }
+    }
+}
+
+/// Typed event queues, one per event type.
+///
+/// Events are buffered on emit and handed to the subscribers when the emulator flushes the bus,
+/// so handlers get full access to the emulator and may emit further events.
+#[derive(Default)]
+pub(crate) struct EventBus {
+    queues: ahash::AHashMap<TypeId, Box<dyn Any + Send>>,
+    /// Dispatchers of the queues holding events, in emission order.
+    pending: VecDeque<Dispatcher>,
+}
+
+impl EventBus {
+    fn queue_mut<E: Event>(&mut self) -> &mut EventQueue<E> {
+        self.queues
+            .entry(TypeId::of::<E>())
+            .or_insert_with(|| Box::new(EventQueue::<E>::default()))
+            .downcast_mut::<EventQueue<E>>()
+            .expect("Event queue type mismatch")
+    }
+
+    pub fn subscribe<E: Event>(
+        &mut self,
+        handler: impl Fn(&mut Emulator, &E) + Send + Sync + 'static,
+    ) {
+        self.queue_mut::<E>().handlers.push(Arc::new(handler));
+    }
+
+    /// Buffers an event, events without subscribers are dropped.
+    pub fn emit<E: Event>(&mut self, event: E) {
+        let queue = self.queue_mut::<E>();
+        if queue.handlers.is_empty() {
+            return;
+        }
+        let was_empty = queue.events.is_empty();
+        queue.events.push(event);
+        if was_empty {
+            self.pending.push_back(Self::dispatch::<E>);
+        }
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    pub fn next_pending(&mut self) -> Option<Dispatcher> {
+        self.pending.pop_front()
+    }
+
+    fn dispatch<E: Event>(emulator: &mut Emulator) {
+        let queue = emulator.event_bus_mut().queue_mut::<E>();
+        let events = std::mem::take(&mut queue.events);
+        let handlers = queue.handlers.clone();
+        for event in &events {
+            for handler in &handlers {
+                handler(emulator, event);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use crate::{EmulationControlEvent, EmulatorState, StateTransitionEvent};
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Ping(u8);
+    impl Event for Ping {}
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Pong(u8);
+    impl Event for Pong {}
+
+    #[test]
+    fn test_handlers_run_on_flush() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let mut emulator = Emulator::new();
+
+        let log = received.clone();
+        emulator.on_event(move |emulator, event: &Ping| {
+            log.lock().unwrap().push(event.0);
+            emulator.emit(Pong(event.0 + 1));
+        });
+        let log = received.clone();
+        emulator.on_event(move |_, event: &Pong| log.lock().unwrap().push(event.0));
+
+        emulator.emit(Ping(1));
+        emulator.emit(Ping(3));
+        assert!(received.lock().unwrap().is_empty());
+
+        emulator.flush_events();
+        assert_eq!(*received.lock().unwrap(), vec![1, 3, 2, 4]);
+    }
+
+    #[test]
+    fn test_state_transitions() {
+        let transitions = Arc::new(Mutex::new(Vec::new()));
+        let mut emulator = Emulator::new();
+        let log = transitions.clone();
+        emulator
+            .on_event(move |_, event: &StateTransitionEvent| log.lock().unwrap().push(event.to));
+
+        emulator.step();
+        emulator.emit(EmulationControlEvent::TogglePause);
+        emulator.step();
+        assert!(emulator.is_paused());
+        emulator.exit();
+        emulator.emit(EmulationControlEvent::Resume);
+        emulator.step();
+
+        assert_eq!(emulator.state(), EmulatorState::Ended);
+        assert_eq!(
+            *transitions.lock().unwrap(),
+            vec![
+                EmulatorState::Running,
+                EmulatorState::Paused,
+                EmulatorState::Ending,
+                EmulatorState::Ended,
+            ]
+        );
+    }
+}
@@ -0,0 +1,12 @@
+mod emulator_events;
+mod event_bus;
+
+pub use emulator_events::{
+    FrameCompletedEvent, ResetEvent, RumbleChangedEvent, SerialByteSentEvent, SpeedChangedEvent,
+};
+pub(crate) use event_bus::EventBus;
+
+/// Marker for types that can travel through the emulator event bus.
+pub trait Event: Send + 'static {}
+
+impl Event for crate::EmulationControlEvent {}
@@ -1,18 +1,37 @@
 mod callback_queue;
 mod components;
 mod control_event;
+mod deferred_calls;
 mod downcast;
 mod edge_detector;
+mod emulation_speed;
 mod emulator;
-mod plugin;
+mod events;
+mod plugins;
+mod reset_kind;
 mod runners;
+mod scheduler;
+mod snapshot;
 mod speed_mode;
+mod state;
+mod step_boundary;
 
 pub use components::Component;
 pub use control_event::EmulationControlEvent;
+pub use deferred_calls::DeferredCalls;
 pub use downcast::Downcastable;
 pub use edge_detector::{EdgeDetector, EdgeMode};
+pub use emulation_speed::EmulationSpeed;
 pub use emulator::Emulator;
-pub use plugin::Plugin;
-pub use runners::{HeadlessRunner, Runner};
+pub use events::{
+    Event, FrameCompletedEvent, ResetEvent, RumbleChangedEvent, SerialByteSentEvent,
+    SpeedChangedEvent,
+};
+pub use plugins::{Plugin, PluginDependencies, PluginError};
+pub use reset_kind::ResetKind;
+pub use runners::{HeadlessRunner, Runner, SerialOutput, StopCondition, StopReason};
+pub use scheduler::{Delay, ScheduledTask, TaskCallback};
+pub use snapshot::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 pub use speed_mode::SpeedMode;
+pub use state::{EmulatorState, StateTransitionEvent};
+pub use step_boundary::StepBoundary;
@@ -1,5 +0,0 @@
-use crate::Emulator;
-
-pub trait Plugin {
-    fn init(self, emulator: &mut Emulator);
-}
@@ -0,0 +1,20 @@
+mod plugin_dependencies;
+mod plugin_error;
+mod plugin_registry;
+
+pub use plugin_dependencies::PluginDependencies;
+pub use plugin_error::PluginError;
+pub(crate) use plugin_registry::PluginRegistry;
+
+use crate::Emulator;
+
+pub trait Plugin: Send + 'static {
+    /// Adds the plugin's components and callbacks, errors abort [`Emulator::build`].
+    fn init(self, emulator: &mut Emulator) -> Result<(), PluginError>;
+
+    /// Components this plugin provides and requires, and how it is ordered relative to others.
+    /// Plugins are initialized, and their callbacks run, in an order satisfying every declaration.
+    fn dependencies(&self) -> PluginDependencies {
+        PluginDependencies::new()
+    }
+}
@@ -0,0 +1,80 @@
+use std::any::TypeId;
+
+use crate::{Component, plugins::Plugin};
+
+/// A component or plugin type, named for error messages.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TypeKey {
+    pub id: TypeId,
+    pub name: &'static str,
+}
+
+impl TypeKey {
+    pub fn of<T: 'static>() -> Self {
+        Self {
+            id: TypeId::of::<T>(),
+            name: std::any::type_name::<T>(),
+        }
+    }
+}
+
+/// Where a plugin goes when no other declaration decides.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum PluginStage {
+    First,
+    #[default]
+    Normal,
+    Last,
+}
+
+/// Declarations a plugin makes about its place among the other plugins.
+#[derive(Debug, Default, Clone)]
+pub struct PluginDependencies {
+    pub(crate) provides: Vec<TypeKey>,
+    pub(crate) requires: Vec<TypeKey>,
+    pub(crate) after: Vec<TypeKey>,
+    pub(crate) before: Vec<TypeKey>,
+    pub(crate) stage: PluginStage,
+}
+
+impl PluginDependencies {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The plugin adds a `C` component.
+    pub fn provides<C: Component>(mut self) -> Self {
+        self.provides.push(TypeKey::of::<C>());
+        self
+    }
+
+    /// The plugin needs a `C` component, initialization fails when nothing provides it.
+    pub fn requires<C: Component>(mut self) -> Self {
+        self.requires.push(TypeKey::of::<C>());
+        self
+    }
+
+    /// The plugin goes after `P`, if `P` is registered.
+    pub fn after<P: Plugin>(mut self) -> Self {
+        self.after.push(TypeKey::of::<P>());
+        self
+    }
+
+    /// The plugin goes before `P`, if `P` is registered.
+    pub fn before<P: Plugin>(mut self) -> Self {
+        self.before.push(TypeKey::of::<P>());
+        self
+    }
+
+    /// The plugin goes before every plugin not declared first.
+    pub fn first(mut self) -> Self {
+        self.stage = PluginStage::First;
+        self
+    }
+
+    /// The plugin goes after every plugin not declared last.
+    pub fn last(mut self) -> Self {
+        self.stage = PluginStage::Last;
+        self
+    }
+}
@@ -0,0 +1,62 @@
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PluginError {
+    /// A plugin requires a component that no registered plugin provides.
+    MissingComponent {
+        plugin: &'static str,
+        component: &'static str,
+    },
+    /// Two plugins provide the same component.
+    DuplicateProvider {
+        component: &'static str,
+        first: &'static str,
+        second: &'static str,
+    },
+    /// The same plugin type was registered twice.
+    DuplicatePlugin(&'static str),
+    /// The declarations cannot all hold, the plugins listed depend on each other in a loop.
+    Cycle(Vec<&'static str>),
+    /// A plugin cannot set itself up, e.g. because of invalid input data.
+    Init {
+        plugin: &'static str,
+        reason: String,
+    },
+}
+
+impl PluginError {
+    /// Initialization failure of the `P` plugin.
+    pub fn init<P: 'static>(reason: impl std::fmt::Display) -> Self {
+        Self::Init {
+            plugin: std::any::type_name::<P>(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for PluginError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingComponent { plugin, component } => write!(
+                f,
+                "plugin `{plugin}` requires component `{component}`, which no registered plugin provides"
+            ),
+            Self::DuplicateProvider {
+                component,
+                first,
+                second,
+            } => write!(
+                f,
+                "component `{component}` is provided by both `{first}` and `{second}`"
+            ),
+            Self::DuplicatePlugin(plugin) => write!(f, "plugin `{plugin}` is registered twice"),
+            Self::Cycle(plugins) => {
+                write!(f, "plugins depend on each other in a cycle: ")?;
+                write!(f, "{}", plugins.join(" -> "))
+            }
+            Self::Init { plugin, reason } => {
+                write!(f, "plugin `{plugin}` failed to initialize: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PluginError {}
@@ -0,0 +1,298 @@
+use std::{any::TypeId, collections::BTreeSet};
+
+use crate::{
+    Emulator,
+    plugins::{Plugin, PluginDependencies, PluginError, plugin_dependencies::TypeKey},
+};
+
+type InitFn = Box<dyn FnOnce(&mut Emulator) -> Result<(), PluginError> + Send>;
+
+struct PluginEntry {
+    key: TypeKey,
+    dependencies: PluginDependencies,
+    init: InitFn,
+}
+
+/// Plugins waiting to be initialized, in registration order.
+#[derive(Default)]
+pub(crate) struct PluginRegistry {
+    entries: Vec<PluginEntry>,
+}
+
+impl PluginRegistry {
+    pub fn add<P: Plugin>(&mut self, plugin: P) {
+        let key = TypeKey::of::<P>();
+        let dependencies = plugin.dependencies();
+        let init = move |emulator: &mut Emulator| {
+            #[cfg(feature = "trace-span")]
+            let _span = {
+                let type_name = std::any::type_name::<P>();
+                tracing::info_span!("plugin init", %type_name).entered()
+            };
+            plugin.init(emulator)
+        };
+        self.entries.push(PluginEntry {
+            key,
+            dependencies,
+            init: Box::new(init),
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Orders the plugins so that every declaration holds.
+    /// Plugins nothing decides between keep their registration order.
+    /// `has_component` tells whether a required component is already on the emulator.
+    pub fn sort(self, has_component: impl Fn(TypeId) -> bool) -> Result<Vec<InitFn>, PluginError> {
+        let successors = self.successors(has_component)?;
+        let mut in_degree = vec![0usize; self.entries.len()];
+        for &next in successors.iter().flatten() {
+            in_degree[next] += 1;
+        }
+
+        let mut ready = (0..self.entries.len())
+            .filter(|&index| in_degree[index] == 0)
+            .collect::<BTreeSet<_>>();
+        let mut order = Vec::with_capacity(self.entries.len());
+        while let Some(index) = ready.pop_first() {
+            order.push(index);
+            for &next in &successors[index] {
+                in_degree[next] -= 1;
+                if in_degree[next] == 0 {
+                    ready.insert(next);
+                }
+            }
+        }
+
+        if order.len() < self.entries.len() {
+            return Err(PluginError::Cycle(self.find_cycle(&successors, &in_degree)));
+        }
+
+        let mut inits = self
+            .entries
+            .into_iter()
+            .map(|entry| Some(entry.init))
+            .collect::<Vec<_>>();
+        Ok(order
+            .into_iter()
+            .map(|index| inits[index].take().expect("Plugin ordered twice"))
+            .collect())
+    }
+
+    /// Edges from every plugin to the plugins that must come after it.
+    fn successors(
+        &self,
+        has_component: impl Fn(TypeId) -> bool,
+    ) -> Result<Vec<Vec<usize>>, PluginError> {
+        let mut plugins = ahash::AHashMap::new();
+        let mut providers = ahash::AHashMap::new();
+        for (index, entry) in self.entries.iter().enumerate() {
+            if plugins.insert(entry.key.id, index).is_some() {
+                return Err(PluginError::DuplicatePlugin(entry.key.name));
+            }
+            for component in &entry.dependencies.provides {
+                if let Some(first) = providers.insert(component.id, index) {
+                    return Err(PluginError::DuplicateProvider {
+                        component: component.name,
+                        first: self.entries[first].key.name,
+                        second: entry.key.name,
+                    });
+                }
+            }
+        }
+
+        let mut successors = vec![Vec::new(); self.entries.len()];
+        for (index, entry) in self.entries.iter().enumerate() {
+            let dependencies = &entry.dependencies;
+            for component in &dependencies.requires {
+                match providers.get(&component.id) {
+                    Some(&provider) if provider != index => successors[provider].push(index),
+                    Some(_) => {}
+                    None if has_component(component.id) => {}
+                    None => {
+                        return Err(PluginError::MissingComponent {
+                            plugin: entry.key.name,
+                            component: component.name,
+                        });
+                    }
+                }
+            }
+            for plugin in &dependencies.after {
+                if let Some(&other) = plugins.get(&plugin.id) {
+                    successors[other].push(index);
+                }
+            }
+            for plugin in &dependencies.before {
+                if let Some(&other) = plugins.get(&plugin.id) {
+                    successors[index].push(other);
+                }
+            }
+            for (other, other_entry) in self.entries.iter().enumerate() {
+                if dependencies.stage < other_entry.dependencies.stage {
+                    successors[index].push(other);
+                }
+            }
+        }
+        Ok(successors)
+    }
+
+    /// Walks back from a plugin left unsorted until a plugin repeats.
+    /// Every unsorted plugin waits on another unsorted one, so the walk always closes a loop.
+    fn find_cycle(&self, successors: &[Vec<usize>], in_degree: &[usize]) -> Vec<&'static str> {
+        let blocked = |index: usize| in_degree[index] > 0;
+        let predecessor = |index: usize| {
+            (0..successors.len())
+                .find(|&other| blocked(other) && successors[other].contains(&index))
+                .expect("Blocked plugin without a blocked predecessor")
+        };
+
+        let start = (0..in_degree.len())
+            .find(|&index| blocked(index))
+            .expect("Cycle without blocked plugins");
+        let mut path = vec![start];
+        let mut current = predecessor(start);
+        while !path.contains(&current) {
+            path.push(current);
+            current = predecessor(current);
+        }
+
+        let loop_start = path.iter().position(|&index| index == current).unwrap();
+        let mut cycle = path[loop_start..]
+            .iter()
+            .rev()
+            .map(|&index| self.entries[index].key.name)
+            .collect::<Vec<_>>();
+        cycle.push(cycle[0]);
+        cycle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Component;
+
+    struct Bus;
+    impl Component for Bus {}
+
+    struct Cpu;
+    impl Component for Cpu {}
+
+    struct BusPlugin;
+    impl Plugin for BusPlugin {
+        fn init(self, emulator: &mut Emulator) -> Result<(), PluginError> {
+            emulator.with_component(Bus);
+            Ok(())
+        }
+
+        fn dependencies(&self) -> PluginDependencies {
+            PluginDependencies::new().provides::<Bus>()
+        }
+    }
+
+    struct CpuPlugin;
+    impl Plugin for CpuPlugin {
+        fn init(self, emulator: &mut Emulator) -> Result<(), PluginError> {
+            assert!(emulator.has_component::<Bus>());
+            emulator.with_component(Cpu);
+            Ok(())
+        }
+
+        fn dependencies(&self) -> PluginDependencies {
+            PluginDependencies::new()
+                .provides::<Cpu>()
+                .requires::<Bus>()
+        }
+    }
+
+    struct LastPlugin;
+    impl Plugin for LastPlugin {
+        fn init(self, emulator: &mut Emulator) -> Result<(), PluginError> {
+            assert!(emulator.has_component::<Cpu>());
+            Ok(())
+        }
+
+        fn dependencies(&self) -> PluginDependencies {
+            PluginDependencies::new().last()
+        }
+    }
+
+    struct CyclicPlugin;
+    impl Plugin for CyclicPlugin {
+        fn init(self, _emulator: &mut Emulator) -> Result<(), PluginError> {
+            Ok(())
+        }
+
+        fn dependencies(&self) -> PluginDependencies {
+            PluginDependencies::new()
+                .requires::<Cpu>()
+                .before::<CpuPlugin>()
+        }
+    }
+
+    #[test]
+    fn initializes_providers_before_dependents() {
+        let emulator = Emulator::new()
+            .with_plugin(LastPlugin)
+            .with_plugin(CpuPlugin)
+            .with_plugin(BusPlugin)
+            .build()
+            .unwrap();
+        assert!(emulator.has_component::<Cpu>());
+    }
+
+    #[test]
+    fn reports_missing_components() {
+        let error = Emulator::new().with_plugin(CpuPlugin).build().unwrap_err();
+        assert_eq!(
+            error,
+            PluginError::MissingComponent {
+                plugin: std::any::type_name::<CpuPlugin>(),
+                component: std::any::type_name::<Bus>(),
+            }
+        );
+    }
+
+    #[test]
+    fn run_reports_plugin_errors() {
+        let result = Emulator::new()
+            .with_plugin(CpuPlugin)
+            .run::<crate::HeadlessRunner>();
+        assert!(matches!(result, Err(PluginError::MissingComponent { .. })));
+    }
+
+    #[test]
+    fn accepts_components_added_directly() {
+        let mut emulator = Emulator::new();
+        emulator.with_component(Bus);
+        assert!(emulator.with_plugin(CpuPlugin).build().is_ok());
+    }
+
+    #[test]
+    fn reports_cycles() {
+        let error = Emulator::new()
+            .with_plugin(BusPlugin)
+            .with_plugin(CpuPlugin)
+            .with_plugin(CyclicPlugin)
+            .build()
+            .unwrap_err();
+        let cpu = std::any::type_name::<CpuPlugin>();
+        let cyclic = std::any::type_name::<CyclicPlugin>();
+        assert_eq!(error, PluginError::Cycle(vec![cyclic, cpu, cyclic]));
+    }
+
+    #[test]
+    fn reports_duplicate_plugins() {
+        let error = Emulator::new()
+            .with_plugin(BusPlugin)
+            .with_plugin(BusPlugin)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            error,
+            PluginError::DuplicatePlugin(std::any::type_name::<BusPlugin>())
+        );
+    }
+}
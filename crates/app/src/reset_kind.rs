@@ -0,0 +1,85 @@
+/// How much of the machine a reset reinitialises.
+///
+/// Battery backed cartridge RAM and the RTC survive both, they are flushed to the save backend
+/// before the reset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetKind {
+    /// Resets the CPU and maps the boot ROM back, memory and peripherals keep their state
+    /// until the boot ROM reinitialises them.
+    Soft,
+    /// Brings every component back to its power on state.
+    Hard,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Component, Emulator, ResetEvent, Snapshot, SnapshotError, SnapshotReader};
+
+    #[derive(Default)]
+    struct Counter {
+        value: u16,
+        resets: Vec<ResetEvent>,
+    }
+
+    impl Component for Counter {}
+
+    impl Snapshot for Counter {
+        fn save(&self, writer: &mut crate::SnapshotWriter) {
+            writer.write_u16(self.value);
+        }
+
+        fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+            self.value = reader.read_u16()?;
+            Ok(())
+        }
+    }
+
+    fn emulator() -> Emulator {
+        let mut emulator = Emulator::new();
+        emulator
+            .with_component(Counter::default())
+            .with_snapshot::<Counter>("counter")
+            .on_event(|emulator, event: &ResetEvent| {
+                let counter = emulator.get_component_mut::<Counter>().unwrap();
+                counter.resets.push(*event);
+            });
+        emulator.build().unwrap()
+    }
+
+    #[test]
+    fn hard_reset_restores_the_power_on_state() {
+        let mut emulator = emulator();
+        for _ in 0..100 {
+            emulator.step();
+        }
+        emulator.get_component_mut::<Counter>().unwrap().value = 7;
+
+        emulator.reset(ResetKind::Hard);
+        assert_eq!(emulator.get_cycles(), 0);
+        let counter = emulator.get_component::<Counter>().unwrap();
+        assert_eq!(counter.value, 0);
+        assert_eq!(
+            counter.resets,
+            [
+                ResetEvent::Starting(ResetKind::Hard),
+                ResetEvent::Finished(ResetKind::Hard)
+            ]
+        );
+    }
+
+    #[test]
+    fn soft_reset_leaves_components_to_their_plugins() {
+        let mut emulator = emulator();
+        for _ in 0..100 {
+            emulator.step();
+        }
+        emulator.get_component_mut::<Counter>().unwrap().value = 7;
+
+        emulator.reset(ResetKind::Soft);
+        assert_eq!(emulator.get_cycles(), 100);
+        let counter = emulator.get_component::<Counter>().unwrap();
+        assert_eq!(counter.value, 7);
+        assert_eq!(counter.resets.len(), 2);
+    }
+}
@@ -1,21 +1,214 @@
-use crate::{emulator::Emulator, runners::Runner};
+use std::time::{Duration, Instant};
 
+use crate::{
+    emulator::Emulator,
+    runners::{Runner, SerialOutput, StopCondition, StopReason},
+};
+
+/// Runs the emulator without a host, until one of its bounds is reached.
+///
+/// Without any bound it runs until the emulator ends or fails.
 #[derive(Debug)]
 pub struct HeadlessRunner {
     emulator: Emulator,
+    /// Steps to run per call to [`HeadlessRunner::run_until_stop`].
+    dots: Option<u64>,
+    /// Wall clock limit per call to [`HeadlessRunner::run_until_stop`].
+    timeout: Option<Duration>,
+    conditions: Vec<StopCondition>,
 }
 
-impl Runner for HeadlessRunner {
-    type Result = ();
+impl HeadlessRunner {
+    /// Steps between two wall clock reads, reading the clock every dot is noticeably slow.
+    const TIMEOUT_CHECK_INTERVAL: u64 = 4096;
 
-    fn new(emulator: Emulator) -> Self {
-        Self { emulator }
+    pub fn new(emulator: Emulator) -> Self {
+        let mut emulator = emulator;
+        SerialOutput::attach(&mut emulator);
+        Self {
+            emulator,
+            dots: None,
+            timeout: None,
+            conditions: Vec::new(),
+        }
     }
 
-    fn run(mut self) {
-        // Never exit
+    /// Stops after `dots` emulator steps.
+    pub fn for_dots(mut self, dots: u64) -> Self {
+        self.dots = Some(dots);
+        self
+    }
+
+    /// Stops after `frames` frames worth of dots.
+    pub fn for_frames(self, frames: u64) -> Self {
+        self.for_dots(frames * Emulator::DOTS_PER_FRAME)
+    }
+
+    /// Stops once `timeout` of wall clock time has elapsed.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Stops once `condition` matches, conditions are checked in registration order.
+    pub fn until(mut self, condition: StopCondition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    /// Stops once `predicate` holds for the emulator.
+    pub fn until_fn<F>(self, label: impl Into<String>, predicate: F) -> Self
+    where
+        F: FnMut(&Emulator) -> bool + 'static,
+    {
+        self.until(StopCondition::new(label, predicate))
+    }
+
+    /// Stops once `pattern` is sent through the serial port.
+    pub fn until_serial(self, pattern: impl Into<Vec<u8>>) -> Self {
+        self.until(StopCondition::serial_contains(pattern))
+    }
+
+    /// Steps the emulator until a bound is reached.
+    /// Bounds restart on every call, so a run can be resumed after it stops.
+    pub fn run_until_stop(&mut self) -> StopReason {
+        let started = Instant::now();
+        let mut steps = 0;
+        let mut next_timeout_check = Self::TIMEOUT_CHECK_INTERVAL;
         loop {
-            self.emulator.step();
+            let state = self.emulator.state();
+            if state.is_finished() {
+                return StopReason::Finished(state);
+            }
+            if self.dots.is_some_and(|dots| steps >= dots) {
+                return StopReason::Completed;
+            }
+
+            // Conditions may watch any state, they are checked after every dot
+            steps += if self.conditions.is_empty() {
+                let budget = self.dots.map_or(u64::MAX, |dots| dots - steps);
+                self.emulator
+                    .step_until_due(budget.min(Self::TIMEOUT_CHECK_INTERVAL))
+            } else {
+                self.emulator.step();
+                1
+            };
+
+            for (index, condition) in self.conditions.iter_mut().enumerate() {
+                if condition.check(&self.emulator) {
+                    return StopReason::Condition {
+                        index,
+                        label: condition.label().to_string(),
+                    };
+                }
+            }
+
+            if let Some(timeout) = self.timeout
+                && steps >= next_timeout_check
+            {
+                if started.elapsed() >= timeout {
+                    return StopReason::TimedOut;
+                }
+                next_timeout_check = steps + Self::TIMEOUT_CHECK_INTERVAL;
+            }
         }
     }
+
+    pub fn emulator(&self) -> &Emulator {
+        &self.emulator
+    }
+
+    pub fn emulator_mut(&mut self) -> &mut Emulator {
+        &mut self.emulator
+    }
+
+    pub fn into_emulator(self) -> Emulator {
+        self.emulator
+    }
+
+    /// Bytes sent through the serial port since the runner was created.
+    pub fn serial_output(&self) -> &[u8] {
+        self.emulator
+            .get_component::<SerialOutput>()
+            .map_or(&[], SerialOutput::bytes)
+    }
+}
+
+impl Runner for HeadlessRunner {
+    type Result = StopReason;
+
+    fn new(emulator: Emulator) -> Self {
+        Self::new(emulator)
+    }
+
+    fn run(mut self) -> StopReason {
+        self.run_until_stop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Delay, SerialByteSentEvent};
+
+    fn send_serial_byte(emulator: &mut Emulator) {
+        let byte = b'a' + (emulator.get_cycles() / 100 % 26) as u8;
+        emulator.emit(SerialByteSentEvent { byte });
+    }
+
+    #[test]
+    fn runs_for_the_given_dots() {
+        let mut runner = HeadlessRunner::new(Emulator::new()).for_dots(1000);
+        assert_eq!(runner.run_until_stop(), StopReason::Completed);
+        assert_eq!(runner.emulator().get_cycles(), 1000);
+        assert_eq!(runner.run_until_stop(), StopReason::Completed);
+        assert_eq!(runner.emulator().get_cycles(), 2000);
+    }
+
+    #[test]
+    fn stops_on_the_first_matching_condition() {
+        let reason = HeadlessRunner::new(Emulator::new())
+            .for_frames(1)
+            .until_fn("never", |_| false)
+            .until_fn("cycle 500", |emulator| emulator.get_cycles() == 500)
+            .run_until_stop();
+        assert_eq!(
+            reason,
+            StopReason::Condition {
+                index: 1,
+                label: "cycle 500".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn stops_on_serial_pattern() {
+        let mut emulator = Emulator::new();
+        emulator.add_periodic_task("serial", Delay::Dots(100), send_serial_byte);
+        let mut runner = HeadlessRunner::new(emulator)
+            .for_dots(2000)
+            .until_serial("cde");
+        assert!(matches!(
+            runner.run_until_stop(),
+            StopReason::Condition { index: 0, .. }
+        ));
+        assert!(runner.serial_output().ends_with(b"cde"));
+        assert_eq!(runner.run_until_stop(), StopReason::Completed);
+    }
+
+    #[test]
+    fn stops_when_the_emulator_ends() {
+        let mut emulator = Emulator::new();
+        emulator.exit();
+        let reason = HeadlessRunner::new(emulator).run_until_stop();
+        assert!(matches!(reason, StopReason::Finished(_)));
+    }
+
+    #[test]
+    fn stops_on_timeout() {
+        let reason = HeadlessRunner::new(Emulator::new())
+            .with_timeout(Duration::ZERO)
+            .run_until_stop();
+        assert_eq!(reason, StopReason::TimedOut);
+    }
 }
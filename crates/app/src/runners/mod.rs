@@ -1,5 +1,9 @@
 mod headless_runner;
 mod runner;
+mod serial_output;
+mod stop_condition;
 
 pub use headless_runner::HeadlessRunner;
 pub use runner::Runner;
+pub use serial_output::SerialOutput;
+pub use stop_condition::{StopCondition, StopReason};
@@ -0,0 +1,31 @@
+use crate::{Component, Emulator, SerialByteSentEvent};
+
+/// Every byte sent through the serial port, collected for headless runs.
+#[derive(Debug, Default)]
+pub struct SerialOutput {
+    bytes: Vec<u8>,
+}
+
+impl SerialOutput {
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Starts collecting serial bytes, does nothing if already collecting.
+    pub(crate) fn attach(emulator: &mut Emulator) {
+        if emulator.has_component::<SerialOutput>() {
+            return;
+        }
+        emulator
+            .with_component(SerialOutput::default())
+            .on_event(Self::on_serial_byte);
+    }
+
+    fn on_serial_byte(emulator: &mut Emulator, event: &SerialByteSentEvent) {
+        if let Some(output) = emulator.get_component_mut::<SerialOutput>() {
+            output.bytes.push(event.byte);
+        }
+    }
+}
+
+impl Component for SerialOutput {}
@@ -0,0 +1,75 @@
+use crate::{Emulator, EmulatorState, runners::SerialOutput};
+
+type Predicate = Box<dyn FnMut(&Emulator) -> bool>;
+
+/// Why a [`HeadlessRunner`](crate::HeadlessRunner) returned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StopReason {
+    /// The dot budget ran out, see [`HeadlessRunner::for_dots`](crate::HeadlessRunner::for_dots).
+    Completed,
+    /// A stop condition matched, `index` is its position in registration order.
+    Condition { index: usize, label: String },
+    /// The wall clock timeout elapsed.
+    TimedOut,
+    /// The emulator reached a terminal state.
+    Finished(EmulatorState),
+}
+
+/// A labeled predicate checked after every step of a headless run.
+pub struct StopCondition {
+    label: String,
+    predicate: Predicate,
+}
+
+impl StopCondition {
+    pub fn new<F>(label: impl Into<String>, predicate: F) -> Self
+    where
+        F: FnMut(&Emulator) -> bool + 'static,
+    {
+        Self {
+            label: label.into(),
+            predicate: Box::new(predicate),
+        }
+    }
+
+    /// Matches once `pattern` shows up in bytes sent through the serial port.
+    /// Only new output is searched, so a pattern already matched does not stop the next run.
+    pub fn serial_contains(pattern: impl Into<Vec<u8>>) -> Self {
+        let pattern = pattern.into();
+        let label = format!(
+            "serial output contains {:?}",
+            String::from_utf8_lossy(&pattern)
+        );
+        let mut searched = 0;
+        Self::new(label, move |emulator| {
+            let Some(output) = emulator.get_component::<SerialOutput>() else {
+                return false;
+            };
+            let bytes = output.bytes();
+            if bytes.len() == searched {
+                return false;
+            }
+            let start = searched.saturating_sub(pattern.len().saturating_sub(1));
+            searched = bytes.len();
+            bytes[start..]
+                .windows(pattern.len().max(1))
+                .any(|window| window == pattern.as_slice())
+        })
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub(crate) fn check(&mut self, emulator: &Emulator) -> bool {
+        (self.predicate)(emulator)
+    }
+}
+
+impl std::fmt::Debug for StopCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StopCondition")
+            .field("label", &self.label)
+            .finish()
+    }
+}
@@ -0,0 +1,333 @@
+use crate::Emulator;
+
+pub type TaskCallback = fn(&mut Emulator);
+
+/// Handle to a task registered on the scheduler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScheduledTask(usize);
+
+/// When a scheduled task is due, relative to the moment it is scheduled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delay {
+    /// Dot cycles, not influenced by speed mode.
+    Dots(u64),
+    /// M cycles, twice as fast in double speed mode.
+    /// Due right after the callbacks of the M-cycle, counted exactly across speed switches.
+    MCycles(u64),
+}
+
+impl Delay {
+    fn clock(self) -> Clock {
+        match self {
+            Self::Dots(_) => Clock::Dots,
+            Self::MCycles(_) => Clock::MCycles,
+        }
+    }
+
+    fn count(self) -> u64 {
+        match self {
+            Self::Dots(count) | Self::MCycles(count) => count,
+        }
+    }
+}
+
+/// Clock a task is due on, M-cycles are counted separately so double speed is exact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Clock {
+    Dots,
+    MCycles,
+}
+
+impl Clock {
+    pub(crate) const ALL: [Clock; 2] = [Clock::Dots, Clock::MCycles];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+struct Task {
+    name: &'static str,
+    callback: TaskCallback,
+    due: Option<(Clock, u64)>,
+    /// Periodic tasks are scheduled again every time they run.
+    period: Option<Delay>,
+}
+
+/// Runs work at a given dot cycle or M-cycle instead of polling for it every cycle.
+///
+/// Each task has at most one pending due cycle, scheduling it again replaces the previous one.
+/// The emulator only compares the current cycle against the nearest due cycle of each clock
+/// while stepping, and [`Emulator::step_until_due`] runs the CPU freely up to it.
+pub(crate) struct Scheduler {
+    tasks: Vec<Task>,
+    /// Nearest due cycle of each clock, `u64::MAX` when nothing is scheduled on it.
+    next_due: [u64; 2],
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self {
+            tasks: Vec::new(),
+            next_due: [u64::MAX; 2],
+        }
+    }
+
+    pub fn add_task(
+        &mut self,
+        name: &'static str,
+        callback: TaskCallback,
+        period: Option<Delay>,
+    ) -> ScheduledTask {
+        assert!(
+            self.tasks.iter().all(|task| task.name != name),
+            "Scheduled task `{name}` registered twice"
+        );
+        self.tasks.push(Task {
+            name,
+            callback,
+            due: None,
+            period,
+        });
+        ScheduledTask(self.tasks.len() - 1)
+    }
+
+    pub fn next_due(&self, clock: Clock) -> u64 {
+        self.next_due[clock.index()]
+    }
+
+    /// Schedules `task` after `delay`, `now` gives the current cycle of each clock.
+    pub fn schedule(&mut self, task: ScheduledTask, delay: Delay, now: impl Fn(Clock) -> u64) {
+        let clock = delay.clock();
+        self.schedule_at(task, clock, now(clock) + delay.count());
+    }
+
+    pub fn schedule_at(&mut self, task: ScheduledTask, clock: Clock, due: u64) {
+        self.tasks[task.0].due = Some((clock, due));
+        self.update_next_due();
+    }
+
+    pub fn cancel(&mut self, task: ScheduledTask) {
+        self.tasks[task.0].due = None;
+        self.update_next_due();
+    }
+
+    /// Cancels every pending run, periodic tasks start over one period from `now`.
+    pub fn restart(&mut self, now: impl Fn(Clock) -> u64) {
+        for task in &mut self.tasks {
+            task.due = task
+                .period
+                .map(|period| (period.clock(), now(period.clock()) + period.count()));
+        }
+        self.update_next_due();
+    }
+
+    /// Unschedules every task, periodic ones included.
+    pub fn clear(&mut self) {
+        for task in &mut self.tasks {
+            task.due = None;
+        }
+        self.next_due = [u64::MAX; 2];
+    }
+
+    pub fn due_cycle(&self, task: ScheduledTask) -> Option<u64> {
+        self.tasks[task.0].due.map(|(_, due)| due)
+    }
+
+    /// Takes the callback of the first task of `clock` due at `now`.
+    /// One-shot tasks are cleared, periodic ones are scheduled a period later.
+    pub fn pop_due(&mut self, clock: Clock, now: u64) -> Option<TaskCallback> {
+        if self.next_due(clock) > now {
+            return None;
+        }
+        let task = self
+            .tasks
+            .iter_mut()
+            .filter(|task| task.due.is_some_and(|(c, due)| c == clock && due <= now))
+            .min_by_key(|task| task.due.map(|(_, due)| due))?;
+        // Periods are counted on the clock the task was due on.
+        task.due = task.period.map(|period| (clock, now + period.count()));
+        let callback = task.callback;
+        self.update_next_due();
+        Some(callback)
+    }
+
+    fn update_next_due(&mut self) {
+        self.next_due = [u64::MAX; 2];
+        for (clock, due) in self.tasks.iter().filter_map(|task| task.due) {
+            let next_due = &mut self.next_due[clock.index()];
+            *next_due = (*next_due).min(due);
+        }
+    }
+
+    /// Pending tasks by name, for save states.
+    pub fn pending(&self) -> impl Iterator<Item = (&'static str, Clock, u64)> + '_ {
+        self.tasks
+            .iter()
+            .filter_map(|task| task.due.map(|(clock, due)| (task.name, clock, due)))
+    }
+
+    /// Replaces every pending task, unknown names are rejected.
+    /// Periodic tasks missing from `pending` are left unscheduled.
+    pub fn restore<'a>(
+        &mut self,
+        pending: impl IntoIterator<Item = (&'a str, Clock, u64)>,
+    ) -> Result<(), String> {
+        self.clear();
+        for (name, clock, due) in pending {
+            let task = self
+                .tasks
+                .iter_mut()
+                .find(|task| task.name == name)
+                .ok_or_else(|| format!("Unknown scheduled task `{name}`"))?;
+            task.due = Some((clock, due));
+        }
+        self.update_next_due();
+        Ok(())
+    }
+}
+
+impl Clock {
+    pub(crate) fn to_u8(self) -> u8 {
+        self as u8
+    }
+
+    pub(crate) fn from_u8(value: u8) -> Option<Self> {
+        Self::ALL.get(value as usize).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SpeedMode;
+
+    fn noop(_: &mut Emulator) {}
+
+    #[test]
+    fn test_pops_tasks_in_due_order() {
+        let mut scheduler = Scheduler::new();
+        let first = scheduler.add_task("first", noop, None);
+        let second = scheduler.add_task("second", noop, None);
+        scheduler.schedule_at(second, Clock::Dots, 10);
+        scheduler.schedule_at(first, Clock::Dots, 20);
+        assert_eq!(scheduler.next_due(Clock::Dots), 10);
+
+        assert!(scheduler.pop_due(Clock::Dots, 9).is_none());
+        assert!(scheduler.pop_due(Clock::Dots, 10).is_some());
+        assert_eq!(scheduler.due_cycle(second), None);
+        assert_eq!(scheduler.next_due(Clock::Dots), 20);
+        assert!(scheduler.pop_due(Clock::Dots, 25).is_some());
+        assert_eq!(scheduler.next_due(Clock::Dots), u64::MAX);
+    }
+
+    #[test]
+    fn test_clocks_are_independent() {
+        let mut scheduler = Scheduler::new();
+        let dots = scheduler.add_task("dots", noop, None);
+        let mcycles = scheduler.add_task("mcycles", noop, None);
+        scheduler.schedule(dots, Delay::Dots(8), |_| 100);
+        scheduler.schedule(mcycles, Delay::MCycles(2), |clock| match clock {
+            Clock::Dots => 100,
+            Clock::MCycles => 25,
+        });
+        assert_eq!(scheduler.next_due(Clock::Dots), 108);
+        assert_eq!(scheduler.next_due(Clock::MCycles), 27);
+
+        assert!(scheduler.pop_due(Clock::MCycles, 108).is_some());
+        assert!(scheduler.pop_due(Clock::MCycles, 108).is_none());
+        assert_eq!(scheduler.due_cycle(dots), Some(108));
+    }
+
+    #[derive(Default)]
+    struct RanAt(Option<u64>);
+
+    impl crate::Component for RanAt {}
+
+    fn record(emulator: &mut Emulator) {
+        let dots = emulator.get_cycles();
+        emulator.get_component_mut::<RanAt>().unwrap().0 = Some(dots);
+    }
+
+    #[test]
+    fn test_mcycle_delays_follow_speed_mode() {
+        for (speed_mode, dots) in [(SpeedMode::Single, 640), (SpeedMode::Double, 320)] {
+            let mut emulator = Emulator::new();
+            emulator.with_component(RanAt::default());
+            emulator.set_speed_mode(speed_mode);
+            let task = emulator.add_scheduled_task("task", record);
+            emulator.schedule(task, Delay::MCycles(160));
+            for _ in 0..1000 {
+                emulator.step();
+            }
+            assert_eq!(emulator.get_component::<RanAt>().unwrap().0, Some(dots));
+        }
+    }
+
+    #[test]
+    fn test_step_until_due_stops_at_the_next_task() {
+        for (speed_mode, dots) in [(SpeedMode::Single, 640), (SpeedMode::Double, 320)] {
+            let mut emulator = Emulator::new();
+            emulator.with_component(RanAt::default());
+            emulator.set_speed_mode(speed_mode);
+            let task = emulator.add_scheduled_task("task", record);
+            emulator.schedule(task, Delay::MCycles(160));
+            // The first step only starts the emulator
+            assert_eq!(emulator.step_until_due(1000), 1);
+            assert_eq!(emulator.step_until_due(1000), dots - 1);
+            assert_eq!(emulator.get_component::<RanAt>().unwrap().0, Some(dots));
+            assert_eq!(emulator.step_until_due(10), 10);
+        }
+    }
+
+    #[test]
+    fn test_periodic_tasks_reschedule_themselves() {
+        let mut scheduler = Scheduler::new();
+        let task = scheduler.add_task("task", noop, Some(Delay::MCycles(2)));
+        scheduler.schedule_at(task, Clock::MCycles, 10);
+        assert!(scheduler.pop_due(Clock::MCycles, 12).is_some());
+        assert_eq!(scheduler.due_cycle(task), Some(14));
+        assert_eq!(scheduler.next_due(Clock::MCycles), 14);
+    }
+
+    #[test]
+    fn test_rescheduling_replaces_the_pending_cycle() {
+        let mut scheduler = Scheduler::new();
+        let task = scheduler.add_task("task", noop, None);
+        scheduler.schedule_at(task, Clock::Dots, 10);
+        scheduler.schedule_at(task, Clock::Dots, 30);
+        assert_eq!(scheduler.next_due(Clock::Dots), 30);
+        assert!(scheduler.pop_due(Clock::Dots, 10).is_none());
+        scheduler.cancel(task);
+        assert_eq!(scheduler.next_due(Clock::Dots), u64::MAX);
+    }
+
+    #[test]
+    fn test_restart_cancels_one_shot_tasks() {
+        let mut scheduler = Scheduler::new();
+        let one_shot = scheduler.add_task("one_shot", noop, None);
+        let periodic = scheduler.add_task("periodic", noop, Some(Delay::Dots(100)));
+        scheduler.schedule_at(one_shot, Clock::MCycles, 10);
+        scheduler.schedule_at(periodic, Clock::Dots, 30);
+
+        scheduler.restart(|_| 50);
+        assert_eq!(scheduler.due_cycle(one_shot), None);
+        assert_eq!(scheduler.due_cycle(periodic), Some(150));
+        assert_eq!(scheduler.next_due(Clock::MCycles), u64::MAX);
+    }
+
+    #[test]
+    fn test_restore_rejects_unknown_tasks() {
+        let mut scheduler = Scheduler::new();
+        let task = scheduler.add_task("task", noop, None);
+        scheduler.schedule_at(task, Clock::MCycles, 10);
+        let pending: Vec<_> = scheduler.pending().collect();
+
+        let mut restored = Scheduler::new();
+        let restored_task = restored.add_task("task", noop, None);
+        restored.restore(pending.iter().copied()).unwrap();
+        assert_eq!(restored.due_cycle(restored_task), Some(10));
+        assert_eq!(restored.next_due(Clock::MCycles), 10);
+        assert!(restored.restore([("other", Clock::Dots, 5)]).is_err());
+    }
+}
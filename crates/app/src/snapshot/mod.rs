@@ -0,0 +1,48 @@
+mod snapshot_error;
+mod snapshot_reader;
+mod snapshot_registry;
+mod snapshot_writer;
+
+pub use snapshot_error::SnapshotError;
+pub use snapshot_reader::SnapshotReader;
+pub(crate) use snapshot_registry::SnapshotRegistry;
+pub use snapshot_writer::SnapshotWriter;
+
+/// State that can be frozen into and restored from a save-state blob.
+///
+/// Restoring happens in place, so anything wired at plugin init
+/// (IOBus hooks, callbacks) stays attached to the restored component.
+pub trait Snapshot {
+    /// Layout version of the serialized state.
+    /// Must be bumped whenever `save`/`load` change their layout.
+    const VERSION: u16 = 1;
+
+    fn save(&self, writer: &mut SnapshotWriter);
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError>;
+}
+
+impl Snapshot for crate::EdgeDetector {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_bool(self.value());
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.set_value(reader.read_bool()?);
+        Ok(())
+    }
+}
+
+impl Snapshot for crate::SpeedMode {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_bool(*self == crate::SpeedMode::Double);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        *self = if reader.read_bool()? {
+            crate::SpeedMode::Double
+        } else {
+            crate::SpeedMode::Single
+        };
+        Ok(())
+    }
+}
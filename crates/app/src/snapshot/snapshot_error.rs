@@ -0,0 +1,62 @@
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The blob does not start with the save-state magic bytes.
+    InvalidMagic,
+    /// The blob was written with an incompatible container format.
+    IncompatibleVersion { expected: u16, found: u16 },
+    /// A section was written with an incompatible layout.
+    IncompatibleSectionVersion {
+        section: String,
+        expected: u16,
+        found: u16,
+    },
+    /// The blob was taken while running a different ROM.
+    ContentMismatch { expected: u64, found: u64 },
+    /// A registered component has no section in the blob.
+    MissingSection(String),
+    /// The blob ended before all the expected data was read.
+    UnexpectedEof,
+    /// The blob is well formed but holds a value the emulator cannot accept.
+    InvalidData(String),
+    /// Loading failed, then restoring the state from before the load failed too.
+    /// The emulator is left partially restored and should not keep running.
+    RollbackFailed {
+        error: Box<SnapshotError>,
+        rollback: Box<SnapshotError>,
+    },
+}
+
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidMagic => write!(f, "not a save state"),
+            Self::IncompatibleVersion { expected, found } => {
+                write!(
+                    f,
+                    "save state version {found} is not supported (expected {expected})"
+                )
+            }
+            Self::IncompatibleSectionVersion {
+                section,
+                expected,
+                found,
+            } => write!(
+                f,
+                "section `{section}` version {found} is not supported (expected {expected})"
+            ),
+            Self::ContentMismatch { expected, found } => write!(
+                f,
+                "save state belongs to a different ROM (expected {expected:#018x}, found {found:#018x})"
+            ),
+            Self::MissingSection(section) => write!(f, "save state is missing section `{section}`"),
+            Self::UnexpectedEof => write!(f, "save state ended unexpectedly"),
+            Self::InvalidData(message) => write!(f, "invalid save state data: {message}"),
+            Self::RollbackFailed { error, rollback } => write!(
+                f,
+                "{error}, then restoring the previous state failed: {rollback}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
@@ -0,0 +1,99 @@
+use crate::snapshot::{Snapshot, SnapshotError};
+
+/// Little-endian binary reader mirroring [`SnapshotWriter`](crate::SnapshotWriter).
+#[derive(Debug)]
+pub struct SnapshotReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> SnapshotReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        let end = self
+            .position
+            .checked_add(len)
+            .ok_or(SnapshotError::UnexpectedEof)?;
+        let bytes = self
+            .data
+            .get(self.position..end)
+            .ok_or(SnapshotError::UnexpectedEof)?;
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], SnapshotError> {
+        let bytes = self.take(N)?;
+        Ok(bytes.try_into().expect("slice length checked by take"))
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take_array::<1>()?[0])
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, SnapshotError> {
+        Ok(u16::from_le_bytes(self.take_array()?))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, SnapshotError> {
+        Ok(u32::from_le_bytes(self.take_array()?))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, SnapshotError> {
+        Ok(u64::from_le_bytes(self.take_array()?))
+    }
+
+    pub fn read_f32(&mut self) -> Result<f32, SnapshotError> {
+        Ok(f32::from_bits(self.read_u32()?))
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, SnapshotError> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            value => Err(SnapshotError::InvalidData(format!(
+                "invalid bool value {value}"
+            ))),
+        }
+    }
+
+    /// Reads a length-prefixed byte slice.
+    pub fn read_bytes(&mut self) -> Result<&'a [u8], SnapshotError> {
+        let len = self.read_u32()? as usize;
+        self.take(len)
+    }
+
+    /// Reads a length-prefixed byte slice into `buffer`, whose length must match.
+    pub fn read_bytes_into(&mut self, buffer: &mut [u8]) -> Result<(), SnapshotError> {
+        let bytes = self.read_bytes()?;
+        if bytes.len() != buffer.len() {
+            return Err(SnapshotError::InvalidData(format!(
+                "expected {} bytes, found {}",
+                buffer.len(),
+                bytes.len()
+            )));
+        }
+        buffer.copy_from_slice(bytes);
+        Ok(())
+    }
+
+    pub fn read_option_u8(&mut self) -> Result<Option<u8>, SnapshotError> {
+        if self.read_bool()? {
+            Ok(Some(self.read_u8()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Restores a nested value in place.
+    pub fn read<T: Snapshot>(&mut self, value: &mut T) -> Result<(), SnapshotError> {
+        value.load(self)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.position >= self.data.len()
+    }
+}
@@ -0,0 +1,296 @@
+use crate::{
+    Component, Emulator,
+    snapshot::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter},
+};
+
+type SaveFn = fn(&Emulator, &mut SnapshotWriter);
+type LoadFn = fn(&mut Emulator, &mut SnapshotReader) -> Result<(), SnapshotError>;
+
+struct SnapshotEntry {
+    key: &'static str,
+    version: u16,
+    save: SaveFn,
+    load: LoadFn,
+}
+
+impl SnapshotEntry {
+    fn emulator() -> Self {
+        Self {
+            key: Emulator::SNAPSHOT_KEY,
+            version: <Emulator as Snapshot>::VERSION,
+            save: |emulator, writer| emulator.save(writer),
+            load: |emulator, reader| emulator.load(reader),
+        }
+    }
+
+    fn component<C: Component + Snapshot>(key: &'static str) -> Self {
+        Self {
+            key,
+            version: C::VERSION,
+            save: |emulator, writer| {
+                emulator
+                    .get_component::<C>()
+                    .expect("Snapshot component missing")
+                    .save(writer)
+            },
+            load: |emulator, reader| {
+                emulator
+                    .get_component_mut::<C>()
+                    .expect("Snapshot component missing")
+                    .load(reader)
+            },
+        }
+    }
+}
+
+/// Keeps track of the components taking part in save states.
+///
+/// Blob layout, all integers little-endian:
+/// magic, format version (u16), content id (u64), section count (u32),
+/// then for every section: key (u32 length + utf8), version (u16), payload (u32 length + bytes).
+pub(crate) struct SnapshotRegistry {
+    entries: Vec<SnapshotEntry>,
+    content_id: u64,
+}
+
+impl SnapshotRegistry {
+    const MAGIC: &'static [u8; 8] = b"YAGBSTAT";
+    const FORMAT_VERSION: u16 = 1;
+
+    pub fn new() -> Self {
+        Self {
+            entries: vec![SnapshotEntry::emulator()],
+            content_id: 0,
+        }
+    }
+
+    pub fn register<C: Component + Snapshot>(&mut self, key: &'static str) {
+        if self.entries.iter().any(|entry| entry.key == key) {
+            panic!("Snapshot section `{key}` registered twice");
+        }
+        self.entries.push(SnapshotEntry::component::<C>(key));
+    }
+
+    pub fn content_id(&self) -> u64 {
+        self.content_id
+    }
+
+    pub fn set_content_id(&mut self, content_id: u64) {
+        self.content_id = content_id;
+    }
+
+    pub fn save(&self, emulator: &Emulator) -> Vec<u8> {
+        let mut writer = SnapshotWriter::new();
+        for byte in Self::MAGIC {
+            writer.write_u8(*byte);
+        }
+        writer.write_u16(Self::FORMAT_VERSION);
+        writer.write_u64(self.content_id);
+        writer.write_u32(self.entries.len() as u32);
+
+        for entry in &self.entries {
+            let mut section = SnapshotWriter::new();
+            (entry.save)(emulator, &mut section);
+            writer.write_bytes(entry.key.as_bytes());
+            writer.write_u16(entry.version);
+            writer.write_bytes(&section.into_bytes());
+        }
+
+        writer.into_bytes()
+    }
+
+    /// Restores every registered section from `data`.
+    /// The blob is fully validated before any component is touched.
+    pub fn load(&self, emulator: &mut Emulator, data: &[u8]) -> Result<(), SnapshotError> {
+        let sections = self.validate(data)?;
+        for (entry, payload) in self.entries.iter().zip(sections) {
+            let mut reader = SnapshotReader::new(payload);
+            (entry.load)(emulator, &mut reader)?;
+            if !reader.is_empty() {
+                return Err(SnapshotError::InvalidData(format!(
+                    "trailing bytes in section `{}`",
+                    entry.key
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the header and returns the payload of every registered section, in registration order.
+    fn validate<'a>(&self, data: &'a [u8]) -> Result<Vec<&'a [u8]>, SnapshotError> {
+        let mut reader = SnapshotReader::new(data);
+
+        for byte in Self::MAGIC {
+            if reader.read_u8().map_err(|_| SnapshotError::InvalidMagic)? != *byte {
+                return Err(SnapshotError::InvalidMagic);
+            }
+        }
+
+        let version = reader.read_u16()?;
+        if version != Self::FORMAT_VERSION {
+            return Err(SnapshotError::IncompatibleVersion {
+                expected: Self::FORMAT_VERSION,
+                found: version,
+            });
+        }
+
+        let content_id = reader.read_u64()?;
+        if content_id != self.content_id {
+            return Err(SnapshotError::ContentMismatch {
+                expected: self.content_id,
+                found: content_id,
+            });
+        }
+
+        // The count comes from the blob, sections are collected as they are read so a
+        // corrupt count runs into the end of the data instead of a huge allocation.
+        let section_count = reader.read_u32()?;
+        let mut sections = Vec::new();
+        for _ in 0..section_count {
+            let key = reader.read_bytes()?;
+            let version = reader.read_u16()?;
+            let payload = reader.read_bytes()?;
+            sections.push((key, version, payload));
+        }
+
+        self.entries
+            .iter()
+            .map(|entry| {
+                let (_, version, payload) = sections
+                    .iter()
+                    .find(|(key, _, _)| *key == entry.key.as_bytes())
+                    .ok_or_else(|| SnapshotError::MissingSection(entry.key.to_string()))?;
+                if *version != entry.version {
+                    return Err(SnapshotError::IncompatibleSectionVersion {
+                        section: entry.key.to_string(),
+                        expected: entry.version,
+                        found: *version,
+                    });
+                }
+                Ok(*payload)
+            })
+            .collect()
+    }
+}
+
+impl Default for SnapshotRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestComponent {
+        value: u16,
+        flag: bool,
+    }
+
+    impl Component for TestComponent {}
+
+    impl Snapshot for TestComponent {
+        fn save(&self, writer: &mut SnapshotWriter) {
+            writer.write_u16(self.value);
+            writer.write_bool(self.flag);
+        }
+
+        fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+            self.value = reader.read_u16()?;
+            self.flag = reader.read_bool()?;
+            Ok(())
+        }
+    }
+
+    fn emulator() -> Emulator {
+        let mut emulator = Emulator::new();
+        emulator
+            .with_component(TestComponent {
+                value: 0x1234,
+                flag: true,
+            })
+            .with_snapshot::<TestComponent>("test");
+        emulator.set_content_id(42);
+        emulator
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut emulator = emulator();
+        let state = emulator.save_state();
+
+        let component = emulator.get_component_mut::<TestComponent>().unwrap();
+        component.value = 0;
+        component.flag = false;
+
+        emulator.load_state(&state).expect("Failed to load state");
+        let component = emulator.get_component::<TestComponent>().unwrap();
+        assert_eq!(component.value, 0x1234);
+        assert!(component.flag);
+    }
+
+    #[test]
+    fn test_rejects_other_content() {
+        let state = emulator().save_state();
+        let mut other = emulator();
+        other.set_content_id(7);
+        assert_eq!(
+            other.load_state(&state),
+            Err(SnapshotError::ContentMismatch {
+                expected: 7,
+                found: 42
+            })
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_blobs() {
+        let mut emulator = emulator();
+        assert_eq!(
+            emulator.load_state(b"not a state"),
+            Err(SnapshotError::InvalidMagic)
+        );
+
+        let mut state = emulator.save_state();
+        state[8] = 0xFF;
+        assert!(matches!(
+            emulator.load_state(&state),
+            Err(SnapshotError::IncompatibleVersion { .. })
+        ));
+
+        let state = emulator.save_state();
+        assert_eq!(
+            emulator.load_state(&state[..state.len() - 1]),
+            Err(SnapshotError::UnexpectedEof)
+        );
+    }
+
+    #[test]
+    fn test_rejects_oversized_section_counts() {
+        let mut emulator = emulator();
+        let mut state = emulator.save_state();
+        // Magic, format version and content id come before the section count.
+        state[18..22].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(
+            emulator.load_state(&state),
+            Err(SnapshotError::UnexpectedEof)
+        );
+    }
+
+    #[test]
+    fn test_failed_load_rolls_back() {
+        let mut emulator = emulator();
+        let mut state = emulator.save_state();
+        // The test section payload is last, corrupt its bool.
+        let last = state.len() - 1;
+        state[last] = 2;
+
+        emulator.get_component_mut::<TestComponent>().unwrap().value = 7;
+        assert!(matches!(
+            emulator.load_state(&state),
+            Err(SnapshotError::InvalidData(_))
+        ));
+        assert_eq!(emulator.get_component::<TestComponent>().unwrap().value, 7);
+    }
+}
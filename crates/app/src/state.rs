@@ -11,6 +11,13 @@ pub enum EmulatorState {
     Error,
 }
 
+impl EmulatorState {
+    /// Ended and Error are terminal, runners exit once reached.
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Ended | Self::Error)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct StateTransitionEvent {
     pub from: EmulatorState,
@@ -0,0 +1,81 @@
+use crate::Emulator;
+
+/// Points of the machine execution a paused emulator can be advanced to.
+///
+/// The emulator does not know where they are, the plugins owning them report them through
+/// [`Emulator::reached_boundary`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepBoundary {
+    /// The CPU finished an instruction or an interrupt dispatch.
+    Instruction,
+    /// The PPU moved to the next scan line.
+    Scanline,
+    /// The PPU entered VBlank.
+    Frame,
+}
+
+impl StepBoundary {
+    /// Dots after which a step gives up and pauses anyway,
+    /// e.g. a frame advance with the LCD off or an instruction step on a halted CPU.
+    pub(crate) fn max_dots(self) -> u64 {
+        match self {
+            Self::Instruction | Self::Frame => Emulator::DOTS_PER_FRAME,
+            Self::Scanline => 456,
+        }
+    }
+}
+
+/// An advance in progress, see [`Emulator::advance_to`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Stepping {
+    pub boundary: StepBoundary,
+    /// Dot cycle at which the step pauses even if the boundary was not reached.
+    pub deadline: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EmulationControlEvent, EmulatorState};
+
+    fn report_scanlines(emulator: &mut Emulator) {
+        if emulator.get_cycles().is_multiple_of(456) {
+            emulator.reached_boundary(StepBoundary::Scanline);
+        }
+    }
+
+    #[test]
+    fn pauses_at_the_requested_boundary() {
+        let mut emulator = Emulator::new();
+        emulator.on_dot_cycle(report_scanlines);
+        emulator.step();
+        emulator.pause();
+
+        emulator.emit(EmulationControlEvent::StepScanline);
+        emulator.flush_events();
+        assert_eq!(emulator.state(), EmulatorState::Running);
+        while emulator.state() == EmulatorState::Running {
+            emulator.step();
+        }
+        assert_eq!(emulator.get_cycles(), 456);
+        assert!(emulator.is_paused());
+
+        for _ in 0..1000 {
+            emulator.step();
+        }
+        assert_eq!(emulator.get_cycles(), 456);
+    }
+
+    #[test]
+    fn pauses_at_the_deadline_when_the_boundary_never_comes() {
+        let mut emulator = Emulator::new();
+        emulator.step();
+        emulator.pause();
+
+        emulator.advance_to(StepBoundary::Frame);
+        while emulator.state() == EmulatorState::Running {
+            emulator.step();
+        }
+        assert_eq!(emulator.get_cycles(), 1 + Emulator::DOTS_PER_FRAME);
+    }
+}
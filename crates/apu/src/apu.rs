@@ -1,3 +1,10 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
+
 use crate::{
     AudioBuffer,
     channels::{NoiseChannel, PulseChannel, WaveChannel},
@@ -10,7 +17,12 @@ pub struct Apu {
     channel_step_accumulator: u8,
     sampler_accumulator: u64,
     sample_rate_hz: u32,
+    /// Sampler accumulator value at which a sample is emitted, `None` while uncapped.
+    /// Scaled with the emulation speed so the output stream is fed at its own rate.
+    sample_threshold: Option<u64>,
     tcycles_per_sample: u32,
+    /// Set while uncapped, the output stream drops what is buffered and plays silence.
+    muted: Arc<AtomicBool>,
     pub ch1: PulseChannel,
     pub ch2: PulseChannel,
     pub ch3: WaveChannel,
@@ -32,7 +44,9 @@ impl Apu {
             channel_step_accumulator: 0,
             sampler_accumulator: 0,
             sample_rate_hz,
+            sample_threshold: Self::sample_threshold(yagber_app::EmulationSpeed::Normal),
             tcycles_per_sample,
+            muted: Arc::new(AtomicBool::new(false)),
             ch1: PulseChannel::new(yagber_memory::AudioChannel::Ch1),
             ch2: PulseChannel::new(yagber_memory::AudioChannel::Ch2),
             ch3: WaveChannel::new(),
@@ -44,11 +58,68 @@ impl Apu {
         }
     }
 
+    /// Emulated time runs `speed` times faster than the output stream consumes samples,
+    /// so samples are taken that much further apart, which shifts the pitch instead of
+    /// overflowing or starving the buffers.
+    /// Uncapped speed has no rate to resample to, audio is muted instead, see [`Apu::mute_flag`].
+    fn sample_threshold(speed: yagber_app::EmulationSpeed) -> Option<u64> {
+        speed
+            .quarters()
+            .map(|quarters| yagber_app::Emulator::TARGET_DOT_FREQ_HZ as u64 * quarters as u64 / 4)
+    }
+
+    pub fn set_speed(&mut self, speed: yagber_app::EmulationSpeed) {
+        self.sample_threshold = Self::sample_threshold(speed);
+        self.muted
+            .store(self.sample_threshold.is_none(), Ordering::Relaxed);
+    }
+
+    /// Whether audio is muted, shared with the output stream which reads it on its own thread.
+    /// Nothing is sampled while muted and the stream discards samples buffered before.
+    pub fn mute_flag(&self) -> Arc<AtomicBool> {
+        self.muted.clone()
+    }
+
+    pub(crate) fn on_speed_changed(
+        emulator: &mut yagber_app::Emulator,
+        event: &yagber_app::SpeedChangedEvent,
+    ) {
+        if let Some(apu) = emulator.get_component_mut::<Self>() {
+            apu.set_speed(event.speed);
+        }
+    }
+
+    /// Soft resets power the APU off and silence the channels until the boot ROM turns it back on,
+    /// the output side is kept.
+    pub(crate) fn on_reset(emulator: &mut yagber_app::Emulator, event: &yagber_app::ResetEvent) {
+        if *event == yagber_app::ResetEvent::Finished(yagber_app::ResetKind::Soft) {
+            let (apu, bus) = emulator
+                .get_components_mut2::<Self, yagber_memory::Bus>()
+                .expect("Apu and/or Bus components not found");
+            apu.reset();
+            bus.io_registers
+                .write_unhooked(yagber_memory::IOType::AUDENA.address(), 0);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.channel_step_accumulator = 0;
+        self.ch1 = PulseChannel::new(yagber_memory::AudioChannel::Ch1);
+        self.ch2 = PulseChannel::new(yagber_memory::AudioChannel::Ch2);
+        self.ch3 = WaveChannel::new();
+        self.ch4 = NoiseChannel::new();
+        self.high_pass_filter = HighPassFilter::new(self.sample_rate_hz);
+        self.sweep = Sweep::new();
+    }
+
     pub(crate) fn on_tcycle(emulator: &mut yagber_app::Emulator) {
         let (apu, bus) = emulator
             .get_components_mut2::<Self, yagber_memory::Bus>()
             .expect("Apu and/or Bus components not found");
 
+        if bus.stopped() {
+            return;
+        }
         apu.tick(bus);
     }
 
@@ -69,13 +140,15 @@ impl Apu {
             self.channel_tick(bus, yagber_memory::AudioChannel::Ch4);
         }
 
+        let Some(threshold) = self.sample_threshold else {
+            return;
+        };
         // Sample at an accurate fractional rate using a phase accumulator.
         // Add sample_rate every T-cycle; emit a sample whenever the accumulator
-        // reaches the system dot frequency.
+        // reaches the system dot frequency, scaled by the emulation speed.
         self.sampler_accumulator = self
             .sampler_accumulator
             .saturating_add(self.sample_rate_hz as u64);
-        let threshold = yagber_app::Emulator::TARGET_DOT_FREQ_HZ as u64;
         while self.sampler_accumulator >= threshold {
             self.sampler_accumulator -= threshold;
 
@@ -264,8 +337,7 @@ impl Apu {
             }
         }
 
-        bus.io_registers
-            .write(yagber_memory::IOType::AUDENA.address(), new_audena);
+        bus.write(yagber_memory::IOType::AUDENA.address(), new_audena);
     }
 
     pub(crate) fn tick_envelope(&mut self, bus: &mut yagber_memory::Bus) {
@@ -299,6 +371,31 @@ impl Default for Apu {
     }
 }
 
+/// The audio buffers hold host-side output and are not part of the machine state.
+impl Snapshot for Apu {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_u8(self.channel_step_accumulator);
+        writer.write_u64(self.sampler_accumulator);
+        writer.write(&self.ch1);
+        writer.write(&self.ch2);
+        writer.write(&self.ch3);
+        writer.write(&self.ch4);
+        writer.write(&self.high_pass_filter);
+        writer.write(&self.sweep);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.channel_step_accumulator = reader.read_u8()? & 0x03;
+        self.sampler_accumulator = reader.read_u64()?;
+        reader.read(&mut self.ch1)?;
+        reader.read(&mut self.ch2)?;
+        reader.read(&mut self.ch3)?;
+        reader.read(&mut self.ch4)?;
+        reader.read(&mut self.high_pass_filter)?;
+        reader.read(&mut self.sweep)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use ringbuf::traits::Consumer;
@@ -308,8 +405,7 @@ mod tests {
     #[test]
     fn test_dac_transform() {
         let mut bus = yagber_memory::Bus::new();
-        bus.io_registers
-            .write(yagber_memory::IOType::AUDENA.address(), 0xFF);
+        bus.write(yagber_memory::IOType::AUDENA.address(), 0xFF);
 
         let mut apu = Apu::new();
         apu.set_sample_rate(1_048_576);
@@ -323,4 +419,56 @@ mod tests {
         assert_eq!(left_consumer.pop_iter().count(), 70224 / 4);
         assert_eq!(right_consumer.pop_iter().count(), 70224 / 4);
     }
+
+    #[test]
+    fn sample_count_follows_emulation_speed() {
+        let mut bus = yagber_memory::Bus::new();
+        bus.write(yagber_memory::IOType::AUDENA.address(), 0xFF);
+
+        let mut apu = Apu::new();
+        apu.set_sample_rate(1_048_576);
+        apu.set_speed(yagber_app::EmulationSpeed::Double);
+        for _ in 0..70224 {
+            apu.tick(&mut bus);
+        }
+        apu.set_speed(yagber_app::EmulationSpeed::Uncapped);
+        for _ in 0..70224 {
+            apu.tick(&mut bus);
+        }
+
+        let mut left_consumer = apu.left_buffer.take_consumer().unwrap();
+        assert_eq!(left_consumer.pop_iter().count(), 70224 / 8);
+    }
+
+    #[test]
+    fn test_soft_reset_silences_the_channels() {
+        use yagber_memory::{Audena, AudioChannel, Bus, IOType};
+
+        let mut emulator = yagber_app::Emulator::new()
+            .with_plugin(yagber_memory::MemoryPlugin::default())
+            .with_plugin(yagber_timer::TimerPlugin)
+            .with_plugin(crate::ApuPlugin)
+            .build()
+            .unwrap();
+        Bus::write_hooked(&mut emulator, IOType::AUDENA.address(), 0x80);
+        Bus::write_hooked(&mut emulator, IOType::AUD1ENV.address(), 0xF0);
+        Bus::write_hooked(&mut emulator, IOType::AUD1HIGH.address(), 0x80);
+        let audena = Audena::from_bus(emulator.get_component::<Bus>().unwrap());
+        assert!(audena.ch_enabled(AudioChannel::Ch1));
+
+        emulator.reset(yagber_app::ResetKind::Soft);
+        let audena = Audena::from_bus(emulator.get_component::<Bus>().unwrap());
+        assert!(!audena.apu_enabled());
+        assert!(!audena.ch_enabled(AudioChannel::Ch1));
+    }
+
+    #[test]
+    fn uncapped_speed_mutes_audio() {
+        let mut apu = Apu::new();
+        let muted = apu.mute_flag();
+        apu.set_speed(yagber_app::EmulationSpeed::Uncapped);
+        assert!(muted.load(Ordering::Relaxed));
+        apu.set_speed(yagber_app::EmulationSpeed::Octuple);
+        assert!(!muted.load(Ordering::Relaxed));
+    }
 }
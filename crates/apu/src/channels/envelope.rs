@@ -1,3 +1,5 @@
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
+
 #[derive(Debug, Default)]
 pub struct Envelope {
     timer: u8,
@@ -28,3 +30,14 @@ impl Envelope {
         self.timer = value;
     }
 }
+
+impl Snapshot for Envelope {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_u8(self.timer);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.timer = reader.read_u8()?;
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{Apu, channels::Envelope};
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 
 #[derive(Debug)]
 pub struct NoiseChannel {
@@ -108,3 +109,24 @@ impl Default for NoiseChannel {
         Self::new()
     }
 }
+
+impl Snapshot for NoiseChannel {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_u16(self.lfsr);
+        writer.write_u16(self.clock);
+        writer.write_u8(self.length_counter);
+        writer.write_u8(self.volume);
+        writer.write(&self.envelope);
+        writer.write_u8(self.sample);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.lfsr = reader.read_u16()?;
+        self.clock = reader.read_u16()?;
+        self.length_counter = reader.read_u8()?;
+        self.volume = reader.read_u8()?;
+        reader.read(&mut self.envelope)?;
+        self.sample = reader.read_u8()?;
+        Ok(())
+    }
+}
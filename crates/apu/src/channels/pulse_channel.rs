@@ -1,3 +1,4 @@
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 use yagber_memory::AudioChannel;
 
 use crate::{Apu, channels::Envelope};
@@ -145,3 +146,24 @@ impl PulseChannel {
         apu.ch2.set_volume(audenv.initial_volume());
     }
 }
+
+impl Snapshot for PulseChannel {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_u16(self.clock);
+        writer.write_u8(self.duty_step_counter);
+        writer.write_u8(self.length_counter);
+        writer.write_u8(self.volume);
+        writer.write(&self.envelope);
+        writer.write_u8(self.sample);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.clock = reader.read_u16()?;
+        self.duty_step_counter = reader.read_u8()?;
+        self.length_counter = reader.read_u8()?;
+        self.volume = reader.read_u8()?;
+        reader.read(&mut self.envelope)?;
+        self.sample = reader.read_u8()?;
+        Ok(())
+    }
+}
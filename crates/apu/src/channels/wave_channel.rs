@@ -1,4 +1,5 @@
 use crate::Apu;
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 
 #[derive(Debug)]
 pub struct WaveChannel {
@@ -78,3 +79,22 @@ impl Default for WaveChannel {
         Self::new()
     }
 }
+
+impl Snapshot for WaveChannel {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_u8(self.wave_index);
+        writer.write_u16(self.clock);
+        writer.write_u8(self.length_counter);
+        writer.write_u8(self.volume.as_u8());
+        writer.write_u8(self.sample);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.wave_index = reader.read_u8()?;
+        self.clock = reader.read_u16()?;
+        self.length_counter = reader.read_u8()?;
+        self.volume = yagber_memory::Aud3Volume::from_u8(reader.read_u8()? & 0b11);
+        self.sample = reader.read_u8()?;
+        Ok(())
+    }
+}
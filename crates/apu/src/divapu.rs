@@ -1,30 +1,23 @@
-use yagber_app::EdgeDetector;
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 use yagber_memory::Bus;
 
 use crate::Apu;
 
+/// Frame sequencer, stepped at 512Hz by the timer when DIV bit 4 falls.
 pub struct DivApu {
     ticks: u8,
-    edge_detector: EdgeDetector,
 }
 
 impl DivApu {
     pub fn new() -> Self {
-        Self {
-            ticks: 0,
-            edge_detector: EdgeDetector::new(yagber_app::EdgeMode::Falling),
-        }
+        Self { ticks: 0 }
     }
 
-    pub(crate) fn on_mcycle(emulator: &mut yagber_app::Emulator) {
-        let (div_apu, bus) = emulator
-            .get_components_mut2::<DivApu, Bus>()
-            .expect("DivApu and/or Bus component missing");
-        if !div_apu.should_tick(bus) {
-            return;
-        }
-
-        let ticks = div_apu.tick();
+    pub(crate) fn on_scheduled(emulator: &mut yagber_app::Emulator) {
+        let ticks = emulator
+            .get_component_mut::<DivApu>()
+            .expect("DivApu component missing")
+            .tick();
 
         let (apu, bus) = emulator
             .get_components_mut2::<Apu, Bus>()
@@ -34,12 +27,23 @@ impl DivApu {
             apu.tick_envelope(bus);
         }
 
-        if ticks % 2 == 0 {
+        if ticks.is_multiple_of(2) {
             apu.tick_sound_length(bus);
         }
 
-        if ticks % 4 == 0 {
+        if ticks.is_multiple_of(4) {
             apu.sweep.tick(bus);
+            // The sweep writes the new period to NR13 and NR14
+            Bus::run_hooks(emulator);
+        }
+    }
+
+    /// Soft resets start the frame sequencer over.
+    pub(crate) fn on_reset(emulator: &mut yagber_app::Emulator, event: &yagber_app::ResetEvent) {
+        if *event == yagber_app::ResetEvent::Finished(yagber_app::ResetKind::Soft) {
+            *emulator
+                .get_component_mut::<DivApu>()
+                .expect("DivApu component missing") = DivApu::new();
         }
     }
 
@@ -50,25 +54,19 @@ impl DivApu {
         }
         self.ticks
     }
+}
 
-    fn should_tick(&mut self, bus: &Bus) -> bool {
-        let speed_mode = yagber_memory::Spd::from_bus(bus);
-        let div_mask = self.div_mask(speed_mode.speed_mode());
-        let div = bus.io_registers.read(yagber_memory::IOType::DIV.address());
-        self.edge_detector.tick((div & div_mask) != 0)
-    }
+impl yagber_app::Component for DivApu {}
 
-    /// To make sure div-apu works on 512Hz.
-    fn div_bit(&self, speed_mode: yagber_app::SpeedMode) -> u8 {
-        match speed_mode {
-            yagber_app::SpeedMode::Single => 4,
-            yagber_app::SpeedMode::Double => 5,
-        }
+impl Snapshot for DivApu {
+    const VERSION: u16 = 2;
+
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_u8(self.ticks);
     }
 
-    fn div_mask(&self, speed_mode: yagber_app::SpeedMode) -> u8 {
-        1 << self.div_bit(speed_mode)
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.ticks = reader.read_u8()?;
+        Ok(())
     }
 }
-
-impl yagber_app::Component for DivApu {}
@@ -1,3 +1,5 @@
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
+
 #[derive(Debug, Default)]
 pub struct HighPassFilter {
     a: f32,
@@ -42,3 +44,21 @@ impl HighPassFilter {
         (out_l, out_r)
     }
 }
+
+/// The coefficient depends on the host sample rate and is not stored.
+impl Snapshot for HighPassFilter {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_f32(self.prev_in_l);
+        writer.write_f32(self.prev_out_l);
+        writer.write_f32(self.prev_in_r);
+        writer.write_f32(self.prev_out_r);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.prev_in_l = reader.read_f32()?;
+        self.prev_out_l = reader.read_f32()?;
+        self.prev_in_r = reader.read_f32()?;
+        self.prev_out_r = reader.read_f32()?;
+        Ok(())
+    }
+}
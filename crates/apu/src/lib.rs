@@ -11,26 +11,46 @@ pub use audio_buffer::{AudioBuffer, ConsumerCache, ProducerCache};
 pub struct ApuPlugin;
 
 impl yagber_app::Plugin for ApuPlugin {
-    fn init(self, emulator: &mut yagber_app::Emulator) {
+    fn init(self, emulator: &mut yagber_app::Emulator) -> Result<(), yagber_app::PluginError> {
         let div_apu = divapu::DivApu::new();
         let apu = apu::Apu::new();
 
+        // The timer owns DIV and schedules DIV-APU steps
+        let div_apu_task = emulator.add_scheduled_task("div_apu", divapu::DivApu::on_scheduled);
+        emulator
+            .get_component_mut::<yagber_timer::Timer>()
+            .expect("Timer component missing")
+            .set_div_apu_task(div_apu_task);
+
         emulator
             .with_component(div_apu)
-            .on_mcycle(divapu::DivApu::on_mcycle)
+            .with_snapshot::<divapu::DivApu>("div_apu")
             .with_component(apu)
-            .on_tcycle(apu::Apu::on_tcycle);
+            .with_snapshot::<apu::Apu>("apu")
+            .on_tcycle(apu::Apu::on_tcycle)
+            .on_event(apu::Apu::on_speed_changed)
+            .on_event(apu::Apu::on_reset)
+            .on_event(divapu::DivApu::on_reset);
 
         use channels::{NoiseChannel, PulseChannel, WaveChannel};
-        let sweep_aud1sweep_hook = emulator.attach_component(sweep::Sweep::on_aud_1_sweep_write);
-        let sweep_aud1high_hook = emulator.attach_components2(sweep::Sweep::on_aud_1_high_write);
-        let ch1_aud1high_hook = emulator.attach_components2(PulseChannel::on_aud_1_high_write);
-        let ch1_aud1env_hook = emulator.attach_component(PulseChannel::on_aud_1_env_write);
-        let ch2_aud2high_hook = emulator.attach_components2(PulseChannel::on_aud_2_high_write);
-        let ch2_aud2env_hook = emulator.attach_component(PulseChannel::on_aud_2_env_write);
-        let ch3_aud3high_hook = emulator.attach_components2(WaveChannel::on_aud_3_high_write);
-        let ch4_aud4go_hook = emulator.attach_components2(NoiseChannel::on_aud_4_go_write);
-        let ch4_aud4env_hook = emulator.attach_component(NoiseChannel::on_aud_4_env_write);
+        let sweep_aud1sweep_hook =
+            yagber_app::Emulator::attach_component(sweep::Sweep::on_aud_1_sweep_write);
+        let sweep_aud1high_hook =
+            yagber_app::Emulator::attach_components2(sweep::Sweep::on_aud_1_high_write);
+        let ch1_aud1high_hook =
+            yagber_app::Emulator::attach_components2(PulseChannel::on_aud_1_high_write);
+        let ch1_aud1env_hook =
+            yagber_app::Emulator::attach_component(PulseChannel::on_aud_1_env_write);
+        let ch2_aud2high_hook =
+            yagber_app::Emulator::attach_components2(PulseChannel::on_aud_2_high_write);
+        let ch2_aud2env_hook =
+            yagber_app::Emulator::attach_component(PulseChannel::on_aud_2_env_write);
+        let ch3_aud3high_hook =
+            yagber_app::Emulator::attach_components2(WaveChannel::on_aud_3_high_write);
+        let ch4_aud4go_hook =
+            yagber_app::Emulator::attach_components2(NoiseChannel::on_aud_4_go_write);
+        let ch4_aud4env_hook =
+            yagber_app::Emulator::attach_component(NoiseChannel::on_aud_4_env_write);
 
         emulator
             .get_component_mut::<yagber_memory::Bus>()
@@ -45,5 +65,14 @@ impl yagber_app::Plugin for ApuPlugin {
             .with_hook(yagber_memory::IOType::AUD3HIGH, ch3_aud3high_hook)
             .with_hook(yagber_memory::IOType::AUD4GO, ch4_aud4go_hook)
             .with_hook(yagber_memory::IOType::AUD4ENV, ch4_aud4env_hook);
+        Ok(())
+    }
+
+    fn dependencies(&self) -> yagber_app::PluginDependencies {
+        yagber_app::PluginDependencies::new()
+            .provides::<divapu::DivApu>()
+            .provides::<Apu>()
+            .requires::<yagber_memory::Bus>()
+            .requires::<yagber_timer::Timer>()
     }
 }
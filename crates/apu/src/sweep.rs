@@ -1,4 +1,5 @@
 use crate::{Apu, channels::PulseChannel};
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 
 #[derive(Debug, Default)]
 pub struct Sweep {
@@ -103,3 +104,18 @@ impl Sweep {
         }
     }
 }
+
+impl Snapshot for Sweep {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_bool(self.enabled);
+        writer.write_u8(self.sweep_timer);
+        writer.write_u16(self.shadow_register);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.enabled = reader.read_bool()?;
+        self.sweep_timer = reader.read_u8()?;
+        self.shadow_register = reader.read_u16()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,30 @@
+/// Error for a `.cdl` file that cannot be used.
+#[derive(Debug)]
+pub enum CdlError {
+    Io(std::io::Error),
+    /// The file was logged for a cartridge of another size.
+    Size {
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl std::fmt::Display for CdlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CdlError::Io(error) => write!(f, "{error}"),
+            CdlError::Size { expected, found } => write!(
+                f,
+                "the log is {found} bytes long, {expected} bytes are expected for this cartridge"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CdlError {}
+
+impl From<std::io::Error> for CdlError {
+    fn from(error: std::io::Error) -> Self {
+        CdlError::Io(error)
+    }
+}
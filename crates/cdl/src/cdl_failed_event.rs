@@ -0,0 +1,21 @@
+use std::path::PathBuf;
+
+/// Saving the code/data log failed.
+#[derive(Debug)]
+pub struct CdlFailedEvent {
+    pub path: PathBuf,
+    pub error: std::io::Error,
+}
+
+impl std::fmt::Display for CdlFailedEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "cannot write the code/data log {}: {}",
+            self.path.display(),
+            self.error
+        )
+    }
+}
+
+impl yagber_app::Event for CdlFailedEvent {}
@@ -0,0 +1,78 @@
+/// How a byte of the cartridge was used, bits of a `.cdl` file.
+///
+/// Bits follow the FCEUX PRG layout: `CODE` and `DATA`, then bits 2-3 for the 8 KiB
+/// window of the address space the byte was reached through, see [`CdlFlags::window`].
+/// Bytes copied by DMA take the bit FCEUX gives to the samples its DMC channel fetches
+/// by DMA. Operands take bit 7, unused by FCEUX, and are tagged `CODE | OPERAND`, so
+/// tools only knowing `CODE` still see whole instructions.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CdlFlags(u8);
+
+impl CdlFlags {
+    /// Executed, as an opcode or an operand.
+    pub const CODE: Self = Self(0x01);
+    /// Read or, in cartridge RAM, written by an instruction.
+    pub const DATA: Self = Self(0x02);
+    /// Bits 2-3, the windows a byte was reached through.
+    pub const WINDOWS: Self = Self(0x0C);
+    /// Read by an OAM DMA or HDMA transfer.
+    pub const DMA: Self = Self(0x40);
+    /// Executed as an operand of the instruction before it.
+    pub const OPERAND: Self = Self(0x80);
+
+    pub fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// The 8 KiB window of `address` in bits 2-3, as FCEUX computes it: `0000`, `2000`,
+    /// `4000` and `6000` for ROM bytes.
+    pub fn window(address: u16) -> Self {
+        Self((address >> 11) as u8 & Self::WINDOWS.0)
+    }
+
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Executed as the first byte of an instruction.
+    pub fn is_opcode(&self) -> bool {
+        self.contains(Self::CODE) && !self.contains(Self::OPERAND)
+    }
+}
+
+impl std::ops::BitOr for CdlFlags {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl std::ops::BitOrAssign for CdlFlags {
+    fn bitor_assign(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_follow_fceux() {
+        assert_eq!(CdlFlags::window(0x0150).bits(), 0x00);
+        assert_eq!(CdlFlags::window(0x2000).bits(), 0x04);
+        assert_eq!(CdlFlags::window(0x5FFF).bits(), 0x08);
+        assert_eq!(CdlFlags::window(0x7FFF).bits(), 0x0C);
+        assert!((CdlFlags::CODE | CdlFlags::window(0x4000)).is_opcode());
+        assert!(!(CdlFlags::CODE | CdlFlags::OPERAND).is_opcode());
+    }
+}
@@ -0,0 +1,134 @@
+use std::path::Path;
+
+use yagber_disasm::BankedAddress;
+
+use crate::{CdlError, CdlFlags};
+
+/// Flags of every byte of the cartridge ROM and RAM.
+///
+/// Saved like FCEUX `.cdl` files, one flag byte per byte of the ROM file followed by one
+/// per byte of the cartridge RAM. Loading a log merges it into this one, so a log
+/// accumulates across sessions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeDataLog {
+    rom: Vec<CdlFlags>,
+    sram: Vec<CdlFlags>,
+}
+
+impl CodeDataLog {
+    pub fn new(rom_size: usize, sram_size: usize) -> Self {
+        Self {
+            rom: vec![CdlFlags::default(); rom_size],
+            sram: vec![CdlFlags::default(); sram_size],
+        }
+    }
+
+    /// An empty log sized for the cartridge, cartridges that cannot be emulated have no RAM.
+    pub fn for_rom(rom: &[u8]) -> Self {
+        let sram_size = yagber_memory::Cartridge::inspect(rom)
+            .map(|(_, mbc_info)| mbc_info.ram_size)
+            .unwrap_or_default();
+        Self::new(rom.len(), sram_size)
+    }
+
+    pub fn rom(&self) -> &[CdlFlags] {
+        &self.rom
+    }
+
+    pub fn sram(&self) -> &[CdlFlags] {
+        &self.sram
+    }
+
+    /// Offsets past the end of the ROM are ignored.
+    pub fn mark_rom(&mut self, offset: usize, flags: CdlFlags) {
+        if let Some(byte) = self.rom.get_mut(offset) {
+            *byte |= flags;
+        }
+    }
+
+    /// Offsets past the end of the cartridge RAM are ignored.
+    pub fn mark_sram(&mut self, offset: usize, flags: CdlFlags) {
+        if let Some(byte) = self.sram.get_mut(offset) {
+            *byte |= flags;
+        }
+    }
+
+    /// Every ROM byte executed as an opcode, as disassembler entry points.
+    pub fn code_entries(&self) -> impl Iterator<Item = BankedAddress> {
+        self.rom
+            .iter()
+            .enumerate()
+            .filter(|(_, flags)| flags.is_opcode())
+            .map(|(offset, _)| BankedAddress::from_rom_offset(offset))
+    }
+
+    /// Adds the flags of a saved log, which must have the same size.
+    pub fn merge_bytes(&mut self, bytes: &[u8]) -> Result<(), CdlError> {
+        let expected = self.rom.len() + self.sram.len();
+        if bytes.len() != expected {
+            return Err(CdlError::Size {
+                expected,
+                found: bytes.len(),
+            });
+        }
+        for (flags, byte) in self.rom.iter_mut().chain(&mut self.sram).zip(bytes) {
+            *flags |= CdlFlags::from_bits(*byte);
+        }
+        Ok(())
+    }
+
+    /// Merges the log saved at `path`, if there is one.
+    pub fn merge_file(&mut self, path: impl AsRef<Path>) -> Result<(), CdlError> {
+        match std::fs::read(path) {
+            Ok(bytes) => self.merge_bytes(&bytes),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.rom
+            .iter()
+            .chain(&self.sram)
+            .map(CdlFlags::bits)
+            .collect()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_saved_logs() {
+        let mut log = CodeDataLog::new(0x8000, 2);
+        log.mark_rom(0x0150, CdlFlags::CODE);
+        log.mark_rom(0x4001, CdlFlags::CODE | CdlFlags::OPERAND);
+        log.mark_sram(1, CdlFlags::DATA);
+        log.mark_rom(0x9000, CdlFlags::DATA);
+        let bytes = log.to_bytes();
+        assert_eq!(bytes.len(), 0x8002);
+
+        let mut other = CodeDataLog::new(0x8000, 2);
+        other.mark_rom(0x0150, CdlFlags::DATA);
+        other.merge_bytes(&bytes).unwrap();
+        assert_eq!(other.rom()[0x0150], CdlFlags::CODE | CdlFlags::DATA);
+        assert_eq!(other.sram()[1], CdlFlags::DATA);
+        assert_eq!(
+            other.code_entries().collect::<Vec<_>>(),
+            [BankedAddress::new(0, 0x0150)]
+        );
+
+        assert!(matches!(
+            CodeDataLog::new(0x4000, 0).merge_bytes(&bytes),
+            Err(CdlError::Size {
+                expected: 0x4000,
+                found: 0x8002
+            })
+        ));
+    }
+}
@@ -0,0 +1,140 @@
+use std::path::PathBuf;
+
+use yagber_app::{Emulator, EmulatorState, StateTransitionEvent};
+use yagber_cpu::{AccessKind, Cpu};
+use yagber_disasm::{BankedAddress, DecodedInstruction};
+use yagber_memory::Bus;
+
+use crate::{CdlFailedEvent, CdlFlags, CodeDataLog};
+
+/// Tags the cartridge bytes the CPU executes and reads, and the ones DMA transfers copy.
+///
+/// Bytes are found through the memory bank controller, so a byte keeps its tag
+/// whichever bank it was reached through. An instruction is tagged once it ran, not when
+/// an interrupt dispatch preempted it. ROM bytes are also tagged with the window they were
+/// reached through. The log is saved to its file, when it has one, when the emulator
+/// exits, a failure emits a [`CdlFailedEvent`].
+#[derive(Debug)]
+pub struct CodeDataLogger {
+    log: CodeDataLog,
+    path: Option<PathBuf>,
+    /// Bytes of the instruction about to run, with its address and the stack pointer
+    /// before it.
+    pending: Option<PendingInstruction>,
+}
+
+#[derive(Debug)]
+struct PendingInstruction {
+    pc: u16,
+    sp: u16,
+    bytes: Vec<(Byte, CdlFlags)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Byte {
+    Rom { offset: usize, window: CdlFlags },
+    Sram(usize),
+}
+
+impl CodeDataLogger {
+    pub(crate) fn new(log: CodeDataLog, path: Option<PathBuf>) -> Self {
+        Self {
+            log,
+            path,
+            pending: None,
+        }
+    }
+
+    pub fn log(&self) -> &CodeDataLog {
+        &self.log
+    }
+
+    fn byte(bus: &Bus, address: u16) -> Option<Byte> {
+        bus.rom_offset(address)
+            .map(|offset| Byte::Rom {
+                offset,
+                window: CdlFlags::window(address),
+            })
+            .or_else(|| bus.sram_offset(address).map(Byte::Sram))
+    }
+
+    fn mark(&mut self, byte: Byte, flags: CdlFlags) {
+        match byte {
+            Byte::Rom { offset, window } => self.log.mark_rom(offset, flags | window),
+            Byte::Sram(offset) => self.log.mark_sram(offset, flags),
+        }
+    }
+
+    pub(crate) fn on_mcycle(emulator: &mut Emulator) {
+        let (logger, cpu, bus) = emulator
+            .get_components_mut3::<Self, Cpu, Bus>()
+            .expect("CodeDataLogger, Cpu and/or Bus component missing");
+
+        if let Some((start, length)) = bus.take_dma_source() {
+            for address in (0..length).map(|offset| start.wrapping_add(offset)) {
+                if let Some(byte) = Self::byte(bus, address) {
+                    logger.mark(byte, CdlFlags::DMA);
+                }
+            }
+        }
+        if let Some(access) = cpu.last_access()
+            && (access.kind == AccessKind::Read || bus.sram_offset(access.address).is_some())
+            && let Some(byte) = Self::byte(bus, access.address)
+        {
+            logger.mark(byte, CdlFlags::DATA);
+        }
+        if cpu.at_instruction_boundary() {
+            logger.instruction_boundary(cpu, bus);
+        }
+    }
+
+    /// Saves the log while the emulator shuts down.
+    pub(crate) fn on_state_transition(emulator: &mut Emulator, event: &StateTransitionEvent) {
+        if event.to != EmulatorState::Ending {
+            return;
+        }
+        let Some(logger) = emulator.get_component::<Self>() else {
+            return;
+        };
+        if let Some(path) = &logger.path
+            && let Err(error) = logger.log.save(path)
+        {
+            let path = path.clone();
+            emulator.emit(CdlFailedEvent { path, error });
+        }
+    }
+
+    /// Tags the instruction that just ran, then reads the bytes of the next one while
+    /// their bank is still mapped.
+    pub(crate) fn instruction_boundary(&mut self, cpu: &Cpu, bus: &Bus) {
+        let (pc, sp) = (cpu.pc(), cpu.sp());
+        if let Some(pending) = self.pending.take() {
+            let pushed = u16::from_le_bytes([bus.read(sp), bus.read(sp.wrapping_add(1))]);
+            let dispatched = sp == pending.sp.wrapping_sub(2)
+                && pushed == pending.pc
+                && matches!(pc, 0x40 | 0x48 | 0x50 | 0x58 | 0x60);
+            if !dispatched {
+                for (byte, flags) in pending.bytes {
+                    self.mark(byte, flags);
+                }
+            }
+        }
+
+        let instruction =
+            DecodedInstruction::decode(BankedAddress::new(bus.bank(pc), pc), |address| {
+                bus.read(address)
+            });
+        let bytes = (0..instruction.bytes().len() as u16)
+            .filter_map(|offset| {
+                let flags = match offset {
+                    0 => CdlFlags::CODE,
+                    _ => CdlFlags::CODE | CdlFlags::OPERAND,
+                };
+                Some((Self::byte(bus, pc.wrapping_add(offset))?, flags))
+            })
+            .collect::<Vec<_>>();
+        self.pending = (!bytes.is_empty()).then_some(PendingInstruction { pc, sp, bytes });
+    }
+}
+
+impl yagber_app::Component for CodeDataLogger {}
@@ -0,0 +1,62 @@
+mod cdl_error;
+mod cdl_failed_event;
+mod cdl_flags;
+mod code_data_log;
+mod code_data_logger;
+
+use std::path::PathBuf;
+
+pub use cdl_error::CdlError;
+pub use cdl_failed_event::CdlFailedEvent;
+pub use cdl_flags::CdlFlags;
+pub use code_data_log::CodeDataLog;
+pub use code_data_logger::CodeDataLogger;
+
+/// Adds a [`CodeDataLogger`], tagging how each cartridge byte is used.
+pub struct CdlPlugin {
+    log: CodeDataLog,
+    path: Option<PathBuf>,
+}
+
+impl CdlPlugin {
+    /// Logs the cartridge made from `rom`, in memory only.
+    pub fn for_rom(rom: &[u8]) -> Self {
+        Self {
+            log: CodeDataLog::for_rom(rom),
+            path: None,
+        }
+    }
+
+    /// Starts from the log saved at `path`, if there is one, and saves it back there
+    /// once the emulator exits.
+    pub fn with_file(mut self, path: impl Into<PathBuf>) -> Result<Self, CdlError> {
+        let path = path.into();
+        self.log.merge_file(&path)?;
+        self.path = Some(path);
+        Ok(self)
+    }
+}
+
+impl yagber_app::Plugin for CdlPlugin {
+    fn init(self, emulator: &mut yagber_app::Emulator) -> Result<(), yagber_app::PluginError> {
+        let mut logger = CodeDataLogger::new(self.log, self.path);
+        if let Some((cpu, bus)) =
+            emulator.get_components_mut2::<yagber_cpu::Cpu, yagber_memory::Bus>()
+        {
+            bus.track_dma_source(true);
+            logger.instruction_boundary(cpu, bus);
+        }
+        emulator
+            .with_component(logger)
+            .on_mcycle(CodeDataLogger::on_mcycle)
+            .on_event(CodeDataLogger::on_state_transition);
+        Ok(())
+    }
+
+    fn dependencies(&self) -> yagber_app::PluginDependencies {
+        yagber_app::PluginDependencies::new()
+            .provides::<CodeDataLogger>()
+            .requires::<yagber_cpu::Cpu>()
+            .requires::<yagber_memory::Bus>()
+    }
+}
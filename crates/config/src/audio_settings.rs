@@ -0,0 +1,18 @@
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AudioSettings {
+    /// Mutes the emulator when `false`.
+    pub enabled: Option<bool>,
+    /// Output volume, from 0.0 to 1.0.
+    pub volume: Option<f32>,
+    /// Audio buffered ahead of the output device, in milliseconds.
+    pub latency_ms: Option<u64>,
+}
+
+impl AudioSettings {
+    pub fn merge(&mut self, other: &Self) {
+        self.enabled = other.enabled.or(self.enabled);
+        self.volume = other.volume.or(self.volume);
+        self.latency_ms = other.latency_ms.or(self.latency_ms);
+    }
+}
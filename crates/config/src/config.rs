@@ -0,0 +1,149 @@
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    AudioSettings, ConfigError, DisplaySettings, GameOverride, InputSettings, SaveSettings,
+    Settings,
+};
+
+/// Contents of the config file.
+///
+/// Global settings come first, `[[games]]` entries override them per game:
+///
+/// ```toml
+/// [display]
+/// scale = 4
+///
+/// [[games]]
+/// title = "TETRIS"
+/// display.scale = 8
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub display: DisplaySettings,
+    pub audio: AudioSettings,
+    pub saves: SaveSettings,
+    pub input: InputSettings,
+    pub games: Vec<GameOverride>,
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/yagber/config.toml`, falling back to `~/.config` when the
+    /// variable is not set.
+    pub fn default_path() -> Option<PathBuf> {
+        let config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(config_home.join("yagber").join("config.toml"))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        std::fs::read_to_string(path)
+            .map_err(ConfigError::Io)?
+            .parse()
+    }
+
+    /// Like [`Config::load`], a missing file gives the default config.
+    pub fn load_if_exists(path: &Path) -> Result<Self, ConfigError> {
+        match Self::load(path) {
+            Err(ConfigError::Io(error)) if error.kind() == std::io::ErrorKind::NotFound => {
+                Ok(Self::default())
+            }
+            result => result,
+        }
+    }
+
+    /// Global settings with every matching game override applied, in file order.
+    pub fn settings_for(&self, title: &str, checksum: u16) -> Settings {
+        let mut settings = Settings {
+            display: self.display.clone(),
+            audio: self.audio.clone(),
+            saves: self.saves.clone(),
+            input: self.input.clone(),
+        };
+        for game in self
+            .games
+            .iter()
+            .filter(|game| game.matches(title, checksum))
+        {
+            settings.merge(&game.settings());
+        }
+        settings
+    }
+}
+
+impl FromStr for Config {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let config: Self = toml::from_str(s).map_err(ConfigError::Parse)?;
+        if let Some(index) = config
+            .games
+            .iter()
+            .position(|game| game.title.is_none() && game.checksum.is_none())
+        {
+            return Err(ConfigError::UnkeyedOverride { index });
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yagber_input::keyboard::KeyCode;
+
+    use super::*;
+
+    const CONFIG: &str = r#"
+        [display]
+        scale = 4
+
+        [input]
+        a = "KeyK"
+
+        [[games]]
+        title = "TETRIS"
+        display.scale = 8
+        audio.volume = 0.5
+
+        [[games]]
+        title = "TETRIS"
+        checksum = 0x1234
+        saves.directory = "tetris"
+    "#;
+
+    #[test]
+    fn game_overrides_apply_on_top_of_global_settings() {
+        let config: Config = CONFIG.parse().unwrap();
+
+        let other = config.settings_for("POKEMON", 0x1234);
+        assert_eq!(other.display.scale, Some(4));
+        assert_eq!(other.input.a, Some(KeyCode::KeyK));
+        assert_eq!(other.audio.volume, None);
+
+        let tetris = config.settings_for("TETRIS", 0x0000);
+        assert_eq!(tetris.display.scale, Some(8));
+        assert_eq!(tetris.audio.volume, Some(0.5));
+        assert_eq!(tetris.saves.directory, None);
+
+        let tetris = config.settings_for("TETRIS", 0x1234);
+        assert_eq!(tetris.saves.directory, Some(PathBuf::from("tetris")));
+    }
+
+    #[test]
+    fn rejects_invalid_configs() {
+        for config in [
+            "[dispaly]\nscale = 4",
+            "[display]\nsize = 4",
+            "[input]\na = \"Keyk\"",
+            "[[games]]\ndisplay.scale = 4",
+            "[[games]]\ntitle = \"TETRIS\"\nscale = 4",
+        ] {
+            assert!(config.parse::<Config>().is_err(), "{config}");
+        }
+    }
+}
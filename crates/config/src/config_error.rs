@@ -0,0 +1,23 @@
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The config file cannot be read.
+    Io(std::io::Error),
+    /// The config file is not valid TOML or holds unknown settings.
+    Parse(toml::de::Error),
+    /// A `[[games]]` entry has neither a title nor a checksum, so it would match every game.
+    UnkeyedOverride { index: usize },
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{error}"),
+            Self::Parse(error) => write!(f, "{}", error.message()),
+            Self::UnkeyedOverride { index } => {
+                write!(f, "games entry {} needs a title or a checksum", index + 1)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
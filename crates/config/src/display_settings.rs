@@ -0,0 +1,12 @@
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DisplaySettings {
+    /// Window scale factor.
+    pub scale: Option<u32>,
+}
+
+impl DisplaySettings {
+    pub fn merge(&mut self, other: &Self) {
+        self.scale = other.scale.or(self.scale);
+    }
+}
@@ -0,0 +1,33 @@
+use crate::{AudioSettings, DisplaySettings, InputSettings, SaveSettings, Settings};
+
+/// Settings applied on top of the global ones for the matching games.
+///
+/// Games are matched by their header title, their header global checksum, or both.
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameOverride {
+    pub title: Option<String>,
+    pub checksum: Option<u16>,
+    pub display: DisplaySettings,
+    pub audio: AudioSettings,
+    pub saves: SaveSettings,
+    pub input: InputSettings,
+}
+
+impl GameOverride {
+    pub fn matches(&self, title: &str, checksum: u16) -> bool {
+        self.title
+            .as_deref()
+            .is_none_or(|expected| expected == title)
+            && self.checksum.is_none_or(|expected| expected == checksum)
+    }
+
+    pub fn settings(&self) -> Settings {
+        Settings {
+            display: self.display.clone(),
+            audio: self.audio.clone(),
+            saves: self.saves.clone(),
+            input: self.input.clone(),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, de::Error};
+use yagber_input::{JoypKey, KeyBindings, keyboard::KeyCode};
+
+/// Keys bound to the joypad buttons, named after [`KeyCode`] variants such as `KeyZ`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InputSettings {
+    #[serde(deserialize_with = "key_code")]
+    pub a: Option<KeyCode>,
+    #[serde(deserialize_with = "key_code")]
+    pub b: Option<KeyCode>,
+    #[serde(deserialize_with = "key_code")]
+    pub select: Option<KeyCode>,
+    #[serde(deserialize_with = "key_code")]
+    pub start: Option<KeyCode>,
+    #[serde(deserialize_with = "key_code")]
+    pub up: Option<KeyCode>,
+    #[serde(deserialize_with = "key_code")]
+    pub down: Option<KeyCode>,
+    #[serde(deserialize_with = "key_code")]
+    pub left: Option<KeyCode>,
+    #[serde(deserialize_with = "key_code")]
+    pub right: Option<KeyCode>,
+}
+
+impl InputSettings {
+    fn keys(&self) -> [(JoypKey, &Option<KeyCode>); JoypKey::ALL.len()] {
+        [
+            (JoypKey::ButtonA, &self.a),
+            (JoypKey::ButtonB, &self.b),
+            (JoypKey::Select, &self.select),
+            (JoypKey::Start, &self.start),
+            (JoypKey::Up, &self.up),
+            (JoypKey::Down, &self.down),
+            (JoypKey::Left, &self.left),
+            (JoypKey::Right, &self.right),
+        ]
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        let fields = [
+            (&mut self.a, &other.a),
+            (&mut self.b, &other.b),
+            (&mut self.select, &other.select),
+            (&mut self.start, &other.start),
+            (&mut self.up, &other.up),
+            (&mut self.down, &other.down),
+            (&mut self.left, &other.left),
+            (&mut self.right, &other.right),
+        ];
+        for (key, other) in fields {
+            if other.is_some() {
+                key.clone_from(other);
+            }
+        }
+    }
+
+    /// Rebinds the buttons that have a key set, the others keep their current key.
+    pub fn apply(&self, bindings: KeyBindings) -> KeyBindings {
+        self.keys()
+            .into_iter()
+            .fold(bindings, |bindings, (button, key)| match key {
+                Some(key) => bindings.with_binding(button, key.clone()),
+                None => bindings,
+            })
+    }
+}
+
+fn key_code<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<KeyCode>, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    KeyCode::from_str(&name)
+        .map(Some)
+        .map_err(|_| D::Error::custom(format!("unknown key `{name}`")))
+}
@@ -0,0 +1,17 @@
+mod audio_settings;
+mod config;
+mod config_error;
+mod display_settings;
+mod game_override;
+mod input_settings;
+mod save_settings;
+mod settings;
+
+pub use audio_settings::AudioSettings;
+pub use config::Config;
+pub use config_error::ConfigError;
+pub use display_settings::DisplaySettings;
+pub use game_override::GameOverride;
+pub use input_settings::InputSettings;
+pub use save_settings::SaveSettings;
+pub use settings::Settings;
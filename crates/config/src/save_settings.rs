@@ -0,0 +1,16 @@
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SaveSettings {
+    /// Directory holding the battery backed save files.
+    pub directory: Option<PathBuf>,
+}
+
+impl SaveSettings {
+    pub fn merge(&mut self, other: &Self) {
+        if let Some(directory) = &other.directory {
+            self.directory = Some(directory.clone());
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use crate::{AudioSettings, DisplaySettings, InputSettings, SaveSettings};
+
+/// Settings read from the config file, unset fields keep the emulator defaults.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Settings {
+    pub display: DisplaySettings,
+    pub audio: AudioSettings,
+    pub saves: SaveSettings,
+    pub input: InputSettings,
+}
+
+impl Settings {
+    /// Fields set in `other` replace the ones in `self`.
+    pub fn merge(&mut self, other: &Self) {
+        self.display.merge(&other.display);
+        self.audio.merge(&other.audio);
+        self.saves.merge(&other.saves);
+        self.input.merge(&other.input);
+    }
+}
@@ -2,10 +2,40 @@ mod output_stream;
 
 use cpal::traits::{DeviceTrait, HostTrait};
 
-pub struct CpalPlugin;
+pub struct CpalPlugin {
+    volume: f32,
+    max_latency: std::time::Duration,
+}
+
+impl CpalPlugin {
+    pub fn new() -> Self {
+        Self {
+            volume: 1.0,
+            max_latency: std::time::Duration::from_millis(100),
+        }
+    }
+
+    /// Output gain, from 0.0 (muted) to 1.0 (full volume).
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = volume.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Audio buffered beyond this is dropped to catch up with the emulator.
+    pub fn with_max_latency(mut self, max_latency: std::time::Duration) -> Self {
+        self.max_latency = max_latency;
+        self
+    }
+}
+
+impl Default for CpalPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl yagber_app::Plugin for CpalPlugin {
-    fn init(self, emulator: &mut yagber_app::Emulator) {
+    fn init(self, emulator: &mut yagber_app::Emulator) -> Result<(), yagber_app::PluginError> {
         let host = cpal::default_host();
         let device = host
             .default_output_device()
@@ -25,8 +55,14 @@ impl yagber_app::Plugin for CpalPlugin {
             .expect("Apu component not found");
 
         apu.set_sample_rate(config.sample_rate.0);
-        let stream = output_stream::OutputStream::new(device, config, apu);
+        let stream =
+            output_stream::OutputStream::new(device, config, apu, self.volume, self.max_latency);
 
         emulator.with_component(stream);
+        Ok(())
+    }
+
+    fn dependencies(&self) -> yagber_app::PluginDependencies {
+        yagber_app::PluginDependencies::new().requires::<yagber_apu::Apu>()
     }
 }
@@ -1,3 +1,8 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
 use cpal::traits::{DeviceTrait, StreamTrait};
 use ringbuf::traits::Consumer;
 
@@ -12,14 +17,18 @@ impl OutputStream {
         device: cpal::Device,
         config: cpal::StreamConfig,
         apu: &mut yagber_apu::Apu,
+        volume: f32,
+        max_latency: std::time::Duration,
     ) -> Self {
         let mut left_buffer: yagber_apu::ConsumerCache =
             apu.left_buffer.take_consumer().expect("No left buffer");
         let mut right_buffer: yagber_apu::ConsumerCache =
             apu.right_buffer.take_consumer().expect("No right buffer");
+        let muted = apu.mute_flag();
         #[cfg(feature = "trace")]
         tracing::trace!("Building output stream");
-        let sample_rate_hz = config.sample_rate.0 as usize;
+        let max_latency_samples =
+            (config.sample_rate.0 as f64 * max_latency.as_secs_f64()) as usize;
         let stream = device
             .build_output_stream(
                 &config,
@@ -27,8 +36,10 @@ impl OutputStream {
                     Self::data_callback(
                         &mut left_buffer,
                         &mut right_buffer,
+                        &muted,
                         data,
-                        sample_rate_hz,
+                        volume,
+                        max_latency_samples,
                         info,
                     )
                 },
@@ -45,24 +56,31 @@ impl OutputStream {
     fn data_callback(
         left_buffer: &mut yagber_apu::ConsumerCache,
         right_buffer: &mut yagber_apu::ConsumerCache,
+        muted: &Arc<AtomicBool>,
         data: &mut [f32],
-        sample_rate_hz: usize,
+        volume: f32,
+        max_latency_samples: usize,
         _: &cpal::OutputCallbackInfo,
     ) {
         data.fill(0.0);
+        // Samples from before the emulator went uncapped would play pitch shifted, drop them.
+        if muted.load(Ordering::Relaxed) {
+            left_buffer.clear();
+            right_buffer.clear();
+            return;
+        }
         let frames = data.len() / 2;
 
         // Fill current callback frames from the buffer.
         for i in 0..frames {
             let l = left_buffer.try_pop().unwrap_or(0.0);
             let r = right_buffer.try_pop().unwrap_or(0.0);
-            data[i * 2] = l;
-            data[i * 2 + 1] = r;
+            data[i * 2] = l * volume;
+            data[i * 2 + 1] = r * volume;
         }
 
-        // After producing current audio, drop up to ~100ms of the oldest
+        // After producing current audio, drop up to the maximum latency worth of the oldest
         // samples (in lockstep L/R) to catch up and keep latency bounded.
-        let max_latency_samples = sample_rate_hz / 10; // ~100ms worth
         for _ in 0..max_latency_samples {
             let l = left_buffer.try_pop();
             let r = right_buffer.try_pop();
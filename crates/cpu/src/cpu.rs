@@ -1,10 +1,15 @@
 use crate::alu::{Alu8, Alu16};
+use crate::cpu_bus::{CpuBus, EmulatorBus};
 use crate::ime::Ime;
+use crate::instruction_history::{ExecutedInstruction, InstructionHistory};
 use crate::instructions::{ConditionCode, Instruction, InstructionType};
 use crate::registers::Registers;
 use arbitrary_int::{u2, u3};
-use yagber_memory::{Bus, Memory};
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
+use yagber_memory::{AccessKind, MemoryAccess};
 
+/// Runs one M-cycle per step, instructions and interrupt dispatches spread their memory
+/// accesses over the M-cycles they take on hardware.
 #[derive(Debug, Clone, Copy)]
 pub struct Cpu {
     pc: u16,
@@ -14,6 +19,74 @@ pub struct Cpu {
     busy: u16,
     halt: bool,
     halt_bug: bool,
+    /// STOP started a speed switch, the speed changes once the pause is over.
+    speed_switch: bool,
+    /// An illegal opcode locked the CPU up, only a reset clears it.
+    locked: bool,
+    /// Recently fetched opcodes, reported when the CPU locks up.
+    history: InstructionHistory,
+    /// Instruction being executed, `None` between instructions.
+    instruction: Option<Instruction>,
+    /// M-cycle reached by the current instruction or interrupt dispatch, 0 being the opcode fetch.
+    mcycle: u8,
+    /// Internal registers holding the bytes read by the previous M-cycles, low then high.
+    z: u8,
+    w: u8,
+    /// The last M-cycle completed an instruction or an interrupt dispatch.
+    boundary: bool,
+    /// Data access made during the last M-cycle.
+    access: Option<MemoryAccess>,
+}
+
+impl Snapshot for Cpu {
+    const VERSION: u16 = 4;
+
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_u16(self.pc);
+        writer.write_u16(self.sp);
+        writer.write(&self.registers);
+        writer.write(&self.ime);
+        writer.write_u16(self.busy);
+        writer.write_bool(self.halt);
+        writer.write_bool(self.halt_bug);
+        writer.write_bool(self.speed_switch);
+        writer.write_bool(self.locked);
+        writer.write_bool(self.instruction.is_some());
+        if let Some(instruction) = &self.instruction {
+            writer.write_bool(instruction.cb_prefix());
+            writer.write_u8(instruction.opcode());
+        }
+        writer.write_u8(self.mcycle);
+        writer.write_u8(self.z);
+        writer.write_u8(self.w);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.pc = reader.read_u16()?;
+        self.sp = reader.read_u16()?;
+        reader.read(&mut self.registers)?;
+        reader.read(&mut self.ime)?;
+        self.busy = reader.read_u16()?;
+        self.halt = reader.read_bool()?;
+        self.halt_bug = reader.read_bool()?;
+        self.speed_switch = reader.read_bool()?;
+        self.locked = reader.read_bool()?;
+        self.instruction = if reader.read_bool()? {
+            let cb_prefix = reader.read_bool()?;
+            let opcode = reader.read_u8()?;
+            Some(if cb_prefix {
+                Instruction::new_cb_prefix(opcode)
+            } else {
+                Instruction::new(opcode)
+            })
+        } else {
+            None
+        };
+        self.mcycle = reader.read_u8()?;
+        self.z = reader.read_u8()?;
+        self.w = reader.read_u8()?;
+        Ok(())
+    }
 }
 
 impl Default for Cpu {
@@ -23,6 +96,9 @@ impl Default for Cpu {
 }
 
 impl Cpu {
+    /// M-cycles the CPU pauses for during a CGB speed switch.
+    pub const SPEED_SWITCH_MCYCLES: u16 = 2050;
+
     pub fn new() -> Self {
         Self {
             pc: 0x0000,
@@ -32,6 +108,100 @@ impl Cpu {
             busy: Default::default(),
             halt: false,
             halt_bug: false,
+            speed_switch: false,
+            locked: false,
+            history: InstructionHistory::default(),
+            instruction: None,
+            mcycle: 0,
+            z: 0,
+            w: 0,
+            boundary: true,
+            access: None,
+        }
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.pc
+    }
+
+    /// Moves execution to `pc`, meant for debuggers stopped between two instructions.
+    pub fn set_pc(&mut self, pc: u16) {
+        self.pc = pc;
+    }
+
+    pub fn sp(&self) -> u16 {
+        self.sp
+    }
+
+    pub fn set_sp(&mut self, sp: u16) {
+        self.sp = sp;
+    }
+
+    pub fn registers(&self) -> &Registers {
+        &self.registers
+    }
+
+    pub fn registers_mut(&mut self) -> &mut Registers {
+        &mut self.registers
+    }
+
+    pub fn ime(&self) -> bool {
+        self.ime.ime()
+    }
+
+    /// Sets IME right away, unlike EI which only takes effect after the next instruction.
+    pub fn set_ime(&mut self, ime: bool) {
+        if ime {
+            self.ime.enable();
+        } else {
+            self.ime.reset_ime();
+        }
+    }
+
+    pub fn halted(&self) -> bool {
+        self.halt
+    }
+
+    /// An illegal opcode locked the CPU up, see [`crate::CpuLockedEvent`].
+    pub fn locked(&self) -> bool {
+        self.locked
+    }
+
+    /// The last opcodes fetched, oldest first.
+    pub fn history(&self) -> &InstructionHistory {
+        &self.history
+    }
+
+    /// The last M-cycle completed an instruction or an interrupt dispatch,
+    /// so `pc` points at the next instruction to run.
+    /// Idle halted, stopped or locked cycles are not boundaries.
+    pub fn at_instruction_boundary(&self) -> bool {
+        self.boundary
+    }
+
+    /// Data read or write made during the last M-cycle, the CPU makes at most one per M-cycle.
+    pub fn last_access(&self) -> Option<MemoryAccess> {
+        self.access
+    }
+
+    /// Stops a headless run when the program counter reaches `address`.
+    /// Only the arrival matches, so resuming does not stop again right away.
+    pub fn pc_reached(address: u16) -> yagber_app::StopCondition {
+        let mut was_there = false;
+        yagber_app::StopCondition::new(format!("pc reached {address:#06X}"), move |emulator| {
+            let is_there = emulator
+                .get_component::<Cpu>()
+                .is_some_and(|cpu| cpu.pc == address);
+            let arrived = is_there && !was_there;
+            was_there = is_there;
+            arrived
+        })
+    }
+
+    /// Soft resets start over from the boot ROM entry point.
+    pub(crate) fn on_reset(emulator: &mut yagber_app::Emulator, event: &yagber_app::ResetEvent) {
+        if *event == yagber_app::ResetEvent::Finished(yagber_app::ResetKind::Soft) {
+            *emulator.get_component_mut::<Cpu>().unwrap() = Cpu::new();
         }
     }
 
@@ -39,19 +209,79 @@ impl Cpu {
         #[cfg(feature = "trace-span")]
         let _span = tracing::info_span!("cpu step").entered();
 
-        let (cpu, bus) = emulator
-            .get_components_mut2::<Cpu, Bus>()
-            .expect("Cpu and/or Bus component missing");
-        cpu.step(bus);
+        // Split off, the CPU steps on the emulator and IO hooks run as it writes
+        emulator
+            .split_component(|cpu: &mut Cpu, emulator| {
+                let was_locked = cpu.locked;
+                cpu.step(&mut EmulatorBus::new(emulator));
+
+                if cpu.locked && !was_locked {
+                    let event = cpu.locked_event();
+                    #[cfg(feature = "trace")]
+                    tracing::error!("{event}");
+                    emulator.emit(event);
+                }
+                if cpu.boundary {
+                    emulator.reached_boundary(yagber_app::StepBoundary::Instruction);
+                }
+            })
+            .expect("Cpu component missing");
+    }
+
+    /// No instruction nor interrupt dispatch is in progress.
+    fn between_instructions(&self) -> bool {
+        self.busy == 0 && self.instruction.is_none() && !self.ime.interrupt_handling()
     }
 
     /// Perform a single CPU step
-    /// Respects instruction timing
     /// Represents a single M-cycle
-    pub fn step(&mut self, bus: &mut Bus) {
+    pub fn step(&mut self, bus: &mut impl CpuBus) {
+        // An instruction is done once its last M-cycle ran, idle halted cycles do not count.
+        self.take_stall(bus);
+        let was_halted = self.between_instructions() && (self.halt || self.locked || bus.stopped());
+        self.access = None;
+        self.step_mcycle(bus);
+        self.boundary = self.between_instructions() && !was_halted;
+    }
+
+    fn step_mcycle(&mut self, bus: &mut impl CpuBus) {
+        // A locked up CPU ignores interrupts too
+        if self.locked {
+            return;
+        }
+
         // If the CPU is busy, decrement the busy counter
         if self.busy > 0 {
             self.busy -= 1;
+            if self.busy == 0 && self.speed_switch {
+                self.switch_speed(bus);
+            }
+            return;
+        }
+
+        // In STOP mode, the CPU wakes up once a joypad line goes low
+        if bus.stopped() {
+            if !bus.joypad_line_low() {
+                return;
+            }
+            bus.set_stopped(false);
+        }
+
+        // Continue the interrupt dispatch
+        if self.ime.interrupt_handling() {
+            self.mcycle += 1;
+            if self.dispatch_interrupt(bus) {
+                self.ime.reset_interrupt_handling();
+            }
+            return;
+        }
+
+        // Continue the current instruction
+        if let Some(instruction) = self.instruction {
+            self.mcycle += 1;
+            if self.execute_mcycle(bus, instruction) {
+                self.finish_instruction();
+            }
             return;
         }
 
@@ -65,78 +295,47 @@ impl Cpu {
             return;
         }
 
-        // Check for interrupts
-        self.check_interrupt(bus);
-        if self.busy != 0 {
-            return;
-        }
-
-        // Handle interrupts
-        if self.ime.interrupt_handling() {
-            self.handle_interrupt(bus);
-            self.ime.reset_interrupt_handling();
+        // Check for interrupts, the dispatch replaces the next opcode fetch
+        if self.ime.ime() && self.any_interrupt_pending(bus) {
+            self.ime.set_interrupt_handling();
+            self.mcycle = 0;
             return;
         }
 
-        // Perform a step
-        self.instruction_step(bus);
+        self.fetch(bus);
     }
 
-    /// Perform a single CPU step
-    /// returns the number of M-cycles taken by the instruction
-    fn instruction_step(&mut self, bus: &mut Bus) {
+    /// Reads the next opcode, single M-cycle instructions run right away.
+    fn fetch(&mut self, bus: &mut impl CpuBus) {
         if self.pc == 0x0100 {
             #[cfg(feature = "trace")]
             tracing::info!("Boot Rom Completed, Starting cartridge");
         }
 
-        // Fetch the next instruction
-        let instruction = self.read_instruction(bus);
+        self.history
+            .push(ExecutedInstruction::new(bus.bank(self.pc), self.pc));
+        let opcode = self.read_next_byte(bus);
+        let instruction = Instruction::new(opcode);
         #[cfg(feature = "trace")]
         tracing::trace!("{:?}", instruction);
 
-        // Execute the instruction
-        self.execute_instruction(bus, &instruction);
-
-        // Update the IME
-        self.ime.update_ime();
-
-        // number of M-cycles taken by the instruction
-        self.busy = instruction.cycles() - 1;
-    }
-
-    fn read_instruction(&mut self, bus: &mut Bus) -> Instruction {
-        // Fetch the next instruction
-        let opcode = self.read_next_byte(bus);
-
-        // Decode the instruction
-        let mut instruction = Instruction::new(opcode);
-        if *instruction.instruction_type() == InstructionType::Prefix {
-            // Handle prefix instructions
-            let prefix_opcode = self.read_next_byte(bus);
-            instruction = Instruction::new_cb_prefix(prefix_opcode)
-        }
-
-        // Check if the instruction needs more bytes
-        if instruction.requires_imm8() {
-            // Read the immediate value
-            let imm8 = self.read_next_byte(bus);
-            instruction.set_imm8(imm8);
-        }
-
-        if instruction.requires_imm16() {
-            // Read the immediate value
-            let lo = self.read_next_byte(bus);
-            let hi = self.read_next_byte(bus);
-            let imm16 = u16::from_le_bytes([lo, hi]);
-            instruction.set_imm16(imm16);
+        self.instruction = Some(instruction);
+        self.mcycle = 0;
+        if self.execute_mcycle(bus, instruction) {
+            self.finish_instruction();
         }
+    }
 
-        instruction
+    fn finish_instruction(&mut self) {
+        self.instruction = None;
+        self.ime.update_ime();
     }
 
-    fn read_next_byte(&mut self, bus: &mut Bus) -> u8 {
+    fn read_next_byte(&mut self, bus: &mut impl CpuBus) -> u8 {
         let byte = bus.read(self.pc);
+        if let Some(instruction) = self.history.last_mut() {
+            instruction.push_byte(byte);
+        }
         // If the halt bug is triggered the cpu fails to increment the PC
         if self.halt_bug {
             self.halt_bug = false;
@@ -146,7 +345,37 @@ impl Cpu {
         byte
     }
 
-    fn read_r8(&self, r8: u3, bus: &mut Bus) -> u8 {
+    fn read(&mut self, bus: &mut impl CpuBus, address: u16) -> u8 {
+        let value = bus.read(address);
+        let access = MemoryAccess {
+            address,
+            value,
+            kind: AccessKind::Read,
+        };
+        bus.record_access(access);
+        self.access = Some(access);
+        value
+    }
+
+    fn write(&mut self, bus: &mut impl CpuBus, address: u16, value: u8) {
+        bus.write(address, value);
+        self.take_stall(bus);
+        let access = MemoryAccess {
+            address,
+            value,
+            kind: AccessKind::Write,
+        };
+        bus.record_access(access);
+        self.access = Some(access);
+    }
+
+    /// 16-bit value made of the internal registers.
+    fn wz(&self) -> u16 {
+        u16::from_le_bytes([self.z, self.w])
+    }
+
+    /// Register operand, (HL) goes through the bus on its own M-cycle instead.
+    fn read_r8(&self, r8: u3) -> u8 {
         match r8.value() {
             0 => self.registers.b(),
             1 => self.registers.c(),
@@ -154,13 +383,13 @@ impl Cpu {
             3 => self.registers.e(),
             4 => self.registers.h(),
             5 => self.registers.l(),
-            6 => bus.read(self.registers.hl()),
             7 => self.registers.a(),
             _ => unreachable!(),
         }
     }
 
-    fn write_r8(&mut self, r8: u3, value: u8, bus: &mut Bus) {
+    /// Register operand, (HL) goes through the bus on its own M-cycle instead.
+    fn write_r8(&mut self, r8: u3, value: u8) {
         match r8.value() {
             0 => self.registers.set_b(value),
             1 => self.registers.set_c(value),
@@ -168,7 +397,6 @@ impl Cpu {
             3 => self.registers.set_e(value),
             4 => self.registers.set_h(value),
             5 => self.registers.set_l(value),
-            6 => bus.write(self.registers.hl(), value),
             7 => self.registers.set_a(value),
             _ => unreachable!(),
         }
@@ -234,93 +462,177 @@ impl Cpu {
         }
     }
 
-    fn stack_push(&mut self, bus: &mut Bus, value: u16) {
-        self.sp = self.sp.wrapping_sub(2);
-        bus.write_u16(self.sp, value);
+    /// Writes the next byte of a push, SP was decremented on the M-cycle before.
+    fn push_byte(&mut self, bus: &mut impl CpuBus, value: u8) {
+        self.write(bus, self.sp, value);
     }
 
-    fn stack_pop(&mut self, bus: &mut Bus) -> u16 {
-        let value = bus.read_u16(self.sp);
-        self.sp = self.sp.wrapping_add(2);
+    fn pop_byte(&mut self, bus: &mut impl CpuBus) -> u8 {
+        let value = self.read(bus, self.sp);
+        self.sp = self.sp.wrapping_add(1);
         value
     }
 
-    fn call(&mut self, bus: &mut Bus, address: u16) {
-        #[cfg(feature = "trace")]
-        tracing::trace!("Calling to {:#06X}", address);
-        self.stack_push(bus, self.pc);
-        self.pc = address;
+    fn locked_event(&self) -> crate::CpuLockedEvent {
+        let history = self.history.iter().copied().collect::<Vec<_>>();
+        crate::CpuLockedEvent {
+            instruction: *history
+                .last()
+                .expect("the illegal opcode is in the history"),
+            history,
+        }
     }
 
-    fn any_interrupt_pending(&self, bus: &mut Bus) -> bool {
-        let ei = bus.read(yagber_memory::IOType::IE.address());
-        let fi = bus.read(yagber_memory::IOType::IF.address());
-        ei & fi != 0
-    }
+    /// STOP, following the flowchart of Pan Docs' "Reducing Power Consumption".
+    ///
+    /// A held button turns it into a HALT, or into a NOP with an interrupt pending.
+    /// Otherwise DIV is reset and the armed speed switch pauses the CPU, or the system clock
+    /// stops until a joypad line goes low. Without an interrupt pending, the byte after the
+    /// opcode is skipped.
+    fn stop(&mut self, bus: &mut impl CpuBus) {
+        let interrupt_pending = self.any_interrupt_pending(bus);
+        if !interrupt_pending {
+            self.pc = self.pc.wrapping_add(1);
+        }
 
-    fn check_interrupt(&mut self, bus: &mut Bus) {
-        if self.any_interrupt_pending(bus) && self.ime.ime() {
-            self.busy = 2; // 2 M-Cycles of NOP
-            self.ime.set_interrupt_handling();
+        if bus.joypad_line_low() {
+            self.halt = !interrupt_pending;
+            return;
         }
-    }
 
-    fn handle_interrupt(&mut self, bus: &mut Bus) {
-        let interrupt = bus.get_priority_interrupt();
-        if let Some(interrupt) = interrupt {
-            #[cfg(feature = "trace")]
-            tracing::trace!("Handling interrupt: {:?}", interrupt);
-            bus.clear_interrupt(interrupt);
-            self.call(bus, interrupt.address());
-            self.busy = 2; // 2 more M-Cycles of NOP
+        bus.reset_div();
+        if bus.speed_switch_armed() {
+            self.speed_switch = true;
+            self.freeze_for(Self::SPEED_SWITCH_MCYCLES);
         } else {
-            #[cfg(feature = "trace")]
-            tracing::warn!("Requested interrupt handling but no interrupt is pending");
+            bus.set_stopped(true);
         }
     }
 
+    /// Ends the speed switch pause.
+    fn switch_speed(&mut self, bus: &mut impl CpuBus) {
+        self.speed_switch = false;
+        bus.switch_speed();
+    }
+
+    fn any_interrupt_pending(&self, bus: &mut impl CpuBus) -> bool {
+        bus.pending_interrupts() != 0
+    }
+
+    /// Runs M-cycle `self.mcycle` of the 5 M-cycles interrupt dispatch.
+    /// Returns whether the dispatch is complete.
+    ///
+    /// The handler is picked after the high byte of PC is pushed, so a push overwriting IE
+    /// can cancel the dispatch, which then jumps to 0x0000.
+    fn dispatch_interrupt(&mut self, bus: &mut impl CpuBus) -> bool {
+        match self.mcycle {
+            1 => self.sp = self.sp.wrapping_sub(1),
+            2 => {
+                self.push_byte(bus, (self.pc >> 8) as u8);
+                self.sp = self.sp.wrapping_sub(1);
+            }
+            3 => {
+                self.push_byte(bus, self.pc as u8);
+                let address = match Self::priority_interrupt(bus) {
+                    Some(interrupt) => {
+                        #[cfg(feature = "trace")]
+                        tracing::trace!("Handling interrupt: {:?}", interrupt);
+                        bus.acknowledge_interrupt(interrupt);
+                        interrupt.address()
+                    }
+                    None => {
+                        #[cfg(feature = "trace")]
+                        tracing::trace!("Interrupt dispatch cancelled");
+                        0x0000
+                    }
+                };
+                [self.z, self.w] = address.to_le_bytes();
+            }
+            4 => {
+                self.pc = self.wz();
+                return true;
+            }
+            _ => {}
+        }
+        false
+    }
+
+    /// Pending interrupt with the highest priority, the lowest bit wins.
+    fn priority_interrupt(bus: &impl CpuBus) -> Option<yagber_memory::InterruptType> {
+        let interrupts = bus.pending_interrupts() & 0x1F;
+        (interrupts != 0)
+            .then(|| yagber_memory::InterruptType::from_u8(interrupts.trailing_zeros() as u8))
+    }
+
     pub fn freeze_for(&mut self, cycles: u16) {
         self.busy = cycles;
     }
 
-    fn execute_instruction(&mut self, bus: &mut Bus, instruction: &Instruction) {
-        // Execute the instruction
+    /// DMA transfers halt the CPU while they hold the bus.
+    fn take_stall(&mut self, bus: &mut impl CpuBus) {
+        let stall = bus.take_stall();
+        if stall > 0 {
+            self.freeze_for(stall);
+        }
+    }
+
+    /// Runs M-cycle `self.mcycle` of `instruction`, 0 being the cycle the opcode is fetched on.
+    /// Returns whether the instruction is complete.
+    ///
+    /// Every memory access lands on the M-cycle it takes on hardware, cycles not listed
+    /// below only read operand bytes or are internal delays.
+    fn execute_mcycle(&mut self, bus: &mut impl CpuBus, instruction: Instruction) -> bool {
+        let cycle = self.mcycle;
+        let hl = instruction.uses_hl_operand();
+
+        // Operand bytes follow the opcode, one per M-cycle
+        if cycle == 1 && (instruction.requires_imm8() || instruction.requires_imm16()) {
+            self.z = self.read_next_byte(bus);
+        } else if cycle == 2 && instruction.requires_imm16() {
+            self.w = self.read_next_byte(bus);
+        }
+
         use InstructionType::*;
-        match instruction.instruction_type() {
+        let instruction_type = *instruction.instruction_type();
+        match (instruction_type, cycle) {
             // Block 0b00
-            Nop => {}
-            LdR16Imm16 => {
-                let r16 = instruction.r16().unwrap();
-                let imm16 = instruction.imm16().unwrap();
-                self.write_r16(r16, imm16);
-            }
-            LdR16memA => {
-                let r16 = instruction.r16().unwrap();
-                let hl = self.read_r16mem(r16);
-                let a = self.registers.a();
-                bus.write(hl, a);
-            }
-            LdAR16mem => {
-                let r16 = instruction.r16().unwrap();
-                let address = self.read_r16mem(r16);
-                let a = bus.read(address);
+            (Nop, 0) => true,
+            (LdR16Imm16, 2) => {
+                self.write_r16(instruction.r16().unwrap(), self.wz());
+                true
+            }
+            (LdR16memA, 1) => {
+                let address = self.read_r16mem(instruction.r16().unwrap());
+                self.write(bus, address, self.registers.a());
+                true
+            }
+            (LdAR16mem, 1) => {
+                let address = self.read_r16mem(instruction.r16().unwrap());
+                let a = self.read(bus, address);
                 self.registers.set_a(a);
+                true
             }
-            LdImm16Sp => {
-                let imm16 = instruction.imm16().unwrap();
-                bus.write_u16(imm16, self.sp);
+            (LdImm16Sp, 3) => {
+                self.write(bus, self.wz(), self.sp as u8);
+                false
             }
-            IncR16 => {
+            (LdImm16Sp, 4) => {
+                self.write(bus, self.wz().wrapping_add(1), (self.sp >> 8) as u8);
+                true
+            }
+            (IncR16, 1) => {
                 let r16 = instruction.r16().unwrap();
                 let value = self.read_r16(r16);
                 self.write_r16(r16, value.wrapping_add(1));
+                true
             }
-            DecR16 => {
+            (DecR16, 1) => {
                 let r16 = instruction.r16().unwrap();
                 let value = self.read_r16(r16);
                 self.write_r16(r16, value.wrapping_sub(1));
+                true
             }
-            AddHlR16 => {
+            (AddHlR16, 1) => {
                 let r_val = self.read_r16(instruction.r16().unwrap());
                 let hl = self.registers.hl();
 
@@ -332,85 +644,36 @@ impl Cpu {
                     .set_n(false)
                     .set_h(result.cb11)
                     .set_c(result.cb15);
+                true
             }
-            IncR8 => {
+            (IncR8 | DecR8, 0) if !hl => {
                 let r8 = instruction.r8().unwrap();
-                let r_val = self.read_r8(r8, bus);
-                let result = Alu8::inc(r_val);
-                self.write_r8(r8, *result, bus);
-                self.registers
-                    .flags_mut()
-                    .set_z_if_zero(*result)
-                    .set_n(false)
-                    .set_h(result.cb3);
+                let result = self.inc_dec(instruction_type, self.read_r8(r8));
+                self.write_r8(r8, result);
+                true
             }
-            DecR8 => {
-                let r8 = instruction.r8().unwrap();
-                let r_val = self.read_r8(r8, bus);
-                let result = Alu8::dec(r_val);
-                self.write_r8(r8, *result, bus);
-                self.registers
-                    .flags_mut()
-                    .set_z_if_zero(*result)
-                    .set_n(true)
-                    .set_h(result.cb3);
+            (IncR8 | DecR8, 1) => {
+                self.z = self.read(bus, self.registers.hl());
+                false
             }
-            LdR8Imm8 => {
-                let r8 = instruction.r8().unwrap();
-                let imm8 = instruction.imm8().unwrap();
-                self.write_r8(r8, imm8, bus);
+            (IncR8 | DecR8, 2) => {
+                let result = self.inc_dec(instruction_type, self.z);
+                self.write(bus, self.registers.hl(), result);
+                true
             }
-            RlCA => {
-                let a = self.registers.a();
-                let result = Alu8::rlc(a);
-                self.registers.set_a(*result);
-
-                self.registers
-                    .flags_mut()
-                    .set_z(false)
-                    .set_n(false)
-                    .set_h(false)
-                    .set_c(result.cb7);
-            }
-            RrCA => {
-                let a = self.registers.a();
-                let result = Alu8::rrc(a);
-                self.registers.set_a(*result);
-
-                self.registers
-                    .flags_mut()
-                    .set_z(false)
-                    .set_n(false)
-                    .set_h(false)
-                    .set_c(result.cb7);
+            (LdR8Imm8, 1) if !hl => {
+                self.write_r8(instruction.r8().unwrap(), self.z);
+                true
             }
-            RlA => {
-                let a = self.registers.a();
-                let carry = self.registers.flags().c_u8();
-                let result = Alu8::rl(a, carry);
-                self.registers.set_a(*result);
-
-                self.registers
-                    .flags_mut()
-                    .set_z(false)
-                    .set_n(false)
-                    .set_h(false)
-                    .set_c(result.cb7);
+            (LdR8Imm8, 2) => {
+                self.write(bus, self.registers.hl(), self.z);
+                true
             }
-            RrA => {
-                let a = self.registers.a();
-                let carry = self.registers.flags().c_u8();
-                let result = Alu8::rr(a, carry);
-                self.registers.set_a(*result);
-
-                self.registers
-                    .flags_mut()
-                    .set_z(false)
-                    .set_n(false)
-                    .set_h(false)
-                    .set_c(result.cb7);
+            (RlCA | RrCA | RlA | RrA, 0) => {
+                self.rotate_a(instruction_type);
+                true
             }
-            Daa => {
+            (Daa, 0) => {
                 let a = self.registers.a();
                 let flags = self.registers.flags();
                 let mut carry = false;
@@ -441,542 +704,616 @@ impl Cpu {
                     .set_z_if_zero(*result)
                     .set_h(false)
                     .set_c(carry);
+                true
             }
-            Cpl => {
+            (Cpl, 0) => {
                 let a = self.registers.a();
                 self.registers.set_a(!a);
                 self.registers.flags_mut().set_n(true).set_h(true);
+                true
             }
-            Scf => {
+            (Scf, 0) => {
                 self.registers
                     .flags_mut()
                     .set_n(false)
                     .set_h(false)
                     .set_c(true);
+                true
             }
-            Ccf => {
+            (Ccf, 0) => {
                 let c = self.registers.flags().c();
                 self.registers
                     .flags_mut()
                     .set_n(false)
                     .set_h(false)
                     .set_c(!c);
+                true
             }
-            JrImm8 => {
-                let imm8 = instruction.imm8().unwrap();
-                let signed_imm = (imm8 as i8) as i16;
-                self.pc = self.pc.wrapping_add_signed(signed_imm);
+            (JrCondImm8, 1) => !self.check_condition(instruction.cond().unwrap()),
+            (JrImm8 | JrCondImm8, 2) => {
+                // sign‐extend 8→16 and add to PC
+                let offset = (self.z as i8) as i16;
+                self.pc = self.pc.wrapping_add_signed(offset);
                 #[cfg(feature = "trace")]
                 tracing::trace!("Jumping to {:#06X}", self.pc);
+                true
             }
-            JrCondImm8 => {
-                let imm8 = instruction.imm8().unwrap();
-                let cc = instruction.cond().unwrap();
-                if self.check_condition(cc) {
-                    // sign‐extend 8→16 and add to PC
-                    let offset = (imm8 as i8) as i16;
-                    self.pc = self.pc.wrapping_add_signed(offset);
-                    #[cfg(feature = "trace")]
-                    tracing::trace!("Jumping to {:#06X}", self.pc);
-                }
-            }
-            Stop => {
-                // Speed switch
-                let spd = yagber_memory::Spd::from_bus(bus);
-                if spd.speed_switch_armed() {
-                    let current_speed = spd.speed_mode();
-                    let new_speed = current_speed.toggle();
-                    bus.write(yagber_memory::IOType::SPD.address(), new_speed.as_spd_bit());
-                }
+            (Stop, 0) => {
+                self.stop(bus);
+                true
             }
             // Block 0b01
-            LdR8R8 => {
+            (LdR8R8, 0) if !hl => {
                 let (r8_dst, r8_src) = instruction.r8_pair().unwrap();
-                let r_val = self.read_r8(r8_src, bus);
-                self.write_r8(r8_dst, r_val, bus);
+                let r_val = self.read_r8(r8_src);
+                self.write_r8(r8_dst, r_val);
+                true
             }
-            Halt => {
+            (LdR8R8, 1) => {
+                let (r8_dst, r8_src) = instruction.r8_pair().unwrap();
+                let address = self.registers.hl();
+                if r8_src.value() == 6 {
+                    let value = self.read(bus, address);
+                    self.write_r8(r8_dst, value);
+                } else {
+                    self.write(bus, address, self.read_r8(r8_src));
+                }
+                true
+            }
+            (Illegal, 0) => {
+                self.locked = true;
+                true
+            }
+            (Halt, 0) => {
                 if !self.ime.ime() && self.any_interrupt_pending(bus) {
                     self.halt_bug = true;
                 } else {
                     self.halt = true;
                 }
+                true
             }
             // Block 0b10
-            AddAR8 => {
-                let r_val = self.read_r8(instruction.r8().unwrap(), bus);
-                let r_a = self.registers.a();
-                let result = Alu8::add(r_a, r_val);
-                self.registers.set_a(*result);
-                self.registers
-                    .flags_mut()
-                    .set_z_if_zero(*result)
-                    .set_n(false)
-                    .set_h(result.cb3)
-                    .set_c(result.cb7);
-            }
-            AdcAR8 => {
-                let r_val = self.read_r8(instruction.r8().unwrap(), bus);
-                let r_a = self.registers.a();
-                let carry = self.registers.flags().c_u8();
-                let result = Alu8::adc(r_a, r_val, carry);
-                self.registers.set_a(*result);
-                self.registers
-                    .flags_mut()
-                    .set_z_if_zero(*result)
-                    .set_n(false)
-                    .set_h(result.cb3)
-                    .set_c(result.cb7);
-            }
-            SubAR8 => {
-                let r_val = self.read_r8(instruction.r8().unwrap(), bus);
-                let r_a = self.registers.a();
-                let result = Alu8::sub(r_a, r_val);
-                self.registers.set_a(*result);
-                self.registers
-                    .flags_mut()
-                    .set_z_if_zero(*result)
-                    .set_n(true)
-                    .set_h(result.cb3)
-                    .set_c(result.cb7);
-            }
-            SbcAR8 => {
-                let r_val = self.read_r8(instruction.r8().unwrap(), bus);
-                let r_a = self.registers.a();
-                let carry = self.registers.flags().c_u8();
-                let result = Alu8::sbc(r_a, r_val, carry);
-                self.registers.set_a(*result);
-                self.registers
-                    .flags_mut()
-                    .set_z_if_zero(*result)
-                    .set_n(true)
-                    .set_h(result.cb3)
-                    .set_c(result.cb7);
-            }
-            AndAR8 => {
-                let r_val = self.read_r8(instruction.r8().unwrap(), bus);
-                let r_a = self.registers.a();
-                let result = r_val & r_a;
-                self.registers.set_a(result);
-                self.registers
-                    .flags_mut()
-                    .set_z_if_zero(result)
-                    .set_n(false)
-                    .set_h(true)
-                    .set_c(false);
+            (AddAR8 | AdcAR8 | SubAR8 | SbcAR8 | AndAR8 | XorAR8 | OrAR8 | CpAR8, 0) if !hl => {
+                let r_val = self.read_r8(instruction.r8().unwrap());
+                self.alu_a(instruction_type, r_val);
+                true
             }
-            XorAR8 => {
-                let r_val = self.read_r8(instruction.r8().unwrap(), bus);
-                let r_a = self.registers.a();
-                let result = r_val ^ r_a;
-                self.registers.set_a(result);
-                self.registers
-                    .flags_mut()
-                    .set_z_if_zero(result)
-                    .set_n(false)
-                    .set_h(false)
-                    .set_c(false);
-            }
-            OrAR8 => {
-                let r_val = self.read_r8(instruction.r8().unwrap(), bus);
-                let r_a = self.registers.a();
-                let result = r_val | r_a;
-                self.registers.set_a(result);
-                self.registers
-                    .flags_mut()
-                    .set_z_if_zero(result)
-                    .set_n(false)
-                    .set_h(false)
-                    .set_c(false);
-            }
-            CpAR8 => {
-                let r_val = self.read_r8(instruction.r8().unwrap(), bus);
-                let r_a = self.registers.a();
-                let result = Alu8::sub(r_a, r_val);
-                self.registers
-                    .flags_mut()
-                    .set_z_if_zero(*result)
-                    .set_n(true)
-                    .set_h(result.cb3)
-                    .set_c(result.cb7);
+            (AddAR8 | AdcAR8 | SubAR8 | SbcAR8 | AndAR8 | XorAR8 | OrAR8 | CpAR8, 1) => {
+                let value = self.read(bus, self.registers.hl());
+                self.alu_a(instruction_type, value);
+                true
             }
             // Block 0b11
-            AddAImm8 => {
-                let a = self.registers.a();
-                let imm8 = instruction.imm8().unwrap();
-                let result = Alu8::add(a, imm8);
-                self.registers.set_a(*result);
-                self.registers
-                    .flags_mut()
-                    .set_z_if_zero(*result)
-                    .set_n(false)
-                    .set_h(result.cb3)
-                    .set_c(result.cb7);
-            }
-            AdcAImm8 => {
-                let a = self.registers.a();
-                let imm8 = instruction.imm8().unwrap();
-                let carry = self.registers.flags().c_u8();
-                let result = Alu8::adc(a, imm8, carry);
-                self.registers.set_a(*result);
-                self.registers
-                    .flags_mut()
-                    .set_z_if_zero(*result)
-                    .set_n(false)
-                    .set_h(result.cb3)
-                    .set_c(result.cb7);
-            }
-            SubAImm8 => {
-                let a = self.registers.a();
-                let imm8 = instruction.imm8().unwrap();
-                let result = Alu8::sub(a, imm8);
-                self.registers.set_a(*result);
-                self.registers
-                    .flags_mut()
-                    .set_z_if_zero(*result)
-                    .set_n(true)
-                    .set_h(result.cb3)
-                    .set_c(result.cb7);
-            }
-            SbcAImm8 => {
-                let a = self.registers.a();
-                let imm8 = instruction.imm8().unwrap();
-                let carry = self.registers.flags().c_u8();
-                let result = Alu8::sbc(a, imm8, carry);
-                self.registers.set_a(*result);
-                self.registers
-                    .flags_mut()
-                    .set_z_if_zero(*result)
-                    .set_n(true)
-                    .set_h(result.cb3)
-                    .set_c(result.cb7);
-            }
-            AndAImm8 => {
-                let a = self.registers.a();
-                let imm8 = instruction.imm8().unwrap();
-                let result = a & imm8;
-                self.registers.set_a(result);
-                self.registers
-                    .flags_mut()
-                    .set_z_if_zero(result)
-                    .set_n(false)
-                    .set_h(true)
-                    .set_c(false);
-            }
-            XorAImm8 => {
-                let a = self.registers.a();
-                let imm8 = instruction.imm8().unwrap();
-                let result = a ^ imm8;
-                self.registers.set_a(result);
-                self.registers
-                    .flags_mut()
-                    .set_z_if_zero(result)
-                    .set_n(false)
-                    .set_h(false)
-                    .set_c(false);
-            }
-            OrAImm8 => {
-                let a = self.registers.a();
-                let imm8 = instruction.imm8().unwrap();
-                let result = a | imm8;
-                self.registers.set_a(result);
-                self.registers
-                    .flags_mut()
-                    .set_z_if_zero(result)
-                    .set_n(false)
-                    .set_h(false)
-                    .set_c(false);
-            }
-            CpAImm8 => {
-                let a = self.registers.a();
-                let imm8 = instruction.imm8().unwrap();
-                let result = Alu8::sub(a, imm8);
-                self.registers
-                    .flags_mut()
-                    .set_z_if_zero(*result)
-                    .set_n(true)
-                    .set_h(result.cb3)
-                    .set_c(result.cb7);
-            }
-            RetCond => {
-                let condition = instruction.cond().unwrap();
-                if self.check_condition(condition) {
-                    let value = self.stack_pop(bus);
-                    self.pc = value;
-                }
-            }
-            Ret => {
-                let value = self.stack_pop(bus);
-                self.pc = value;
-            }
-            RetI => {
-                let value = self.stack_pop(bus);
-                self.pc = value;
+            (
+                AddAImm8 | AdcAImm8 | SubAImm8 | SbcAImm8 | AndAImm8 | XorAImm8 | OrAImm8 | CpAImm8,
+                1,
+            ) => {
+                self.alu_a(instruction_type, self.z);
+                true
+            }
+            (RetCond, 1) => !self.check_condition(instruction.cond().unwrap()),
+            (RetCond, 2) | (Ret | RetI | PopR16stk, 1) => {
+                self.z = self.pop_byte(bus);
+                false
+            }
+            (RetCond, 3) | (Ret | RetI, 2) => {
+                self.w = self.pop_byte(bus);
+                false
+            }
+            (RetCond, 4) | (Ret, 3) => {
+                self.pc = self.wz();
+                true
+            }
+            (RetI, 3) => {
+                self.pc = self.wz();
                 self.ime.set_ime();
+                true
             }
-            JpCondImm16 => {
-                let imm16 = instruction.imm16().unwrap();
-                if self.check_condition(instruction.cond().unwrap()) {
-                    self.pc = imm16;
-                    #[cfg(feature = "trace")]
-                    tracing::trace!("Jumping to {:#06X}", imm16);
-                }
+            (PopR16stk, 2) => {
+                self.w = self.pop_byte(bus);
+                self.write_r16stk(instruction.r16().unwrap(), self.wz());
+                true
             }
-            JpImm16 => {
-                let imm16 = instruction.imm16().unwrap();
-                self.pc = imm16;
+            (JpCondImm16 | CallCondImm16, 2) => !self.check_condition(instruction.cond().unwrap()),
+            (JpCondImm16 | JpImm16, 3) => {
+                self.pc = self.wz();
                 #[cfg(feature = "trace")]
-                tracing::trace!("Jumping to {:#06X}", imm16);
+                tracing::trace!("Jumping to {:#06X}", self.pc);
+                true
             }
-            JpHl => {
+            (JpHl, 0) => {
                 let hl = self.registers.hl();
                 self.pc = hl;
                 #[cfg(feature = "trace")]
                 tracing::trace!("Jumping to HL: {:#06X}", hl);
+                true
             }
-            CallCondImm16 => {
-                let imm16 = instruction.imm16().unwrap();
-                if self.check_condition(instruction.cond().unwrap()) {
-                    self.call(bus, imm16);
-                }
-            }
-            CallImm16 => {
-                let imm16 = instruction.imm16().unwrap();
-                self.call(bus, imm16);
+            (CallCondImm16 | CallImm16, 3) | (RstTgt3 | PushR16stk, 1) => {
+                self.sp = self.sp.wrapping_sub(1);
+                false
             }
-            RstTgt3 => {
-                let tgt = instruction.tgt3().unwrap();
-                let addr = (tgt.value() as u16) << 3;
-                self.call(bus, addr);
+            (CallCondImm16 | CallImm16, 4) | (RstTgt3, 2) => {
+                self.push_byte(bus, (self.pc >> 8) as u8);
+                self.sp = self.sp.wrapping_sub(1);
+                false
             }
-            PopR16stk => {
-                let r16 = instruction.r16().unwrap();
-                let value = self.stack_pop(bus);
-                self.write_r16stk(r16, value);
-            }
-            PushR16stk => {
-                let r16 = instruction.r16().unwrap();
-                let value = self.read_r16stk(r16);
-                self.stack_push(bus, value);
+            (CallCondImm16 | CallImm16, 5) => {
+                self.push_byte(bus, self.pc as u8);
+                self.pc = self.wz();
+                #[cfg(feature = "trace")]
+                tracing::trace!("Calling to {:#06X}", self.pc);
+                true
             }
-            Prefix => {
+            (RstTgt3, 3) => {
+                self.push_byte(bus, self.pc as u8);
+                let tgt = instruction.tgt3().unwrap();
+                self.pc = (tgt.value() as u16) << 3;
+                true
+            }
+            (PushR16stk, 2) => {
+                let value = self.read_r16stk(instruction.r16().unwrap());
+                self.push_byte(bus, (value >> 8) as u8);
+                self.sp = self.sp.wrapping_sub(1);
+                false
+            }
+            (PushR16stk, 3) => {
+                let value = self.read_r16stk(instruction.r16().unwrap());
+                self.push_byte(bus, value as u8);
+                true
+            }
+            (Prefix, 0) => false,
+            (Prefix, 1) => {
+                let prefix_opcode = self.read_next_byte(bus);
+                let instruction = Instruction::new_cb_prefix(prefix_opcode);
                 #[cfg(feature = "trace")]
-                tracing::trace!("Prefix instruction encountered");
+                tracing::trace!("{:?}", instruction);
+                self.instruction = Some(instruction);
+                if instruction.uses_hl_operand() {
+                    return false;
+                }
+                let r8 = instruction.r8().unwrap();
+                if let Some(result) = self.cb_operation(&instruction, self.read_r8(r8)) {
+                    self.write_r8(r8, result);
+                }
+                true
             }
-            LdhCA => {
-                let a = self.registers.a();
-                let c = self.registers.c() as u16;
-                let address = 0xFF00 + c;
-                bus.write(address, a);
+            (LdhCA, 1) => {
+                let address = 0xFF00 + self.registers.c() as u16;
+                self.write(bus, address, self.registers.a());
+                true
             }
-            LdhImm8A => {
-                let a = self.registers.a();
-                let imm8 = instruction.imm8().unwrap() as u16;
-                let address = 0xFF00 + imm8;
-                bus.write(address, a);
+            (LdhImm8A, 2) => {
+                let address = 0xFF00 + self.z as u16;
+                self.write(bus, address, self.registers.a());
+                true
             }
-            LdImm16A => {
-                let a = self.registers.a();
-                let imm16 = instruction.imm16().unwrap();
-                bus.write(imm16, a);
+            (LdImm16A, 3) => {
+                self.write(bus, self.wz(), self.registers.a());
+                true
             }
-            LdhAC => {
-                let c = self.registers.c() as u16;
-                let address = 0xFF00 + c;
-                let a = bus.read(address);
+            (LdhAC, 1) => {
+                let address = 0xFF00 + self.registers.c() as u16;
+                let a = self.read(bus, address);
                 self.registers.set_a(a);
+                true
             }
-            LdhAImm8 => {
-                let imm8 = instruction.imm8().unwrap() as u16;
-                let address = 0xFF00 + imm8;
-                let a = bus.read(address);
+            (LdhAImm8, 2) => {
+                let address = 0xFF00 + self.z as u16;
+                let a = self.read(bus, address);
                 self.registers.set_a(a);
+                true
             }
-            LdAImm16 => {
-                let imm16 = instruction.imm16().unwrap();
-                let a = bus.read(imm16);
+            (LdAImm16, 3) => {
+                let a = self.read(bus, self.wz());
                 self.registers.set_a(a);
+                true
             }
-            AddSpImm8 => {
-                let sp = self.sp;
-                let imm8 = instruction.imm8().unwrap();
-                let val_u16 = imm8 as i8 as u16; // sign-extend into 16-bit two's-complement
-
-                // This does not use the Alu16::add function because it uses 8-bit flags
-                let result = sp.wrapping_add(val_u16);
-                let half_carry = ((sp & 0x0F) + (val_u16 & 0x0F)) > 0x0F;
-                let carry = ((sp & 0xFF) + (val_u16 & 0xFF)) > 0xFF;
-
-                self.sp = result;
-                self.registers
-                    .flags_mut()
-                    .set_z(false)
-                    .set_n(false)
-                    .set_h(half_carry)
-                    .set_c(carry);
+            (AddSpImm8, 3) => {
+                self.sp = self.add_sp_imm8();
+                true
             }
-            LdHlSpImm8 => {
-                let sp = self.sp;
-                let imm8 = instruction.imm8().unwrap();
-                let val_u16 = imm8 as i8 as u16; // sign-extend into 16-bit two's-complement
-
-                // This does not use the Alu16::add function because it uses 8-bit flags
-                let result = sp.wrapping_add(val_u16);
-                let half_carry = ((sp & 0x0F) + (val_u16 & 0x0F)) > 0x0F;
-                let carry = ((sp & 0xFF) + (val_u16 & 0xFF)) > 0xFF;
-
+            (LdHlSpImm8, 2) => {
+                let result = self.add_sp_imm8();
                 self.registers.set_hl(result);
-                self.registers
-                    .flags_mut()
-                    .set_z(false)
-                    .set_n(false)
-                    .set_h(half_carry)
-                    .set_c(carry);
+                true
             }
-            LdSpHl => {
-                let hl = self.registers.hl();
-                self.sp = hl;
+            (LdSpHl, 1) => {
+                self.sp = self.registers.hl();
+                true
             }
-            Di => {
+            (Di, 0) => {
                 self.ime.reset_ime();
+                true
             }
-            Ei => {
+            (Ei, 0) => {
                 self.ime.set_ime();
-            }
-            // CB Prefix instructions
-            RlcR8 => {
-                let r8 = instruction.r8().unwrap();
-                let r_val = self.read_r8(r8, bus);
-                let result = Alu8::rlc(r_val);
-                self.write_r8(r8, *result, bus);
+                true
+            }
+            // CB Prefix instructions on (HL), the register ones complete with the prefix
+            (BitB3R8, 2) => {
+                let value = self.read(bus, self.registers.hl());
+                self.cb_operation(&instruction, value);
+                true
+            }
+            (
+                RlcR8 | RrcR8 | RlR8 | RrR8 | SlaR8 | SraR8 | SwapR8 | SrlR8 | ResB3R8 | SetB3R8,
+                2,
+            ) => {
+                self.z = self.read(bus, self.registers.hl());
+                false
+            }
+            (
+                RlcR8 | RrcR8 | RlR8 | RrR8 | SlaR8 | SraR8 | SwapR8 | SrlR8 | ResB3R8 | SetB3R8,
+                3,
+            ) => {
+                let result = self.cb_operation(&instruction, self.z).unwrap();
+                self.write(bus, self.registers.hl(), result);
+                true
+            }
+            _ => false,
+        }
+    }
 
-                self.registers
-                    .flags_mut()
-                    .set_z_if_zero(*result)
-                    .set_n(false)
-                    .set_h(false)
-                    .set_c(result.cb7);
-            }
-            RrcR8 => {
-                let r8 = instruction.r8().unwrap();
-                let r_val = self.read_r8(r8, bus);
-                let result = Alu8::rrc(r_val);
-                self.write_r8(r8, *result, bus);
+    fn inc_dec(&mut self, instruction_type: InstructionType, value: u8) -> u8 {
+        let decrement = instruction_type == InstructionType::DecR8;
+        let result = if decrement {
+            Alu8::dec(value)
+        } else {
+            Alu8::inc(value)
+        };
+        self.registers
+            .flags_mut()
+            .set_z_if_zero(*result)
+            .set_n(decrement)
+            .set_h(result.cb3);
+        *result
+    }
 
-                self.registers
-                    .flags_mut()
-                    .set_z_if_zero(*result)
-                    .set_n(false)
-                    .set_h(false)
-                    .set_c(result.cb7);
-            }
-            RlR8 => {
-                let r8 = instruction.r8().unwrap();
-                let r_val = self.read_r8(r8, bus);
-                let carry = self.registers.flags().c_u8();
-                let result = Alu8::rl(r_val, carry);
-                self.write_r8(r8, *result, bus);
+    fn rotate_a(&mut self, instruction_type: InstructionType) {
+        let a = self.registers.a();
+        let carry = self.registers.flags().c_u8();
+        let result = match instruction_type {
+            InstructionType::RlCA => Alu8::rlc(a),
+            InstructionType::RrCA => Alu8::rrc(a),
+            InstructionType::RlA => Alu8::rl(a, carry),
+            InstructionType::RrA => Alu8::rr(a, carry),
+            _ => unreachable!(),
+        };
+        self.registers.set_a(*result);
 
+        self.registers
+            .flags_mut()
+            .set_z(false)
+            .set_n(false)
+            .set_h(false)
+            .set_c(result.cb7);
+    }
+
+    /// 8-bit arithmetic and logic on A, with a register, (HL) or immediate operand.
+    fn alu_a(&mut self, instruction_type: InstructionType, value: u8) {
+        use InstructionType::*;
+        let a = self.registers.a();
+        let carry = self.registers.flags().c_u8();
+        match instruction_type {
+            AddAR8 | AddAImm8 | AdcAR8 | AdcAImm8 => {
+                let result = match instruction_type {
+                    AddAR8 | AddAImm8 => Alu8::add(a, value),
+                    _ => Alu8::adc(a, value, carry),
+                };
+                self.registers.set_a(*result);
                 self.registers
                     .flags_mut()
                     .set_z_if_zero(*result)
                     .set_n(false)
-                    .set_h(false)
+                    .set_h(result.cb3)
                     .set_c(result.cb7);
             }
-            RrR8 => {
-                let r8 = instruction.r8().unwrap();
-                let r_val = self.read_r8(r8, bus);
-                let carry = self.registers.flags().c_u8();
-                let result = Alu8::rr(r_val, carry);
-                self.write_r8(r8, *result, bus);
-
+            SubAR8 | SubAImm8 | SbcAR8 | SbcAImm8 | CpAR8 | CpAImm8 => {
+                let result = match instruction_type {
+                    SbcAR8 | SbcAImm8 => Alu8::sbc(a, value, carry),
+                    _ => Alu8::sub(a, value),
+                };
+                if !matches!(instruction_type, CpAR8 | CpAImm8) {
+                    self.registers.set_a(*result);
+                }
                 self.registers
                     .flags_mut()
                     .set_z_if_zero(*result)
-                    .set_n(false)
-                    .set_h(false)
+                    .set_n(true)
+                    .set_h(result.cb3)
                     .set_c(result.cb7);
             }
-            SlaR8 => {
-                let r8 = instruction.r8().unwrap();
-                let r_val = self.read_r8(r8, bus);
-                let result = Alu8::sla(r_val);
-                self.write_r8(r8, *result, bus);
-
+            AndAR8 | AndAImm8 | XorAR8 | XorAImm8 | OrAR8 | OrAImm8 => {
+                let (result, half_carry) = match instruction_type {
+                    AndAR8 | AndAImm8 => (a & value, true),
+                    XorAR8 | XorAImm8 => (a ^ value, false),
+                    _ => (a | value, false),
+                };
+                self.registers.set_a(result);
                 self.registers
                     .flags_mut()
-                    .set_z_if_zero(*result)
+                    .set_z_if_zero(result)
                     .set_n(false)
-                    .set_h(false)
-                    .set_c(result.cb7);
+                    .set_h(half_carry)
+                    .set_c(false);
             }
-            SraR8 => {
-                let r8 = instruction.r8().unwrap();
-                let r_val = self.read_r8(r8, bus);
-                let result = Alu8::sra(r_val);
-                self.write_r8(r8, *result, bus);
+            _ => unreachable!(),
+        }
+    }
+
+    /// SP plus the signed immediate in Z, with the flags of an 8-bit addition.
+    fn add_sp_imm8(&mut self) -> u16 {
+        let sp = self.sp;
+        let val_u16 = self.z as i8 as u16; // sign-extend into 16-bit two's-complement
 
+        // This does not use the Alu16::add function because it uses 8-bit flags
+        let result = sp.wrapping_add(val_u16);
+        let half_carry = ((sp & 0x0F) + (val_u16 & 0x0F)) > 0x0F;
+        let carry = ((sp & 0xFF) + (val_u16 & 0xFF)) > 0xFF;
+
+        self.registers
+            .flags_mut()
+            .set_z(false)
+            .set_n(false)
+            .set_h(half_carry)
+            .set_c(carry);
+        result
+    }
+
+    /// Rotates, shifts and bit operations of the CB prefix.
+    /// Returns the value to write back, BIT only sets flags.
+    fn cb_operation(&mut self, instruction: &Instruction, value: u8) -> Option<u8> {
+        use InstructionType::*;
+        let instruction_type = *instruction.instruction_type();
+        match instruction_type {
+            BitB3R8 => {
+                let bit = instruction.b3().unwrap();
+                let result = value & (1 << bit.value());
                 self.registers
                     .flags_mut()
-                    .set_z_if_zero(*result)
+                    .set_z_if_zero(result)
                     .set_n(false)
-                    .set_h(false)
-                    .set_c(result.cb7);
+                    .set_h(true);
+                None
+            }
+            ResB3R8 => {
+                let bit = instruction.b3().unwrap();
+                Some(value & !(1 << bit.value()))
+            }
+            SetB3R8 => {
+                let bit = instruction.b3().unwrap();
+                Some(value | (1 << bit.value()))
             }
             SwapR8 => {
-                let r8 = instruction.r8().unwrap();
-                let r_val = self.read_r8(r8, bus);
-                let result = r_val.rotate_right(4);
-                self.write_r8(r8, result, bus);
-
+                let result = value.rotate_right(4);
                 self.registers
                     .flags_mut()
                     .set_z_if_zero(result)
                     .set_n(false)
                     .set_h(false)
                     .set_c(false);
+                Some(result)
             }
-            SrlR8 => {
-                let r8 = instruction.r8().unwrap();
-                let r_val = self.read_r8(r8, bus);
-                let result = Alu8::srl(r_val);
-                self.write_r8(r8, *result, bus);
-
+            _ => {
+                let carry = self.registers.flags().c_u8();
+                let result = match instruction_type {
+                    RlcR8 => Alu8::rlc(value),
+                    RrcR8 => Alu8::rrc(value),
+                    RlR8 => Alu8::rl(value, carry),
+                    RrR8 => Alu8::rr(value, carry),
+                    SlaR8 => Alu8::sla(value),
+                    SraR8 => Alu8::sra(value),
+                    SrlR8 => Alu8::srl(value),
+                    _ => unreachable!(),
+                };
                 self.registers
                     .flags_mut()
                     .set_z_if_zero(*result)
                     .set_n(false)
                     .set_h(false)
                     .set_c(result.cb7);
+                Some(*result)
             }
-            BitB3R8 => {
-                let r8 = instruction.r8().unwrap();
-                let r_val = self.read_r8(r8, bus);
-                let bit = instruction.b3().unwrap();
-                let result = r_val & (1 << bit.value());
-                self.registers
-                    .flags_mut()
-                    .set_z_if_zero(result)
-                    .set_n(false)
-                    .set_h(true);
+        }
+    }
+}
+
+impl yagber_app::Component for Cpu {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yagber_memory::Bus;
+
+    const PROGRAM: u16 = 0xC000;
+    const ILLEGAL_OPCODES: [u8; 11] = [
+        0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC, 0xFD,
+    ];
+
+    fn setup(program: &[u8], flags: u8) -> (Cpu, Bus) {
+        let mut bus = Bus::new();
+        for (offset, byte) in program.iter().enumerate() {
+            bus.write(PROGRAM + offset as u16, *byte);
+        }
+        let mut cpu = Cpu::new();
+        cpu.pc = PROGRAM;
+        cpu.sp = 0xDFF0;
+        cpu.registers.set_af(flags as u16);
+        cpu.registers.set_hl(0xD000);
+        (cpu, bus)
+    }
+
+    fn run_instruction(cpu: &mut Cpu, bus: &mut impl CpuBus) -> u16 {
+        let mut cycles = 0;
+        loop {
+            cpu.step(bus);
+            cycles += 1;
+            if cpu.between_instructions() {
+                return cycles;
             }
-            ResB3R8 => {
-                let r8 = instruction.r8().unwrap();
-                let r_val = self.read_r8(r8, bus);
-                let bit = instruction.b3().unwrap();
-                let result = r_val & !(1 << bit.value());
-                self.write_r8(r8, result, bus);
+        }
+    }
+
+    #[test]
+    fn instructions_take_their_documented_mcycles() {
+        for prefixed in [false, true] {
+            for opcode in 0..=0xFF {
+                if !prefixed && opcode == 0xCB {
+                    continue;
+                }
+                let instruction = if prefixed {
+                    Instruction::new_cb_prefix(opcode)
+                } else {
+                    Instruction::new(opcode)
+                };
+                let program = if prefixed {
+                    vec![0xCB, opcode, 0x00, 0x00]
+                } else {
+                    vec![opcode, 0x00, 0x00]
+                };
+
+                // Z and C set, then clear, so every condition is both taken and not taken
+                for flags in [0x90, 0x00] {
+                    let (mut cpu, mut bus) = setup(&program, flags);
+                    let taken = instruction
+                        .cond()
+                        .is_none_or(|cond| cpu.check_condition(cond));
+                    let expected = match instruction.cycles_not_taken() {
+                        Some(cycles) if !taken => cycles,
+                        _ => instruction.cycles(),
+                    };
+                    assert_eq!(
+                        run_instruction(&mut cpu, &mut bus),
+                        expected,
+                        "{instruction:?} with flags {flags:#04X}"
+                    );
+                }
             }
-            SetB3R8 => {
-                let r8 = instruction.r8().unwrap();
-                let r_val = self.read_r8(r8, bus);
-                let bit = instruction.b3().unwrap();
-                let result = r_val | (1 << bit.value());
-                self.write_r8(r8, result, bus);
+        }
+    }
+
+    #[test]
+    fn call_pushes_the_return_address_on_its_last_two_mcycles() {
+        // CALL $C100
+        let (mut cpu, mut bus) = setup(&[0xCD, 0x00, 0xC1], 0x00);
+        bus.write(0xDFEF, 0x00);
+        bus.write(0xDFEE, 0x00);
+        for _ in 0..4 {
+            cpu.step(&mut bus);
+        }
+        assert_eq!(bus.read(0xDFEF), 0x00);
+
+        cpu.step(&mut bus);
+        assert_eq!(bus.read(0xDFEF), 0xC0);
+        assert_eq!(bus.read(0xDFEE), 0x00);
+
+        cpu.step(&mut bus);
+        assert_eq!(bus.read(0xDFEE), 0x03);
+        assert_eq!(cpu.pc(), 0xC100);
+        assert!(cpu.between_instructions());
+    }
+
+    #[test]
+    fn interrupt_dispatch_takes_five_mcycles() {
+        let (mut cpu, mut bus) = setup(&[0x00], 0x00);
+        cpu.ime.set_ime();
+        cpu.ime.update_ime();
+        cpu.ime.update_ime();
+        bus.write(yagber_memory::IOType::IE.address(), 0x01);
+        bus.request_interrupt(yagber_memory::InterruptType::VBlank);
+
+        assert_eq!(run_instruction(&mut cpu, &mut bus), 5);
+        assert_eq!(cpu.pc(), 0x0040);
+        assert_eq!(bus.read(0xDFEF), 0xC0);
+        assert_eq!(bus.read(0xDFEE), 0x00);
+    }
+
+    #[test]
+    fn illegal_opcodes_lock_the_cpu_up() {
+        for opcode in ILLEGAL_OPCODES {
+            // NOP, then the illegal opcode
+            let (mut cpu, mut bus) = setup(&[0x00, opcode, 0x00], 0x00);
+            cpu.ime.set_ime();
+            bus.write(yagber_memory::IOType::IE.address(), 0x01);
+
+            run_instruction(&mut cpu, &mut bus);
+            run_instruction(&mut cpu, &mut bus);
+            assert!(cpu.locked());
+            assert_eq!(
+                InstructionType::from_opcode(opcode),
+                InstructionType::Illegal
+            );
+
+            bus.request_interrupt(yagber_memory::InterruptType::VBlank);
+            for _ in 0..10 {
+                cpu.step(&mut bus);
+                assert!(!cpu.at_instruction_boundary());
             }
+            assert_eq!(cpu.pc(), PROGRAM + 2);
+
+            let event = cpu.locked_event();
+            assert_eq!(event.instruction.address, PROGRAM + 1);
+            assert_eq!(event.instruction.bytes(), [opcode]);
+            assert_eq!(event.history.len(), 2);
         }
     }
-}
 
-impl yagber_app::Component for Cpu {}
+    #[test]
+    fn stop_waits_for_a_joypad_line_to_go_low() {
+        use yagber_memory::IOType;
+
+        // STOP, then NOP skipped as the second byte
+        let (mut cpu, mut bus) = setup(&[0x10, 0x00, 0x00], 0x00);
+        bus.write(IOType::JOYP.address(), 0xEF);
+        bus.write(IOType::DIV.address(), 0xAB);
+
+        assert_eq!(run_instruction(&mut cpu, &mut bus), 1);
+        assert!(bus.stopped());
+        assert_eq!(cpu.pc(), PROGRAM + 2);
+        assert_eq!(bus.read(IOType::DIV.address()), 0x00);
+
+        for _ in 0..10 {
+            cpu.step(&mut bus);
+            assert!(!cpu.at_instruction_boundary());
+        }
+        assert_eq!(cpu.pc(), PROGRAM + 2);
+
+        // Pressing A
+        bus.write(IOType::JOYP.address(), 0xEE);
+        cpu.step(&mut bus);
+        assert!(!bus.stopped());
+        assert_eq!(cpu.pc(), PROGRAM + 3);
+    }
+
+    #[test]
+    fn stop_switches_speed_once_the_pause_is_over() {
+        use yagber_memory::IOType;
+
+        let (mut cpu, mut bus) = setup(&[0x10, 0x00, 0x00], 0x00);
+        bus.write(IOType::JOYP.address(), 0xCF);
+        bus.write(IOType::SPD.address(), 0x01);
+
+        assert_eq!(
+            run_instruction(&mut cpu, &mut bus),
+            1 + Cpu::SPEED_SWITCH_MCYCLES
+        );
+        assert!(!bus.stopped());
+        assert_eq!(cpu.pc(), PROGRAM + 2);
+        assert_eq!(bus.read(IOType::SPD.address()), 0x80);
+    }
+
+    #[test]
+    fn stop_with_a_button_held_halts() {
+        use yagber_memory::IOType;
+
+        let (mut cpu, mut bus) = setup(&[0x10, 0x00, 0x00], 0x00);
+        bus.write(IOType::JOYP.address(), 0xDE);
+        bus.write(IOType::DIV.address(), 0xAB);
+
+        run_instruction(&mut cpu, &mut bus);
+        assert!(cpu.halted());
+        assert!(!bus.stopped());
+        assert_eq!(cpu.pc(), PROGRAM + 2);
+        assert_eq!(bus.read(IOType::DIV.address()), 0xAB);
+    }
+}
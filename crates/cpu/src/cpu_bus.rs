@@ -0,0 +1,189 @@
+use yagber_app::Emulator;
+use yagber_memory::{Bus, IOType, InterruptType, Memory, MemoryAccess, Spd};
+
+/// Memory the CPU runs on, along with the few signals it exchanges with the rest of the machine.
+///
+/// Every [`Memory::read`] and [`Memory::write`] is a bus access made on the current M-cycle.
+/// The defaults fit a flat RAM: IE and IF are plain bytes, no button is ever pressed, STOP
+/// does not stop any clock and there is no speed switch.
+pub trait CpuBus: Memory {
+    /// Interrupts both enabled in IE and requested in IF.
+    fn pending_interrupts(&self) -> u8 {
+        self.read(IOType::IE.address()) & self.read(IOType::IF.address())
+    }
+
+    /// Clears the IF bit of `interrupt` once its dispatch picked it.
+    fn acknowledge_interrupt(&mut self, interrupt: InterruptType) {
+        let address = IOType::IF.address();
+        let value = self.read(address) & !(1 << interrupt.bit());
+        self.write(address, value);
+    }
+
+    /// Bank mapped at `address`, recorded in the instruction history.
+    fn bank(&self, _address: u16) -> u16 {
+        0
+    }
+
+    /// A button of a selected group is pressed.
+    fn joypad_line_low(&self) -> bool {
+        false
+    }
+
+    /// The system clock is stopped by a STOP instruction.
+    fn stopped(&self) -> bool {
+        false
+    }
+
+    fn set_stopped(&mut self, _stopped: bool) {}
+
+    /// STOP resets the divider.
+    fn reset_div(&mut self) {}
+
+    /// KEY1 asks for a speed switch on the next STOP.
+    fn speed_switch_armed(&self) -> bool {
+        false
+    }
+
+    /// Toggles the CPU speed once the speed switch pause is over.
+    fn switch_speed(&mut self) {}
+
+    /// Told about every data access right after it is made.
+    fn record_access(&mut self, _access: MemoryAccess) {}
+
+    /// M-cycles a DMA transfer halts the CPU for, taken once.
+    fn take_stall(&mut self) -> u16 {
+        0
+    }
+}
+
+impl CpuBus for Bus {
+    fn acknowledge_interrupt(&mut self, interrupt: InterruptType) {
+        self.clear_interrupt(interrupt);
+    }
+
+    fn bank(&self, address: u16) -> u16 {
+        Bus::bank(self, address)
+    }
+
+    fn joypad_line_low(&self) -> bool {
+        self.read(IOType::JOYP.address()) & 0x0F != 0x0F
+    }
+
+    fn stopped(&self) -> bool {
+        Bus::stopped(self)
+    }
+
+    fn set_stopped(&mut self, stopped: bool) {
+        Bus::set_stopped(self, stopped);
+    }
+
+    fn reset_div(&mut self) {
+        self.write(IOType::DIV.address(), 0);
+    }
+
+    fn speed_switch_armed(&self) -> bool {
+        Spd::from_bus(self).speed_switch_armed()
+    }
+
+    /// [`yagber_app::Emulator::set_speed_mode`] runs from the SPD hook, so the timer, DIV and
+    /// APU tick at the new speed from the next M-cycle.
+    fn switch_speed(&mut self) {
+        let speed_mode = Spd::from_bus(self).speed_mode().toggle();
+        self.write_io_unchecked(IOType::SPD.address(), speed_mode.as_spd_bit());
+    }
+
+    fn record_access(&mut self, access: MemoryAccess) {
+        Bus::record_access(self, access);
+    }
+
+    fn take_stall(&mut self) -> u16 {
+        self.take_cpu_stall()
+    }
+}
+
+/// The bus of a running emulator, the hooks of other components run before each write returns.
+///
+/// The CPU steps on it split off the emulator, hooks stall it through [`Bus::stall_cpu`].
+pub(crate) struct EmulatorBus<'a> {
+    emulator: &'a mut Emulator,
+}
+
+impl<'a> EmulatorBus<'a> {
+    pub(crate) fn new(emulator: &'a mut Emulator) -> Self {
+        Self { emulator }
+    }
+
+    fn bus(&self) -> &Bus {
+        self.emulator
+            .get_component::<Bus>()
+            .expect("Bus component missing")
+    }
+
+    fn bus_mut(&mut self) -> &mut Bus {
+        self.emulator
+            .get_component_mut::<Bus>()
+            .expect("Bus component missing")
+    }
+
+    /// Changes the bus, then runs the hooks the change triggered.
+    fn hooked(&mut self, f: impl FnOnce(&mut Bus)) {
+        f(self.bus_mut());
+        Bus::run_hooks(self.emulator);
+    }
+}
+
+impl Memory for EmulatorBus<'_> {
+    fn read(&self, address: u16) -> u8 {
+        self.bus().read(address)
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        self.hooked(|bus| bus.write(address, value));
+    }
+}
+
+impl CpuBus for EmulatorBus<'_> {
+    fn pending_interrupts(&self) -> u8 {
+        CpuBus::pending_interrupts(self.bus())
+    }
+
+    fn acknowledge_interrupt(&mut self, interrupt: InterruptType) {
+        self.hooked(|bus| CpuBus::acknowledge_interrupt(bus, interrupt));
+    }
+
+    fn bank(&self, address: u16) -> u16 {
+        CpuBus::bank(self.bus(), address)
+    }
+
+    fn joypad_line_low(&self) -> bool {
+        CpuBus::joypad_line_low(self.bus())
+    }
+
+    fn stopped(&self) -> bool {
+        CpuBus::stopped(self.bus())
+    }
+
+    fn set_stopped(&mut self, stopped: bool) {
+        CpuBus::set_stopped(self.bus_mut(), stopped);
+    }
+
+    fn reset_div(&mut self) {
+        self.hooked(CpuBus::reset_div);
+    }
+
+    fn speed_switch_armed(&self) -> bool {
+        CpuBus::speed_switch_armed(self.bus())
+    }
+
+    fn switch_speed(&mut self) {
+        self.hooked(CpuBus::switch_speed);
+    }
+
+    fn record_access(&mut self, access: MemoryAccess) {
+        CpuBus::record_access(self.bus_mut(), access);
+    }
+
+    fn take_stall(&mut self) -> u16 {
+        CpuBus::take_stall(self.bus_mut())
+    }
+}
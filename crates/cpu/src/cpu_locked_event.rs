@@ -0,0 +1,23 @@
+use crate::ExecutedInstruction;
+
+/// The CPU fetched an illegal opcode and locked up, only a reset brings it back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuLockedEvent {
+    /// The illegal opcode.
+    pub instruction: ExecutedInstruction,
+    /// The instructions that led there, oldest first, ending with the illegal opcode.
+    pub history: Vec<ExecutedInstruction>,
+}
+
+impl std::fmt::Display for CpuLockedEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ExecutedInstruction { bank, address, .. } = self.instruction;
+        write!(
+            f,
+            "CPU locked up on illegal opcode ${:02X} at {bank:02X}:{address:04X}",
+            self.instruction.opcode()
+        )
+    }
+}
+
+impl yagber_app::Event for CpuLockedEvent {}
@@ -1,3 +1,5 @@
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct Ime {
     ime: bool,
@@ -21,6 +23,12 @@ impl Ime {
         }
     }
 
+    /// Sets IME right away, without the delay of EI.
+    pub fn enable(&mut self) {
+        self.ime = true;
+        self.ei_delay = None;
+    }
+
     pub fn reset_ime(&mut self) {
         self.ime = false;
         self.ei_delay = None;
@@ -43,3 +51,18 @@ impl Ime {
         self.interrupt_handling
     }
 }
+
+impl Snapshot for Ime {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_bool(self.ime);
+        writer.write_option_u8(self.ei_delay);
+        writer.write_bool(self.interrupt_handling);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.ime = reader.read_bool()?;
+        self.ei_delay = reader.read_option_u8()?;
+        self.interrupt_handling = reader.read_bool()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+/// An instruction the CPU fetched, with the bank mapped at its address.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutedInstruction {
+    pub bank: u16,
+    pub address: u16,
+    /// Opcode and operands, as read by the CPU.
+    bytes: [u8; 3],
+    length: u8,
+}
+
+impl ExecutedInstruction {
+    pub fn new(bank: u16, address: u16) -> Self {
+        Self {
+            bank,
+            address,
+            ..Self::default()
+        }
+    }
+
+    pub fn opcode(&self) -> u8 {
+        self.bytes[0]
+    }
+
+    /// The bytes read so far, operands the instruction never reached are missing.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes[..usize::from(self.length)]
+    }
+
+    pub(crate) fn push_byte(&mut self, byte: u8) {
+        if let Some(slot) = self.bytes.get_mut(usize::from(self.length)) {
+            *slot = byte;
+            self.length += 1;
+        }
+    }
+}
+
+/// The last [`InstructionHistory::LEN`] instructions the CPU started, kept for diagnostics.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct InstructionHistory {
+    entries: [ExecutedInstruction; Self::LEN],
+    /// Where the next entry goes, the oldest one once the history is full.
+    next: usize,
+    len: usize,
+}
+
+impl InstructionHistory {
+    pub const LEN: usize = 16;
+
+    pub fn push(&mut self, instruction: ExecutedInstruction) {
+        self.entries[self.next] = instruction;
+        self.next = (self.next + 1) % Self::LEN;
+        self.len = (self.len + 1).min(Self::LEN);
+    }
+
+    /// Oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &ExecutedInstruction> {
+        let start = (self.next + Self::LEN - self.len) % Self::LEN;
+        (0..self.len).map(move |index| &self.entries[(start + index) % Self::LEN])
+    }
+
+    pub fn last(&self) -> Option<&ExecutedInstruction> {
+        self.iter().last()
+    }
+
+    pub(crate) fn last_mut(&mut self) -> Option<&mut ExecutedInstruction> {
+        let index = (self.next + Self::LEN - 1) % Self::LEN;
+        (self.len > 0).then(|| &mut self.entries[index])
+    }
+}
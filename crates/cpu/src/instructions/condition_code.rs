@@ -4,7 +4,7 @@ use arbitrary_int::u2;
 pub struct ConditionCode(u2);
 
 impl ConditionCode {
-    pub fn new(value: u2) -> Self {
+    pub const fn new(value: u2) -> Self {
         Self(value)
     }
 
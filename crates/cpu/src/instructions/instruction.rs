@@ -5,11 +5,10 @@ use crate::instructions::{ConditionCode, InstructionType};
 /// Almost all instructions are 1 byte long and the extras are present in the same as the opcode
 /// imm8 and imm16 work a bit differently than the others, they are not present in the opcode
 /// but in the next one and two bytes respectively
-/// Since the instruction does not have access to the memory or pc, these values are passed
-/// later by the instruction decoder
+/// The CPU reads them one M-cycle at a time while executing the instruction
 ///
 /// see [Cpu Instruction Set](https://gbdev.io/pandocs/CPU_Instruction_Set.html) for more details
-#[derive(Default)]
+#[derive(Clone, Copy)]
 pub struct Instruction {
     /// Cb prefix
     cb_prefix: bool,
@@ -17,30 +16,41 @@ pub struct Instruction {
     opcode: u8,
     /// The instruction type
     instruction_type: InstructionType,
-    /// The 8-bit immediate value, if applicable
-    imm8: Option<u8>,
-    /// The 16-bit immediate value, if applicable
-    imm16: Option<u16>,
+    r8: Option<u3>,
+    r8_pair: Option<(u3, u3)>,
+    r16: Option<u2>,
+    cond: Option<ConditionCode>,
+    b3: Option<u3>,
+    tgt3: Option<u3>,
+    imm8: bool,
+    imm16: bool,
+    hl: bool,
+    /// M-cycles with conditional branches taken
+    cycles: u8,
+    /// M-cycles of a conditional branch that is not taken
+    cycles_not_taken: Option<u8>,
+}
+
+/// Every opcode, decoded at compile time
+static INSTRUCTIONS: [Instruction; 256] = Instruction::table(false);
+/// Every opcode following the CB prefix, decoded at compile time
+static CB_INSTRUCTIONS: [Instruction; 256] = Instruction::table(true);
+
+impl Default for Instruction {
+    fn default() -> Self {
+        Self::new(0x00)
+    }
 }
 
 impl Instruction {
     /// Create a new instruction from the opcode
     pub fn new(opcode: u8) -> Self {
-        Self {
-            opcode,
-            instruction_type: InstructionType::from_opcode(opcode),
-            ..Self::default()
-        }
+        INSTRUCTIONS[usize::from(opcode)]
     }
 
     /// Create a new instruction from the CB prefix opcode
     pub fn new_cb_prefix(opcode: u8) -> Self {
-        Self {
-            opcode,
-            cb_prefix: true,
-            instruction_type: InstructionType::from_opcode_cb_prefix(opcode),
-            ..Self::default()
-        }
+        CB_INSTRUCTIONS[usize::from(opcode)]
     }
 
     /// Get the instruction type
@@ -48,21 +58,129 @@ impl Instruction {
         &self.instruction_type
     }
 
-    /// Set the 8-bit immediate value
-    pub fn set_imm8(&mut self, imm8: u8) {
-        self.imm8 = Some(imm8);
+    /// Get the opcode, the byte after the prefix for CB prefix instructions
+    pub fn opcode(&self) -> u8 {
+        self.opcode
     }
 
-    /// Set the 16-bit immediate value
-    pub fn set_imm16(&mut self, imm16: u16) {
-        self.imm16 = Some(imm16);
+    /// Is this one of the instructions following the CB prefix?
+    pub fn cb_prefix(&self) -> bool {
+        self.cb_prefix
     }
 
     /// Do we need to read a signed 8‑bit immediate after the opcode?
     pub fn requires_imm8(&self) -> bool {
+        self.imm8
+    }
+
+    /// Do we need to read a 16‑bit immediate (little‑endian) after the opcode?
+    pub fn requires_imm16(&self) -> bool {
+        self.imm16
+    }
+
+    /// Bytes the CPU reads, including the CB prefix and the immediates
+    pub fn length(&self) -> u8 {
+        1 + self.cb_prefix as u8 + self.imm8 as u8 + 2 * self.imm16 as u8
+    }
+
+    /// Get the r8 from instructions that use it
+    /// r8: 3-bit, one of the 8-bit register
+    pub fn r8(&self) -> Option<u3> {
+        self.r8
+    }
+
+    /// LdR8R8 instructions use two 3-bit registers
+    /// returns the pair (dst, src)
+    pub fn r8_pair(&self) -> Option<(u3, u3)> {
+        self.r8_pair
+    }
+
+    /// Get the r16 from instructions that use it
+    /// r16: 2-bit, one of the 16-bit registers
+    /// r16stk: 2-bit, one of the 16-bit registers of the stack
+    /// r16mem: 2-bit, one of the 16-bit registers of the memory
+    pub fn r16(&self) -> Option<u2> {
+        self.r16
+    }
+
+    /// Get the condition code from instructions that use it
+    /// cond: condition code (z, nz, c, nc)
+    /// The condition code is a 2-bit value
+    pub fn cond(&self) -> Option<ConditionCode> {
+        self.cond
+    }
+
+    /// Get the b3 from instructions that use it
+    /// b3: 3-bit bit index
+    /// The b3 is a 3-bit value
+    pub fn b3(&self) -> Option<u3> {
+        self.b3
+    }
+
+    /// Get the target 3 from instructions that use it
+    /// tgt3: rst target address, divided by 8
+    /// The tgt3 is a 3-bit value
+    pub fn tgt3(&self) -> Option<u3> {
+        self.tgt3
+    }
+
+    /// Is the (HL) memory operand used in place of an 8-bit register?
+    pub fn uses_hl_operand(&self) -> bool {
+        self.hl
+    }
+
+    /// Get the duration in M-cycles of the instruction, with conditional branches taken
+    /// CB prefix instructions include the prefix
+    pub fn cycles(&self) -> u16 {
+        u16::from(self.cycles)
+    }
+
+    /// Get the duration in M-cycles of a conditional branch that is not taken
+    pub fn cycles_not_taken(&self) -> Option<u16> {
+        self.cycles_not_taken.map(u16::from)
+    }
+
+    const fn table(cb_prefix: bool) -> [Self; 256] {
+        let mut table = [Self::decode(false, 0x00); 256];
+        let mut opcode = 0;
+        while opcode < 256 {
+            table[opcode] = Self::decode(cb_prefix, opcode as u8);
+            opcode += 1;
+        }
+        table
+    }
+
+    const fn decode(cb_prefix: bool, opcode: u8) -> Self {
+        let instruction_type = if cb_prefix {
+            InstructionType::from_opcode_cb_prefix(opcode)
+        } else {
+            InstructionType::from_opcode(opcode)
+        };
+        let r8 = Self::decode_r8(cb_prefix, opcode, instruction_type);
+        let r8_pair = Self::decode_r8_pair(opcode, instruction_type);
+        let hl = Self::decode_hl(r8, r8_pair);
+        Self {
+            cb_prefix,
+            opcode,
+            instruction_type,
+            r8,
+            r8_pair,
+            r16: Self::decode_r16(opcode, instruction_type),
+            cond: Self::decode_cond(opcode, instruction_type),
+            b3: Self::decode_b3(opcode, instruction_type),
+            tgt3: Self::decode_tgt3(opcode, instruction_type),
+            imm8: Self::decode_imm8(instruction_type),
+            imm16: Self::decode_imm16(instruction_type),
+            hl,
+            cycles: Self::decode_cycles(instruction_type, hl),
+            cycles_not_taken: Self::decode_cycles_not_taken(instruction_type),
+        }
+    }
+
+    const fn decode_imm8(instruction_type: InstructionType) -> bool {
         use InstructionType::*;
         matches!(
-            self.instruction_type,
+            instruction_type,
             // plain 8‑bit loads and jumps:
             LdR8Imm8
             | JrImm8
@@ -85,11 +203,10 @@ impl Instruction {
         )
     }
 
-    /// Do we need to read a 16‑bit immediate (little‑endian) after the opcode?
-    pub fn requires_imm16(&self) -> bool {
+    const fn decode_imm16(instruction_type: InstructionType) -> bool {
         use InstructionType::*;
         matches!(
-            self.instruction_type,
+            instruction_type,
             // load 16‑bit constants:
             LdR16Imm16
             | LdImm16Sp
@@ -104,125 +221,103 @@ impl Instruction {
         )
     }
 
-    /// Get the 8-bit immediate value
-    pub fn imm8(&self) -> Option<u8> {
-        self.imm8
-    }
-
-    /// Get the 16-bit immediate value
-    pub fn imm16(&self) -> Option<u16> {
-        self.imm16
-    }
-
-    /// Get the r8 from instructions that use it
-    /// r8: 3-bit, one of the 8-bit register
-    pub fn r8(&self) -> Option<u3> {
-        if self.cb_prefix {
-            return Some(u3::from_u8(self.opcode & 0b111));
-        }
+    const fn decode_r8(
+        cb_prefix: bool,
+        opcode: u8,
+        instruction_type: InstructionType,
+    ) -> Option<u3> {
         use InstructionType::*;
-        match self.opcode >> 6 {
-            0b10 => Some(self.opcode & 0b111),
-            0b00 => match self.instruction_type {
+        if cb_prefix {
+            return Some(u3::from_u8(opcode & 0b111));
+        }
+        match opcode >> 6 {
+            0b10 => Some(u3::from_u8(opcode & 0b111)),
+            0b00 => match instruction_type {
                 // 0b00??_?000
-                IncR8 | DecR8 | LdR8Imm8 => Some((self.opcode >> 3) & 0b111),
+                IncR8 | DecR8 | LdR8Imm8 => Some(u3::from_u8((opcode >> 3) & 0b111)),
                 _ => None,
             },
             _ => None,
         }
-        .map(u3::from_u8)
     }
 
-    /// LdR8R8 instructions use two 3-bit registers
-    /// returns the pair (dst, src)
-    pub fn r8_pair(&self) -> Option<(u3, u3)> {
-        use InstructionType::*;
-        match self.instruction_type {
+    const fn decode_r8_pair(opcode: u8, instruction_type: InstructionType) -> Option<(u3, u3)> {
+        match instruction_type {
             // 0b01??_????
-            LdR8R8 => Some(((self.opcode >> 3) & 0b111, self.opcode & 0b111)),
+            InstructionType::LdR8R8 => Some((
+                u3::from_u8((opcode >> 3) & 0b111),
+                u3::from_u8(opcode & 0b111),
+            )),
             _ => None,
         }
-        .map(|(a, b)| (u3::from_u8(a), u3::from_u8(b)))
     }
 
-    /// Get the r16 from instructions that use it
-    /// r16: 2-bit, one of the 16-bit registers
-    /// r16stk: 2-bit, one of the 16-bit registers of the stack
-    /// r16mem: 2-bit, one of the 16-bit registers of the memory
-    pub fn r16(&self) -> Option<u2> {
+    const fn decode_r16(opcode: u8, instruction_type: InstructionType) -> Option<u2> {
         use InstructionType::*;
-
-        if matches!(
-            self.instruction_type,
+        match instruction_type {
             // 0b00??_0000
-            LdR16Imm16
-                | LdR16memA
-                | LdAR16mem
-                | IncR16
-                | DecR16
-                | AddHlR16
-                | PopR16stk
-                | PushR16stk
-        ) {
-            Some(u2::from_u8((self.opcode >> 4) & 0b11))
-        } else {
-            None
+            LdR16Imm16 | LdR16memA | LdAR16mem | IncR16 | DecR16 | AddHlR16 | PopR16stk
+            | PushR16stk => Some(u2::from_u8((opcode >> 4) & 0b11)),
+            _ => None,
         }
     }
 
-    /// Get the condition code from instructions that use it
-    /// cond: condition code (z, nz, c, nc)
-    /// The condition code is a 2-bit value
-    pub fn cond(&self) -> Option<ConditionCode> {
+    const fn decode_cond(opcode: u8, instruction_type: InstructionType) -> Option<ConditionCode> {
         use InstructionType::*;
-        match self.instruction_type {
+        match instruction_type {
             // 0b001?_?000
             JrCondImm8 | RetCond | JpCondImm16 | CallCondImm16 => {
-                Some(ConditionCode::new(u2::from_u8((self.opcode >> 3) & 0b11)))
+                Some(ConditionCode::new(u2::from_u8((opcode >> 3) & 0b11)))
             }
             _ => None,
         }
     }
 
-    /// Get the b3 from instructions that use it
-    /// b3: 3-bit bit index
-    /// The b3 is a 3-bit value
-    pub fn b3(&self) -> Option<u3> {
+    const fn decode_b3(opcode: u8, instruction_type: InstructionType) -> Option<u3> {
         use InstructionType::*;
-        match self.instruction_type {
+        match instruction_type {
             // 0b01??_????
-            BitB3R8 | ResB3R8 | SetB3R8 => Some(u3::from_u8((self.opcode >> 3) & 0b111)),
+            BitB3R8 | ResB3R8 | SetB3R8 => Some(u3::from_u8((opcode >> 3) & 0b111)),
             _ => None,
         }
     }
 
-    /// Get the target 3 from instructions that use it
-    /// tgt3: rst target address, divided by 8
-    /// The tgt3 is a 3-bit value
-    pub fn tgt3(&self) -> Option<u3> {
-        use InstructionType::*;
-        match self.instruction_type {
+    const fn decode_tgt3(opcode: u8, instruction_type: InstructionType) -> Option<u3> {
+        match instruction_type {
             // 0b11??_?111
-            RstTgt3 => Some(u3::from_u8((self.opcode >> 3) & 0b111)),
+            InstructionType::RstTgt3 => Some(u3::from_u8((opcode >> 3) & 0b111)),
             _ => None,
         }
     }
 
-    /// Get the duration in M-cycles of the instruction
-    pub fn cycles(&self) -> u16 {
+    const fn decode_hl(r8: Option<u3>, r8_pair: Option<(u3, u3)>) -> bool {
+        const HL: u8 = 6;
+        let r8_is_hl = match r8 {
+            Some(r8) => r8.value() == HL,
+            None => false,
+        };
+        let pair_has_hl = match r8_pair {
+            Some((dst, src)) => dst.value() == HL || src.value() == HL,
+            None => false,
+        };
+        r8_is_hl || pair_has_hl
+    }
+
+    const fn decode_cycles(instruction_type: InstructionType, hl: bool) -> u8 {
         use InstructionType::*;
-        match self.instruction_type {
+        match instruction_type {
             // 0b00xxxxxx
             Nop => 1,
             LdR16Imm16 => 3,
             LdR16memA => 2,
             LdAR16mem => 2,
-            LdImm16Sp => 3,
+            LdImm16Sp => 5,
             IncR16 => 2,
             DecR16 => 2,
             AddHlR16 => 2,
-            IncR8 => 1,
-            DecR8 => 1,
+            IncR8 | DecR8 if hl => 3,
+            IncR8 | DecR8 => 1,
+            LdR8Imm8 if hl => 3,
             LdR8Imm8 => 2,
             RlCA | RrCA => 1,
             RlA | RrA => 1,
@@ -230,13 +325,15 @@ impl Instruction {
             Cpl => 1,
             Scf => 1,
             Ccf => 1,
-            JrImm8 => 2,
+            JrImm8 => 3,
             JrCondImm8 => 3, // taken
             Stop => 1,
             // 0b01xxxxxx
+            LdR8R8 if hl => 2,
             LdR8R8 => 1,
             Halt => 1,
             // 0b10xxxxxx
+            AddAR8 | AdcAR8 | SubAR8 | SbcAR8 | AndAR8 | XorAR8 | OrAR8 | CpAR8 if hl => 2,
             AddAR8 | AdcAR8 | SubAR8 | SbcAR8 | AndAR8 | XorAR8 | OrAR8 | CpAR8 => 1,
             // 0b11xxxxxx
             AddAImm8 | AdcAImm8 | SubAImm8 | SbcAImm8 | AndAImm8 | XorAImm8 | OrAImm8 | CpAImm8 => {
@@ -255,6 +352,12 @@ impl Instruction {
             PushR16stk => 4,
             // CB‑prefix group (Prefix itself costs 1)
             Prefix => 1,
+            BitB3R8 if hl => 3,
+            RlcR8 | RrcR8 | RlR8 | RrR8 | SlaR8 | SraR8 | SwapR8 | SrlR8 | ResB3R8 | SetB3R8
+                if hl =>
+            {
+                4
+            }
             RlcR8 | RrcR8 | RlR8 | RrR8 | SlaR8 | SraR8 | SwapR8 | SrlR8 => 2,
             BitB3R8 | ResB3R8 | SetB3R8 => 2,
             // High‑page / I/O style loads
@@ -269,6 +372,19 @@ impl Instruction {
             // DI/EI
             Di => 1,
             Ei => 1,
+            // The fetch, then the CPU locks up
+            Illegal => 1,
+        }
+    }
+
+    const fn decode_cycles_not_taken(instruction_type: InstructionType) -> Option<u8> {
+        use InstructionType::*;
+        match instruction_type {
+            JrCondImm8 => Some(2),
+            RetCond => Some(2),
+            JpCondImm16 => Some(3),
+            CallCondImm16 => Some(3),
+            _ => None,
         }
     }
 }
@@ -283,12 +399,6 @@ impl std::fmt::Debug for Instruction {
         if self.cb_prefix {
             dbg.field("cb_prefix", &self.cb_prefix);
         }
-        if let Some(imm8) = self.imm8 {
-            dbg.field("imm8", &format_args!("0x{imm8:02X}"));
-        }
-        if let Some(imm16) = self.imm16 {
-            dbg.field("imm16", &format_args!("0x{imm16:04X}"));
-        }
         if let Some(r8) = self.r8() {
             dbg.field("r8", &format_args!("0x{r8:02X}"));
         }
@@ -2,7 +2,7 @@ use super::binary_utils::match_mask;
 
 /// Blocks are defined by the first two bits of the opcode
 /// where instruction is the instruction name in assembly
-#[derive(Debug, Default, Eq, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
 pub enum InstructionType {
     // Block 0b00
     /// No operation
@@ -137,6 +137,9 @@ pub enum InstructionType {
     Di,
     /// binary: 0b1111_1011
     Ei,
+    /// Locks the CPU up until reset
+    /// binary: 0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC and 0xFD
+    Illegal,
     // $cb prefix instructions
     /// binary: 0b0000_0???
     RlcR8,
@@ -164,7 +167,7 @@ pub enum InstructionType {
 
 impl InstructionType {
     /// Decode the opcode into an instruction type
-    pub fn from_opcode(opcode: u8) -> Self {
+    pub const fn from_opcode(opcode: u8) -> Self {
         // Block 0b00
         if opcode == 0x00 {
             InstructionType::Nop
@@ -297,12 +300,12 @@ impl InstructionType {
         } else if opcode == 0xFB {
             InstructionType::Ei
         } else {
-            panic!("Unimplemented opcode: 0x{opcode:02X}");
+            InstructionType::Illegal
         }
     }
 
     /// Decode a CB‑prefixed opcode (i.e. the byte after 0xCB)
-    pub fn from_opcode_cb_prefix(opcode: u8) -> Self {
+    pub const fn from_opcode_cb_prefix(opcode: u8) -> Self {
         // Top two bits select the major group:
         // 00: rotate/shift/swap, 01: BIT b3, r8, 10: RES b3, r8, 11: SET b3, r8
         match opcode >> 6 {
@@ -317,7 +320,7 @@ impl InstructionType {
                     0x28 => InstructionType::SraR8,
                     0x30 => InstructionType::SwapR8,
                     0x38 => InstructionType::SrlR8,
-                    _ => unreachable!("invalid CB rotate/shift opcode: 0x{:02X}", opcode),
+                    _ => unreachable!(),
                 }
             }
             0b01 => InstructionType::BitB3R8, // 0x40–0x7F
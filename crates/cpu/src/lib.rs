@@ -1,17 +1,35 @@
 mod alu;
 mod cpu;
+mod cpu_bus;
+mod cpu_locked_event;
 mod ime;
+mod instruction_history;
 mod instructions;
 mod registers;
 
 pub use cpu::Cpu;
+pub use cpu_bus::CpuBus;
+pub use cpu_locked_event::CpuLockedEvent;
+pub use instruction_history::{ExecutedInstruction, InstructionHistory};
+pub use instructions::{ConditionCode, Instruction, InstructionType};
+pub use registers::{FlagRegister, Registers};
+pub use yagber_memory::{AccessKind, MemoryAccess};
 
 pub struct CpuPlugin;
 
 impl yagber_app::Plugin for CpuPlugin {
-    fn init(self, emulator: &mut yagber_app::Emulator) {
+    fn init(self, emulator: &mut yagber_app::Emulator) -> Result<(), yagber_app::PluginError> {
         emulator
             .with_component(Cpu::default())
-            .on_mcycle(Cpu::on_mcycle);
+            .with_snapshot::<Cpu>("cpu")
+            .on_mcycle(Cpu::on_mcycle)
+            .on_event(Cpu::on_reset);
+        Ok(())
+    }
+
+    fn dependencies(&self) -> yagber_app::PluginDependencies {
+        yagber_app::PluginDependencies::new()
+            .provides::<Cpu>()
+            .requires::<yagber_memory::Bus>()
     }
 }
@@ -1,5 +1,7 @@
 use std::fmt::Debug;
 
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
+
 #[derive(Debug, Clone, Copy)]
 pub struct Registers {
     a: u8,
@@ -63,7 +65,7 @@ impl Registers {
         FlagRegister::new(self.f)
     }
 
-    pub fn flags_mut(&mut self) -> FlagRegisterMut {
+    pub fn flags_mut(&mut self) -> FlagRegisterMut<'_> {
         FlagRegisterMut::new(&mut self.f)
     }
 
@@ -245,3 +247,29 @@ impl<'r> FlagRegisterMut<'r> {
         self
     }
 }
+
+impl Snapshot for Registers {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        for value in [
+            self.a, self.b, self.c, self.d, self.e, self.f, self.h, self.l,
+        ] {
+            writer.write_u8(value);
+        }
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        for value in [
+            &mut self.a,
+            &mut self.b,
+            &mut self.c,
+            &mut self.d,
+            &mut self.e,
+            &mut self.f,
+            &mut self.h,
+            &mut self.l,
+        ] {
+            *value = reader.read_u8()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,272 @@
+//! Runs the SM83 single step tests from <https://github.com/SingleStepTests/sm83>.
+//!
+//! Each JSON file holds the vectors of one opcode: the initial state, the state after running
+//! the instruction and the bus activity of every M-cycle. The CPU runs on a flat 64 KiB RAM.
+//!
+//! The vectors follow the fetch/execute overlap of the hardware: the opcode at the initial PC
+//! was fetched by the previous instruction, and the last M-cycle fetches the next opcode.
+
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use yagber_cpu::{AccessKind, Cpu, CpuBus, MemoryAccess};
+use yagber_memory::{IOType, InterruptType, Memory};
+
+const TESTS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../test_roms/sm83/v1/");
+
+/// M-cycles after which a test is given up, the longest instruction takes 6.
+const MAX_MCYCLES: usize = 8;
+
+/// JSON files of a complete checkout, one per legal opcode and CB-prefixed opcode.
+const MIN_TEST_FILES: usize = 500;
+
+/// Failures printed per opcode, the rest are only counted.
+const MAX_REPORTED_FAILURES: usize = 3;
+
+#[derive(Debug, Deserialize)]
+struct TestCase {
+    name: String,
+    initial: State,
+    #[serde(rename = "final")]
+    expected: State,
+    cycles: Vec<Option<Cycle>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct State {
+    pc: u16,
+    sp: u16,
+    a: u8,
+    b: u8,
+    c: u8,
+    d: u8,
+    e: u8,
+    f: u8,
+    h: u8,
+    l: u8,
+    ime: u8,
+    ie: Option<u8>,
+    ram: Vec<(u16, u8)>,
+}
+
+/// Address, data and pins of an M-cycle, `r-m` for a read and `-wm` for a write.
+#[derive(Debug, Deserialize)]
+struct Cycle(Option<u16>, Option<u8>, String);
+
+impl Cycle {
+    fn access(&self) -> Option<MemoryAccess> {
+        let kind = match self.2.as_bytes() {
+            [b'r', ..] => AccessKind::Read,
+            [_, b'w', ..] => AccessKind::Write,
+            _ => return None,
+        };
+        Some(MemoryAccess {
+            address: self.0?,
+            value: self.1?,
+            kind,
+        })
+    }
+}
+
+/// Flat RAM recording every access, IE and IF are plain bytes.
+struct FlatMemory {
+    ram: Vec<u8>,
+    accesses: RefCell<Vec<MemoryAccess>>,
+}
+
+impl FlatMemory {
+    fn new() -> Self {
+        Self {
+            ram: vec![0; 0x10000],
+            accesses: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn take_accesses(&self) -> Vec<MemoryAccess> {
+        std::mem::take(&mut self.accesses.borrow_mut())
+    }
+}
+
+impl Memory for FlatMemory {
+    fn read(&self, address: u16) -> u8 {
+        let value = self.ram[address as usize];
+        self.accesses.borrow_mut().push(MemoryAccess {
+            address,
+            value,
+            kind: AccessKind::Read,
+        });
+        value
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        self.ram[address as usize] = value;
+        self.accesses.borrow_mut().push(MemoryAccess {
+            address,
+            value,
+            kind: AccessKind::Write,
+        });
+    }
+}
+
+/// Interrupt checks are internal to the CPU, they do not show on the bus.
+impl CpuBus for FlatMemory {
+    fn pending_interrupts(&self) -> u8 {
+        self.ram[IOType::IE.address() as usize] & self.ram[IOType::IF.address() as usize]
+    }
+
+    fn acknowledge_interrupt(&mut self, interrupt: InterruptType) {
+        self.ram[IOType::IF.address() as usize] &= !(1 << interrupt.bit());
+    }
+}
+
+fn setup(state: &State) -> (Cpu, FlatMemory) {
+    let mut memory = FlatMemory::new();
+    for &(address, value) in &state.ram {
+        memory.ram[address as usize] = value;
+    }
+    if let Some(ie) = state.ie {
+        memory.ram[IOType::IE.address() as usize] = ie;
+    }
+
+    let mut cpu = Cpu::new();
+    cpu.set_pc(state.pc);
+    cpu.set_sp(state.sp);
+    cpu.set_ime(state.ime != 0);
+    let registers = cpu.registers_mut();
+    registers.set_af(u16::from_be_bytes([state.a, state.f]));
+    registers.set_bc(u16::from_be_bytes([state.b, state.c]));
+    registers.set_de(u16::from_be_bytes([state.d, state.e]));
+    registers.set_hl(u16::from_be_bytes([state.h, state.l]));
+    (cpu, memory)
+}
+
+/// Runs the instruction at PC, returning the accesses of each M-cycle it took.
+fn run_instruction(cpu: &mut Cpu, memory: &mut FlatMemory) -> Vec<Vec<MemoryAccess>> {
+    let mut cycles = Vec::new();
+    while cycles.len() < MAX_MCYCLES {
+        cpu.step(memory);
+        cycles.push(memory.take_accesses());
+        if cpu.at_instruction_boundary() {
+            break;
+        }
+    }
+    cycles
+}
+
+fn run_test(test: &TestCase) -> Result<(), String> {
+    let (mut cpu, mut memory) = setup(&test.initial);
+    let mut cycles = run_instruction(&mut cpu, &mut memory);
+
+    // Shift the opcode fetch of this instruction out and the one of the next instruction in
+    if !cycles.is_empty() {
+        cycles.remove(0);
+    }
+    let next_pc = cpu.pc();
+    cycles.push(vec![MemoryAccess {
+        address: next_pc,
+        value: memory.ram[next_pc as usize],
+        kind: AccessKind::Read,
+    }]);
+
+    let mut errors = Vec::new();
+    let expected_cycles = test
+        .cycles
+        .iter()
+        .map(|cycle| cycle.as_ref().and_then(Cycle::access).into_iter().collect())
+        .collect::<Vec<Vec<_>>>();
+    if cycles != expected_cycles {
+        errors.push(format!(
+            "cycles: expected {expected_cycles:X?}, got {cycles:X?}"
+        ));
+    }
+
+    let expected = &test.expected;
+    let registers = cpu.registers();
+    let actual_registers = [
+        ("pc", next_pc.wrapping_add(1), expected.pc),
+        ("sp", cpu.sp(), expected.sp),
+        ("a", registers.a().into(), expected.a.into()),
+        ("f", registers.f().into(), expected.f.into()),
+        ("b", registers.b().into(), expected.b.into()),
+        ("c", registers.c().into(), expected.c.into()),
+        ("d", registers.d().into(), expected.d.into()),
+        ("e", registers.e().into(), expected.e.into()),
+        ("h", registers.h().into(), expected.h.into()),
+        ("l", registers.l().into(), expected.l.into()),
+        ("ime", cpu.ime().into(), expected.ime.into()),
+    ];
+    for (name, actual, expected) in actual_registers {
+        if actual != expected {
+            errors.push(format!(
+                "{name}: expected {expected:#06X}, got {actual:#06X}"
+            ));
+        }
+    }
+
+    for &(address, value) in &expected.ram {
+        let actual = memory.ram[address as usize];
+        if actual != value {
+            errors.push(format!(
+                "ram[{address:#06X}]: expected {value:#04X}, got {actual:#04X}"
+            ));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("\n  "))
+    }
+}
+
+/// Runs every test of an opcode file, returning the number of tests and of failures.
+fn run_file(path: &Path) -> (usize, usize) {
+    let json = fs::read_to_string(path).expect("Failed to read test file");
+    let tests: Vec<TestCase> = serde_json::from_str(&json)
+        .unwrap_or_else(|error| panic!("Failed to parse {path:?}: {error}"));
+
+    let mut failures = 0;
+    for test in &tests {
+        if let Err(error) = run_test(test) {
+            failures += 1;
+            if failures <= MAX_REPORTED_FAILURES {
+                println!("{}:\n  {error}", test.name);
+            }
+        }
+    }
+    if failures > 0 {
+        println!("{path:?}: {failures}/{} failed", tests.len());
+    }
+    (tests.len(), failures)
+}
+
+#[test]
+fn test_sm83_single_step() {
+    assert!(fs::metadata(TESTS_PATH).is_ok(), "Test ROM not found!");
+
+    let mut paths = fs::read_dir(TESTS_PATH)
+        .expect("Failed to read the SM83 tests")
+        .map(|entry| entry.expect("Failed to read test entry").path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+    assert!(
+        paths.len() >= MIN_TEST_FILES,
+        "Expected at least {MIN_TEST_FILES} SM83 test files, found {}",
+        paths.len()
+    );
+
+    let (mut total, mut failed) = (0, 0);
+    for path in &paths {
+        let (tests, failures) = run_file(path);
+        total += tests;
+        failed += failures;
+    }
+    println!("{} opcodes, {failed}/{total} tests failed", paths.len());
+    assert_eq!(failed, 0);
+}
@@ -0,0 +1,40 @@
+use crate::{CodeLocation, Expression};
+
+/// Stops before the instruction at a location runs, if its condition holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Breakpoint {
+    location: CodeLocation,
+    condition: Option<Expression>,
+}
+
+impl Breakpoint {
+    pub fn new(location: CodeLocation) -> Self {
+        Self {
+            location,
+            condition: None,
+        }
+    }
+
+    pub fn with_condition(mut self, condition: Expression) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
+    pub fn location(&self) -> CodeLocation {
+        self.location
+    }
+
+    pub fn condition(&self) -> Option<&Expression> {
+        self.condition.as_ref()
+    }
+}
+
+impl std::fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "break {}", self.location)?;
+        if let Some(condition) = &self.condition {
+            write!(f, " if {condition}")?;
+        }
+        Ok(())
+    }
+}
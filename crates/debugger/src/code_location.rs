@@ -0,0 +1,75 @@
+use std::str::FromStr;
+
+use crate::parse_error::{ParseError, parse_address};
+
+/// Address of an instruction, optionally tied to the bank mapped there.
+///
+/// Written `BB:AAAA` with a bank, such as `03:4A10`, or `AAAA` to match every bank,
+/// both in hexadecimal. Banks follow [`yagber_memory::Bus::bank`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeLocation {
+    pub bank: Option<u16>,
+    pub address: u16,
+}
+
+impl CodeLocation {
+    pub fn new(address: u16) -> Self {
+        Self {
+            bank: None,
+            address,
+        }
+    }
+
+    pub fn with_bank(mut self, bank: u16) -> Self {
+        self.bank = Some(bank);
+        self
+    }
+
+    pub fn matches(&self, bank: u16, address: u16) -> bool {
+        self.address == address && self.bank.is_none_or(|expected| expected == bank)
+    }
+}
+
+impl FromStr for CodeLocation {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.split_once(':') {
+            Some((bank, address)) => {
+                let bank = u16::from_str_radix(bank, 16)
+                    .map_err(|_| ParseError::InvalidNumber(bank.to_string()))?;
+                Ok(Self::new(parse_address(address)?).with_bank(bank))
+            }
+            None => Ok(Self::new(parse_address(text)?)),
+        }
+    }
+}
+
+impl std::fmt::Display for CodeLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.bank {
+            Some(bank) => write!(f, "{bank:02X}:{:04X}", self.address),
+            None => write!(f, "{:04X}", self.address),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_banked_and_unbanked_locations() {
+        let location = "03:4A10".parse::<CodeLocation>().unwrap();
+        assert_eq!(location, CodeLocation::new(0x4A10).with_bank(3));
+        assert!(location.matches(3, 0x4A10));
+        assert!(!location.matches(4, 0x4A10));
+
+        let location = "$0150".parse::<CodeLocation>().unwrap();
+        assert!(location.matches(0, 0x0150) && location.matches(7, 0x0150));
+        assert_eq!(location.to_string(), "0150");
+
+        assert!("03:".parse::<CodeLocation>().is_err());
+        assert!("4A10G".parse::<CodeLocation>().is_err());
+    }
+}
@@ -0,0 +1,298 @@
+use std::{fmt::Write, str::FromStr};
+
+use yagber_app::Emulator;
+use yagber_cpu::Cpu;
+use yagber_memory::Bus;
+
+use crate::{
+    Breakpoint, CodeLocation, Debugger, Expression, WatchKind, Watchpoint,
+    parse_error::{ParseError, parse_address},
+};
+
+/// A line of the REPL, see [`Command::HELP`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Command {
+    Break(Breakpoint),
+    Watch(Watchpoint),
+    Delete(Option<u32>),
+    List,
+    Continue,
+    Step,
+    Next,
+    Finish,
+    Pause,
+    Registers,
+    Examine { address: u16, length: u16 },
+    Print(Expression),
+    Help,
+    Quit,
+}
+
+impl Command {
+    pub(crate) const HELP: &'static str = "\
+break LOCATION [if EXPR]    stop at LOCATION, `AAAA` or `BB:AAAA` in hexadecimal
+watch START[-END] [if EXPR] stop after a write to the address range
+rwatch, awatch              same as watch, for reads or for any access
+delete [ID]                 remove a breakpoint or watchpoint, or all of them
+list                        list breakpoints and watchpoints
+continue, c                 resume until the next stop
+step, s                     run one instruction, entering calls
+next, n                     run one instruction, stepping over calls
+finish, f                   run until the current function returns
+pause                       stop after the current instruction
+registers, r                show the CPU registers
+x ADDRESS [LENGTH]          dump LENGTH bytes of memory, 16 by default
+print EXPR, p EXPR          evaluate an expression, such as `[hl] + 1 == a`
+quit, q                     exit the emulator";
+
+    const DEFAULT_EXAMINE_LENGTH: u16 = 16;
+
+    /// Runs the command, returns what the REPL prints.
+    pub(crate) fn execute(self, emulator: &mut Emulator) -> String {
+        match self {
+            Command::Break(breakpoint) => {
+                let text = breakpoint.to_string();
+                let id = debugger(emulator).add_breakpoint(breakpoint);
+                format!("{id}: {text}")
+            }
+            Command::Watch(watchpoint) => {
+                let text = watchpoint.to_string();
+                let id = debugger(emulator).add_watchpoint(watchpoint);
+                format!("{id}: {text}")
+            }
+            Command::Delete(Some(id)) => {
+                if debugger(emulator).remove(id) {
+                    format!("deleted {id}")
+                } else {
+                    format!("no breakpoint or watchpoint {id}")
+                }
+            }
+            Command::Delete(None) => {
+                debugger(emulator).clear();
+                "deleted every breakpoint and watchpoint".to_string()
+            }
+            Command::List => list(debugger(emulator)),
+            Command::Continue => {
+                Debugger::resume(emulator);
+                String::new()
+            }
+            Command::Step => {
+                Debugger::step_into(emulator);
+                String::new()
+            }
+            Command::Next => {
+                Debugger::step_over(emulator);
+                String::new()
+            }
+            Command::Finish => {
+                Debugger::step_out(emulator);
+                String::new()
+            }
+            Command::Pause => {
+                Debugger::pause(emulator);
+                String::new()
+            }
+            Command::Registers => {
+                let (cpu, bus) = machine(emulator);
+                registers(cpu, bus)
+            }
+            Command::Examine { address, length } => {
+                let (_, bus) = machine(emulator);
+                examine(bus, address, length)
+            }
+            Command::Print(expression) => {
+                let (cpu, bus) = machine(emulator);
+                let value = expression.evaluate(cpu, bus);
+                format!("{value} (${value:X})")
+            }
+            Command::Help => Self::HELP.to_string(),
+            Command::Quit => {
+                emulator.exit();
+                String::new()
+            }
+        }
+    }
+}
+
+impl FromStr for Command {
+    type Err = ParseError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let line = line.trim();
+        let (word, arguments) = line.split_once(' ').unwrap_or((line, ""));
+        if let "print" | "p" = word {
+            return Ok(Command::Print(arguments.parse()?));
+        }
+        let (arguments, condition) = match arguments.split_once(" if ") {
+            Some((arguments, condition)) => (arguments, Some(condition.parse::<Expression>()?)),
+            None => (arguments, None),
+        };
+        let conditional = matches!(word, "break" | "b" | "watch" | "rwatch" | "awatch");
+        if condition.is_some() && !conditional {
+            return Err(ParseError::UnexpectedToken("if".to_string()));
+        }
+        let mut arguments = arguments.split_whitespace();
+        let mut argument = |name| arguments.next().ok_or(ParseError::MissingArgument(name));
+
+        let command = match word {
+            "break" | "b" => {
+                let location = argument("location")?.parse::<CodeLocation>()?;
+                let breakpoint = Breakpoint::new(location);
+                Command::Break(match condition {
+                    Some(condition) => breakpoint.with_condition(condition),
+                    None => breakpoint,
+                })
+            }
+            "watch" | "rwatch" | "awatch" => {
+                let kind = match word {
+                    "watch" => WatchKind::Write,
+                    "rwatch" => WatchKind::Read,
+                    _ => WatchKind::Access,
+                };
+                let range = argument("address range")?;
+                let (start, end) = range.split_once('-').unwrap_or((range, range));
+                let watchpoint = Watchpoint::new(parse_address(start)?..=parse_address(end)?, kind);
+                Command::Watch(match condition {
+                    Some(condition) => watchpoint.with_condition(condition),
+                    None => watchpoint,
+                })
+            }
+            "delete" | "d" => Command::Delete(
+                arguments
+                    .next()
+                    .map(|id| {
+                        id.parse()
+                            .map_err(|_| ParseError::InvalidNumber(id.to_string()))
+                    })
+                    .transpose()?,
+            ),
+            "list" | "l" => Command::List,
+            "continue" | "c" => Command::Continue,
+            "step" | "s" => Command::Step,
+            "next" | "n" => Command::Next,
+            "finish" | "f" => Command::Finish,
+            "pause" => Command::Pause,
+            "registers" | "r" => Command::Registers,
+            "x" => {
+                let address = parse_address(argument("address")?)?;
+                let length = match arguments.next() {
+                    Some(length) => length
+                        .parse()
+                        .map_err(|_| ParseError::InvalidNumber(length.to_string()))?,
+                    None => Self::DEFAULT_EXAMINE_LENGTH,
+                };
+                Command::Examine { address, length }
+            }
+            "help" | "h" => Command::Help,
+            "quit" | "q" => Command::Quit,
+            _ => return Err(ParseError::UnknownCommand(word.to_string())),
+        };
+        if let Some(extra) = arguments.next() {
+            return Err(ParseError::UnexpectedToken(extra.to_string()));
+        }
+        Ok(command)
+    }
+}
+
+fn debugger(emulator: &mut Emulator) -> &mut Debugger {
+    emulator
+        .get_component_mut::<Debugger>()
+        .expect("Debugger component missing")
+}
+
+fn machine(emulator: &Emulator) -> (&Cpu, &Bus) {
+    let cpu = emulator
+        .get_component::<Cpu>()
+        .expect("Cpu component missing");
+    let bus = emulator
+        .get_component::<Bus>()
+        .expect("Bus component missing");
+    (cpu, bus)
+}
+
+fn list(debugger: &Debugger) -> String {
+    let mut text = String::new();
+    for (id, breakpoint) in debugger.breakpoints() {
+        let _ = writeln!(text, "{id}: {breakpoint}");
+    }
+    for (id, watchpoint) in debugger.watchpoints() {
+        let _ = writeln!(text, "{id}: {watchpoint}");
+    }
+    if text.is_empty() {
+        return "no breakpoints or watchpoints".to_string();
+    }
+    text.trim_end().to_string()
+}
+
+pub(crate) fn registers(cpu: &Cpu, bus: &Bus) -> String {
+    let registers = cpu.registers();
+    let flags = registers.flags();
+    let flag = |set: bool, name: char| if set { name } else { '-' };
+    format!(
+        "AF={:04X} BC={:04X} DE={:04X} HL={:04X} SP={:04X} PC={} IME={} [{}{}{}{}]",
+        registers.af(),
+        registers.bc(),
+        registers.de(),
+        registers.hl(),
+        cpu.sp(),
+        Debugger::location(cpu, bus),
+        cpu.ime() as u8,
+        flag(flags.z(), 'Z'),
+        flag(flags.n(), 'N'),
+        flag(flags.h(), 'H'),
+        flag(flags.c(), 'C'),
+    )
+}
+
+fn examine(bus: &Bus, address: u16, length: u16) -> String {
+    let mut text = String::new();
+    for offset in 0..length {
+        let current = address.wrapping_add(offset);
+        if offset % 16 == 0 {
+            if offset != 0 {
+                text.push('\n');
+            }
+            let _ = write!(text, "{current:04X}:");
+        }
+        let _ = write!(text, " {:02X}", bus.read(current));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!("c".parse::<Command>(), Ok(Command::Continue));
+        assert_eq!(
+            "x c000 32".parse::<Command>(),
+            Ok(Command::Examine {
+                address: 0xC000,
+                length: 32
+            })
+        );
+
+        let Ok(Command::Break(breakpoint)) = "break 03:4A10 if a == $3C".parse::<Command>() else {
+            panic!("expected a breakpoint");
+        };
+        assert_eq!(breakpoint.to_string(), "break 03:4A10 if a == $3C");
+
+        let Ok(Command::Watch(watchpoint)) = "awatch FF40-FF4B".parse::<Command>() else {
+            panic!("expected a watchpoint");
+        };
+        assert_eq!(watchpoint.kind(), WatchKind::Access);
+        assert_eq!(watchpoint.range(), &(0xFF40..=0xFF4B));
+
+        assert_eq!(
+            "frobnicate".parse::<Command>(),
+            Err(ParseError::UnknownCommand("frobnicate".to_string()))
+        );
+        assert_eq!(
+            "break".parse::<Command>(),
+            Err(ParseError::MissingArgument("location"))
+        );
+        assert!("step 3".parse::<Command>().is_err());
+    }
+}
@@ -0,0 +1,345 @@
+use std::collections::BTreeMap;
+
+use yagber_app::Emulator;
+use yagber_cpu::Cpu;
+use yagber_memory::Bus;
+
+use crate::{Breakpoint, CodeLocation, DebuggerStoppedEvent, StopReason, Watchpoint};
+
+/// Pauses the emulator on breakpoints, watchpoints and finished steps.
+///
+/// The emulator only ever stops between two instructions. Breakpoints and watchpoints share
+/// one id sequence, ids are never reused.
+#[derive(Debug, Default)]
+pub struct Debugger {
+    breakpoints: BTreeMap<u32, Breakpoint>,
+    watchpoints: BTreeMap<u32, Watchpoint>,
+    next_id: u32,
+    step: Option<Step>,
+    /// Watchpoint hit by the instruction in progress, reported once it is done.
+    pending: Option<StopReason>,
+    last_stop: Option<StopReason>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Step {
+    /// Stops on the next instruction boundary.
+    Into,
+    /// Stops once the call started at the step returns to `address`, or on the next
+    /// boundary when the step did not start on a call.
+    Over { address: u16, sp: u16 },
+    /// Stops after the return that pops the frame the step started in.
+    Out { sp: u16, returning: bool },
+    /// Stops on the next instruction boundary, reported as [`StopReason::Paused`].
+    Pause,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the breakpoint id.
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> u32 {
+        let id = self.allocate_id();
+        self.breakpoints.insert(id, breakpoint);
+        id
+    }
+
+    /// Returns the watchpoint id.
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) -> u32 {
+        let id = self.allocate_id();
+        self.watchpoints.insert(id, watchpoint);
+        id
+    }
+
+    fn allocate_id(&mut self) -> u32 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    /// Removes the breakpoint or watchpoint `id`, returns whether it existed.
+    pub fn remove(&mut self, id: u32) -> bool {
+        self.breakpoints.remove(&id).is_some() || self.watchpoints.remove(&id).is_some()
+    }
+
+    /// Removes every breakpoint and watchpoint.
+    pub fn clear(&mut self) {
+        self.breakpoints.clear();
+        self.watchpoints.clear();
+    }
+
+    pub fn watchpoint(&self, id: u32) -> Option<&Watchpoint> {
+        self.watchpoints.get(&id)
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = (u32, &Breakpoint)> {
+        self.breakpoints
+            .iter()
+            .map(|(id, breakpoint)| (*id, breakpoint))
+    }
+
+    pub fn watchpoints(&self) -> impl Iterator<Item = (u32, &Watchpoint)> {
+        self.watchpoints
+            .iter()
+            .map(|(id, watchpoint)| (*id, watchpoint))
+    }
+
+    /// Why the debugger last paused the emulator.
+    pub fn last_stop(&self) -> Option<StopReason> {
+        self.last_stop
+    }
+
+    /// Where the CPU is, with the bank mapped at the program counter.
+    pub fn location(cpu: &Cpu, bus: &Bus) -> CodeLocation {
+        CodeLocation::new(cpu.pc()).with_bank(bus.bank(cpu.pc()))
+    }
+
+    /// Resumes until the next breakpoint or watchpoint.
+    pub fn resume(emulator: &mut Emulator) {
+        Self::start(emulator, None);
+    }
+
+    /// Runs a single instruction, entering calls and interrupt handlers.
+    pub fn step_into(emulator: &mut Emulator) {
+        Self::start(emulator, Some(Step::Into));
+    }
+
+    /// Runs a single instruction, a call or `RST` runs until it returns.
+    pub fn step_over(emulator: &mut Emulator) {
+        let step = emulator
+            .get_components_mut2::<Cpu, Bus>()
+            .map(|(cpu, bus)| {
+                let length = match bus.read(cpu.pc()) {
+                    // CALL a16, CALL cc, a16
+                    0xCD | 0xC4 | 0xCC | 0xD4 | 0xDC => 3,
+                    // RST
+                    0xC7 | 0xCF | 0xD7 | 0xDF | 0xE7 | 0xEF | 0xF7 | 0xFF => 1,
+                    _ => return Step::Into,
+                };
+                Step::Over {
+                    address: cpu.pc().wrapping_add(length),
+                    sp: cpu.sp(),
+                }
+            });
+        Self::start(emulator, step);
+    }
+
+    /// Runs until the current function returns to its caller.
+    pub fn step_out(emulator: &mut Emulator) {
+        let step = emulator
+            .get_components_mut2::<Cpu, Bus>()
+            .map(|(cpu, bus)| Step::Out {
+                sp: cpu.sp(),
+                returning: is_return(bus.read(cpu.pc())),
+            });
+        Self::start(emulator, step);
+    }
+
+    /// Stops a running emulator once the current instruction is done.
+    pub fn pause(emulator: &mut Emulator) {
+        if emulator.is_paused() {
+            return;
+        }
+        if let Some(debugger) = emulator.get_component_mut::<Self>() {
+            debugger.step = Some(Step::Pause);
+        }
+    }
+
+    fn start(emulator: &mut Emulator, step: Option<Step>) {
+        if let Some(debugger) = emulator.get_component_mut::<Self>() {
+            debugger.step = step;
+        }
+        emulator.resume();
+    }
+
+    pub(crate) fn on_mcycle(emulator: &mut Emulator) {
+        let (debugger, cpu, bus) = emulator
+            .get_components_mut3::<Self, Cpu, Bus>()
+            .expect("Debugger, Cpu and/or Bus component missing");
+        let reason = debugger.check(cpu, bus);
+        bus.clear_accesses();
+        if let Some(reason) = reason {
+            emulator.pause();
+            emulator.emit(DebuggerStoppedEvent { reason });
+        }
+    }
+
+    fn check(&mut self, cpu: &Cpu, bus: &Bus) -> Option<StopReason> {
+        if self.pending.is_none() {
+            self.pending = bus.accesses().iter().find_map(|&access| {
+                self.watchpoints
+                    .iter()
+                    .find(|(_, watchpoint)| {
+                        watchpoint.matches(access.address, access.kind)
+                            && watchpoint
+                                .condition()
+                                .is_none_or(|condition| condition.is_true(cpu, bus))
+                    })
+                    .map(|(id, _)| StopReason::Watchpoint { id: *id, access })
+            });
+        }
+
+        if !cpu.at_instruction_boundary() {
+            return None;
+        }
+        let reason = self
+            .pending
+            .take()
+            .or_else(|| self.step_done(cpu, bus))
+            .or_else(|| self.breakpoint_hit(cpu, bus))?;
+        self.step = None;
+        self.last_stop = Some(reason);
+        Some(reason)
+    }
+
+    fn step_done(&mut self, cpu: &Cpu, bus: &Bus) -> Option<StopReason> {
+        match self.step.as_mut()? {
+            Step::Into => Some(StopReason::Step),
+            Step::Pause => Some(StopReason::Paused),
+            Step::Over { address, sp } => {
+                (cpu.pc() == *address && cpu.sp() >= *sp).then_some(StopReason::Step)
+            }
+            Step::Out { sp, returning } => {
+                if *returning && cpu.sp() > *sp {
+                    return Some(StopReason::Step);
+                }
+                // An interrupt dispatched instead of the return is popped by its RETI
+                *returning = is_return(bus.read(cpu.pc()));
+                None
+            }
+        }
+    }
+
+    fn breakpoint_hit(&self, cpu: &Cpu, bus: &Bus) -> Option<StopReason> {
+        if self.breakpoints.is_empty() {
+            return None;
+        }
+        let bank = bus.bank(cpu.pc());
+        self.breakpoints
+            .iter()
+            .find(|(_, breakpoint)| {
+                breakpoint.location().matches(bank, cpu.pc())
+                    && breakpoint
+                        .condition()
+                        .is_none_or(|condition| condition.is_true(cpu, bus))
+            })
+            .map(|(id, _)| StopReason::Breakpoint { id: *id })
+    }
+}
+
+/// RET, RETI and RET cc.
+fn is_return(opcode: u8) -> bool {
+    matches!(opcode, 0xC9 | 0xD9 | 0xC0 | 0xC8 | 0xD0 | 0xD8)
+}
+
+impl yagber_app::Component for Debugger {}
+
+#[cfg(test)]
+mod tests {
+    use yagber_cpu::{AccessKind, MemoryAccess};
+
+    use super::*;
+    use crate::{DebuggerPlugin, WatchKind};
+
+    /// Runs the CGB boot ROM, which starts with:
+    /// `0000 LD SP,$FFFE`, `0003 LD A,$02`, `0005 JP $007C`, `007C LDH ($70),A`,
+    /// `007E LD A,$FC`, `0080 LDH ($47),A`, `0082 CALL $0275`, `0085 CALL $0200`.
+    fn emulator() -> Emulator {
+        Emulator::new()
+            .with_plugin(yagber_memory::MemoryPlugin::default())
+            .with_plugin(yagber_cpu::CpuPlugin)
+            .with_plugin(
+                DebuggerPlugin::new()
+                    .with_breakpoint(Breakpoint::new(CodeLocation::new(0x007C).with_bank(0))),
+            )
+            .build()
+            .unwrap()
+    }
+
+    fn run_until_stopped(emulator: &mut Emulator) -> (StopReason, u16, u16) {
+        for _ in 0..1_000_000 {
+            emulator.step();
+            if emulator.is_paused() {
+                let reason = emulator.get_component::<Debugger>().unwrap().last_stop();
+                let cpu = emulator.get_component::<Cpu>().unwrap();
+                return (reason.unwrap(), cpu.pc(), cpu.sp());
+            }
+        }
+        panic!("the debugger never stopped");
+    }
+
+    #[test]
+    fn stops_on_breakpoints_and_watchpoints() {
+        let mut emulator = emulator();
+        let (reason, pc, _) = run_until_stopped(&mut emulator);
+        assert_eq!((reason, pc), (StopReason::Breakpoint { id: 1 }, 0x007C));
+
+        let debugger = emulator.get_component_mut::<Debugger>().unwrap();
+        let id = debugger.add_watchpoint(Watchpoint::new(0xFF70..=0xFF70, WatchKind::Write));
+        Debugger::resume(&mut emulator);
+        let access = MemoryAccess {
+            address: 0xFF70,
+            value: 0x02,
+            kind: AccessKind::Write,
+        };
+        let (reason, pc, _) = run_until_stopped(&mut emulator);
+        assert_eq!(
+            (reason, pc),
+            (StopReason::Watchpoint { id, access }, 0x007E)
+        );
+    }
+
+    #[test]
+    fn stops_on_dma_accesses() {
+        let mut emulator = Emulator::new()
+            .with_plugin(yagber_memory::MemoryPlugin::default())
+            .with_plugin(yagber_cpu::CpuPlugin)
+            .with_plugin(yagber_dma::DmaPlugin)
+            .with_plugin(DebuggerPlugin::new())
+            .build()
+            .unwrap();
+        let debugger = emulator.get_component_mut::<Debugger>().unwrap();
+        let id = debugger.add_watchpoint(Watchpoint::new(0x4000..=0x409F, WatchKind::Read));
+        Bus::write_hooked(&mut emulator, yagber_memory::IOType::DMA.address(), 0x40);
+
+        let (reason, _, _) = run_until_stopped(&mut emulator);
+        let access = MemoryAccess {
+            address: 0x4000,
+            value: 0xFF,
+            kind: AccessKind::Read,
+        };
+        assert_eq!(reason, StopReason::Watchpoint { id, access });
+    }
+
+    #[test]
+    fn steps_into_over_and_out_of_calls() {
+        let mut emulator = emulator();
+        run_until_stopped(&mut emulator);
+        for expected in [0x007E, 0x0080, 0x0082] {
+            Debugger::step_into(&mut emulator);
+            assert_eq!(
+                run_until_stopped(&mut emulator),
+                (StopReason::Step, expected, 0xFFFE)
+            );
+        }
+
+        Debugger::step_over(&mut emulator);
+        assert_eq!(
+            run_until_stopped(&mut emulator),
+            (StopReason::Step, 0x0085, 0xFFFE)
+        );
+
+        Debugger::step_into(&mut emulator);
+        assert_eq!(
+            run_until_stopped(&mut emulator),
+            (StopReason::Step, 0x0200, 0xFFFC)
+        );
+
+        Debugger::step_out(&mut emulator);
+        assert_eq!(
+            run_until_stopped(&mut emulator),
+            (StopReason::Step, 0x0088, 0xFFFE)
+        );
+    }
+}
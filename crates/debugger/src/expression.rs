@@ -0,0 +1,402 @@
+use std::str::FromStr;
+
+use yagber_cpu::Cpu;
+use yagber_memory::Bus;
+
+use crate::parse_error::ParseError;
+
+/// Integer expression over the CPU registers and the bus, such as `a == $3C && [hl] != 0`.
+///
+/// Numbers are decimal unless prefixed with `$` or `0x`, `[address]` reads a byte from the bus.
+/// Operators follow Rust precedence, comparisons, `!`, `&&` and `||` give 1 or 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expression {
+    source: String,
+    node: Node,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Number(u32),
+    Register(Register),
+    Memory(Box<Node>),
+    Not(Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Register {
+    A,
+    F,
+    B,
+    C,
+    D,
+    E,
+    H,
+    L,
+    Af,
+    Bc,
+    De,
+    Hl,
+    Sp,
+    Pc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Add,
+    Sub,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(u32),
+    Identifier(String),
+    Operator(&'static str),
+}
+
+impl Expression {
+    pub fn evaluate(&self, cpu: &Cpu, bus: &Bus) -> u32 {
+        self.node.evaluate(cpu, bus)
+    }
+
+    /// Any non zero value is true.
+    pub fn is_true(&self, cpu: &Cpu, bus: &Bus) -> bool {
+        self.evaluate(cpu, bus) != 0
+    }
+}
+
+impl FromStr for Expression {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let node = parser.or()?;
+        if let Some(token) = parser.next() {
+            return Err(ParseError::UnexpectedToken(token.to_string()));
+        }
+        Ok(Self {
+            source: text.trim().to_string(),
+            node,
+        })
+    }
+}
+
+impl std::fmt::Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Node {
+    fn evaluate(&self, cpu: &Cpu, bus: &Bus) -> u32 {
+        match self {
+            Node::Number(value) => *value,
+            Node::Register(register) => register.read(cpu),
+            Node::Memory(address) => bus.read(address.evaluate(cpu, bus) as u16) as u32,
+            Node::Not(operand) => (operand.evaluate(cpu, bus) == 0) as u32,
+            Node::Binary(operator, left, right) => {
+                let left = left.evaluate(cpu, bus);
+                // `&&` and `||` short-circuit so `[address]` reads stay cheap
+                match operator {
+                    BinaryOp::Or if left != 0 => return 1,
+                    BinaryOp::And if left == 0 => return 0,
+                    _ => {}
+                }
+                operator.apply(left, right.evaluate(cpu, bus))
+            }
+        }
+    }
+}
+
+impl Register {
+    fn from_name(name: &str) -> Option<Self> {
+        let register = match name.to_ascii_lowercase().as_str() {
+            "a" => Register::A,
+            "f" => Register::F,
+            "b" => Register::B,
+            "c" => Register::C,
+            "d" => Register::D,
+            "e" => Register::E,
+            "h" => Register::H,
+            "l" => Register::L,
+            "af" => Register::Af,
+            "bc" => Register::Bc,
+            "de" => Register::De,
+            "hl" => Register::Hl,
+            "sp" => Register::Sp,
+            "pc" => Register::Pc,
+            _ => return None,
+        };
+        Some(register)
+    }
+
+    fn read(self, cpu: &Cpu) -> u32 {
+        let registers = cpu.registers();
+        match self {
+            Register::A => registers.a() as u32,
+            Register::F => registers.f() as u32,
+            Register::B => registers.b() as u32,
+            Register::C => registers.c() as u32,
+            Register::D => registers.d() as u32,
+            Register::E => registers.e() as u32,
+            Register::H => registers.h() as u32,
+            Register::L => registers.l() as u32,
+            Register::Af => registers.af() as u32,
+            Register::Bc => registers.bc() as u32,
+            Register::De => registers.de() as u32,
+            Register::Hl => registers.hl() as u32,
+            Register::Sp => cpu.sp() as u32,
+            Register::Pc => cpu.pc() as u32,
+        }
+    }
+}
+
+impl BinaryOp {
+    fn apply(self, left: u32, right: u32) -> u32 {
+        match self {
+            BinaryOp::Or => (left != 0 || right != 0) as u32,
+            BinaryOp::And => (left != 0 && right != 0) as u32,
+            BinaryOp::Eq => (left == right) as u32,
+            BinaryOp::Ne => (left != right) as u32,
+            BinaryOp::Lt => (left < right) as u32,
+            BinaryOp::Le => (left <= right) as u32,
+            BinaryOp::Gt => (left > right) as u32,
+            BinaryOp::Ge => (left >= right) as u32,
+            BinaryOp::BitOr => left | right,
+            BinaryOp::BitXor => left ^ right,
+            BinaryOp::BitAnd => left & right,
+            BinaryOp::Add => left.wrapping_add(right),
+            BinaryOp::Sub => left.wrapping_sub(right),
+        }
+    }
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Number(value) => write!(f, "{value}"),
+            Token::Identifier(name) => f.write_str(name),
+            Token::Operator(operator) => f.write_str(operator),
+        }
+    }
+}
+
+/// Longest operators first, so `&&` is not read as two `&`.
+const OPERATORS: [&str; 18] = [
+    "||", "&&", "==", "!=", "<=", ">=", "<", ">", "|", "^", "&", "+", "-", "!", "[", "]", "(", ")",
+];
+
+fn tokenize(text: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut rest = text.trim_start();
+    while let Some(first) = rest.chars().next() {
+        let length = if first == '$' || first.is_ascii_alphanumeric() || first == '_' {
+            let length = rest[1..]
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .map_or(rest.len(), |length| length + 1);
+            tokens.push(word_token(&rest[..length])?);
+            length
+        } else if let Some(operator) = OPERATORS.iter().find(|&&op| rest.starts_with(op)) {
+            tokens.push(Token::Operator(operator));
+            operator.len()
+        } else {
+            return Err(ParseError::UnexpectedToken(first.to_string()));
+        };
+        rest = rest[length..].trim_start();
+    }
+    Ok(tokens)
+}
+
+fn word_token(word: &str) -> Result<Token, ParseError> {
+    let invalid = || ParseError::InvalidNumber(word.to_string());
+    if let Some(digits) = word.strip_prefix('$').or_else(|| word.strip_prefix("0x")) {
+        u32::from_str_radix(digits, 16)
+            .map(Token::Number)
+            .map_err(|_| invalid())
+    } else if word.starts_with(|c: char| c.is_ascii_digit()) {
+        word.parse().map(Token::Number).map_err(|_| invalid())
+    } else {
+        Ok(Token::Identifier(word.to_string()))
+    }
+}
+
+/// Recursive descent, one method per precedence level from the loosest binding.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, operator: &str) -> bool {
+        let matched = matches!(
+            self.tokens.get(self.position),
+            Some(Token::Operator(token)) if *token == operator
+        );
+        if matched {
+            self.position += 1;
+        }
+        matched
+    }
+
+    fn expect(&mut self, operator: &str) -> Result<(), ParseError> {
+        if self.eat(operator) {
+            return Ok(());
+        }
+        match self.next() {
+            Some(token) => Err(ParseError::UnexpectedToken(token.to_string())),
+            None => Err(ParseError::UnexpectedEnd),
+        }
+    }
+
+    /// Parses a left associative level made of `operators` over `operand`.
+    fn binary(
+        &mut self,
+        operators: &[(&str, BinaryOp)],
+        operand: fn(&mut Self) -> Result<Node, ParseError>,
+    ) -> Result<Node, ParseError> {
+        let mut node = operand(self)?;
+        'outer: loop {
+            for (text, operator) in operators {
+                if self.eat(text) {
+                    node = Node::Binary(*operator, Box::new(node), Box::new(operand(self)?));
+                    continue 'outer;
+                }
+            }
+            return Ok(node);
+        }
+    }
+
+    fn or(&mut self) -> Result<Node, ParseError> {
+        self.binary(&[("||", BinaryOp::Or)], Self::and)
+    }
+
+    fn and(&mut self) -> Result<Node, ParseError> {
+        self.binary(&[("&&", BinaryOp::And)], Self::comparison)
+    }
+
+    /// Comparisons do not chain, `a < b < c` is rejected.
+    fn comparison(&mut self) -> Result<Node, ParseError> {
+        const COMPARISONS: [(&str, BinaryOp); 6] = [
+            ("==", BinaryOp::Eq),
+            ("!=", BinaryOp::Ne),
+            ("<=", BinaryOp::Le),
+            (">=", BinaryOp::Ge),
+            ("<", BinaryOp::Lt),
+            (">", BinaryOp::Gt),
+        ];
+        let left = self.bit_or()?;
+        for (text, operator) in COMPARISONS {
+            if self.eat(text) {
+                let right = self.bit_or()?;
+                return Ok(Node::Binary(operator, Box::new(left), Box::new(right)));
+            }
+        }
+        Ok(left)
+    }
+
+    fn bit_or(&mut self) -> Result<Node, ParseError> {
+        self.binary(&[("|", BinaryOp::BitOr)], Self::bit_xor)
+    }
+
+    fn bit_xor(&mut self) -> Result<Node, ParseError> {
+        self.binary(&[("^", BinaryOp::BitXor)], Self::bit_and)
+    }
+
+    fn bit_and(&mut self) -> Result<Node, ParseError> {
+        self.binary(&[("&", BinaryOp::BitAnd)], Self::sum)
+    }
+
+    fn sum(&mut self) -> Result<Node, ParseError> {
+        self.binary(&[("+", BinaryOp::Add), ("-", BinaryOp::Sub)], Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Node, ParseError> {
+        if self.eat("!") {
+            return Ok(Node::Not(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Node, ParseError> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Node::Number(value)),
+            Some(Token::Identifier(name)) => Register::from_name(&name)
+                .map(Node::Register)
+                .ok_or(ParseError::UnknownRegister(name)),
+            Some(Token::Operator("[")) => {
+                let address = self.or()?;
+                self.expect("]")?;
+                Ok(Node::Memory(Box::new(address)))
+            }
+            Some(Token::Operator("(")) => {
+                let node = self.or()?;
+                self.expect(")")?;
+                Ok(node)
+            }
+            Some(token) => Err(ParseError::UnexpectedToken(token.to_string())),
+            None => Err(ParseError::UnexpectedEnd),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(text: &str, bus: &Bus) -> u32 {
+        text.parse::<Expression>()
+            .unwrap()
+            .evaluate(&Cpu::new(), bus)
+    }
+
+    #[test]
+    fn follows_operator_precedence() {
+        let bus = Bus::new();
+        assert_eq!(evaluate("1 + 2 == 3 && !0", &bus), 1);
+        assert_eq!(evaluate("$F0 | $0F & $3C", &bus), 0xFC);
+        assert_eq!(evaluate("(1 || 0) + 1", &bus), 2);
+        assert_eq!(evaluate("0x10 - 1 >= 15", &bus), 1);
+    }
+
+    #[test]
+    fn reads_registers_and_memory() {
+        let mut bus = Bus::new();
+        bus.write(0xC010, 0x42);
+        assert_eq!(evaluate("[$C000 + $10] == $42", &bus), 1);
+        assert_eq!(evaluate("PC + sp", &bus), 0);
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        assert_eq!(
+            "a == ix".parse::<Expression>(),
+            Err(ParseError::UnknownRegister("ix".to_string()))
+        );
+        assert_eq!("[hl".parse::<Expression>(), Err(ParseError::UnexpectedEnd));
+        assert!("1 < 2 < 3".parse::<Expression>().is_err());
+        assert!("$GG".parse::<Expression>().is_err());
+        assert!("a @ 1".parse::<Expression>().is_err());
+    }
+}
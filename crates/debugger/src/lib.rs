@@ -0,0 +1,77 @@
+mod breakpoint;
+mod code_location;
+mod command;
+mod debugger;
+mod expression;
+mod parse_error;
+mod repl;
+mod stop_reason;
+mod watchpoint;
+
+pub use breakpoint::Breakpoint;
+pub use code_location::CodeLocation;
+pub use debugger::Debugger;
+pub use expression::Expression;
+pub use parse_error::ParseError;
+pub use stop_reason::{DebuggerStoppedEvent, StopReason};
+pub use watchpoint::{WatchKind, Watchpoint};
+
+/// Adds a [`Debugger`], stopping the emulator on breakpoints, watchpoints and steps.
+pub struct DebuggerPlugin {
+    breakpoints: Vec<Breakpoint>,
+    repl: bool,
+}
+
+impl DebuggerPlugin {
+    pub fn new() -> Self {
+        Self {
+            breakpoints: Vec::new(),
+            repl: false,
+        }
+    }
+
+    /// Breakpoint set before the first instruction runs.
+    pub fn with_breakpoint(mut self, breakpoint: Breakpoint) -> Self {
+        self.breakpoints.push(breakpoint);
+        self
+    }
+
+    /// Reads commands from stdin and reports stops on stdout, `help` lists the commands.
+    pub fn with_repl(mut self) -> Self {
+        self.repl = true;
+        self
+    }
+}
+
+impl Default for DebuggerPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl yagber_app::Plugin for DebuggerPlugin {
+    fn init(self, emulator: &mut yagber_app::Emulator) -> Result<(), yagber_app::PluginError> {
+        let mut debugger = Debugger::new();
+        for breakpoint in self.breakpoints {
+            debugger.add_breakpoint(breakpoint);
+        }
+        emulator
+            .with_component(debugger)
+            .on_mcycle(Debugger::on_mcycle);
+        emulator
+            .get_component_mut::<yagber_memory::Bus>()
+            .expect("Bus component missing")
+            .record_accesses(true);
+        if self.repl {
+            repl::Repl::attach(emulator);
+        }
+        Ok(())
+    }
+
+    fn dependencies(&self) -> yagber_app::PluginDependencies {
+        yagber_app::PluginDependencies::new()
+            .provides::<Debugger>()
+            .requires::<yagber_cpu::Cpu>()
+            .requires::<yagber_memory::Bus>()
+    }
+}
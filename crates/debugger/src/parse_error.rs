@@ -0,0 +1,42 @@
+/// Error for debugger input that could not be parsed, such as a REPL command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// A number or address is not valid hexadecimal or decimal.
+    InvalidNumber(String),
+    /// An expression names something that is not a register.
+    UnknownRegister(String),
+    /// An expression holds a character or token where it does not belong.
+    UnexpectedToken(String),
+    /// An expression or command stops early.
+    UnexpectedEnd,
+    /// A command word the REPL does not know.
+    UnknownCommand(String),
+    /// A command is missing one of its arguments.
+    MissingArgument(&'static str),
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::InvalidNumber(text) => write!(f, "invalid number `{text}`"),
+            ParseError::UnknownRegister(name) => write!(f, "unknown register `{name}`"),
+            ParseError::UnexpectedToken(token) => write!(f, "unexpected `{token}`"),
+            ParseError::UnexpectedEnd => write!(f, "unexpected end of input"),
+            ParseError::UnknownCommand(command) => {
+                write!(f, "unknown command `{command}`, try `help`")
+            }
+            ParseError::MissingArgument(argument) => write!(f, "missing {argument}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses a hexadecimal address, with or without a `$` or `0x` prefix.
+pub(crate) fn parse_address(text: &str) -> Result<u16, ParseError> {
+    let digits = text
+        .strip_prefix('$')
+        .or_else(|| text.strip_prefix("0x"))
+        .unwrap_or(text);
+    u16::from_str_radix(digits, 16).map_err(|_| ParseError::InvalidNumber(text.to_string()))
+}
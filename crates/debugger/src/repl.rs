@@ -0,0 +1,71 @@
+use std::io::BufRead;
+
+use yagber_app::{DeferredCalls, Emulator};
+use yagber_cpu::{AccessKind, Cpu};
+use yagber_memory::Bus;
+
+use crate::{
+    DebuggerStoppedEvent, StopReason,
+    command::{self, Command},
+};
+
+/// Reads debugger commands from stdin, one per line, and prints their results to stdout.
+///
+/// Lines are read on a thread of their own and run as deferred calls, so the REPL works
+/// whichever runner drives the emulator, paused or not.
+pub(crate) struct Repl;
+
+impl Repl {
+    pub(crate) fn attach(emulator: &mut Emulator) {
+        let calls = emulator.deferred_calls();
+        std::thread::Builder::new()
+            .name("debugger repl".to_string())
+            .spawn(move || Self::read_commands(calls))
+            .expect("Failed to spawn the debugger REPL thread");
+        emulator.on_event(Self::on_stopped);
+    }
+
+    fn read_commands(calls: DeferredCalls) {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                return;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            match line.parse::<Command>() {
+                Ok(command) => calls.push(move |emulator| {
+                    let output = command.execute(emulator);
+                    if !output.is_empty() {
+                        println!("{output}");
+                    }
+                }),
+                Err(error) => println!("error: {error}"),
+            }
+        }
+    }
+
+    fn on_stopped(emulator: &mut Emulator, event: &DebuggerStoppedEvent) {
+        let (Some(cpu), Some(bus)) = (
+            emulator.get_component::<Cpu>(),
+            emulator.get_component::<Bus>(),
+        ) else {
+            return;
+        };
+        match event.reason {
+            StopReason::Breakpoint { id } => println!("breakpoint {id}"),
+            StopReason::Watchpoint { id, access } => {
+                let (verb, preposition) = match access.kind {
+                    AccessKind::Read => ("read", "from"),
+                    AccessKind::Write => ("wrote", "to"),
+                };
+                println!(
+                    "watchpoint {id}: {verb} ${:02X} {preposition} {:04X}",
+                    access.value, access.address
+                );
+            }
+            StopReason::Step | StopReason::Paused => {}
+        }
+        println!("{}", command::registers(cpu, bus));
+    }
+}
@@ -0,0 +1,25 @@
+use yagber_cpu::MemoryAccess;
+
+/// Why the [`Debugger`](crate::Debugger) paused the emulator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    Breakpoint {
+        id: u32,
+    },
+    Watchpoint {
+        id: u32,
+        access: MemoryAccess,
+    },
+    /// A step in, over or out finished.
+    Step,
+    /// [`Debugger::pause`](crate::Debugger::pause) was called.
+    Paused,
+}
+
+/// The debugger paused the emulator, the CPU sits between two instructions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DebuggerStoppedEvent {
+    pub reason: StopReason,
+}
+
+impl yagber_app::Event for DebuggerStoppedEvent {}
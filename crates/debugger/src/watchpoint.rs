@@ -0,0 +1,84 @@
+use std::ops::RangeInclusive;
+
+use yagber_cpu::AccessKind;
+
+use crate::Expression;
+
+/// Which data accesses a [`Watchpoint`] stops on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    Access,
+}
+
+impl WatchKind {
+    pub fn matches(self, kind: AccessKind) -> bool {
+        match self {
+            WatchKind::Read => kind == AccessKind::Read,
+            WatchKind::Write => kind == AccessKind::Write,
+            WatchKind::Access => true,
+        }
+    }
+}
+
+/// Stops once the instruction reading or writing an address range is done, if its condition
+/// held when the access was made.
+///
+/// Any bus address can be watched, IO registers included. CPU data accesses count, along with
+/// the bytes OAM DMA and HDMA transfers read and write. Opcode and operand fetches do not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watchpoint {
+    range: RangeInclusive<u16>,
+    kind: WatchKind,
+    condition: Option<Expression>,
+}
+
+impl Watchpoint {
+    pub fn new(range: RangeInclusive<u16>, kind: WatchKind) -> Self {
+        Self {
+            range,
+            kind,
+            condition: None,
+        }
+    }
+
+    pub fn with_condition(mut self, condition: Expression) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
+    pub fn range(&self) -> &RangeInclusive<u16> {
+        &self.range
+    }
+
+    pub fn kind(&self) -> WatchKind {
+        self.kind
+    }
+
+    pub fn condition(&self) -> Option<&Expression> {
+        self.condition.as_ref()
+    }
+
+    pub fn matches(&self, address: u16, kind: AccessKind) -> bool {
+        self.kind.matches(kind) && self.range.contains(&address)
+    }
+}
+
+impl std::fmt::Display for Watchpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let command = match self.kind {
+            WatchKind::Read => "rwatch",
+            WatchKind::Write => "watch",
+            WatchKind::Access => "awatch",
+        };
+        write!(f, "{command} {:04X}", self.range.start())?;
+        if self.range.end() != self.range.start() {
+            write!(f, "-{:04X}", self.range.end())?;
+        }
+        if let Some(condition) = &self.condition {
+            write!(f, " if {condition}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+use std::str::FromStr;
+
+use crate::ParseError;
+
+/// Address with the bank it belongs to, such as a byte of the cartridge ROM.
+///
+/// Bank 0 is mapped at `0000-3FFF`, every other bank at `4000-7FFF`. Written `BB:AAAA`
+/// in hexadecimal, like RGBDS symbol files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BankedAddress {
+    pub bank: u16,
+    pub address: u16,
+}
+
+impl BankedAddress {
+    pub const BANK_SIZE: usize = 0x4000;
+
+    pub fn new(bank: u16, address: u16) -> Self {
+        Self { bank, address }
+    }
+
+    /// Location of the byte at `offset` in the ROM file.
+    pub fn from_rom_offset(offset: usize) -> Self {
+        let bank = offset / Self::BANK_SIZE;
+        let address = (offset % Self::BANK_SIZE) as u16 + if bank == 0 { 0 } else { 0x4000 };
+        Self::new(bank as u16, address)
+    }
+
+    /// Offset of the byte in the ROM file, `None` outside of the bank's address range.
+    pub fn rom_offset(&self) -> Option<usize> {
+        let bank = usize::from(self.bank);
+        let address = usize::from(self.address);
+        match (self.bank, self.address) {
+            (0, 0x0000..=0x3FFF) => Some(address),
+            (1.., 0x4000..=0x7FFF) => Some(bank * Self::BANK_SIZE + address - 0x4000),
+            _ => None,
+        }
+    }
+
+    /// Bank of `target` as seen from code at this location, `None` when it depends on
+    /// the bank mapped at run time.
+    pub fn bank_of(&self, target: u16) -> Option<u16> {
+        match target {
+            0x0000..=0x3FFF => Some(0),
+            0x4000..=0x7FFF if self.bank != 0 => Some(self.bank),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for BankedAddress {
+    type Err = ParseError;
+
+    /// A location without a bank is in bank 0 below `4000`, and in bank 1 above.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseError::InvalidLocation(text.to_string());
+        let hex = |digits: &str| u16::from_str_radix(digits, 16).map_err(|_| invalid());
+        match text.split_once(':') {
+            Some((bank, address)) => Ok(Self::new(hex(bank)?, hex(address)?)),
+            None => {
+                let address = hex(text.strip_prefix('$').unwrap_or(text))?;
+                Ok(Self::new(u16::from(address >= 0x4000), address))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for BankedAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:02X}:{:04X}", self.bank, self.address)
+    }
+}
@@ -0,0 +1,261 @@
+use yagber_cpu::{Instruction, InstructionType};
+
+use crate::{BankedAddress, Flow, SymbolTable};
+
+const R8: [&str; 8] = ["b", "c", "d", "e", "h", "l", "[hl]", "a"];
+const R16: [&str; 4] = ["bc", "de", "hl", "sp"];
+const R16_STK: [&str; 4] = ["bc", "de", "hl", "af"];
+const R16_MEM: [&str; 4] = ["[bc]", "[de]", "[hl+]", "[hl-]"];
+const COND: [&str; 4] = ["nz", "z", "nc", "c"];
+
+/// An instruction with its operands, rendered in RGBDS syntax.
+///
+/// Illegal opcodes decode to a single byte shown as `db`. `STOP` is two bytes long,
+/// like RGBDS assembles it.
+#[derive(Debug, Clone, Copy)]
+pub struct DecodedInstruction {
+    location: BankedAddress,
+    bytes: [u8; 3],
+    length: u8,
+    instruction: Instruction,
+}
+
+impl DecodedInstruction {
+    /// Decodes the instruction at `location`, `read` returns the byte at an address.
+    pub fn decode(location: BankedAddress, mut read: impl FnMut(u16) -> u8) -> Self {
+        let address = location.address;
+        let opcode = read(address);
+        let mut bytes = [opcode, 0, 0];
+        let instruction = if opcode == 0xCB {
+            bytes[1] = read(address.wrapping_add(1));
+            Instruction::new_cb_prefix(bytes[1])
+        } else {
+            Instruction::new(opcode)
+        };
+        let length = match instruction.instruction_type() {
+            InstructionType::Stop => 2,
+            _ => instruction.length(),
+        };
+        for offset in 1..length {
+            bytes[usize::from(offset)] = read(address.wrapping_add(u16::from(offset)));
+        }
+        Self {
+            location,
+            bytes,
+            length,
+            instruction,
+        }
+    }
+
+    pub fn location(&self) -> BankedAddress {
+        self.location
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes[..usize::from(self.length)]
+    }
+
+    pub fn instruction(&self) -> Instruction {
+        self.instruction
+    }
+
+    /// Address of the byte following the instruction.
+    pub fn next_address(&self) -> u16 {
+        self.location.address.wrapping_add(u16::from(self.length))
+    }
+
+    pub fn flow(&self) -> Flow {
+        use InstructionType::*;
+        match self.instruction.instruction_type() {
+            JrImm8 => Flow::Jump(self.relative_target()),
+            JrCondImm8 => Flow::Branch(self.relative_target()),
+            JpImm16 => Flow::Jump(self.imm16()),
+            JpCondImm16 => Flow::Branch(self.imm16()),
+            CallImm16 | CallCondImm16 => Flow::Call(self.imm16()),
+            RstTgt3 => Flow::Call(self.rst_target()),
+            Ret | RetI | JpHl | Illegal => Flow::End,
+            _ => Flow::Next,
+        }
+    }
+
+    /// The instruction in RGBDS syntax, with addresses replaced by their label when
+    /// `symbols` has one.
+    pub fn text(&self, symbols: &SymbolTable) -> String {
+        use InstructionType::*;
+        let instruction = self.instruction;
+        let r8 = || R8[usize::from(instruction.r8().map_or(0, |r8| r8.value()))];
+        let r16 = || usize::from(instruction.r16().map_or(0, |r16| r16.value()));
+        let cond = || COND[usize::from(instruction.cond().map_or(0, |cond| cond.value()))];
+        let b3 = || instruction.b3().map_or(0, |b3| b3.value());
+        let imm8 = self.bytes[1];
+        let imm16 = self.imm16();
+        let address = |target: u16| self.address(symbols, target);
+
+        match instruction.instruction_type() {
+            Nop => "nop".to_string(),
+            Illegal => format!("db ${:02X}", self.bytes[0]),
+            LdR16Imm16 => format!("ld {}, ${imm16:04X}", R16[r16()]),
+            LdR16memA => format!("ld {}, a", R16_MEM[r16()]),
+            LdAR16mem => format!("ld a, {}", R16_MEM[r16()]),
+            LdImm16Sp => format!("ld [{}], sp", address(imm16)),
+            IncR16 => format!("inc {}", R16[r16()]),
+            DecR16 => format!("dec {}", R16[r16()]),
+            AddHlR16 => format!("add hl, {}", R16[r16()]),
+            IncR8 => format!("inc {}", r8()),
+            DecR8 => format!("dec {}", r8()),
+            LdR8Imm8 => format!("ld {}, ${imm8:02X}", r8()),
+            RlCA => "rlca".to_string(),
+            RrCA => "rrca".to_string(),
+            RlA => "rla".to_string(),
+            RrA => "rra".to_string(),
+            Daa => "daa".to_string(),
+            Cpl => "cpl".to_string(),
+            Scf => "scf".to_string(),
+            Ccf => "ccf".to_string(),
+            JrImm8 => format!("jr {}", address(self.relative_target())),
+            JrCondImm8 => format!("jr {}, {}", cond(), address(self.relative_target())),
+            Stop if imm8 == 0 => "stop".to_string(),
+            Stop => format!("db $10, ${imm8:02X}"),
+            LdR8R8 => {
+                let (dst, src) = instruction
+                    .r8_pair()
+                    .map_or((0, 0), |(dst, src)| (dst.value(), src.value()));
+                format!("ld {}, {}", R8[usize::from(dst)], R8[usize::from(src)])
+            }
+            Halt => "halt".to_string(),
+            AddAR8 => format!("add a, {}", r8()),
+            AdcAR8 => format!("adc a, {}", r8()),
+            SubAR8 => format!("sub a, {}", r8()),
+            SbcAR8 => format!("sbc a, {}", r8()),
+            AndAR8 => format!("and a, {}", r8()),
+            XorAR8 => format!("xor a, {}", r8()),
+            OrAR8 => format!("or a, {}", r8()),
+            CpAR8 => format!("cp a, {}", r8()),
+            AddAImm8 => format!("add a, ${imm8:02X}"),
+            AdcAImm8 => format!("adc a, ${imm8:02X}"),
+            SubAImm8 => format!("sub a, ${imm8:02X}"),
+            SbcAImm8 => format!("sbc a, ${imm8:02X}"),
+            AndAImm8 => format!("and a, ${imm8:02X}"),
+            XorAImm8 => format!("xor a, ${imm8:02X}"),
+            OrAImm8 => format!("or a, ${imm8:02X}"),
+            CpAImm8 => format!("cp a, ${imm8:02X}"),
+            RetCond => format!("ret {}", cond()),
+            Ret => "ret".to_string(),
+            RetI => "reti".to_string(),
+            JpCondImm16 => format!("jp {}, {}", cond(), address(imm16)),
+            JpImm16 => format!("jp {}", address(imm16)),
+            JpHl => "jp hl".to_string(),
+            CallCondImm16 => format!("call {}, {}", cond(), address(imm16)),
+            CallImm16 => format!("call {}", address(imm16)),
+            RstTgt3 => format!("rst ${:02X}", self.rst_target()),
+            PopR16stk => format!("pop {}", R16_STK[r16()]),
+            PushR16stk => format!("push {}", R16_STK[r16()]),
+            // Only reached for a CB byte decoded on its own
+            Prefix => "db $CB".to_string(),
+            LdhCA => "ldh [c], a".to_string(),
+            LdhImm8A => format!("ldh [{}], a", address(0xFF00 | u16::from(imm8))),
+            LdImm16A => format!("ld [{}], a", address(imm16)),
+            LdhAC => "ldh a, [c]".to_string(),
+            LdhAImm8 => format!("ldh a, [{}]", address(0xFF00 | u16::from(imm8))),
+            LdAImm16 => format!("ld a, [{}]", address(imm16)),
+            AddSpImm8 => format!("add sp, {}", imm8 as i8),
+            LdHlSpImm8 => match imm8 as i8 {
+                offset @ 0.. => format!("ld hl, sp + {offset}"),
+                offset => format!("ld hl, sp - {}", offset.unsigned_abs()),
+            },
+            LdSpHl => "ld sp, hl".to_string(),
+            Di => "di".to_string(),
+            Ei => "ei".to_string(),
+            RlcR8 => format!("rlc {}", r8()),
+            RrcR8 => format!("rrc {}", r8()),
+            RlR8 => format!("rl {}", r8()),
+            RrR8 => format!("rr {}", r8()),
+            SlaR8 => format!("sla {}", r8()),
+            SraR8 => format!("sra {}", r8()),
+            SwapR8 => format!("swap {}", r8()),
+            SrlR8 => format!("srl {}", r8()),
+            BitB3R8 => format!("bit {}, {}", b3(), r8()),
+            ResB3R8 => format!("res {}, {}", b3(), r8()),
+            SetB3R8 => format!("set {}, {}", b3(), r8()),
+        }
+    }
+
+    /// The label of `target`, or the address when there is none. Switchable bank
+    /// addresses are only labeled from code in the same bank.
+    fn address(&self, symbols: &SymbolTable, target: u16) -> String {
+        let bank = self.location.bank_of(target);
+        let label = match (bank, target) {
+            (None, 0x4000..=0x7FFF) => None,
+            _ => symbols.label(bank, target),
+        };
+        label.map_or_else(|| format!("${target:04X}"), str::to_string)
+    }
+
+    fn imm16(&self) -> u16 {
+        u16::from_le_bytes([self.bytes[1], self.bytes[2]])
+    }
+
+    fn relative_target(&self) -> u16 {
+        self.next_address()
+            .wrapping_add_signed(i16::from(self.bytes[1] as i8))
+    }
+
+    fn rst_target(&self) -> u16 {
+        self.instruction
+            .tgt3()
+            .map_or(0, |tgt3| u16::from(tgt3.value()) * 8)
+    }
+}
+
+impl std::fmt::Display for DecodedInstruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.text(&SymbolTable::new()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(bytes: &[u8], symbols: &SymbolTable) -> String {
+        let location = BankedAddress::new(1, 0x4000);
+        let instruction = DecodedInstruction::decode(location, |address| {
+            bytes
+                .get(usize::from(address - 0x4000))
+                .copied()
+                .unwrap_or(0)
+        });
+        assert_eq!(instruction.bytes(), bytes);
+        instruction.text(symbols)
+    }
+
+    #[test]
+    fn renders_rgbds_syntax() {
+        let mut symbols = SymbolTable::new();
+        symbols.insert(BankedAddress::new(1, 0x4010), "Loop");
+        symbols.insert(BankedAddress::new(0, 0xFF44), "rLY");
+        let none = SymbolTable::new();
+
+        assert_eq!(text(&[0x00], &none), "nop");
+        assert_eq!(text(&[0x01, 0x34, 0x12], &none), "ld bc, $1234");
+        assert_eq!(text(&[0x2A], &none), "ld a, [hl+]");
+        assert_eq!(text(&[0x32], &none), "ld [hl-], a");
+        assert_eq!(text(&[0x70], &none), "ld [hl], b");
+        assert_eq!(text(&[0xAF], &none), "xor a, a");
+        assert_eq!(text(&[0xFE, 0x90], &none), "cp a, $90");
+        assert_eq!(text(&[0x20, 0x0E], &symbols), "jr nz, Loop");
+        assert_eq!(text(&[0x18, 0xFE], &none), "jr $4000");
+        assert_eq!(text(&[0xC3, 0x10, 0x40], &symbols), "jp Loop");
+        assert_eq!(text(&[0xDC, 0x00, 0x50], &symbols), "call c, $5000");
+        assert_eq!(text(&[0xF0, 0x44], &symbols), "ldh a, [rLY]");
+        assert_eq!(text(&[0xE2], &none), "ldh [c], a");
+        assert_eq!(text(&[0xF8, 0xFE], &none), "ld hl, sp - 2");
+        assert_eq!(text(&[0xE8, 0x04], &none), "add sp, 4");
+        assert_eq!(text(&[0xFF], &none), "rst $38");
+        assert_eq!(text(&[0xF5], &none), "push af");
+        assert_eq!(text(&[0xCB, 0x7E], &none), "bit 7, [hl]");
+        assert_eq!(text(&[0xCB, 0x37], &none), "swap a");
+        assert_eq!(text(&[0x10, 0x00], &none), "stop");
+        assert_eq!(text(&[0xD3], &none), "db $D3");
+    }
+}
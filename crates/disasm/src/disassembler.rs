@@ -0,0 +1,132 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{BankedAddress, DecodedInstruction, Disassembly};
+
+/// Walks a ROM from its entry points, following jumps and calls.
+///
+/// Targets in the switchable bank are followed from code in the same bank. From bank 0
+/// they are only followed when the ROM has a single switchable bank, otherwise the bank
+/// depends on the memory bank controller and the target needs an extra entry point.
+pub struct Disassembler<'a> {
+    rom: &'a [u8],
+    entries: Vec<BankedAddress>,
+}
+
+impl<'a> Disassembler<'a> {
+    /// The entry point at `0100` and the interrupt handlers.
+    pub const DEFAULT_ENTRIES: [u16; 6] = [0x0100, 0x0040, 0x0048, 0x0050, 0x0058, 0x0060];
+
+    pub fn new(rom: &'a [u8]) -> Self {
+        Self {
+            rom,
+            entries: Self::DEFAULT_ENTRIES
+                .iter()
+                .map(|address| BankedAddress::new(0, *address))
+                .collect(),
+        }
+    }
+
+    /// Also walks the code reached from `entry`, such as a function only called
+    /// through a pointer or after a bank switch.
+    pub fn with_entry(mut self, entry: BankedAddress) -> Self {
+        self.entries.push(entry);
+        self
+    }
+
+    pub fn run(self) -> Disassembly {
+        let mut instructions = BTreeMap::new();
+        let mut targets = BTreeSet::new();
+        let mut pending = self.entries.clone();
+        targets.extend(self.entries.iter().copied());
+
+        while let Some(location) = pending.pop() {
+            if instructions.contains_key(&location) || self.byte(location).is_none() {
+                continue;
+            }
+            let instruction = DecodedInstruction::decode(location, |address| {
+                self.byte(BankedAddress::new(location.bank, address))
+                    .unwrap_or(0xFF)
+            });
+            let flow = instruction.flow();
+            if let Some(target) = flow.target()
+                && let Some(bank) = self.bank_of(location, target)
+            {
+                let target = BankedAddress::new(bank, target);
+                targets.insert(target);
+                pending.push(target);
+            }
+            if flow.falls_through() {
+                pending.push(BankedAddress::new(
+                    location.bank,
+                    instruction.next_address(),
+                ));
+            }
+            instructions.insert(location, instruction);
+        }
+        Disassembly::new(instructions, targets)
+    }
+
+    fn byte(&self, location: BankedAddress) -> Option<u8> {
+        self.rom.get(location.rom_offset()?).copied()
+    }
+
+    fn bank_of(&self, location: BankedAddress, target: u16) -> Option<u16> {
+        let single_bank = self.rom.len() <= 2 * BankedAddress::BANK_SIZE;
+        match location.bank_of(target) {
+            None if single_bank && (0x4000..=0x7FFF).contains(&target) => Some(1),
+            bank => bank,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SymbolTable;
+
+    #[test]
+    fn follows_jumps_calls_and_banks() {
+        let mut rom = vec![0x00; 4 * BankedAddress::BANK_SIZE];
+        let code: &[(usize, &[u8])] = &[
+            // 0100: nop, jp 0150
+            (0x0100, &[0x00, 0xC3, 0x50, 0x01]),
+            // 0150: call 0200, jr z 0150, ret
+            (0x0150, &[0xCD, 0x00, 0x02, 0x28, 0xFB, 0xC9]),
+            // 0200: illegal
+            (0x0200, &[0xD3]),
+            // interrupt handlers: reti
+            (0x0040, &[0xD9]),
+            (0x0048, &[0xD9]),
+            (0x0050, &[0xD9]),
+            (0x0058, &[0xD9]),
+            (0x0060, &[0xD9]),
+            // 02:4000: jp 4000
+            (0x8000, &[0xC3, 0x00, 0x40]),
+        ];
+        for (offset, bytes) in code {
+            rom[*offset..*offset + bytes.len()].copy_from_slice(bytes);
+        }
+
+        let disassembly = Disassembler::new(&rom)
+            .with_entry("02:4000".parse().unwrap())
+            .run();
+        let addresses = disassembly
+            .instructions()
+            .map(|instruction| instruction.location().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            addresses,
+            [
+                "00:0040", "00:0048", "00:0050", "00:0058", "00:0060", "00:0100", "00:0101",
+                "00:0150", "00:0153", "00:0155", "00:0200", "02:4000"
+            ]
+        );
+
+        let mut symbols = SymbolTable::new();
+        symbols.insert(BankedAddress::new(0, 0x0150), "Main");
+        let listing = disassembly.listing(&symbols).to_string();
+        assert!(listing.contains("00:0101  C3 50 01  jp Main\n"));
+        assert!(listing.contains("\nMain:\n00:0150  CD 00 02  call $0200\n"));
+        assert!(listing.contains("02:4000  C3 00 40  jp $4000"));
+    }
+}
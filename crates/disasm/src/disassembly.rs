@@ -0,0 +1,81 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{BankedAddress, DecodedInstruction, SymbolTable};
+
+/// The instructions reached by a [`crate::Disassembler`], in address order.
+#[derive(Debug, Clone)]
+pub struct Disassembly {
+    instructions: BTreeMap<BankedAddress, DecodedInstruction>,
+    targets: BTreeSet<BankedAddress>,
+}
+
+impl Disassembly {
+    pub(crate) fn new(
+        instructions: BTreeMap<BankedAddress, DecodedInstruction>,
+        targets: BTreeSet<BankedAddress>,
+    ) -> Self {
+        Self {
+            instructions,
+            targets,
+        }
+    }
+
+    pub fn instructions(&self) -> impl Iterator<Item = &DecodedInstruction> {
+        self.instructions.values()
+    }
+
+    pub fn get(&self, location: BankedAddress) -> Option<&DecodedInstruction> {
+        self.instructions.get(&location)
+    }
+
+    /// Is the location an entry point, or jumped to or called by an instruction?
+    pub fn is_target(&self, location: BankedAddress) -> bool {
+        self.targets.contains(&location)
+    }
+
+    /// One instruction per line, preceded by its location and bytes. Labels from
+    /// `symbols` start their own line, and a blank line separates unconnected code.
+    pub fn listing<'a>(&'a self, symbols: &'a SymbolTable) -> Listing<'a> {
+        Listing {
+            disassembly: self,
+            symbols,
+        }
+    }
+}
+
+/// See [`Disassembly::listing`].
+pub struct Listing<'a> {
+    disassembly: &'a Disassembly,
+    symbols: &'a SymbolTable,
+}
+
+impl std::fmt::Display for Listing<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut next = None;
+        for instruction in self.disassembly.instructions() {
+            let location = instruction.location();
+            if next.is_some_and(|next| next != location) {
+                writeln!(f)?;
+            }
+            if let Some(label) = self.symbols.label(Some(location.bank), location.address) {
+                writeln!(f, "{label}:")?;
+            }
+            let bytes = instruction
+                .bytes()
+                .iter()
+                .map(|byte| format!("{byte:02X}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(
+                f,
+                "{location}  {bytes:<8}  {}",
+                instruction.text(self.symbols)
+            )?;
+            next = Some(BankedAddress::new(
+                location.bank,
+                instruction.next_address(),
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+/// Where execution goes after an instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    /// Runs the next instruction.
+    Next,
+    /// Always jumps to the address.
+    Jump(u16),
+    /// Jumps to the address or runs the next instruction, depending on a condition.
+    Branch(u16),
+    /// Calls the address, which usually returns to the next instruction.
+    Call(u16),
+    /// Returns, jumps through a register or cannot run, the next instruction is unknown.
+    End,
+}
+
+impl Flow {
+    /// Does the next instruction run afterwards, at least some of the time?
+    pub fn falls_through(&self) -> bool {
+        !matches!(self, Flow::Jump(_) | Flow::End)
+    }
+
+    /// The address jumped to or called.
+    pub fn target(&self) -> Option<u16> {
+        match self {
+            Flow::Jump(target) | Flow::Branch(target) | Flow::Call(target) => Some(*target),
+            Flow::Next | Flow::End => None,
+        }
+    }
+}
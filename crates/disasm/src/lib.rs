@@ -0,0 +1,15 @@
+mod banked_address;
+mod decoded_instruction;
+mod disassembler;
+mod disassembly;
+mod flow;
+mod parse_error;
+mod symbol_table;
+
+pub use banked_address::BankedAddress;
+pub use decoded_instruction::DecodedInstruction;
+pub use disassembler::Disassembler;
+pub use disassembly::{Disassembly, Listing};
+pub use flow::Flow;
+pub use parse_error::ParseError;
+pub use symbol_table::SymbolTable;
@@ -0,0 +1,23 @@
+/// Error for a location or symbol file that could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// A location is not `BB:AAAA` or `AAAA` in hexadecimal.
+    InvalidLocation(String),
+    /// A line of a symbol file is not `BB:AAAA name`, lines count from 1.
+    InvalidSymbol { line: usize, text: String },
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::InvalidLocation(text) => {
+                write!(f, "invalid location `{text}`, expected `BB:AAAA`")
+            }
+            ParseError::InvalidSymbol { line, text } => {
+                write!(f, "line {line}: expected `BB:AAAA name`, found `{text}`")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
@@ -0,0 +1,139 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use crate::{BankedAddress, ParseError};
+
+/// Labels read from an RGBDS `.sym` file, one `BB:AAAA name` per line.
+///
+/// Only the first label of a location is kept, RGBDS lists a global label before the
+/// local labels sharing its address.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SymbolTable {
+    labels: BTreeMap<BankedAddress, String>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, location: BankedAddress, name: impl Into<String>) {
+        self.labels.entry(location).or_insert_with(|| name.into());
+    }
+
+    /// The label at `address`, in any bank when `bank` is `None`.
+    pub fn label(&self, bank: Option<u16>, address: u16) -> Option<&str> {
+        match bank {
+            Some(bank) => self.labels.get(&BankedAddress::new(bank, address)),
+            None => self
+                .labels
+                .iter()
+                .find(|(location, _)| location.address == address)
+                .map(|(_, name)| name),
+        }
+        .map(String::as_str)
+    }
+
+    /// The closest label at or before `location` in the same bank and memory area, with
+    /// the distance to it.
+    pub fn containing(&self, location: BankedAddress) -> Option<(&str, u16)> {
+        let area_start = match location.address {
+            0x0000..=0x3FFF => 0x0000,
+            0x4000..=0x7FFF => 0x4000,
+            0x8000..=0x9FFF => 0x8000,
+            0xA000..=0xBFFF => 0xA000,
+            0xC000..=0xCFFF => 0xC000,
+            0xD000..=0xDFFF => 0xD000,
+            0xE000..=0xFFFF => 0xE000,
+        };
+        let start = BankedAddress::new(location.bank, area_start);
+        self.labels
+            .range(start..=location)
+            .next_back()
+            .map(|(label, name)| (name.as_str(), location.address - label.address))
+    }
+
+    /// The label of `location`, `Label+$offset` past the closest one in its bank, or the
+    /// location itself.
+    pub fn name(&self, location: BankedAddress) -> String {
+        match self.containing(location) {
+            Some((name, 0)) => name.to_string(),
+            Some((name, offset)) => format!("{name}+${offset:X}"),
+            None => location.to_string(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (BankedAddress, &str)> {
+        self.labels
+            .iter()
+            .map(|(location, name)| (*location, name.as_str()))
+    }
+}
+
+impl FromStr for SymbolTable {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut table = Self::new();
+        for (index, line) in text.lines().enumerate() {
+            let content = line.split(';').next().unwrap_or_default().trim();
+            if content.is_empty() {
+                continue;
+            }
+            let invalid = || ParseError::InvalidSymbol {
+                line: index + 1,
+                text: line.to_string(),
+            };
+            let mut words = content.split_whitespace();
+            let (Some(location), Some(name), None) = (words.next(), words.next(), words.next())
+            else {
+                return Err(invalid());
+            };
+            if !location.contains(':') {
+                return Err(invalid());
+            }
+            table.insert(location.parse().map_err(|_| invalid())?, name);
+        }
+        Ok(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rgbds_symbol_files() {
+        let table = "\
+; File generated by rgblink
+00:0150 Start
+00:0150 Start.init
+03:4a10 LoadMap ; comment
+00:c000 wBuffer
+"
+        .parse::<SymbolTable>()
+        .unwrap();
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.label(Some(0), 0x0150), Some("Start"));
+        assert_eq!(table.label(Some(3), 0x4A10), Some("LoadMap"));
+        assert_eq!(table.label(Some(4), 0x4A10), None);
+        assert_eq!(table.label(None, 0xC000), Some("wBuffer"));
+        assert_eq!(table.name(BankedAddress::new(3, 0x4A1F)), "LoadMap+$F");
+        assert_eq!(table.name(BankedAddress::new(3, 0x4000)), "03:4000");
+
+        assert_eq!(
+            "00:0150\n".parse::<SymbolTable>(),
+            Err(ParseError::InvalidSymbol {
+                line: 1,
+                text: "00:0150".to_string()
+            })
+        );
+    }
+}
@@ -8,7 +8,9 @@ pub struct Display {
 impl Display {
     pub const WIDTH: u32 = 160;
     pub const HEIGHT: u32 = 144;
+    /// Default window scale, see [`crate::DisplayPlugin::with_scale`].
     pub const SCALE_FACTOR: u32 = 6;
+    pub(crate) const TITLE: &str = "YAGBER";
 
     pub fn new(window: Window) -> Result<Self, pixels::Error> {
         let window = std::sync::Arc::new(window);
@@ -41,6 +43,41 @@ impl Display {
     pub fn request_redraw(&self) {
         self.window.request_redraw();
     }
+
+    /// The window is created lazily, frames completed before that are not drawn.
+    pub(crate) fn on_frame_completed(
+        emulator: &mut yagber_app::Emulator,
+        _event: &yagber_app::FrameCompletedEvent,
+    ) {
+        if let Some(display) = emulator.get_component::<Self>() {
+            display.request_redraw();
+        }
+    }
+
+    /// The title tells a locked up CPU apart from a game showing a still screen.
+    pub(crate) fn on_cpu_locked(
+        emulator: &mut yagber_app::Emulator,
+        _event: &yagber_cpu::CpuLockedEvent,
+    ) {
+        if let Some(display) = emulator.get_component::<Self>() {
+            display
+                .window
+                .set_title(&format!("{} - CPU locked up", Self::TITLE));
+        }
+    }
+
+    pub(crate) fn on_reset(emulator: &mut yagber_app::Emulator, event: &yagber_app::ResetEvent) {
+        if let (yagber_app::ResetEvent::Finished(_), Some(display)) =
+            (event, emulator.get_component::<Self>())
+        {
+            display.window.set_title(Self::TITLE);
+        }
+    }
 }
 
 impl yagber_app::Component for Display {}
+
+/// Window scale picked when the plugin was built, read once the window gets created.
+pub(crate) struct DisplayScale(pub u32);
+
+impl yagber_app::Component for DisplayScale {}
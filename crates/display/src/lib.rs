@@ -6,10 +6,45 @@ mod winit_runner;
 pub use display::Display;
 pub use winit_runner::WinitRunner;
 
-pub struct DisplayPlugin;
+use display::DisplayScale;
+
+pub struct DisplayPlugin {
+    scale: u32,
+}
+
+impl DisplayPlugin {
+    pub fn new() -> Self {
+        Self {
+            scale: Display::SCALE_FACTOR,
+        }
+    }
+
+    /// Window size as a multiple of the screen resolution.
+    pub fn with_scale(mut self, scale: u32) -> Self {
+        self.scale = scale.max(1);
+        self
+    }
+}
+
+impl Default for DisplayPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl yagber_app::Plugin for DisplayPlugin {
-    fn init(self, _emulator: &mut yagber_app::Emulator) {
-        // TODO: Implement
+    fn init(self, emulator: &mut yagber_app::Emulator) -> Result<(), yagber_app::PluginError> {
+        emulator
+            .with_component(DisplayScale(self.scale))
+            .on_event(Display::on_frame_completed)
+            .on_event(Display::on_cpu_locked)
+            .on_event(Display::on_reset);
+        Ok(())
+    }
+
+    fn dependencies(&self) -> yagber_app::PluginDependencies {
+        yagber_app::PluginDependencies::new()
+            .requires::<yagber_ppu::Ppu>()
+            .requires::<yagber_input::InputEventQueue>()
     }
 }
@@ -1,8 +1,8 @@
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use winit::{application::ApplicationHandler, dpi::LogicalSize, window::WindowAttributes};
 use yagber_app::Emulator;
 
-use crate::display::Display;
+use crate::display::{Display, DisplayScale};
 
 pub struct WinitApp {
     emulator: Emulator,
@@ -17,14 +17,59 @@ impl WinitApp {
         }
     }
 
-    pub fn window_attributes() -> WindowAttributes {
+    /// Longest stretch of wall clock time caught up at once.
+    /// Running behind for longer, e.g. at 8x on a slow host, drops the backlog instead of
+    /// trying to catch up forever.
+    const MAX_CATCH_UP: Duration = Duration::from_millis(100);
+    /// Wall clock time spent emulating per event loop iteration while uncapped.
+    const UNCAPPED_SLICE: Duration = Duration::from_millis(16);
+    /// Steps between two wall clock reads while uncapped.
+    const UNCAPPED_CHECK_INTERVAL: u64 = 4096;
+
+    /// Runs the dots matching the wall clock time elapsed since the last call, at `multiplier`.
+    fn run_paced(&mut self, multiplier: f64) {
+        let now = Instant::now();
+        let elapsed = now.saturating_duration_since(self.last_instant);
+        let dots_per_second = Emulator::TARGET_DOT_FREQ_HZ as f64 * multiplier;
+        let dots_to_run =
+            (elapsed.min(Self::MAX_CATCH_UP).as_secs_f64() * dots_per_second).floor() as u64;
+
+        if dots_to_run > 0 {
+            if elapsed > Self::MAX_CATCH_UP {
+                self.last_instant = now;
+            } else {
+                self.last_instant += Duration::from_secs_f64(dots_to_run as f64 / dots_per_second);
+            }
+
+            let mut ran = 0;
+            while ran < dots_to_run {
+                ran += self.emulator.step_until_due(dots_to_run - ran);
+            }
+        }
+    }
+
+    fn run_uncapped(&mut self) {
+        let start = Instant::now();
+        while start.elapsed() < Self::UNCAPPED_SLICE {
+            let mut ran = 0;
+            while ran < Self::UNCAPPED_CHECK_INTERVAL {
+                ran += self
+                    .emulator
+                    .step_until_due(Self::UNCAPPED_CHECK_INTERVAL - ran);
+            }
+        }
+        // Pacing starts over from here once the speed is capped again.
+        self.last_instant = Instant::now();
+    }
+
+    pub fn window_attributes(scale: u32) -> WindowAttributes {
         WindowAttributes::default()
             .with_inner_size(LogicalSize::new(
-                Display::WIDTH * Display::SCALE_FACTOR,
-                Display::HEIGHT * Display::SCALE_FACTOR,
+                Display::WIDTH * scale,
+                Display::HEIGHT * scale,
             ))
             .with_resizable(false)
-            .with_title("YAGBER")
+            .with_title(Display::TITLE)
     }
 }
 
@@ -35,8 +80,12 @@ impl ApplicationHandler for WinitApp {
 
         let display = self.emulator.get_component::<Display>();
         if display.is_none() {
+            let scale = self
+                .emulator
+                .get_component::<DisplayScale>()
+                .map_or(Display::SCALE_FACTOR, |scale| scale.0);
             let window = event_loop
-                .create_window(Self::window_attributes())
+                .create_window(Self::window_attributes(scale))
                 .expect("Failed to create window");
             let display = Display::new(window).expect("Failed to create display");
             self.emulator.with_component(display);
@@ -61,6 +110,7 @@ impl ApplicationHandler for WinitApp {
             WindowEvent::CloseRequested => {
                 #[cfg(feature = "trace")]
                 tracing::info!("The close button was pressed; stopping");
+                self.emulator.exit();
                 event_loop.exit();
             }
             WindowEvent::RedrawRequested => {
@@ -99,33 +149,17 @@ impl ApplicationHandler for WinitApp {
         }
     }
 
-    fn about_to_wait(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop) {
+    fn about_to_wait(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         #[cfg(feature = "trace-span")]
         let _span = tracing::info_span!("winit app about to wait").entered();
 
-        let now = Instant::now();
-        let elapsed = now.saturating_duration_since(self.last_instant);
-        let dots_to_run =
-            (elapsed.as_secs_f64() * Emulator::TARGET_DOT_FREQ_HZ as f64).floor() as u64;
-
-        if dots_to_run > 0 {
-            let advanced_ns = dots_to_run * Emulator::NANOS_PER_DOT;
-            self.last_instant += std::time::Duration::from_nanos(advanced_ns);
-
-            for _ in 0..dots_to_run {
-                self.emulator.step();
+        match self.emulator.speed().multiplier() {
+            Some(multiplier) => self.run_paced(multiplier),
+            None => self.run_uncapped(),
+        }
 
-                let ppu = self
-                    .emulator
-                    .get_component::<yagber_ppu::Ppu>()
-                    .expect("PPU component missing");
-                if ppu.just_entered_mode(yagber_ppu::PpuMode::VBlank) {
-                    self.emulator
-                        .get_component_mut::<Display>()
-                        .unwrap()
-                        .request_redraw();
-                }
-            }
+        if self.emulator.state().is_finished() {
+            event_loop.exit();
         }
     }
 }
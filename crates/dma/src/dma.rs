@@ -1,41 +1,60 @@
+use yagber_app::{Delay, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
+
 pub struct Dma {
     enabled: bool,
-    cycles: u32,
     source_addr: u16,
 }
 
 impl Dma {
-    const DMA_DELAY_CYCLES: u32 = 160;
+    /// The transfer starts one M-cycle after the DMA register write.
+    pub(crate) const STARTUP_DELAY: Delay = Delay::MCycles(1);
+    /// The hardware copies one byte per M-cycle, 160 in all.
+    /// OAM is locked to the CPU meanwhile, so all of them are copied at once when the last one is due.
+    pub(crate) const TRANSFER_DELAY: Delay = Delay::MCycles(161);
     const DMA_TARGET_ADDR: u16 = 0xFE00;
 
     pub fn new() -> Self {
         Self {
             enabled: false,
-            cycles: 0,
             source_addr: 0,
         }
     }
 
     fn start(&mut self, source_addr: u16) {
         self.enabled = true;
-        self.cycles = 0;
         self.source_addr = source_addr;
     }
 
-    pub fn on_mcycle(emulator: &mut yagber_app::Emulator) {
+    /// Scheduled [`Dma::STARTUP_DELAY`] after the DMA register write.
+    pub(crate) fn on_start_due(emulator: &mut yagber_app::Emulator) {
         let (dma, bus) = emulator
             .get_components_mut2::<Dma, yagber_memory::Bus>()
             .expect("DMA and/or Bus component missing");
         if dma.enabled {
-            dma.step(bus);
+            bus.oam.set_dma_active(true);
         }
     }
 
-    fn step(&mut self, bus: &mut yagber_memory::Bus) {
-        self.cycles += 1;
-        if self.cycles == Self::DMA_DELAY_CYCLES {
-            self.perform_transfer(bus);
-            self.disable();
+    /// Scheduled [`Dma::TRANSFER_DELAY`] after the DMA register write.
+    pub(crate) fn on_transfer_due(emulator: &mut yagber_app::Emulator) {
+        let (dma, bus) = emulator
+            .get_components_mut2::<Dma, yagber_memory::Bus>()
+            .expect("DMA and/or Bus component missing");
+        if dma.enabled {
+            dma.perform_transfer(bus);
+            dma.disable();
+        }
+        bus.oam.set_dma_active(false);
+    }
+
+    /// Soft resets abort a transfer in flight, its scheduled steps are cancelled with the rest.
+    pub(crate) fn on_reset(emulator: &mut yagber_app::Emulator, event: &yagber_app::ResetEvent) {
+        if *event == yagber_app::ResetEvent::Finished(yagber_app::ResetKind::Soft) {
+            let (dma, bus) = emulator
+                .get_components_mut2::<Dma, yagber_memory::Bus>()
+                .expect("DMA and/or Bus component missing");
+            dma.disable();
+            bus.oam.set_dma_active(false);
         }
     }
 
@@ -48,8 +67,8 @@ impl Dma {
         let target_addr = Self::DMA_TARGET_ADDR;
 
         for i in 0..0xA0 {
-            let value = bus.read(source_addr + i);
-            bus.write(target_addr + i, value);
+            let value = bus.read_dma(source_addr + i);
+            bus.write_dma(target_addr + i, value);
         }
     }
 
@@ -61,3 +80,61 @@ impl Dma {
 }
 
 impl yagber_app::Component for Dma {}
+
+/// The remaining delay lives in the emulator scheduler.
+impl Snapshot for Dma {
+    const VERSION: u16 = 2;
+
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_bool(self.enabled);
+        writer.write_u16(self.source_addr);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.enabled = reader.read_bool()?;
+        self.source_addr = reader.read_u16()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yagber_app::{Emulator, ResetKind};
+    use yagber_memory::{Bus, IOType};
+
+    fn emulator() -> Emulator {
+        let mut emulator = Emulator::new();
+        emulator.with_component(yagber_cpu::Cpu::new());
+        emulator
+            .with_plugin(yagber_memory::MemoryPlugin::default())
+            .with_plugin(crate::DmaPlugin)
+            .build()
+            .unwrap()
+    }
+
+    fn step_mcycles(emulator: &mut Emulator, mcycles: u32) {
+        for _ in 0..mcycles * 4 {
+            emulator.step();
+        }
+    }
+
+    #[test]
+    fn soft_reset_aborts_a_transfer_in_flight() {
+        let mut emulator = emulator();
+        let bus = emulator.get_component_mut::<Bus>().unwrap();
+        for offset in 0..0xA0 {
+            bus.write(0xC000 + offset, 0xAA);
+            bus.write(0xFE00 + offset, 0x11);
+        }
+        Bus::write_hooked(&mut emulator, IOType::DMA.address(), 0xC0);
+        step_mcycles(&mut emulator, 4);
+        let bus = emulator.get_component::<Bus>().unwrap();
+        assert_eq!(bus.read(0xFE00), 0xFF, "OAM is locked during the transfer");
+
+        emulator.reset(ResetKind::Soft);
+        step_mcycles(&mut emulator, 200);
+        let bus = emulator.get_component::<Bus>().unwrap();
+        assert_eq!(bus.read(0xFE00), 0x11);
+        assert_eq!(bus.read(0xFE9F), 0x11);
+    }
+}
@@ -1,4 +1,4 @@
-use yagber_cpu::Cpu;
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 use yagber_memory::{Bus, IOType};
 
 pub struct Hdma {
@@ -24,8 +24,8 @@ impl Hdma {
 
     fn transfer(bus: &mut Bus, src: u16, dst: u16, len: u16) {
         for i in 0..len {
-            let value = bus.read(src + i);
-            bus.write(dst + i, value);
+            let value = bus.read_dma(src + i);
+            bus.write_dma(dst + i, value);
         }
     }
 
@@ -44,7 +44,7 @@ impl Hdma {
         dst | 0x8000
     }
 
-    pub(crate) fn on_hdma_len_write(&mut self, bus: &mut Bus, cpu: &mut Cpu, value: u8) {
+    pub(crate) fn on_hdma_len_write(&mut self, bus: &mut Bus, value: u8) {
         let hblank_mode = value & 0x80 != 0;
         let blocks = value & 0x7F;
 
@@ -79,11 +79,11 @@ impl Hdma {
             Self::transfer(bus, src, dst, bytes);
             bus.io_registers
                 .write_unhooked(IOType::HdmaLen.address(), 0xFF);
-            cpu.freeze_for(bytes);
+            bus.stall_cpu(bytes);
         }
     }
 
-    pub(crate) fn on_stat_write(&mut self, bus: &mut Bus, cpu: &mut Cpu, value: u8) {
+    pub(crate) fn on_stat_write(&mut self, bus: &mut Bus, value: u8) {
         let stat = yagber_memory::Stat::new(value);
         let just_entered_hblank = self.last_stat_mode != 0 && stat.mode() == 0;
         self.last_stat_mode = stat.mode();
@@ -93,7 +93,7 @@ impl Hdma {
 
         let bytes = 0x10;
         Self::transfer(bus, self.src, self.dst, bytes);
-        cpu.freeze_for(bytes);
+        bus.stall_cpu(bytes);
         self.src = self.src.wrapping_add(bytes);
         self.dst = self.dst.wrapping_add(bytes);
 
@@ -111,3 +111,24 @@ impl Hdma {
 }
 
 impl yagber_app::Component for Hdma {}
+
+impl Snapshot for Hdma {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_bool(self.active);
+        writer.write_bool(self.paused);
+        writer.write_u8(self.blocks);
+        writer.write_u8(self.last_stat_mode);
+        writer.write_u16(self.src);
+        writer.write_u16(self.dst);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.active = reader.read_bool()?;
+        self.paused = reader.read_bool()?;
+        self.blocks = reader.read_u8()?;
+        self.last_stat_mode = reader.read_u8()?;
+        self.src = reader.read_u16()?;
+        self.dst = reader.read_u16()?;
+        Ok(())
+    }
+}
@@ -4,15 +4,26 @@ mod hdma;
 pub struct DmaPlugin;
 
 impl yagber_app::Plugin for DmaPlugin {
-    fn init(self, emulator: &mut yagber_app::Emulator) {
+    fn init(self, emulator: &mut yagber_app::Emulator) -> Result<(), yagber_app::PluginError> {
         emulator
             .with_component(dma::Dma::new())
             .with_component(hdma::Hdma::new())
-            .on_mcycle(dma::Dma::on_mcycle);
+            .with_snapshot::<dma::Dma>("dma")
+            .with_snapshot::<hdma::Hdma>("hdma")
+            .on_event(dma::Dma::on_reset);
 
-        let dma_hook = emulator.attach_component(dma::Dma::on_dma_write);
-        let hdma_len_hook = emulator.attach_components3(hdma::Hdma::on_hdma_len_write);
-        let hdma_stat_hook = emulator.attach_components3(hdma::Hdma::on_stat_write);
+        let start_task = emulator.add_scheduled_task("dma_start", dma::Dma::on_start_due);
+        let dma_task = emulator.add_scheduled_task("dma_transfer", dma::Dma::on_transfer_due);
+        let dma_hook = move |emulator: &mut yagber_app::Emulator, value| {
+            let dma = emulator
+                .get_component_mut::<dma::Dma>()
+                .expect("DMA component missing");
+            dma::Dma::on_dma_write(dma, value);
+            emulator.schedule(start_task, dma::Dma::STARTUP_DELAY);
+            emulator.schedule(dma_task, dma::Dma::TRANSFER_DELAY);
+        };
+        let hdma_len_hook = yagber_app::Emulator::attach_components2(hdma::Hdma::on_hdma_len_write);
+        let hdma_stat_hook = yagber_app::Emulator::attach_components2(hdma::Hdma::on_stat_write);
 
         emulator
             .get_component_mut::<yagber_memory::Bus>()
@@ -21,5 +32,14 @@ impl yagber_app::Plugin for DmaPlugin {
             .with_hook(yagber_memory::IOType::DMA, dma_hook)
             .with_hook(yagber_memory::IOType::HdmaLen, hdma_len_hook)
             .with_hook(yagber_memory::IOType::STAT, hdma_stat_hook);
+        Ok(())
+    }
+
+    fn dependencies(&self) -> yagber_app::PluginDependencies {
+        yagber_app::PluginDependencies::new()
+            .provides::<dma::Dma>()
+            .provides::<hdma::Hdma>()
+            .requires::<yagber_memory::Bus>()
+            .requires::<yagber_cpu::Cpu>()
     }
 }
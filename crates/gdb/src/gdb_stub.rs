@@ -0,0 +1,693 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{Read, Write},
+    net::{Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::JoinHandle,
+};
+
+use yagber_app::{DeferredCalls, Emulator, EmulatorState, StateTransitionEvent};
+use yagber_cpu::Cpu;
+use yagber_debugger::{
+    Breakpoint, CodeLocation, Debugger, DebuggerStoppedEvent, StopReason, WatchKind, Watchpoint,
+};
+use yagber_memory::Bus;
+
+use crate::packet::{Incoming, PacketReader, encode};
+
+/// Serves one GDB client at a time, mapping its requests onto the [`Debugger`], the [`Cpu`]
+/// and the [`Bus`].
+///
+/// Packets are read on a thread of their own and handled as deferred calls once the
+/// emulator is paused, the client only sends them while the target is stopped.
+/// The thread is joined when the emulator ends.
+#[derive(Debug, Default)]
+pub struct GdbStub {
+    connection: Arc<Connection>,
+    /// The connection thread and the address that wakes it up.
+    server: Option<(JoinHandle<()>, SocketAddr)>,
+    /// A client is connected and its attach was handled.
+    attached: bool,
+    pending: VecDeque<String>,
+    /// The client resumed the target and waits for a stop reply.
+    running: bool,
+    /// Debugger ids of the breakpoints and watchpoints the client set, by packet fields.
+    points: HashMap<(u8, u32, u32), u32>,
+}
+
+impl GdbStub {
+    const TARGET_XML: &'static str = concat!(
+        r#"<?xml version="1.0"?><!DOCTYPE target SYSTEM "gdb-target.dtd">"#,
+        r#"<target version="1.0"><feature name="org.gnu.gdb.z80.cpu">"#,
+        r#"<reg name="af" bitsize="16" type="int"/>"#,
+        r#"<reg name="bc" bitsize="16" type="int"/>"#,
+        r#"<reg name="de" bitsize="16" type="data_ptr"/>"#,
+        r#"<reg name="hl" bitsize="16" type="data_ptr"/>"#,
+        r#"<reg name="sp" bitsize="16" type="data_ptr"/>"#,
+        r#"<reg name="pc" bitsize="16" type="code_ptr"/>"#,
+        "</feature></target>"
+    );
+    const REGISTER_COUNT: usize = 6;
+    /// Largest packet the client may send and the stub replies with, data included.
+    const PACKET_SIZE: u32 = 0x1000;
+    const SIGINT: u8 = 2;
+    const SIGTRAP: u8 = 5;
+
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn listen(emulator: &mut Emulator, listener: TcpListener) {
+        let calls = emulator.deferred_calls();
+        let stub = stub(emulator);
+        let connection = stub.connection.clone();
+        let wake_address = listener.local_addr().map(|mut address| {
+            if address.ip().is_unspecified() {
+                address.set_ip(match address {
+                    SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+                    SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+                });
+            }
+            address
+        });
+        let server = std::thread::Builder::new()
+            .name("gdb stub".to_string())
+            .spawn(move || Self::serve(listener, calls, connection))
+            .expect("Failed to spawn the GDB stub thread");
+        // Without an address to wake it up, the thread is left to end with the process
+        stub.server = wake_address.ok().map(|address| (server, address));
+    }
+
+    fn serve(listener: TcpListener, calls: DeferredCalls, connection: Arc<Connection>) {
+        for stream in listener.incoming() {
+            if connection.closing.load(Ordering::Acquire) {
+                return;
+            }
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let Ok(writer) = stream.try_clone() else {
+                continue;
+            };
+            #[cfg(feature = "trace")]
+            tracing::info!("GDB client connected from {:?}", stream.peer_addr());
+            let _ = stream.set_nodelay(true);
+            connection.no_ack.store(false, Ordering::Release);
+            if !connection.open(writer) {
+                return;
+            }
+            calls.push(Self::attach);
+            Self::read_client(stream, &calls, &connection);
+            connection.close();
+            calls.push(Self::detach);
+        }
+    }
+
+    /// Forwards the client packets until it disconnects.
+    fn read_client(mut stream: TcpStream, calls: &DeferredCalls, connection: &Connection) {
+        let mut reader = PacketReader::default();
+        let mut buffer = [0; 4096];
+        loop {
+            let read = match stream.read(&mut buffer) {
+                Ok(0) | Err(_) => return,
+                Ok(read) => read,
+            };
+            for byte in &buffer[..read] {
+                let acknowledgment = match reader.push(*byte) {
+                    Some(Incoming::Packet(packet)) => {
+                        calls.push(move |emulator| Self::receive(emulator, packet));
+                        b'+'
+                    }
+                    Some(Incoming::Corrupt) => b'-',
+                    Some(Incoming::Interrupt) => {
+                        calls.push(Self::interrupt);
+                        continue;
+                    }
+                    None => continue,
+                };
+                if !connection.no_ack.load(Ordering::Acquire) && !connection.send(&[acknowledgment])
+                {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// The client expects the target to be stopped once attached.
+    fn attach(emulator: &mut Emulator) {
+        let stub = stub(emulator);
+        stub.attached = true;
+        stub.pending.clear();
+        stub.running = false;
+        if emulator.is_paused() {
+            Self::poll(emulator);
+        } else {
+            Debugger::pause(emulator);
+        }
+    }
+
+    /// Removes what the client set and lets the emulator run on.
+    fn detach(emulator: &mut Emulator) {
+        let stub = stub(emulator);
+        if !std::mem::take(&mut stub.attached) {
+            return;
+        }
+        stub.running = false;
+        let ids = stub.points.drain().map(|(_, id)| id).collect::<Vec<_>>();
+        let debugger = debugger(emulator);
+        for id in ids {
+            debugger.remove(id);
+        }
+        Debugger::resume(emulator);
+    }
+
+    fn receive(emulator: &mut Emulator, packet: String) {
+        stub(emulator).pending.push_back(packet);
+        Self::poll(emulator);
+    }
+
+    fn interrupt(emulator: &mut Emulator) {
+        if stub(emulator).running {
+            Debugger::pause(emulator);
+        }
+    }
+
+    pub(crate) fn on_stopped(emulator: &mut Emulator, event: &DebuggerStoppedEvent) {
+        let stub = stub(emulator);
+        if !stub.attached {
+            return;
+        }
+        if std::mem::take(&mut stub.running) {
+            let reply = Self::stop_reply(emulator, event.reason);
+            Self::send(emulator, &reply);
+        }
+        Self::poll(emulator);
+    }
+
+    /// Handles the queued packets while the emulator stays paused.
+    fn poll(emulator: &mut Emulator) {
+        while emulator.is_paused() {
+            let Some(packet) = stub(emulator).pending.pop_front() else {
+                return;
+            };
+            if let Some(reply) = Self::execute(emulator, &packet) {
+                Self::send(emulator, &reply);
+            }
+        }
+    }
+
+    pub(crate) fn on_state_transition(emulator: &mut Emulator, event: &StateTransitionEvent) {
+        if event.to == EmulatorState::Ending {
+            stub(emulator).shutdown();
+        }
+    }
+
+    /// Disconnects the client and joins the connection thread.
+    fn shutdown(&mut self) {
+        let Some((server, wake_address)) = self.server.take() else {
+            return;
+        };
+        self.connection.closing.store(true, Ordering::Release);
+        self.connection.close();
+        // The thread may be waiting for a connection
+        if TcpStream::connect(wake_address).is_ok() {
+            let _ = server.join();
+        }
+    }
+
+    fn send(emulator: &mut Emulator, reply: &str) {
+        let stub = stub(emulator);
+        if stub.attached && !stub.connection.send(&encode(reply)) {
+            stub.attached = false;
+        }
+    }
+
+    fn stop_reply(emulator: &Emulator, reason: StopReason) -> String {
+        let StopReason::Watchpoint { id, access } = reason else {
+            let signal = match reason {
+                StopReason::Paused => Self::SIGINT,
+                _ => Self::SIGTRAP,
+            };
+            return format!("S{signal:02x}");
+        };
+        let kind = emulator
+            .get_component::<Debugger>()
+            .and_then(|debugger| debugger.watchpoint(id))
+            .map_or(WatchKind::Access, Watchpoint::kind);
+        let name = match kind {
+            WatchKind::Write => "watch",
+            WatchKind::Read => "rwatch",
+            WatchKind::Access => "awatch",
+        };
+        format!("T{:02x}{name}:{:x};", Self::SIGTRAP, access.address)
+    }
+
+    /// Returns the reply, `None` when the client waits for the target to stop instead.
+    /// Unsupported packets get the empty reply.
+    fn execute(emulator: &mut Emulator, packet: &str) -> Option<String> {
+        let (command, arguments) = packet.split_at(packet.len().min(1));
+        let reply = match command {
+            "?" => format!("S{:02x}", Self::SIGTRAP),
+            "q" => Self::query(arguments),
+            "Q" if arguments == "StartNoAckMode" => {
+                stub(emulator)
+                    .connection
+                    .no_ack
+                    .store(true, Ordering::Release);
+                "OK".to_string()
+            }
+            "H" => "OK".to_string(),
+            "g" => {
+                let (cpu, _) = machine(emulator);
+                registers(cpu).iter().map(|value| hex_u16(*value)).collect()
+            }
+            "G" => or_error(Self::write_registers(emulator, arguments)),
+            "p" => match parse_hex(arguments)
+                .and_then(|index| registers(machine(emulator).0).get(index as usize).copied())
+            {
+                Some(value) => hex_u16(value),
+                None => error(),
+            },
+            "P" => or_error(Self::write_register(emulator, arguments)),
+            "m" => Self::read_memory(emulator, arguments).unwrap_or_else(error),
+            "M" => or_error(Self::write_memory(emulator, arguments)),
+            "Z" | "z" => or_error(Self::update_point(emulator, command == "Z", arguments)),
+            "c" | "s" => {
+                if let Some(address) = parse_address(arguments) {
+                    machine(emulator).0.set_pc(address as u16);
+                }
+                stub(emulator).running = true;
+                if command == "c" {
+                    Debugger::resume(emulator);
+                } else {
+                    Debugger::step_into(emulator);
+                }
+                return None;
+            }
+            "D" => {
+                Self::send(emulator, "OK");
+                Self::detach(emulator);
+                return None;
+            }
+            "k" => {
+                Self::detach(emulator);
+                stub(emulator).connection.close();
+                emulator.exit();
+                return None;
+            }
+            _ => String::new(),
+        };
+        Some(reply)
+    }
+
+    fn query(query: &str) -> String {
+        if query.starts_with("Supported") {
+            return format!(
+                "PacketSize={:x};qXfer:features:read+;QStartNoAckMode+",
+                Self::PACKET_SIZE
+            );
+        }
+        if query == "Attached" {
+            return "1".to_string();
+        }
+        if let Some(range) = query.strip_prefix("Xfer:features:read:target.xml:") {
+            let Some((offset, length)) = range.split_once(',') else {
+                return error();
+            };
+            let (Some(offset), Some(length)) = (parse_hex(offset), parse_hex(length)) else {
+                return error();
+            };
+            let xml = Self::TARGET_XML.as_bytes();
+            let start = (offset as usize).min(xml.len());
+            let end = start.saturating_add(length as usize).min(xml.len());
+            let chunk = String::from_utf8_lossy(&xml[start..end]);
+            let more = if end < xml.len() { 'm' } else { 'l' };
+            return format!("{more}{chunk}");
+        }
+        String::new()
+    }
+
+    fn write_registers(emulator: &mut Emulator, arguments: &str) -> Option<()> {
+        let values = arguments
+            .as_bytes()
+            .chunks(4)
+            .map(|digits| std::str::from_utf8(digits).ok().and_then(parse_hex_u16))
+            .collect::<Option<Vec<_>>>()?;
+        if values.len() < Self::REGISTER_COUNT {
+            return None;
+        }
+        let (cpu, _) = machine(emulator);
+        for (index, value) in values.into_iter().take(Self::REGISTER_COUNT).enumerate() {
+            set_register(cpu, index, value);
+        }
+        Some(())
+    }
+
+    fn write_register(emulator: &mut Emulator, arguments: &str) -> Option<()> {
+        let (index, value) = arguments.split_once('=')?;
+        let index = parse_hex(index)? as usize;
+        if index >= Self::REGISTER_COUNT {
+            return None;
+        }
+        set_register(machine(emulator).0, index, parse_hex_u16(value)?);
+        Some(())
+    }
+
+    /// Reads stop at the end of the address space and where the reply would outgrow a packet,
+    /// the client asks for the rest.
+    fn read_memory(emulator: &mut Emulator, arguments: &str) -> Option<String> {
+        let (address, length) = arguments.split_once(',')?;
+        let address = parse_address(address)?;
+        let length = parse_hex(length)?
+            .min(0x10000 - address)
+            .min(Self::PACKET_SIZE / 2);
+        let (_, bus) = machine(emulator);
+        let bytes = (address..address + length)
+            .map(|address| format!("{:02x}", bus.read(address as u16)))
+            .collect();
+        Some(bytes)
+    }
+
+    /// Writes go through the bus like CPU writes do, so ROM writes reach the MBC.
+    /// Writes running past the end of the address space are rejected.
+    fn write_memory(emulator: &mut Emulator, arguments: &str) -> Option<()> {
+        let (range, data) = arguments.split_once(':')?;
+        let (address, length) = range.split_once(',')?;
+        let (address, length) = (parse_address(address)?, parse_hex(length)?);
+        if length > 0x10000 - address {
+            return None;
+        }
+        let bytes = data
+            .as_bytes()
+            .chunks(2)
+            .map(|digits| {
+                let digits = std::str::from_utf8(digits).ok()?;
+                u8::from_str_radix(digits, 16).ok()
+            })
+            .collect::<Option<Vec<_>>>()?;
+        if bytes.len() != length as usize {
+            return None;
+        }
+        for (address, byte) in (address..).zip(bytes) {
+            Bus::write_hooked(emulator, address as u16, byte);
+        }
+        Some(())
+    }
+
+    /// `Z type,address,kind`, breakpoint addresses above 0xFFFF carry the ROM bank in their
+    /// upper bits, `0x34A10` being `03:4A10`. Watchpoints stop at the end of the address space.
+    fn update_point(emulator: &mut Emulator, insert: bool, arguments: &str) -> Option<()> {
+        let mut fields = arguments.split(',');
+        let kind = fields.next()?.bytes().next()?;
+        let address = parse_hex(fields.next()?)?;
+        let length = parse_hex(fields.next()?)?;
+        let key = (kind, address, length);
+
+        if !insert {
+            let id = stub(emulator).points.remove(&key)?;
+            debugger(emulator).remove(id);
+            return Some(());
+        }
+        if stub(emulator).points.contains_key(&key) {
+            return Some(());
+        }
+        if kind != b'0' && kind != b'1' && address > 0xFFFF {
+            return None;
+        }
+        let start = address as u16;
+        let end = address.saturating_add(length.saturating_sub(1)).min(0xFFFF) as u16;
+        let debugger = debugger(emulator);
+        let id = match kind {
+            b'0' | b'1' => {
+                let mut location = CodeLocation::new(start);
+                if address > 0xFFFF {
+                    location = location.with_bank((address >> 16) as u16);
+                }
+                debugger.add_breakpoint(Breakpoint::new(location))
+            }
+            b'2' => debugger.add_watchpoint(Watchpoint::new(start..=end, WatchKind::Write)),
+            b'3' => debugger.add_watchpoint(Watchpoint::new(start..=end, WatchKind::Read)),
+            b'4' => debugger.add_watchpoint(Watchpoint::new(start..=end, WatchKind::Access)),
+            _ => return None,
+        };
+        stub(emulator).points.insert(key, id);
+        Some(())
+    }
+}
+
+impl Drop for GdbStub {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl yagber_app::Component for GdbStub {}
+
+/// Connection state shared with the connection thread.
+#[derive(Debug, Default)]
+struct Connection {
+    /// Write half of the connected client, the only writer so acknowledgments never land
+    /// in the middle of a reply.
+    client: Mutex<Option<TcpStream>>,
+    /// Set once the client asked to stop acknowledging packets.
+    no_ack: AtomicBool,
+    /// Set once the stub shuts down, no client is served anymore.
+    closing: AtomicBool,
+}
+
+impl Connection {
+    /// `false` once the stub shuts down.
+    fn open(&self, client: TcpStream) -> bool {
+        let mut current = self.client.lock().expect("GDB client poisoned");
+        if self.closing.load(Ordering::Acquire) {
+            return false;
+        }
+        *current = Some(client);
+        true
+    }
+
+    /// Disconnects the client, its connection thread sees the end of the stream.
+    fn close(&self) {
+        if let Some(client) = self.client.lock().expect("GDB client poisoned").take() {
+            let _ = client.shutdown(Shutdown::Both);
+        }
+    }
+
+    fn send(&self, bytes: &[u8]) -> bool {
+        self.client
+            .lock()
+            .expect("GDB client poisoned")
+            .as_mut()
+            .is_some_and(|client| client.write_all(bytes).is_ok())
+    }
+}
+
+fn stub(emulator: &mut Emulator) -> &mut GdbStub {
+    emulator
+        .get_component_mut::<GdbStub>()
+        .expect("GdbStub component missing")
+}
+
+fn debugger(emulator: &mut Emulator) -> &mut Debugger {
+    emulator
+        .get_component_mut::<Debugger>()
+        .expect("Debugger component missing")
+}
+
+fn machine(emulator: &mut Emulator) -> (&mut Cpu, &mut Bus) {
+    emulator
+        .get_components_mut2::<Cpu, Bus>()
+        .expect("Cpu and/or Bus component missing")
+}
+
+/// AF, BC, DE, HL, SP and PC, in the order of the target description.
+fn registers(cpu: &Cpu) -> [u16; GdbStub::REGISTER_COUNT] {
+    let registers = cpu.registers();
+    [
+        registers.af(),
+        registers.bc(),
+        registers.de(),
+        registers.hl(),
+        cpu.sp(),
+        cpu.pc(),
+    ]
+}
+
+fn set_register(cpu: &mut Cpu, index: usize, value: u16) {
+    match index {
+        0 => cpu.registers_mut().set_af(value),
+        1 => cpu.registers_mut().set_bc(value),
+        2 => cpu.registers_mut().set_de(value),
+        3 => cpu.registers_mut().set_hl(value),
+        4 => cpu.set_sp(value),
+        5 => cpu.set_pc(value),
+        _ => unreachable!("Register {index} out of range"),
+    }
+}
+
+fn parse_hex(text: &str) -> Option<u32> {
+    u32::from_str_radix(text, 16).ok()
+}
+
+/// A memory address, `None` past the 64 KiB address space.
+fn parse_address(text: &str) -> Option<u32> {
+    parse_hex(text).filter(|&address| address <= 0xFFFF)
+}
+
+/// Registers travel as target byte order, little endian, hex.
+fn parse_hex_u16(text: &str) -> Option<u16> {
+    let value = u16::from_str_radix(text, 16).ok()?;
+    (text.len() == 4).then(|| value.swap_bytes())
+}
+
+fn hex_u16(value: u16) -> String {
+    let [low, high] = value.to_le_bytes();
+    format!("{low:02x}{high:02x}")
+}
+
+fn error() -> String {
+    "E01".to_string()
+}
+
+fn or_error(result: Option<()>) -> String {
+    match result {
+        Some(()) => "OK".to_string(),
+        None => error(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::GdbPlugin;
+
+    struct Session {
+        emulator: Emulator,
+        client: TcpStream,
+        reader: PacketReader,
+    }
+
+    impl Session {
+        /// Runs the CGB boot ROM, see the debugger tests for its first instructions.
+        fn connect() -> Self {
+            let plugin = GdbPlugin::bind(("127.0.0.1", 0)).unwrap();
+            let address = plugin.local_addr().unwrap();
+            let emulator = Emulator::new()
+                .with_plugin(yagber_memory::MemoryPlugin::default())
+                .with_plugin(yagber_cpu::CpuPlugin)
+                .with_plugin(yagber_debugger::DebuggerPlugin::new())
+                .with_plugin(plugin)
+                .build()
+                .unwrap();
+            let client = TcpStream::connect(address).unwrap();
+            client.set_nonblocking(true).unwrap();
+            Self {
+                emulator,
+                client,
+                reader: PacketReader::default(),
+            }
+        }
+
+        /// Sends `packet` and steps the emulator until the reply arrives.
+        fn request(&mut self, packet: &str) -> String {
+            self.client.write_all(&encode(packet)).unwrap();
+            self.reply()
+        }
+
+        fn reply(&mut self) -> String {
+            let mut buffer = [0; 4096];
+            for _ in 0..1_000_000 {
+                self.emulator.step();
+                let read = match self.client.read(&mut buffer) {
+                    Ok(read) => read,
+                    Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => {
+                        std::thread::sleep(Duration::from_micros(10));
+                        continue;
+                    }
+                    Err(error) => panic!("{error}"),
+                };
+                for byte in &buffer[..read] {
+                    if let Some(Incoming::Packet(reply)) = self.reader.push(*byte) {
+                        return reply;
+                    }
+                }
+            }
+            panic!("no reply to the request");
+        }
+    }
+
+    #[test]
+    fn serves_a_debugging_session() {
+        let mut session = Session::connect();
+        assert!(session.request("qSupported:swbreak+").contains("qXfer"));
+        assert_eq!(session.request("QStartNoAckMode"), "OK");
+        assert_eq!(session.request("?"), "S05");
+        assert!(session.emulator.is_paused());
+
+        assert_eq!(session.request("Z0,7c,1"), "OK");
+        session.client.write_all(&encode("c")).unwrap();
+        assert_eq!(session.reply(), "S05");
+        let registers = session.request("g");
+        assert_eq!(&registers[16..], "feff7c00");
+
+        assert_eq!(session.request("Z2,ff70,1"), "OK");
+        session.client.write_all(&encode("c")).unwrap();
+        assert_eq!(session.reply(), "T05watch:ff70;");
+        assert_eq!(session.request("p5"), "7e00");
+
+        assert_eq!(session.request("m0,3"), "31feff");
+        assert_eq!(session.request("Mc000,2:1234"), "OK");
+        assert_eq!(session.request("mc000,2"), "1234");
+        assert_eq!(
+            session.request("m0,10000").len(),
+            GdbStub::PACKET_SIZE as usize
+        );
+        assert_eq!(session.request("mfffe,10").len(), 4);
+        assert_eq!(session.request("m10000,1"), "E01");
+        assert_eq!(session.request("Mffff,2:1234"), "E01");
+        assert_eq!(session.request("P0=00c0"), "OK");
+        assert_eq!(session.request("p0"), "00c0");
+
+        session.client.write_all(&encode("s")).unwrap();
+        assert_eq!(session.reply(), "S05");
+        assert_eq!(session.request("p5"), "8000");
+    }
+
+    #[test]
+    fn test_kill_ends_the_session_and_the_emulator() {
+        let mut session = Session::connect();
+        assert_eq!(session.request("?"), "S05");
+
+        session.client.write_all(&encode("k")).unwrap();
+        let mut buffer = [0; 64];
+        for _ in 0..1_000_000 {
+            session.emulator.step();
+            match session.client.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(_) => continue,
+                Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(Duration::from_micros(10));
+                }
+                Err(error) => panic!("{error}"),
+            }
+        }
+        assert_eq!(session.emulator.state(), EmulatorState::Ended);
+        assert!(stub(&mut session.emulator).server.is_none());
+    }
+
+    #[test]
+    fn test_dropping_the_emulator_joins_the_connection_thread() {
+        let session = Session::connect();
+        drop(session.emulator);
+
+        let mut client = session.client;
+        client.set_nonblocking(false).unwrap();
+        let mut buffer = [0; 64];
+        while client.read(&mut buffer).is_ok_and(|read| read > 0) {}
+    }
+}
@@ -0,0 +1,46 @@
+mod gdb_stub;
+mod packet;
+
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+
+pub use gdb_stub::GdbStub;
+
+/// Lets a GDB client attach over TCP, through the GDB remote serial protocol.
+///
+/// Registers are exposed as AF, BC, DE, HL, SP and PC, 16 bits each. Needs the
+/// [`yagber_debugger::DebuggerPlugin`], which the stub drives.
+pub struct GdbPlugin {
+    listener: TcpListener,
+}
+
+impl GdbPlugin {
+    pub const DEFAULT_PORT: u16 = 2345;
+
+    /// Listens on `address` right away, so a port already in use is reported here.
+    pub fn bind(address: impl ToSocketAddrs) -> std::io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(address)?,
+        })
+    }
+
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+}
+
+impl yagber_app::Plugin for GdbPlugin {
+    fn init(self, emulator: &mut yagber_app::Emulator) -> Result<(), yagber_app::PluginError> {
+        emulator
+            .with_component(GdbStub::new())
+            .on_event(GdbStub::on_stopped)
+            .on_event(GdbStub::on_state_transition);
+        GdbStub::listen(emulator, self.listener);
+        Ok(())
+    }
+
+    fn dependencies(&self) -> yagber_app::PluginDependencies {
+        yagber_app::PluginDependencies::new()
+            .provides::<GdbStub>()
+            .requires::<yagber_debugger::Debugger>()
+    }
+}
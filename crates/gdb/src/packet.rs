@@ -0,0 +1,117 @@
+/// What the client sent, acknowledgments aside.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Incoming {
+    /// Payload of a packet whose checksum matched.
+    Packet(String),
+    /// A packet whose checksum did not match, the client sends it again once nacked.
+    Corrupt,
+    /// Ctrl-C, sent outside of any packet while the target runs.
+    Interrupt,
+}
+
+/// Splits the client byte stream into packets, `$payload#checksum`.
+#[derive(Debug, Default)]
+pub(crate) struct PacketReader {
+    payload: Vec<u8>,
+    state: ReaderState,
+}
+
+#[derive(Debug, Default)]
+enum ReaderState {
+    #[default]
+    Idle,
+    Payload,
+    /// Checksum digits read so far.
+    Checksum(Vec<u8>),
+}
+
+impl PacketReader {
+    const INTERRUPT: u8 = 0x03;
+
+    pub(crate) fn push(&mut self, byte: u8) -> Option<Incoming> {
+        match &mut self.state {
+            ReaderState::Idle => match byte {
+                b'$' => {
+                    self.payload.clear();
+                    self.state = ReaderState::Payload;
+                }
+                Self::INTERRUPT => return Some(Incoming::Interrupt),
+                // Acknowledgments, and noise between packets
+                _ => {}
+            },
+            ReaderState::Payload => match byte {
+                b'#' => self.state = ReaderState::Checksum(Vec::with_capacity(2)),
+                _ => self.payload.push(byte),
+            },
+            ReaderState::Checksum(digits) => {
+                digits.push(byte);
+                if digits.len() < 2 {
+                    return None;
+                }
+                let expected = std::str::from_utf8(digits)
+                    .ok()
+                    .and_then(|digits| u8::from_str_radix(digits, 16).ok());
+                self.state = ReaderState::Idle;
+                if expected != Some(checksum(&self.payload)) {
+                    return Some(Incoming::Corrupt);
+                }
+                return Some(match String::from_utf8(std::mem::take(&mut self.payload)) {
+                    Ok(payload) => Incoming::Packet(payload),
+                    Err(_) => Incoming::Corrupt,
+                });
+            }
+        }
+        None
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |sum, byte| sum.wrapping_add(*byte))
+}
+
+/// Frames `payload` as a packet, escaping the bytes the protocol reserves.
+pub(crate) fn encode(payload: &str) -> Vec<u8> {
+    let mut escaped = Vec::with_capacity(payload.len());
+    for byte in payload.bytes() {
+        if let b'$' | b'#' | b'}' | b'*' = byte {
+            escaped.extend([b'}', byte ^ 0x20]);
+        } else {
+            escaped.push(byte);
+        }
+    }
+    let mut packet = Vec::with_capacity(escaped.len() + 4);
+    packet.push(b'$');
+    packet.extend(&escaped);
+    packet.extend(format!("#{:02x}", checksum(&escaped)).bytes());
+    packet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(bytes: &[u8]) -> Vec<Incoming> {
+        let mut reader = PacketReader::default();
+        bytes.iter().filter_map(|byte| reader.push(*byte)).collect()
+    }
+
+    #[test]
+    fn splits_the_stream_into_packets() {
+        assert_eq!(
+            read(b"+$g#67+$m100,2#5c\x03$g#00"),
+            [
+                Incoming::Packet("g".to_string()),
+                Incoming::Packet("m100,2".to_string()),
+                Incoming::Interrupt,
+                Incoming::Corrupt,
+            ]
+        );
+    }
+
+    #[test]
+    fn encodes_with_checksum_and_escapes() {
+        assert_eq!(encode("OK"), b"$OK#9a");
+        assert_eq!(encode("a#"), b"$a}\x03#e1");
+        assert_eq!(read(&encode("S05")), [Incoming::Packet("S05".to_string())]);
+    }
+}
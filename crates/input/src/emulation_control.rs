@@ -8,18 +8,17 @@ impl EmulationControl {
             .get_component_mut::<crate::InputEventQueue>()
             .expect("EmulationControl must be initialized after InputPlugin");
 
-        let mut latest_event = None;
+        let mut events = Vec::new();
         while let Some(event) = event_queue.pop_event::<Self>() {
-            let event = Self::emulation_control_event_from_input(event);
-            if event.is_some() {
-                latest_event = event;
+            if let Some(event) = Self::emulation_control_event_from_input(event) {
+                events.push(event);
             }
         }
 
-        if let Some(event) = latest_event {
+        for event in events {
             #[cfg(feature = "trace")]
             tracing::trace!("Emulation Control: {:?}", event);
-            emulator.handle_control_event(event);
+            emulator.emit(event);
         }
     }
 
@@ -28,12 +27,44 @@ impl EmulationControl {
     ) -> Option<yagber_app::EmulationControlEvent> {
         match input {
             InputEvent::Keyboard(keyboard_input) => {
-                if keyboard_input.state == crate::KeyState::Pressed
-                    && keyboard_input.key_code == crate::physical_input::keyboard::KeyCode::KeyP
-                {
-                    Some(yagber_app::EmulationControlEvent::TogglePause)
-                } else {
-                    None
+                let pressed = keyboard_input.state == crate::KeyState::Pressed;
+                match keyboard_input.key_code {
+                    crate::physical_input::keyboard::KeyCode::KeyP if pressed => {
+                        Some(yagber_app::EmulationControlEvent::TogglePause)
+                    }
+                    crate::physical_input::keyboard::KeyCode::KeyF if pressed => {
+                        Some(yagber_app::EmulationControlEvent::FrameAdvance)
+                    }
+                    crate::physical_input::keyboard::KeyCode::KeyN if pressed => {
+                        Some(yagber_app::EmulationControlEvent::StepInstruction)
+                    }
+                    crate::physical_input::keyboard::KeyCode::KeyL if pressed => {
+                        Some(yagber_app::EmulationControlEvent::StepScanline)
+                    }
+                    crate::physical_input::keyboard::KeyCode::Equal if pressed => {
+                        Some(yagber_app::EmulationControlEvent::SpeedUp)
+                    }
+                    crate::physical_input::keyboard::KeyCode::Minus if pressed => {
+                        Some(yagber_app::EmulationControlEvent::SlowDown)
+                    }
+                    crate::physical_input::keyboard::KeyCode::Digit0 if pressed => {
+                        Some(yagber_app::EmulationControlEvent::SetSpeed(
+                            yagber_app::EmulationSpeed::Normal,
+                        ))
+                    }
+                    crate::physical_input::keyboard::KeyCode::Tab => {
+                        Some(yagber_app::EmulationControlEvent::FastForward(pressed))
+                    }
+                    crate::physical_input::keyboard::KeyCode::F1 if pressed => Some(
+                        yagber_app::EmulationControlEvent::Reset(yagber_app::ResetKind::Soft),
+                    ),
+                    crate::physical_input::keyboard::KeyCode::F2 if pressed => Some(
+                        yagber_app::EmulationControlEvent::Reset(yagber_app::ResetKind::Hard),
+                    ),
+                    crate::physical_input::keyboard::KeyCode::KeyR => {
+                        Some(yagber_app::EmulationControlEvent::Rewind(pressed))
+                    }
+                    _ => None,
                 }
             }
         }
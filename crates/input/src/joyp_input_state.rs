@@ -1,6 +1,6 @@
 use strum::EnumCount;
 
-use crate::{InputEventQueue, input_event::InputEvent, key_state::KeyState};
+use crate::{InputEventQueue, KeyBindings, input_event::InputEvent, key_state::KeyState};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, EnumCount)]
 pub enum JoypKey {
@@ -15,19 +15,20 @@ pub enum JoypKey {
 }
 
 impl JoypKey {
-    pub fn from_input_event(input_event: InputEvent) -> Option<Self> {
+    pub const ALL: [Self; Self::COUNT] = [
+        Self::ButtonA,
+        Self::ButtonB,
+        Self::Select,
+        Self::Start,
+        Self::Up,
+        Self::Down,
+        Self::Left,
+        Self::Right,
+    ];
+
+    pub fn from_input_event(input_event: &InputEvent, bindings: &KeyBindings) -> Option<Self> {
         match input_event {
-            InputEvent::Keyboard(keyboard_input) => match keyboard_input.key_code {
-                crate::keyboard::KeyCode::KeyZ => Some(JoypKey::ButtonA),
-                crate::keyboard::KeyCode::KeyX => Some(JoypKey::ButtonB),
-                crate::keyboard::KeyCode::Backspace => Some(JoypKey::Select),
-                crate::keyboard::KeyCode::Enter => Some(JoypKey::Start),
-                crate::keyboard::KeyCode::ArrowUp => Some(JoypKey::Up),
-                crate::keyboard::KeyCode::ArrowDown => Some(JoypKey::Down),
-                crate::keyboard::KeyCode::ArrowLeft => Some(JoypKey::Left),
-                crate::keyboard::KeyCode::ArrowRight => Some(JoypKey::Right),
-                _ => None,
-            },
+            InputEvent::Keyboard(keyboard_input) => bindings.button(&keyboard_input.key_code),
         }
     }
 }
@@ -39,8 +40,8 @@ pub struct JoypEvent {
 }
 
 impl JoypEvent {
-    pub fn from_input_event(input_event: InputEvent) -> Option<Self> {
-        let key = JoypKey::from_input_event(input_event.clone())?;
+    pub fn from_input_event(input_event: InputEvent, bindings: &KeyBindings) -> Option<Self> {
+        let key = JoypKey::from_input_event(&input_event, bindings)?;
         let state = match input_event {
             InputEvent::Keyboard(keyboard_input) => keyboard_input.state,
         };
@@ -48,29 +49,51 @@ impl JoypEvent {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct JoypInputState {
     key_states: [KeyState; JoypKey::COUNT],
+    bindings: KeyBindings,
 }
 
 impl JoypInputState {
     pub fn new() -> Self {
+        Self::with_bindings(KeyBindings::default())
+    }
+
+    pub fn with_bindings(bindings: KeyBindings) -> Self {
         Self {
             key_states: [KeyState::Released; JoypKey::COUNT],
+            bindings,
         }
     }
 
     pub(crate) fn on_mcycle(emulator: &mut yagber_app::Emulator) {
-        let (event_queue, joyp_input_state) = emulator
-            .get_components_mut2::<InputEventQueue, JoypInputState>()
-            .expect("JoypInputState and InputEventQueue must be initialized");
+        let (event_queue, joyp_input_state, bus) = emulator
+            .get_components_mut3::<InputEventQueue, JoypInputState, yagber_memory::Bus>()
+            .expect("JoypInputState, InputEventQueue and Bus must be initialized");
         while let Some(event) = event_queue.pop_event::<Self>() {
             joyp_input_state.handle_input(event);
         }
+        joyp_input_state.update_lines(bus);
+    }
+
+    /// Drives the button lines of the selected groups, from the selection bits stored in
+    /// JOYP. A line going low requests the joypad interrupt and wakes the CPU from STOP.
+    fn update_lines(&self, bus: &mut yagber_memory::Bus) {
+        let joyp = bus.io_registers.read(yagber_memory::IOType::JOYP.address());
+        let selected_buttons = yagber_memory::JoypRegister::new(joyp).selected_buttons();
+        let lower_nibble = self.lower_nibble(selected_buttons);
+        if joyp & !lower_nibble & 0x0F != 0 {
+            bus.request_interrupt(yagber_memory::InterruptType::Joypad);
+        }
+        bus.io_registers.write_unhooked(
+            yagber_memory::IOType::JOYP.address(),
+            0xC0 | selected_buttons.as_bits() | lower_nibble,
+        );
     }
 
     fn handle_input(&mut self, input: InputEvent) {
-        let joyp_event = JoypEvent::from_input_event(input);
+        let joyp_event = JoypEvent::from_input_event(input, &self.bindings);
         let Some(joyp_event) = joyp_event else {
             return;
         };
@@ -90,12 +113,15 @@ impl JoypInputState {
         &mut self.key_states[key as usize]
     }
 
-    pub(crate) fn joyp_transformer(&mut self, (_old_value, new_value): (u8, u8)) -> Option<u8> {
-        let joyp = yagber_memory::JoypRegister::new(new_value);
-        let selected_buttons = joyp.selected_buttons();
-        let lower_nibble = self.lower_nibble(selected_buttons);
-        let new_joyp = 0xC0 | selected_buttons.as_bits() | lower_nibble;
-        Some(new_joyp)
+    /// Only the selection bits are writable, the button lines are set by [`Self::on_joyp_write`].
+    pub(crate) fn joyp_transformer((old_value, new_value): (u8, u8)) -> Option<u8> {
+        Some(0xC0 | (new_value & 0x30) | (old_value & 0x0F))
+    }
+
+    /// Selecting a group with a button held pulls its line low right away, which requests
+    /// the joypad interrupt like a press does.
+    pub(crate) fn on_joyp_write(&mut self, bus: &mut yagber_memory::Bus, _value: u8) {
+        self.update_lines(bus);
     }
 
     fn lower_nibble(&self, selected_buttons: yagber_memory::SelectedButtons) -> u8 {
@@ -145,3 +171,37 @@ impl Default for JoypInputState {
 }
 
 impl yagber_app::Component for JoypInputState {}
+
+#[cfg(test)]
+mod tests {
+    use yagber_app::Emulator;
+    use yagber_memory::{Bus, IOType};
+
+    use super::*;
+
+    #[test]
+    fn selecting_a_group_updates_the_lines() {
+        let mut emulator = Emulator::new()
+            .with_plugin(yagber_memory::MemoryPlugin::default())
+            .with_plugin(crate::InputPlugin::new())
+            .build()
+            .unwrap();
+        *emulator
+            .get_component_mut::<JoypInputState>()
+            .unwrap()
+            .key_state_mut(JoypKey::ButtonA) = KeyState::Pressed;
+        let joyp = IOType::JOYP.address();
+        let interrupt_flag = IOType::IF.address();
+
+        Bus::write_hooked(&mut emulator, interrupt_flag, 0x00);
+        Bus::write_hooked(&mut emulator, joyp, 0x20);
+        let bus = emulator.get_component::<Bus>().unwrap();
+        assert_eq!(bus.read(joyp), 0xEF);
+        assert_eq!(bus.read(interrupt_flag) & 0x10, 0x00);
+
+        Bus::write_hooked(&mut emulator, joyp, 0x10);
+        let bus = emulator.get_component::<Bus>().unwrap();
+        assert_eq!(bus.read(joyp), 0xDE);
+        assert_eq!(bus.read(interrupt_flag) & 0x10, 0x10);
+    }
+}
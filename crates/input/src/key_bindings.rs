@@ -0,0 +1,62 @@
+use strum::EnumCount;
+
+use crate::{joyp_input_state::JoypKey, keyboard::KeyCode};
+
+/// Keyboard key pressing each joypad button.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBindings {
+    keys: [KeyCode; JoypKey::COUNT],
+}
+
+impl KeyBindings {
+    /// Arrows for the directions, Z and X for A and B, Backspace and Enter for Select and Start.
+    pub fn new() -> Self {
+        Self {
+            keys: [
+                KeyCode::KeyZ,
+                KeyCode::KeyX,
+                KeyCode::Backspace,
+                KeyCode::Enter,
+                KeyCode::ArrowUp,
+                KeyCode::ArrowDown,
+                KeyCode::ArrowLeft,
+                KeyCode::ArrowRight,
+            ],
+        }
+    }
+
+    pub fn with_binding(mut self, button: JoypKey, key: KeyCode) -> Self {
+        self.keys[button as usize] = key;
+        self
+    }
+
+    pub fn key(&self, button: JoypKey) -> &KeyCode {
+        &self.keys[button as usize]
+    }
+
+    /// The first button bound to `key`.
+    pub fn button(&self, key: &KeyCode) -> Option<JoypKey> {
+        JoypKey::ALL
+            .into_iter()
+            .find(|&button| self.key(button) == key)
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebinding_replaces_the_default_key() {
+        let bindings = KeyBindings::new().with_binding(JoypKey::ButtonA, KeyCode::KeyK);
+        assert_eq!(bindings.button(&KeyCode::KeyK), Some(JoypKey::ButtonA));
+        assert_eq!(bindings.button(&KeyCode::KeyZ), None);
+        assert_eq!(bindings.button(&KeyCode::Enter), Some(JoypKey::Start));
+    }
+}
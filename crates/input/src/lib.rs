@@ -2,22 +2,47 @@ mod emulation_control;
 mod input_event;
 mod input_event_queue;
 mod joyp_input_state;
+mod key_bindings;
 mod key_state;
 mod physical_input;
 
 pub use input_event::InputEvent;
 pub use input_event_queue::InputEventQueue;
-pub use joyp_input_state::JoypInputState;
+pub use joyp_input_state::{JoypInputState, JoypKey};
+pub use key_bindings::KeyBindings;
 pub use key_state::KeyState;
 pub use physical_input::keyboard;
 
-pub struct InputPlugin;
+pub struct InputPlugin {
+    bindings: KeyBindings,
+}
+
+impl InputPlugin {
+    pub fn new() -> Self {
+        Self {
+            bindings: KeyBindings::default(),
+        }
+    }
+
+    pub fn with_bindings(mut self, bindings: KeyBindings) -> Self {
+        self.bindings = bindings;
+        self
+    }
+}
+
+impl Default for InputPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl yagber_app::Plugin for InputPlugin {
-    fn init(self, emulator: &mut yagber_app::Emulator) {
+    fn init(self, emulator: &mut yagber_app::Emulator) -> Result<(), yagber_app::PluginError> {
         emulator
             .with_component(input_event_queue::InputEventQueue::default())
-            .with_component(joyp_input_state::JoypInputState::new())
+            .with_component(joyp_input_state::JoypInputState::with_bindings(
+                self.bindings,
+            ))
             .on_fixed_step(joyp_input_state::JoypInputState::on_mcycle)
             .on_fixed_step(emulation_control::EmulationControl::on_mcycle);
 
@@ -27,13 +52,26 @@ impl yagber_app::Plugin for InputPlugin {
             .with_observer::<joyp_input_state::JoypInputState>()
             .with_observer::<emulation_control::EmulationControl>();
 
-        let joyp_transformer =
-            emulator.attach_component(joyp_input_state::JoypInputState::joyp_transformer);
+        let joyp_hook = yagber_app::Emulator::attach_components2(
+            joyp_input_state::JoypInputState::on_joyp_write,
+        );
 
         emulator
             .get_component_mut::<yagber_memory::Bus>()
             .expect("InputPlugin must be initialized after MemoryPlugin")
             .io_registers
-            .add_transformer(yagber_memory::IOType::JOYP, joyp_transformer);
+            .with_transformer(
+                yagber_memory::IOType::JOYP,
+                joyp_input_state::JoypInputState::joyp_transformer,
+            )
+            .with_hook(yagber_memory::IOType::JOYP, joyp_hook);
+        Ok(())
+    }
+
+    fn dependencies(&self) -> yagber_app::PluginDependencies {
+        yagber_app::PluginDependencies::new()
+            .provides::<InputEventQueue>()
+            .provides::<JoypInputState>()
+            .requires::<yagber_memory::Bus>()
     }
 }
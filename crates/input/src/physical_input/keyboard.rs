@@ -142,13 +142,16 @@ pub enum NativeKeyCode {
 /// ## Updating
 ///
 /// The resource is updated inside of the [`keyboard_input_system`].
-#[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone)]
+///
+/// Parses from the variant name, such as `KeyZ` or `ArrowUp`.
+#[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, strum::EnumString)]
 pub enum KeyCode {
     /// This variant is used when the key cannot be translated to any other variant.
     ///
     /// The native keycode is provided (if available) so you're able to more reliably match
     /// key-press and key-release events by hashing the [`KeyCode`]. It is also possible to use
     /// this for keybinds for non-standard keys, but such keybinds are tied to a given platform.
+    #[strum(disabled)]
     Unidentified(NativeKeyCode),
     /// <kbd>\`</kbd> on a US keyboard. This is also called a backtick or grave.
     /// This is the <kbd>半角</kbd>/<kbd>全角</kbd>/<kbd>漢字</kbd>
@@ -32,11 +32,29 @@ impl LinkCablePlugin {
 }
 
 impl yagber_app::Plugin for LinkCablePlugin {
-    fn init(mut self, emulator: &mut yagber_app::Emulator) {
+    fn init(mut self, emulator: &mut yagber_app::Emulator) -> Result<(), yagber_app::PluginError> {
         let link_cable = std::mem::take(&mut self.link_cable).unwrap();
         emulator
             .with_component(link_cable)
-            .on_mcycle(LinkCable::on_mcycle);
+            .with_snapshot::<LinkCable>("link_cable");
+
+        let transfer_task = emulator.add_scheduled_task("serial_transfer", LinkCable::on_transfer);
+        emulator
+            .get_component_mut::<yagber_memory::Bus>()
+            .expect("Bus component missing")
+            .io_registers
+            .with_hook(yagber_memory::IOType::SC, move |emulator, value| {
+                if LinkCable::starts_transfer(value) {
+                    emulator.schedule(transfer_task, LinkCable::TRANSFER_DELAY);
+                }
+            });
+        Ok(())
+    }
+
+    fn dependencies(&self) -> yagber_app::PluginDependencies {
+        yagber_app::PluginDependencies::new()
+            .provides::<LinkCable>()
+            .requires::<yagber_memory::Bus>()
     }
 }
 
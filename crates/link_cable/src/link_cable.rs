@@ -1,3 +1,4 @@
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 use yagber_memory::{Bus, Memory};
 
 use crate::dest::{Destination, DestinationCollector};
@@ -63,14 +64,27 @@ impl LinkCable {
         ram.read(yagber_memory::IOType::SB.address())
     }
 
-    pub fn on_mcycle(emulator: &mut yagber_app::Emulator) {
+    /// Transfers complete one M-cycle after being started.
+    pub(crate) const TRANSFER_DELAY: yagber_app::Delay = yagber_app::Delay::MCycles(1);
+
+    /// Whether an SC write starts a transfer driven by the internal clock.
+    pub(crate) fn starts_transfer(sc: u8) -> bool {
+        sc & 0x80 != 0 && LinkCableMode::from_bit(sc & 0x01 != 0) == LinkCableMode::Master
+    }
+
+    pub(crate) fn on_transfer(emulator: &mut yagber_app::Emulator) {
         let (link_cable, bus) = emulator
             .get_components_mut2::<LinkCable, Bus>()
             .expect("LinkCable and/or Bus component missing");
-        link_cable.step(bus);
+        let sent = link_cable.step(bus);
+        Bus::run_hooks(emulator);
+        if let Some(byte) = sent {
+            emulator.emit(yagber_app::SerialByteSentEvent { byte });
+        }
     }
 
-    pub fn step(&mut self, ram: &mut Bus) {
+    /// Returns the byte sent, if a transfer happened.
+    pub fn step(&mut self, ram: &mut Bus) -> Option<u8> {
         if Self::transfer_enabled(ram) && Self::read_mode(ram) == LinkCableMode::Master {
             let data = Self::read_data(ram);
             let _result = self.destinations.write(data);
@@ -81,6 +95,9 @@ impl LinkCable {
             ram.write(yagber_memory::IOType::SB.address(), 0xFF);
             ram.write(yagber_memory::IOType::SC.address(), 0);
             ram.request_interrupt(yagber_memory::InterruptType::Serial);
+            Some(data)
+        } else {
+            None
         }
     }
 
@@ -95,3 +112,13 @@ impl LinkCable {
 }
 
 impl yagber_app::Component for LinkCable {}
+
+/// Pending transfers live in the emulator scheduler, so the only serial state is in SB/SC.
+/// Output destinations belong to the host and are kept as they are.
+impl Snapshot for LinkCable {
+    fn save(&self, _writer: &mut SnapshotWriter) {}
+
+    fn load(&mut self, _reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        Ok(())
+    }
+}
@@ -14,6 +14,12 @@ impl LogPlugin {
             level: tracing::Level::INFO,
         }
     }
+
+    /// Extra `tracing` directives, such as `yagber_cpu=trace`, `RUST_LOG` still takes precedence.
+    pub fn with_filter(mut self, filter: &str) -> Self {
+        self.filter = format!("{DEFAULT_FILTER},{filter}");
+        self
+    }
 }
 
 impl Default for LogPlugin {
@@ -23,7 +29,7 @@ impl Default for LogPlugin {
 }
 
 impl yagber_app::Plugin for LogPlugin {
-    fn init(self, _emulator: &mut yagber_app::Emulator) {
+    fn init(self, _emulator: &mut yagber_app::Emulator) -> Result<(), yagber_app::PluginError> {
         dotenv::dotenv().ok();
 
         // Start with the base registry.
@@ -96,6 +102,13 @@ impl yagber_app::Plugin for LogPlugin {
 
         #[cfg(feature = "tracing-tracy")]
         tracing::info!("Tracy tracing enabled");
+        Ok(())
+    }
+
+    fn dependencies(&self) -> yagber_app::PluginDependencies {
+        yagber_app::PluginDependencies::new()
+            // Installs the subscriber before other plugins trace their initialization
+            .first()
     }
 }
 
@@ -12,6 +12,9 @@ pub struct BootRom {
 }
 
 impl BootRom {
+    /// Size of a CGB boot ROM, the only kind the bus can map.
+    pub const SIZE: usize = 0x0900;
+
     pub fn new() -> Self {
         // CGB boot ROM is split into two parts
         // 0x0000–0x00FF: CGB boot ROM
@@ -41,6 +44,12 @@ impl BootRom {
     }
 }
 
+impl Default for BootRom {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Memory for BootRom {
     fn read(&self, address: u16) -> u8 {
         self.read(address as usize)
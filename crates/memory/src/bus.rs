@@ -1,12 +1,25 @@
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
+
 use crate::{
-    ByteRegister, IOType, InterruptType, boot_rom::BootRom, cartridges::Cartridge, cram::Cram,
-    io_registers::IOBus, memory::Memory, oam::Oam, ram::Ram, vram::Vram, wram::Wram,
+    AccessKind, ByteRegister, IOType, InterruptType, MemoryAccess, Model,
+    boot_rom::BootRom,
+    cartridges::{
+        BatteryState, Cartridge, CartridgeError, CartridgeHeader, ClockKind, SaveLocation,
+    },
+    cram::Cram,
+    io_registers::IOBus,
+    memory::Memory,
+    oam::Oam,
+    ram::Ram,
+    vram::Vram,
+    wram::Wram,
 };
 
 #[derive(Debug)]
 pub struct Bus {
     boot_rom: BootRom,
     cartridge: Cartridge,
+    model: Model,
     pub io_registers: IOBus,
     hram: Ram,
     ie: ByteRegister,
@@ -15,6 +28,23 @@ pub struct Bus {
     pub oam: Oam,
     pub background_cram: Cram,
     pub object_cram: Cram,
+    rumble: bool,
+    /// Rumble state not yet reported through [`yagber_app::RumbleChangedEvent`].
+    rumble_update: Option<bool>,
+    /// Battery backed cartridge state set aside while a hard reset is in progress.
+    reset_battery_state: Option<BatteryState>,
+    /// Start and length of the bytes read by DMA transfers since the last
+    /// [`Bus::take_dma_source`], only the latest run of consecutive bytes is kept.
+    dma_source: Option<(u16, u16)>,
+    /// See [`Bus::track_dma_source`].
+    track_dma_source: bool,
+    /// Data accesses since the last [`Bus::clear_accesses`], `None` unless recording,
+    /// see [`Bus::record_accesses`].
+    accesses: Option<Vec<MemoryAccess>>,
+    /// The CPU executed STOP, see [`Bus::stopped`].
+    stopped: bool,
+    /// M-cycles a DMA transfer stalls the CPU for, see [`Bus::stall_cpu`].
+    cpu_stall: u16,
 }
 
 impl Bus {
@@ -22,6 +52,7 @@ impl Bus {
         Self {
             boot_rom: BootRom::new(),
             cartridge: Cartridge::empty(),
+            model: Model::default(),
             vram: Vram::new(),
             wram: Wram::new(),
             oam: Oam::new(),
@@ -30,6 +61,14 @@ impl Bus {
             ie: ByteRegister::new(0x00),
             background_cram: Cram::new(),
             object_cram: Cram::new(),
+            rumble: false,
+            rumble_update: None,
+            reset_battery_state: None,
+            dma_source: None,
+            track_dma_source: false,
+            accesses: None,
+            stopped: false,
+            cpu_stall: 0,
         }
     }
 
@@ -58,6 +97,74 @@ impl Bus {
         }
     }
 
+    /// Reads a byte for an OAM DMA or HDMA transfer, see [`Bus::take_dma_source`].
+    pub fn read_dma(&mut self, address: u16) -> u8 {
+        if self.track_dma_source {
+            self.dma_source = match self.dma_source {
+                Some((start, length)) if start.wrapping_add(length) == address => {
+                    Some((start, length + 1))
+                }
+                _ => Some((address, 1)),
+            };
+        }
+        let value = self.read(address);
+        self.record_access(MemoryAccess {
+            address,
+            value,
+            kind: AccessKind::Read,
+        });
+        value
+    }
+
+    /// Writes a byte for an OAM DMA or HDMA transfer, OAM is written even while locked.
+    pub fn write_dma(&mut self, address: u16, value: u8) {
+        match address {
+            0xFE00..=0xFE9F => self.oam.write_dma(address, value),
+            _ => self.write(address, value),
+        }
+        self.record_access(MemoryAccess {
+            address,
+            value,
+            kind: AccessKind::Write,
+        });
+    }
+
+    /// Starts or stops recording the data accesses of the CPU and DMA transfers,
+    /// for debuggers to watch. Plain [`Bus::read`] and [`Bus::write`] calls are not recorded.
+    pub fn record_accesses(&mut self, record: bool) {
+        self.accesses = record.then(Vec::new);
+    }
+
+    /// Adds a data access made through [`Bus::read`] or [`Bus::write`] to [`Bus::accesses`].
+    pub fn record_access(&mut self, access: MemoryAccess) {
+        if let Some(accesses) = &mut self.accesses {
+            accesses.push(access);
+        }
+    }
+
+    /// Data accesses recorded since the last [`Bus::clear_accesses`], oldest first.
+    pub fn accesses(&self) -> &[MemoryAccess] {
+        self.accesses.as_deref().unwrap_or_default()
+    }
+
+    pub fn clear_accesses(&mut self) {
+        if let Some(accesses) = &mut self.accesses {
+            accesses.clear();
+        }
+    }
+
+    /// Starts or stops keeping the bytes DMA transfers read, for code/data loggers.
+    pub fn track_dma_source(&mut self, track: bool) {
+        self.track_dma_source = track;
+        self.dma_source = None;
+    }
+
+    /// Start and length of the bytes DMA transfers read since the last call, `None` unless
+    /// tracking, see [`Bus::track_dma_source`].
+    pub fn take_dma_source(&mut self) -> Option<(u16, u16)> {
+        self.dma_source.take()
+    }
+
     pub fn write(&mut self, address: u16, value: u8) {
         match address {
             // ROM
@@ -75,7 +182,10 @@ impl Bus {
             // Unusable
             0xFEA0..=0xFEFF => {}
             // IO Registers
-            0xFF00..=0xFF7F => self.io_registers.write(address, value),
+            0xFF00..=0xFF7F => {
+                self.io_registers.write(address, value);
+                self.run_bus_hooks();
+            }
             // HRAM
             0xFF80..=0xFFFE => self.hram.write(address, value),
             // Interrupt Enable Register
@@ -83,12 +193,63 @@ impl Bus {
         }
     }
 
+    /// Writes an IO register bypassing its transformer, hooks still run.
+    pub fn write_io_unchecked(&mut self, address: u16, value: u8) {
+        self.io_registers.write_unchecked(address, value);
+        self.run_bus_hooks();
+    }
+
+    /// Hooks writing registers themselves run theirs before the remaining hooks of the outer write.
+    fn run_bus_hooks(&mut self) {
+        while let Some((index, value)) = self.io_registers.pop_pending_bus_hooks() {
+            let mut position = 0;
+            while let Some(hook) = self.io_registers.bus_hook(index, position) {
+                hook(self, value);
+                position += 1;
+            }
+        }
+    }
+
+    /// Writes like [`Bus::write`], then runs the hooks of other components before returning.
+    pub fn write_hooked(emulator: &mut yagber_app::Emulator, address: u16, value: u8) {
+        emulator
+            .get_component_mut::<Bus>()
+            .expect("Bus component missing")
+            .write(address, value);
+        Bus::run_hooks(emulator);
+    }
+
+    /// Runs the hooks of other components for the IO writes made since the last call, in
+    /// write order. Components writing straight on the bus call it once they let go of it.
+    ///
+    /// Hooks get the whole emulator, components split off it are missing, see
+    /// [`yagber_app::Emulator::split_component`].
+    pub fn run_hooks(emulator: &mut yagber_app::Emulator) {
+        loop {
+            let bus = emulator
+                .get_component_mut::<Bus>()
+                .expect("Bus component missing");
+            bus.run_bus_hooks();
+            let Some((index, value)) = bus.io_registers.pop_pending_hooks() else {
+                return;
+            };
+            let mut position = 0;
+            while let Some(hook) = emulator
+                .get_component::<Bus>()
+                .and_then(|bus| bus.io_registers.hook(index, position))
+            {
+                hook(emulator, value);
+                position += 1;
+            }
+        }
+    }
+
     pub fn request_interrupt(&mut self, interrupt: InterruptType) {
         let bit = 1 << interrupt.bit();
         let if_reg = self.read(IOType::IF.address());
         let new_if_reg = if_reg | bit;
         if new_if_reg != if_reg {
-            self.io_registers.write(IOType::IF.address(), new_if_reg);
+            self.write(IOType::IF.address(), new_if_reg);
         }
     }
 
@@ -97,7 +258,7 @@ impl Bus {
         let if_reg = self.read(IOType::IF.address());
         let new_if_reg = if_reg & !bit;
         if new_if_reg != if_reg {
-            self.io_registers.write(IOType::IF.address(), new_if_reg);
+            self.write(IOType::IF.address(), new_if_reg);
         }
     }
 
@@ -117,8 +278,48 @@ impl Bus {
         self.io_registers.read(IOType::BANK.address()) == 0
     }
 
-    pub fn load_rom(&mut self, data: &[u8]) {
-        self.cartridge = Cartridge::new(data);
+    /// The system clock is stopped by a STOP instruction, the LCD, the timer and the APU do
+    /// not run until a joypad line goes low.
+    pub fn stopped(&self) -> bool {
+        self.stopped
+    }
+
+    pub fn set_stopped(&mut self, stopped: bool) {
+        self.stopped = stopped;
+    }
+
+    /// Halts the CPU for `mcycles` while a DMA transfer holds the bus, from its next M-cycle
+    /// or right after the write that started the transfer.
+    pub fn stall_cpu(&mut self, mcycles: u16) {
+        self.cpu_stall = mcycles;
+    }
+
+    /// M-cycles the CPU is to be halted for, see [`Bus::stall_cpu`].
+    pub fn take_cpu_stall(&mut self) -> u16 {
+        std::mem::take(&mut self.cpu_stall)
+    }
+
+    pub fn load_rom(
+        &mut self,
+        data: &[u8],
+        clock: ClockKind,
+        saves: &SaveLocation,
+    ) -> Result<(), CartridgeError> {
+        self.cartridge = Cartridge::new(data, clock, saves)?;
+        Ok(())
+    }
+
+    pub fn load_boot_rom(&mut self, boot_rom: BootRom) {
+        self.boot_rom = boot_rom;
+    }
+
+    pub fn set_model(&mut self, model: Model) {
+        self.model = model;
+    }
+
+    /// Identifies the loaded ROM, see [`yagber_app::Emulator::set_content_id`].
+    pub fn content_id(&self) -> u64 {
+        self.cartridge.content_id()
     }
 
     pub fn read_rom(&self, address: u16) -> u8 {
@@ -128,20 +329,139 @@ impl Bus {
             if let 0x0000..=0x00FF | 0x0200..=0x08FF = address {
                 return self.boot_rom.read(address as usize);
             }
+            if address as usize == CartridgeHeader::CGB_FLAG_ADR {
+                return self.model.boot_cgb_flag(self.cartridge.read(address));
+            }
         }
         self.cartridge.read(address)
     }
 
+    /// Bank mapped at `address`, for the banked ROM, external RAM and WRAM areas, 0 everywhere else.
+    /// The boot ROM counts as bank 0 while it is mapped.
+    pub fn bank(&self, address: u16) -> u16 {
+        match address {
+            0x0000..=0x7FFF => {
+                let boot_rom_mapped =
+                    matches!(address, 0x0000..=0x00FF | 0x0200..=0x08FF) && self.booting();
+                if boot_rom_mapped {
+                    0
+                } else {
+                    self.cartridge.rom_bank(address)
+                }
+            }
+            0xA000..=0xBFFF => self.cartridge.ram_bank(address),
+            0xD000..=0xDFFF => self.wram.switchable_bank() as u16,
+            _ => 0,
+        }
+    }
+
+    /// Offset in the ROM file of the byte mapped at `address`, `None` where the boot ROM
+    /// is mapped or without a cartridge.
+    pub fn rom_offset(&self, address: u16) -> Option<usize> {
+        let boot_rom_mapped =
+            matches!(address, 0x0000..=0x00FF | 0x0200..=0x08FF) && self.booting();
+        match address {
+            0x0000..=0x7FFF if !boot_rom_mapped => self.cartridge.rom_offset(address),
+            _ => None,
+        }
+    }
+
+    /// Offset in the cartridge RAM of the byte mapped at `address`, see
+    /// [`Cartridge::ram_offset`].
+    pub fn sram_offset(&self, address: u16) -> Option<usize> {
+        match address {
+            0xA000..=0xBFFF => self.cartridge.ram_offset(address),
+            _ => None,
+        }
+    }
+
     pub fn write_rom(&mut self, address: u16, value: u8) {
-        self.cartridge.write(address, value);
+        if let Some(rumble) = self.cartridge.write_rom(address, value)
+            && rumble != self.rumble
+        {
+            self.rumble = rumble;
+            self.rumble_update = Some(rumble);
+        }
+    }
+
+    pub fn rumble(&self) -> bool {
+        self.rumble
     }
 
-    pub(crate) fn on_tcycle(emulator: &mut yagber_app::Emulator) {
-        emulator.get_component_mut::<Self>().unwrap().tick();
+    /// The cartridge clock only counts seconds, ticking it a few times per frame is plenty.
+    pub(crate) const CARTRIDGE_TICK_DOTS: u64 =
+        yagber_app::Emulator::TARGET_DOT_FREQ_HZ as u64 / 64;
+
+    pub(crate) fn on_cartridge_tick(emulator: &mut yagber_app::Emulator) {
+        let bus = emulator.get_component_mut::<Self>().unwrap();
+        bus.tick();
+        if let Some(enabled) = bus.rumble_update.take() {
+            emulator.emit(yagber_app::RumbleChangedEvent { enabled });
+        }
     }
 
     fn tick(&mut self) {
-        self.cartridge.tick();
+        self.cartridge.tick(Self::CARTRIDGE_TICK_DOTS);
+    }
+
+    /// Flushes the save before any reset, battery backed state is set aside across hard resets.
+    /// Soft resets map the boot ROM back in and drop back to single speed.
+    pub(crate) fn on_reset(emulator: &mut yagber_app::Emulator, event: &yagber_app::ResetEvent) {
+        let bus = emulator.get_component_mut::<Self>().unwrap();
+        match *event {
+            yagber_app::ResetEvent::Starting(kind) => {
+                bus.cartridge.flush_save();
+                if kind == yagber_app::ResetKind::Hard {
+                    bus.reset_battery_state = Some(bus.cartridge.battery_state());
+                }
+            }
+            yagber_app::ResetEvent::Finished(kind) => {
+                if let Some(state) = bus.reset_battery_state.take() {
+                    bus.cartridge.restore_battery_state(state);
+                }
+                if kind == yagber_app::ResetKind::Soft {
+                    bus.io_registers.write_unhooked(IOType::BANK.address(), 0);
+                    bus.io_registers.write_unhooked(IOType::SPD.address(), 0);
+                    bus.stopped = false;
+                    emulator.set_speed_mode(yagber_app::SpeedMode::Single);
+                }
+            }
+        }
+    }
+}
+
+impl Snapshot for Bus {
+    const VERSION: u16 = 9;
+
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.model);
+        writer.write(&self.cartridge);
+        writer.write(&self.io_registers);
+        writer.write(&self.hram);
+        writer.write_u8(self.ie.read());
+        writer.write(&self.vram);
+        writer.write(&self.wram);
+        writer.write(&self.oam);
+        writer.write(&self.background_cram);
+        writer.write(&self.object_cram);
+        writer.write_bool(self.stopped);
+        writer.write_u16(self.cpu_stall);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        reader.read(&mut self.model)?;
+        reader.read(&mut self.cartridge)?;
+        reader.read(&mut self.io_registers)?;
+        reader.read(&mut self.hram)?;
+        self.ie.write(reader.read_u8()?);
+        reader.read(&mut self.vram)?;
+        reader.read(&mut self.wram)?;
+        reader.read(&mut self.oam)?;
+        reader.read(&mut self.background_cram)?;
+        reader.read(&mut self.object_cram)?;
+        self.stopped = reader.read_bool()?;
+        self.cpu_stall = reader.read_u16()?;
+        Ok(())
     }
 }
 
@@ -1,14 +1,26 @@
+use std::time::Duration;
+
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
+
 use crate::{
     cartridges::{
-        CartridgeHeader, Mbc, Rtc,
+        CartridgeError, CartridgeHeader, Clock, ClockKind, Mbc, Rtc,
         cartridge_mbc_info::CartridgeMbcInfo,
         external_ram_address::MbcDeviceUpdate,
         mbc::MbcKind,
-        saves::{Save, SaveBackend, SaveBackendKind},
+        saves::{Save, SaveBackend, SaveBackendKind, SaveLocation},
     },
     ram::Ram,
 };
 
+/// Battery backed cartridge state, carried over hard resets.
+/// The clock is not part of it, so no time is lost while resetting.
+#[derive(Debug, Default)]
+pub(crate) struct BatteryState {
+    ram: Option<Ram>,
+    rtc: Option<Rtc>,
+}
+
 #[derive(Default)]
 pub enum Cartridge {
     #[default]
@@ -19,23 +31,43 @@ pub enum Cartridge {
         ram: Option<Ram>,
         rtc: Option<Rtc>,
         save_backend: SaveBackendKind,
+        content_id: u64,
+        clock: ClockKind,
+        /// Clock time the RTC was last advanced to.
+        rtc_synced_at: Duration,
     },
 }
 
 impl Cartridge {
-    pub fn new(rom: &[u8]) -> Self {
-        let header = CartridgeHeader::new(rom);
-        let mbc_info = CartridgeMbcInfo::new(&header);
-
-        #[cfg(feature = "trace")]
-        tracing::debug!("{mbc_info:?}");
+    /// Reads the header and checks the cartridge can be emulated, without loading it.
+    pub fn inspect(rom: &[u8]) -> Result<(CartridgeHeader, CartridgeMbcInfo), CartridgeError> {
+        let header = CartridgeHeader::new(rom)?;
+        let mbc_info = CartridgeMbcInfo::new(&header)?;
 
-        // Check if the ROM size is valid
+        if !mbc_info.mbc_type.is_supported() {
+            return Err(CartridgeError::UnsupportedMbc(mbc_info.mbc_type));
+        }
         if rom.len() < mbc_info.rom_size {
-            panic!("ROM size is smaller than expected");
+            return Err(CartridgeError::TruncatedRom {
+                expected: mbc_info.rom_size,
+                found: rom.len(),
+            });
         }
 
-        let mut save_backend = SaveBackendKind::new(&header, &mbc_info);
+        Ok((header, mbc_info))
+    }
+
+    pub fn new(
+        rom: &[u8],
+        mut clock: ClockKind,
+        saves: &SaveLocation,
+    ) -> Result<Self, CartridgeError> {
+        let (header, mbc_info) = Self::inspect(rom)?;
+
+        #[cfg(feature = "trace")]
+        tracing::debug!("{mbc_info:?}");
+
+        let mut save_backend = SaveBackendKind::new(&header, &mbc_info, saves)?;
         let save = save_backend.read();
 
         let mbc = MbcKind::new(&mbc_info);
@@ -47,9 +79,10 @@ impl Cartridge {
         } else {
             None
         };
+        clock.resume_from(Duration::from_secs(save.timestamp.max(0) as u64));
+        let now = clock.now();
         let rtc = if mbc_info.includes_timer {
-            let now_seconds = chrono::Utc::now().timestamp();
-            let seconds_since_save = now_seconds - save.timestamp;
+            let seconds_since_save = now.as_secs() as i64 - save.timestamp;
             let rtc_registers = match save.rtc_registers {
                 Some(mut regs) => {
                     if seconds_since_save > 0 && !regs.halted() {
@@ -64,19 +97,30 @@ impl Cartridge {
             None
         };
 
-        Self::Loaded {
+        Ok(Self::Loaded {
             mbc,
             rom,
             ram,
             rtc,
             save_backend,
-        }
+            content_id: header.content_id(),
+            clock,
+            rtc_synced_at: now,
+        })
     }
 
     pub fn empty() -> Self {
         Self::Empty
     }
 
+    /// Identifies the loaded ROM, 0 for an empty cartridge.
+    pub fn content_id(&self) -> u64 {
+        match self {
+            Self::Empty => 0,
+            Self::Loaded { content_id, .. } => *content_id,
+        }
+    }
+
     pub fn read_rom(&self, address: u16) -> u8 {
         match self {
             Self::Empty => {
@@ -91,24 +135,60 @@ impl Cartridge {
         }
     }
 
-    pub fn write_rom(&mut self, address: u16, value: u8) {
+    /// ROM bank mapped at `address`, 0 for an empty cartridge.
+    pub fn rom_bank(&self, address: u16) -> u16 {
         match self {
-            Self::Empty => (),
-            Self::Loaded { mbc, rtc, .. } => {
-                let mbc_device_update = mbc.rom_write(address, value);
-                if let Some(update) = mbc_device_update {
-                    match update {
-                        MbcDeviceUpdate::RtcLatch => {
-                            if let Some(rtc_ref) = rtc {
-                                rtc_ref.latch_write(value);
-                            }
-                        }
-                        MbcDeviceUpdate::RumbleMotor(_state) => {
-                            // unimplemented!()
-                        }
+            Self::Empty => 0,
+            Self::Loaded { mbc, .. } => (mbc.rom_address(address) / 0x4000) as u16,
+        }
+    }
+
+    /// External RAM bank mapped at `address`, 0 for an empty cartridge or an RTC register.
+    pub fn ram_bank(&self, address: u16) -> u16 {
+        match self {
+            Self::Loaded { mbc, .. } => match mbc.ram_address(address) {
+                super::ExternalRamAddress::ExternalRam(offset) => (offset / 0x2000) as u16,
+                super::ExternalRamAddress::Rtc(_) => 0,
+            },
+            Self::Empty => 0,
+        }
+    }
+
+    /// Offset in the ROM file of the byte mapped at `address`, `None` for an empty cartridge.
+    pub fn rom_offset(&self, address: u16) -> Option<usize> {
+        match self {
+            Self::Empty => None,
+            Self::Loaded { mbc, .. } => Some(mbc.rom_address(address)),
+        }
+    }
+
+    /// Offset in the external RAM of the byte mapped at `address`, `None` while the RAM
+    /// is disabled, missing or replaced by an RTC register.
+    pub fn ram_offset(&self, address: u16) -> Option<usize> {
+        match self {
+            Self::Loaded {
+                mbc, ram: Some(_), ..
+            } if mbc.ram_enabled() => match mbc.ram_address(address) {
+                super::ExternalRamAddress::ExternalRam(offset) => Some(offset),
+                super::ExternalRamAddress::Rtc(_) => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the rumble motor state when the write drives it.
+    pub fn write_rom(&mut self, address: u16, value: u8) -> Option<bool> {
+        match self {
+            Self::Empty => None,
+            Self::Loaded { mbc, .. } => match mbc.rom_write(address, value)? {
+                MbcDeviceUpdate::RtcLatch => {
+                    if let Some(rtc) = self.synced_rtc() {
+                        rtc.latch_write(value);
                     }
+                    None
                 }
-            }
+                MbcDeviceUpdate::RumbleMotor(state) => Some(state),
+            },
         }
     }
 
@@ -140,7 +220,7 @@ impl Cartridge {
     pub fn write_ram(&mut self, address: u16, value: u8) {
         match self {
             Self::Empty => (),
-            Self::Loaded { mbc, ram, rtc, .. } => {
+            Self::Loaded { mbc, ram, .. } => {
                 if !mbc.ram_enabled() {
                     return;
                 }
@@ -151,8 +231,7 @@ impl Cartridge {
                         }
                     }
                     super::ExternalRamAddress::Rtc(rtc_register_kind) => {
-                        if let Some(rtc) = rtc {
-                            rtc.tick();
+                        if let Some(rtc) = self.synced_rtc() {
                             rtc.write_register(rtc_register_kind, value);
                         }
                     }
@@ -171,50 +250,171 @@ impl Cartridge {
 
     pub fn write(&mut self, address: u16, value: u8) {
         match address {
-            0x0000..=0x7FFF => self.write_rom(address, value),
+            0x0000..=0x7FFF => {
+                self.write_rom(address, value);
+            }
             0xA000..=0xBFFF => self.write_ram(address, value),
             _ => panic!("Invalid address: {address:#X}"),
         }
     }
 
-    pub fn tick(&mut self) {
-        let Cartridge::Loaded { rtc, .. } = self else {
+    /// Writes battery backed RAM and the RTC through the save backend.
+    pub fn flush_save(&mut self) {
+        let rtc_registers = self.synced_rtc().map(|rtc| rtc.registers);
+        let Cartridge::Loaded {
+            ram,
+            save_backend,
+            clock,
+            ..
+        } = self
+        else {
             return;
         };
+        let timestamp = clock.now().as_secs() as i64;
+        let data = ram.as_ref().map(|r| r.to_vec());
+        let save = Save {
+            data,
+            rtc_registers,
+            timestamp,
+        };
+        save_backend.write(&save);
+    }
+
+    /// State surviving a hard reset, RAM only when it is battery backed.
+    pub(crate) fn battery_state(&self) -> BatteryState {
+        match self {
+            Self::Empty => BatteryState::default(),
+            Self::Loaded {
+                ram,
+                rtc,
+                save_backend,
+                ..
+            } => BatteryState {
+                ram: ram.clone().filter(|_| save_backend.is_persistent()),
+                rtc: *rtc,
+            },
+        }
+    }
 
-        if let Some(rtc) = rtc {
-            rtc.tick();
+    pub(crate) fn restore_battery_state(&mut self, state: BatteryState) {
+        let Self::Loaded { ram, rtc, .. } = self else {
+            return;
+        };
+        if let Some(state_ram) = state.ram {
+            *ram = Some(state_ram);
+        }
+        if let Some(state_rtc) = state.rtc {
+            *rtc = Some(state_rtc);
         }
     }
+
+    /// Called every `dots` emulated dots, moves emulated clocks and the RTC forward.
+    pub fn tick(&mut self, dots: u64) {
+        let Cartridge::Loaded { clock, .. } = self else {
+            return;
+        };
+        clock.advance_dots(dots);
+        self.synced_rtc();
+    }
+
+    /// The RTC, advanced by the clock time elapsed since it was last synced.
+    fn synced_rtc(&mut self) -> Option<&mut Rtc> {
+        let Cartridge::Loaded {
+            rtc,
+            clock,
+            rtc_synced_at,
+            ..
+        } = self
+        else {
+            return None;
+        };
+        let rtc = rtc.as_mut()?;
+        let now = clock.now();
+        rtc.advance(now.saturating_sub(*rtc_synced_at));
+        *rtc_synced_at = now;
+        Some(rtc)
+    }
 }
 
-impl Drop for Cartridge {
-    fn drop(&mut self) {
+/// The ROM itself is not stored, save states are tied to it through the content id.
+/// The clock position is, so emulated time and save timestamps replay the same after a load.
+impl Snapshot for Cartridge {
+    fn save(&self, writer: &mut SnapshotWriter) {
         match self {
-            Cartridge::Empty => {}
-            Cartridge::Loaded {
+            Self::Empty => writer.write_bool(false),
+            Self::Loaded {
+                mbc,
                 ram,
                 rtc,
-                save_backend,
+                clock,
+                rtc_synced_at,
                 ..
             } => {
-                let timestamp = chrono::Utc::now().timestamp();
-                let data = ram.as_ref().map(|r| r.to_vec());
-                let rtc_registers = rtc.as_mut().map(|rtc| {
-                    rtc.tick();
-                    rtc.registers
-                });
-                let save = Save {
-                    data,
-                    rtc_registers,
-                    timestamp,
-                };
-                save_backend.write(&save);
+                writer.write_bool(true);
+                writer.write(mbc);
+                writer.write_bool(ram.is_some());
+                if let Some(ram) = ram {
+                    writer.write(ram);
+                }
+                writer.write_bool(rtc.is_some());
+                if let Some(rtc) = rtc {
+                    writer.write(rtc);
+                }
+                writer.write(clock);
+                writer.write_u64(rtc_synced_at.as_secs());
+                writer.write_u32(rtc_synced_at.subsec_nanos());
+            }
+        }
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        let loaded = reader.read_bool()?;
+        match self {
+            Self::Empty if !loaded => Ok(()),
+            Self::Loaded {
+                mbc,
+                ram,
+                rtc,
+                clock,
+                rtc_synced_at,
+                ..
+            } if loaded => {
+                reader.read(mbc)?;
+                if reader.read_bool()? != ram.is_some() {
+                    return Err(SnapshotError::InvalidData(
+                        "cartridge RAM presence mismatch".to_string(),
+                    ));
+                }
+                if let Some(ram) = ram {
+                    reader.read(ram)?;
+                }
+                if reader.read_bool()? != rtc.is_some() {
+                    return Err(SnapshotError::InvalidData(
+                        "cartridge RTC presence mismatch".to_string(),
+                    ));
+                }
+                if let Some(rtc) = rtc {
+                    reader.read(rtc)?;
+                }
+                reader.read(clock)?;
+                let seconds = reader.read_u64()?;
+                let nanos = reader.read_u32()?.min(999_999_999);
+                *rtc_synced_at = Duration::new(seconds, nanos);
+                Ok(())
             }
+            _ => Err(SnapshotError::InvalidData(
+                "cartridge presence mismatch".to_string(),
+            )),
         }
     }
 }
 
+impl Drop for Cartridge {
+    fn drop(&mut self) {
+        self.flush_save();
+    }
+}
+
 impl std::fmt::Debug for Cartridge {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -223,3 +423,92 @@ impl std::fmt::Debug for Cartridge {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridges::{EmulatedClock, ManualClock};
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    /// 32 KiB MBC3 ROM with a timer, four RAM banks and a battery.
+    fn mbc3_timer_rom() -> Vec<u8> {
+        let mut rom = vec![0; 0x8000];
+        rom[0x0147] = 0x10;
+        rom[0x0149] = 0x03;
+        rom
+    }
+
+    /// Latches the RTC and reads the low byte of the day counter.
+    fn latched_days(cartridge: &mut Cartridge) -> u8 {
+        cartridge.write(0x0000, 0x0A);
+        cartridge.write(0x6000, 0x00);
+        cartridge.write(0x6000, 0x01);
+        cartridge.write(0x4000, 0x0B);
+        cartridge.read(0xA000)
+    }
+
+    #[test]
+    fn ram_bank_follows_the_mbc() {
+        let mut cartridge = Cartridge::new(
+            &mbc3_timer_rom(),
+            ClockKind::default(),
+            &SaveLocation::Memory,
+        )
+        .unwrap();
+        assert_eq!(cartridge.ram_bank(0xA000), 0);
+        cartridge.write(0x4000, 0x02);
+        assert_eq!(cartridge.ram_bank(0xBFFF), 2);
+    }
+
+    #[test]
+    fn rtc_follows_a_manual_clock_jumping_days_ahead() {
+        let clock = ManualClock::new(Duration::from_secs(1_000_000));
+        let mut cartridge = Cartridge::new(
+            &mbc3_timer_rom(),
+            clock.clone().into(),
+            &SaveLocation::Memory,
+        )
+        .unwrap();
+        assert_eq!(latched_days(&mut cartridge), 0);
+
+        clock.advance(3 * DAY);
+        assert_eq!(latched_days(&mut cartridge), 3);
+
+        clock.advance(4 * DAY);
+        cartridge.tick(4);
+        assert_eq!(latched_days(&mut cartridge), 7);
+    }
+
+    #[test]
+    fn snapshot_restores_the_emulated_clock() {
+        let mut cartridge = Cartridge::new(
+            &mbc3_timer_rom(),
+            EmulatedClock::from_save().into(),
+            &SaveLocation::Memory,
+        )
+        .unwrap();
+        let day_in_dots = DAY.as_secs() * yagber_app::Emulator::TARGET_DOT_FREQ_HZ as u64;
+        cartridge.tick(day_in_dots);
+        let mut writer = SnapshotWriter::new();
+        writer.write(&cartridge);
+        let snapshot = writer.into_bytes();
+
+        cartridge.tick(2 * day_in_dots);
+        assert_eq!(latched_days(&mut cartridge), 3);
+
+        SnapshotReader::new(&snapshot).read(&mut cartridge).unwrap();
+        let Cartridge::Loaded {
+            clock,
+            rtc_synced_at,
+            ..
+        } = &cartridge
+        else {
+            unreachable!();
+        };
+        assert_eq!(clock.now(), DAY);
+        assert_eq!(*rtc_synced_at, DAY);
+        cartridge.tick(day_in_dots);
+        assert_eq!(latched_days(&mut cartridge), 2);
+    }
+}
@@ -0,0 +1,48 @@
+use crate::cartridges::cartridge_mbc_info::MbcType;
+
+#[derive(Debug)]
+pub enum CartridgeError {
+    /// The ROM is too short to hold a cartridge header.
+    MissingHeader { size: usize },
+    /// The header declares a cartridge type this emulator does not know.
+    UnknownType(u8),
+    /// The header declares a ROM size code this emulator does not know.
+    UnknownRomSize(u8),
+    /// The header declares a RAM size code this emulator does not know.
+    UnknownRamSize(u8),
+    /// The cartridge uses a memory bank controller that is not emulated.
+    UnsupportedMbc(MbcType),
+    /// The ROM holds fewer bytes than its header declares.
+    TruncatedRom { expected: usize, found: usize },
+    /// Battery backed saves are not available on this platform.
+    SavesUnsupported,
+    /// The save file cannot be opened.
+    Save(std::io::Error),
+}
+
+impl std::fmt::Display for CartridgeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingHeader { size } => write!(
+                f,
+                "ROM is {size} bytes long, too short to hold a cartridge header"
+            ),
+            Self::UnknownType(code) => write!(f, "unknown cartridge type {code:#04X}"),
+            Self::UnknownRomSize(code) => write!(f, "unknown ROM size code {code:#04X}"),
+            Self::UnknownRamSize(code) => write!(f, "unknown RAM size code {code:#04X}"),
+            Self::UnsupportedMbc(mbc_type) => {
+                write!(f, "memory bank controller {mbc_type:?} is not supported")
+            }
+            Self::TruncatedRom { expected, found } => write!(
+                f,
+                "ROM is {found} bytes long but its header declares {expected} bytes"
+            ),
+            Self::SavesUnsupported => {
+                write!(f, "battery backed saves are not supported on this platform")
+            }
+            Self::Save(error) => write!(f, "cannot open the save file: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for CartridgeError {}
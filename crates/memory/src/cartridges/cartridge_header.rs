@@ -1,5 +1,7 @@
 #![allow(dead_code)]
 
+use crate::cartridges::CartridgeError;
+
 #[derive(Debug, Clone)]
 pub struct CartridgeHeader {
     pub entry_point: [u8; 4],
@@ -22,7 +24,7 @@ impl CartridgeHeader {
     const ENTRY_POINT: usize = 0x0100;
     const LOGO_ADR: usize = 0x0104;
     const TITLE_ADR: usize = 0x0134;
-    const CGB_FLAG_ADR: usize = 0x0143;
+    pub(crate) const CGB_FLAG_ADR: usize = 0x0143;
     const LICENCE_CODE_ADR: usize = 0x0144;
     const SGB_FLAG_ADR: usize = 0x0146;
     const TYPE_ADR: usize = 0x0147;
@@ -30,12 +32,18 @@ impl CartridgeHeader {
     const RAM_SIZE_ADR: usize = 0x0149;
     const DESTINATION_CODE_ADR: usize = 0x014A;
     const OLD_LICENSE_CODE_ADR: usize = 0x014B;
-    const MASK_ROM_ADR: usize = 0x014D;
-    const CHECKSUM_ADR: usize = 0x014C;
+    const MASK_ROM_ADR: usize = 0x014C;
+    const CHECKSUM_ADR: usize = 0x014D;
     const GLOBAL_CHECKSUM_ADR: usize = 0x014E;
+    /// First address past the header.
+    const END: usize = 0x0150;
+
+    pub fn new(rom: &[u8]) -> Result<Self, CartridgeError> {
+        if rom.len() < Self::END {
+            return Err(CartridgeError::MissingHeader { size: rom.len() });
+        }
 
-    pub fn new(rom: &[u8]) -> Self {
-        Self {
+        Ok(Self {
             entry_point: Self::entry_point_from_rom(rom),
             logo: Self::logo_from_rom(rom),
             title: Self::title_from_rom(rom),
@@ -50,7 +58,40 @@ impl CartridgeHeader {
             mask_rom_version: Self::mask_rom_version_from_rom(rom),
             checksum: Self::checksum_from_rom(rom),
             global_checksum: Self::global_checksum_from_rom(rom),
-        }
+        })
+    }
+
+    /// Header checksum the boot ROM verifies, to compare against [`Self::checksum`].
+    pub fn computed_checksum(rom: &[u8]) -> u8 {
+        rom[Self::TITLE_ADR..=Self::MASK_ROM_ADR]
+            .iter()
+            .fold(0u8, |checksum, &byte| {
+                checksum.wrapping_sub(byte).wrapping_sub(1)
+            })
+    }
+
+    /// Identifies the ROM from its title and checksums.
+    /// Computed with FNV-1a so it stays stable across builds.
+    pub fn content_id(&self) -> u64 {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        self.title
+            .bytes()
+            .chain([
+                self.cgb_flag,
+                self.type_code,
+                self.rom_size,
+                self.ram_size,
+                // The two bytes were read from each other's address once,
+                // hashing them in this order keeps content ids from back then.
+                self.checksum,
+                self.mask_rom_version,
+            ])
+            .chain(self.global_checksum)
+            .fold(FNV_OFFSET_BASIS, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+            })
     }
 
     fn entry_point_from_rom(rom: &[u8]) -> [u8; 4] {
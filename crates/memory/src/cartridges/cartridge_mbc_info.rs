@@ -1,4 +1,4 @@
-use crate::cartridges::cartridge_header::CartridgeHeader;
+use crate::cartridges::{CartridgeError, cartridge_header::CartridgeHeader};
 
 const MBC2_RAM_SIZE: usize = 0x200; // 512B
 
@@ -19,6 +19,16 @@ pub enum MbcType {
     PocketCamera,
 }
 
+impl MbcType {
+    /// Whether the memory bank controller is emulated.
+    pub fn is_supported(self) -> bool {
+        matches!(
+            self,
+            Self::Mbc0 | Self::Mbc1 | Self::Mbc2 | Self::Mbc3 | Self::Mbc5
+        )
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct CartridgeMbcInfo {
     pub mbc_type: MbcType,
@@ -29,18 +39,19 @@ pub struct CartridgeMbcInfo {
     pub includes_ram: bool,
     pub includes_battery: bool,
     pub includes_timer: bool,
-    #[allow(dead_code)]
     pub includes_rumble: bool,
 }
 
 impl CartridgeMbcInfo {
-    pub fn new(header: &CartridgeHeader) -> Self {
-        let rom_bank_count = rom_bank_count(header.rom_size);
-        let ram_bank_count = ram_bank_count(header.ram_size);
+    pub fn new(header: &CartridgeHeader) -> Result<Self, CartridgeError> {
+        let rom_bank_count = rom_bank_count(header.rom_size)
+            .ok_or(CartridgeError::UnknownRomSize(header.rom_size))?;
+        let ram_bank_count = ram_bank_count(header.ram_size)
+            .ok_or(CartridgeError::UnknownRamSize(header.ram_size))?;
         let rom_size = rom_bank_count * 0x4000;
         let ram_size = ram_bank_count * 0x2000;
 
-        match header.type_code {
+        let info = match header.type_code {
             0x00 => Self {
                 mbc_type: MbcType::Mbc0,
                 rom_bank_count,
@@ -280,35 +291,36 @@ impl CartridgeMbcInfo {
                 includes_battery: true,
                 ..Default::default()
             },
-            _ => panic!("Invalid cartridge type code: {}", header.type_code),
-        }
+            _ => return Err(CartridgeError::UnknownType(header.type_code)),
+        };
+        Ok(info)
     }
 }
 
-fn rom_bank_count(code: u8) -> usize {
+/// The 0x52..=0x54 sizes only appear in unofficial documentation and are not supported.
+fn rom_bank_count(code: u8) -> Option<usize> {
     match code {
-        0x00 => 2,   // 32KB, 2 banks (no banking)
-        0x01 => 4,   // 64KB, 4 banks
-        0x02 => 8,   // 128KB, 8 banks
-        0x03 => 16,  // 256KB, 16 banks
-        0x04 => 32,  // 512KB, 32 banks
-        0x05 => 64,  // 1MB, 64 banks
-        0x06 => 128, // 2MB, 128 banks
-        0x07 => 256, // 4MB, 256 banks
-        0x08 => 512, // 8MB, 512 banks
-        0x52..=0x54 => unimplemented!("weird rom size"),
-        _ => unreachable!(),
+        0x00 => Some(2),   // 32KB, 2 banks (no banking)
+        0x01 => Some(4),   // 64KB, 4 banks
+        0x02 => Some(8),   // 128KB, 8 banks
+        0x03 => Some(16),  // 256KB, 16 banks
+        0x04 => Some(32),  // 512KB, 32 banks
+        0x05 => Some(64),  // 1MB, 64 banks
+        0x06 => Some(128), // 2MB, 128 banks
+        0x07 => Some(256), // 4MB, 256 banks
+        0x08 => Some(512), // 8MB, 512 banks
+        _ => None,
     }
 }
 
-fn ram_bank_count(code: u8) -> usize {
+/// Code 0x01 is marked as unused.
+fn ram_bank_count(code: u8) -> Option<usize> {
     match code {
-        0x00 => 0,
-        0x01 => unreachable!("RAM size 0x01 marked as unused"),
-        0x02 => 1,  // 8KB, 1 bank of 8KB
-        0x03 => 4,  // 32KB, 4 banks of 8KB
-        0x04 => 16, // 128KB, 16 bank of 8KB
-        0x05 => 8,  // 64KB, 8 bank of 8KB
-        _ => unreachable!(),
+        0x00 => Some(0),
+        0x02 => Some(1),  // 8KB, 1 bank of 8KB
+        0x03 => Some(4),  // 32KB, 4 banks of 8KB
+        0x04 => Some(16), // 128KB, 16 bank of 8KB
+        0x05 => Some(8),  // 64KB, 8 bank of 8KB
+        _ => None,
     }
 }
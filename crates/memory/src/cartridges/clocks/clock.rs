@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
+
+use crate::cartridges::{EmulatedClock, ManualClock, WallClock};
+
+/// Source of the current date for the cartridge, used by the RTC and save timestamps.
+pub trait Clock {
+    /// Time elapsed since the Unix epoch.
+    fn now(&self) -> Duration;
+
+    /// Called as the emulator advances, only clocks following emulated time care.
+    fn advance_dots(&mut self, _dots: u64) {}
+
+    /// Called once the save is read, with the time it was written at.
+    /// Clocks without a start date of their own carry on from there.
+    fn resume_from(&mut self, _saved_at: Duration) {}
+}
+
+#[derive(Debug, Clone)]
+pub enum ClockKind {
+    Wall(WallClock),
+    Emulated(EmulatedClock),
+    Manual(ManualClock),
+}
+
+impl Default for ClockKind {
+    fn default() -> Self {
+        Self::Wall(WallClock)
+    }
+}
+
+impl Clock for ClockKind {
+    fn now(&self) -> Duration {
+        match self {
+            ClockKind::Wall(wall_clock) => wall_clock.now(),
+            ClockKind::Emulated(emulated_clock) => emulated_clock.now(),
+            ClockKind::Manual(manual_clock) => manual_clock.now(),
+        }
+    }
+
+    fn advance_dots(&mut self, dots: u64) {
+        match self {
+            ClockKind::Wall(wall_clock) => wall_clock.advance_dots(dots),
+            ClockKind::Emulated(emulated_clock) => emulated_clock.advance_dots(dots),
+            ClockKind::Manual(manual_clock) => manual_clock.advance_dots(dots),
+        }
+    }
+
+    fn resume_from(&mut self, saved_at: Duration) {
+        match self {
+            ClockKind::Wall(wall_clock) => wall_clock.resume_from(saved_at),
+            ClockKind::Emulated(emulated_clock) => emulated_clock.resume_from(saved_at),
+            ClockKind::Manual(manual_clock) => manual_clock.resume_from(saved_at),
+        }
+    }
+}
+
+/// Only emulated time is stored, the other clocks keep following their own source.
+impl Snapshot for ClockKind {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        match self {
+            ClockKind::Emulated(emulated_clock) => {
+                writer.write_bool(true);
+                writer.write(emulated_clock);
+            }
+            ClockKind::Wall(_) | ClockKind::Manual(_) => writer.write_bool(false),
+        }
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        if reader.read_bool()? {
+            let mut stored = EmulatedClock::from_save();
+            reader.read(&mut stored)?;
+            if let ClockKind::Emulated(emulated_clock) = self {
+                *emulated_clock = stored;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<WallClock> for ClockKind {
+    fn from(clock: WallClock) -> Self {
+        Self::Wall(clock)
+    }
+}
+
+impl From<EmulatedClock> for ClockKind {
+    fn from(clock: EmulatedClock) -> Self {
+        Self::Emulated(clock)
+    }
+}
+
+impl From<ManualClock> for ClockKind {
+    fn from(clock: ManualClock) -> Self {
+        Self::Manual(clock)
+    }
+}
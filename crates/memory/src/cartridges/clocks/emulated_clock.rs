@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
+
+use crate::cartridges::Clock;
+
+/// Time driven by emulated dots, starting from a fixed date or from the time of the save.
+/// Runs are reproducible, no matter how fast or how often the emulator is paused.
+#[derive(Debug, Clone, Copy)]
+pub struct EmulatedClock {
+    epoch: Option<Duration>,
+    dots: u64,
+}
+
+impl EmulatedClock {
+    pub fn new(epoch: Duration) -> Self {
+        Self {
+            epoch: Some(epoch),
+            dots: 0,
+        }
+    }
+
+    /// Starts where the save left off, so RTC saves keep consistent timestamps.
+    /// Without a save, time starts at the Unix epoch.
+    pub fn from_save() -> Self {
+        Self {
+            epoch: None,
+            dots: 0,
+        }
+    }
+}
+
+impl Clock for EmulatedClock {
+    fn now(&self) -> Duration {
+        let nanos =
+            self.dots as u128 * 1_000_000_000 / yagber_app::Emulator::TARGET_DOT_FREQ_HZ as u128;
+        self.epoch.unwrap_or_default() + Duration::from_nanos(nanos as u64)
+    }
+
+    fn advance_dots(&mut self, dots: u64) {
+        self.dots += dots;
+    }
+
+    fn resume_from(&mut self, saved_at: Duration) {
+        self.epoch.get_or_insert(saved_at);
+    }
+}
+
+impl Snapshot for EmulatedClock {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_u64(self.epoch.unwrap_or_default().as_secs());
+        writer.write_u32(self.epoch.unwrap_or_default().subsec_nanos());
+        writer.write_u64(self.dots);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        let seconds = reader.read_u64()?;
+        let nanos = reader.read_u32()?.min(999_999_999);
+        self.epoch = Some(Duration::new(seconds, nanos));
+        self.dots = reader.read_u64()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_emulated_dots() {
+        let epoch = Duration::from_secs(1_000_000);
+        let mut clock = EmulatedClock::new(epoch);
+        clock.advance_dots(yagber_app::Emulator::TARGET_DOT_FREQ_HZ as u64 * 3 / 2);
+        assert_eq!(clock.now(), epoch + Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn resumes_from_the_save_without_a_fixed_date() {
+        let saved_at = Duration::from_secs(1_000_000);
+        let mut clock = EmulatedClock::from_save();
+        clock.resume_from(saved_at);
+        assert_eq!(clock.now(), saved_at);
+
+        let epoch = Duration::from_secs(5);
+        let mut clock = EmulatedClock::new(epoch);
+        clock.resume_from(saved_at);
+        assert_eq!(clock.now(), epoch);
+    }
+}
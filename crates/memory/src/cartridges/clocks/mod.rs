@@ -0,0 +1,9 @@
+mod clock;
+mod emulated_clock;
+mod manual_clock;
+mod wall_clock;
+
+pub use clock::{Clock, ClockKind};
+pub use emulated_clock::EmulatedClock;
+pub use manual_clock::ManualClock;
+pub use wall_clock::WallClock;
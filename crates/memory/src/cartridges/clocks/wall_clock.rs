@@ -0,0 +1,15 @@
+use std::time::{Duration, SystemTime};
+
+use crate::cartridges::Clock;
+
+/// The host system time.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WallClock;
+
+impl Clock for WallClock {
+    fn now(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+}
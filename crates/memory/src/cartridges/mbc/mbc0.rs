@@ -1,3 +1,5 @@
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
+
 use crate::cartridges::{ExternalRamAddress, Mbc, external_ram_address::MbcDeviceUpdate};
 
 pub struct Mbc0;
@@ -19,10 +21,18 @@ impl Mbc for Mbc0 {
     }
 
     fn ram_address(&self, address: u16) -> ExternalRamAddress {
-        ExternalRamAddress::ExternalRam(address as usize)
+        ExternalRamAddress::ExternalRam((address & 0x1FFF) as usize)
     }
 
     fn ram_enabled(&self) -> bool {
         true
     }
 }
+
+impl Snapshot for Mbc0 {
+    fn save(&self, _writer: &mut SnapshotWriter) {}
+
+    fn load(&mut self, _reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 use arbitrary_int::{u2, u5};
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 
 use crate::cartridges::{ExternalRamAddress, Mbc, external_ram_address::MbcDeviceUpdate};
 
@@ -107,3 +108,20 @@ impl Mbc for Mbc1 {
         self.ram_enabled
     }
 }
+
+impl Snapshot for Mbc1 {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_bool(self.ram_enabled);
+        writer.write_u8(self.rom_bank_number.value());
+        writer.write_u8(self.ram_bank_number.value());
+        writer.write_u8(self.mode);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.ram_enabled = reader.read_bool()?;
+        self.rom_bank_number = u5::from_u8(reader.read_u8()? & 0x1F);
+        self.ram_bank_number = u2::from_u8(reader.read_u8()? & 0x03);
+        self.mode = reader.read_u8()? & 0x01;
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 use arbitrary_int::{u4, u7};
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 
 use crate::cartridges::{ExternalRamAddress, Mbc, external_ram_address::MbcDeviceUpdate};
 
@@ -94,3 +95,18 @@ impl Mbc for Mbc3 {
         self.ram_enabled
     }
 }
+
+impl Snapshot for Mbc3 {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_bool(self.ram_enabled);
+        writer.write_u8(self.rom_bank_number.value());
+        writer.write_u8(self.ram_bank_number.value());
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.ram_enabled = reader.read_bool()?;
+        self.rom_bank_number = u7::from_u8(reader.read_u8()? & 0x7F);
+        self.ram_bank_number = u4::from_u8(reader.read_u8()? & 0x0F);
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 use arbitrary_int::{u4, u9};
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 
 use crate::cartridges::{ExternalRamAddress, Mbc, external_ram_address::MbcDeviceUpdate};
 
@@ -93,3 +94,18 @@ impl Mbc for Mbc5 {
         self.ram_enabled
     }
 }
+
+impl Snapshot for Mbc5 {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_bool(self.ram_enabled);
+        writer.write_u16(self.rom_bank_number.value());
+        writer.write_u8(self.ram_bank_number.value());
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.ram_enabled = reader.read_bool()?;
+        self.rom_bank_number = u9::from_u16(reader.read_u16()? & 0x01FF);
+        self.ram_bank_number = u4::from_u8(reader.read_u8()? & 0x0F);
+        Ok(())
+    }
+}
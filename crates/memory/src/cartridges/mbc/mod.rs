@@ -10,6 +10,8 @@ pub use mbc2::Mbc2;
 pub use mbc3::Mbc3;
 pub use mbc5::Mbc5;
 
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
+
 use crate::cartridges::{
     ExternalRamAddress,
     cartridge_mbc_info::{CartridgeMbcInfo, MbcType},
@@ -32,6 +34,7 @@ pub enum MbcKind {
 }
 
 impl MbcKind {
+    /// Callers check [`MbcType::is_supported`] first.
     pub fn new(info: &CartridgeMbcInfo) -> Self {
         match info.mbc_type {
             MbcType::Mbc0 => MbcKind::Mbc0(Mbc0::new()),
@@ -43,7 +46,7 @@ impl MbcKind {
                 info.ram_bank_count,
                 info.includes_rumble,
             )),
-            _ => unimplemented!("Unsupported MBC type: {:?}", info.mbc_type),
+            _ => unreachable!("Unsupported MBC type: {:?}", info.mbc_type),
         }
     }
 }
@@ -90,6 +93,48 @@ impl Mbc for MbcKind {
     }
 }
 
+impl MbcKind {
+    fn tag(&self) -> u8 {
+        match self {
+            MbcKind::Mbc0(_) => 0,
+            MbcKind::Mbc1(_) => 1,
+            MbcKind::Mbc2(_) => 2,
+            MbcKind::Mbc3(_) => 3,
+            MbcKind::Mbc5(_) => 5,
+        }
+    }
+}
+
+/// The MBC type comes from the ROM header, so only the bank state is restored.
+impl Snapshot for MbcKind {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_u8(self.tag());
+        match self {
+            MbcKind::Mbc0(mbc) => writer.write(mbc),
+            MbcKind::Mbc1(mbc) => writer.write(mbc),
+            MbcKind::Mbc2(mbc) => writer.write(mbc),
+            MbcKind::Mbc3(mbc) => writer.write(mbc),
+            MbcKind::Mbc5(mbc) => writer.write(mbc),
+        }
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        let tag = reader.read_u8()?;
+        if tag != self.tag() {
+            return Err(SnapshotError::InvalidData(format!(
+                "MBC type {tag} does not match the loaded cartridge"
+            )));
+        }
+        match self {
+            MbcKind::Mbc0(mbc) => reader.read(mbc),
+            MbcKind::Mbc1(mbc) => reader.read(mbc),
+            MbcKind::Mbc2(mbc) => reader.read(mbc),
+            MbcKind::Mbc3(mbc) => reader.read(mbc),
+            MbcKind::Mbc5(mbc) => reader.read(mbc),
+        }
+    }
+}
+
 impl From<&CartridgeMbcInfo> for MbcKind {
     fn from(info: &CartridgeMbcInfo) -> Self {
         Self::new(info)
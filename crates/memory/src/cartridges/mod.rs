@@ -1,13 +1,20 @@
 mod cartridge;
+mod cartridge_error;
 mod cartridge_header;
 mod cartridge_mbc_info;
+mod clocks;
 mod external_ram_address;
 mod mbc;
 mod rtc;
 mod saves;
 
+pub(crate) use cartridge::BatteryState;
 pub use cartridge::Cartridge;
+pub use cartridge_error::CartridgeError;
 pub use cartridge_header::CartridgeHeader;
+pub use cartridge_mbc_info::{CartridgeMbcInfo, MbcType};
+pub use clocks::{Clock, ClockKind, EmulatedClock, ManualClock, WallClock};
 pub use external_ram_address::ExternalRamAddress;
 pub use mbc::Mbc;
 pub use rtc::{Rtc, RtcRegisterKind};
+pub use saves::SaveLocation;
@@ -1,7 +1,12 @@
+use std::time::Duration;
+
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
+
 #[derive(Debug, Clone, Copy)]
 pub struct Rtc {
     pub registers: RtcRegisters,
-    pub last_tick: std::time::Instant,
+    /// Time counted towards the next second.
+    progress: Duration,
     latched_registers: Option<RtcRegisters>,
     last_latch_value: u8,
 }
@@ -10,19 +15,21 @@ impl Rtc {
     pub fn from_registers(registers: RtcRegisters) -> Self {
         Self {
             registers,
-            last_tick: std::time::Instant::now(),
+            progress: Duration::ZERO,
             latched_registers: None,
             last_latch_value: 0,
         }
     }
 
-    pub fn tick(&mut self) {
-        let seconds = self.last_tick.elapsed().as_secs();
+    /// Moves the clock forward, time passing while halted is lost.
+    pub fn advance(&mut self, elapsed: Duration) {
+        self.progress += elapsed;
+        let seconds = self.progress.as_secs();
         if seconds > 0 {
             if !self.registers.halted() {
                 self.registers.advance_by(seconds);
             }
-            self.last_tick += std::time::Duration::from_secs(seconds);
+            self.progress -= Duration::from_secs(seconds);
         }
     }
 
@@ -40,17 +47,44 @@ impl Rtc {
 
     /// Handle writes to the MBC3 latch register (0x6000..=0x7FFF).
     /// A transition from 0 to 1 latches the current time into readable registers.
+    /// The clock is expected to be advanced up to now.
     pub fn latch_write(&mut self, value: u8) {
         let value = value & 0x01;
         if self.last_latch_value == 0 && value == 1 {
             // Latch snapshot of current time
-            self.tick();
             self.latched_registers = Some(self.registers);
         }
         self.last_latch_value = value;
     }
 }
 
+/// The sub-second progress is stored, so the clock ticks at the same emulated moments after a load.
+impl Snapshot for Rtc {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.registers);
+        writer.write_u32(self.progress.subsec_nanos());
+        writer.write_bool(self.latched_registers.is_some());
+        if let Some(latched) = &self.latched_registers {
+            writer.write(latched);
+        }
+        writer.write_u8(self.last_latch_value);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        reader.read(&mut self.registers)?;
+        self.progress = Duration::from_nanos(reader.read_u32()?.min(999_999_999) as u64);
+        self.latched_registers = if reader.read_bool()? {
+            let mut latched = RtcRegisters::default();
+            reader.read(&mut latched)?;
+            Some(latched)
+        } else {
+            None
+        };
+        self.last_latch_value = reader.read_u8()? & 0x01;
+        Ok(())
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub struct RtcRegisters {
     seconds: u8,
@@ -115,6 +149,25 @@ impl RtcRegisters {
     }
 }
 
+impl Snapshot for RtcRegisters {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_u8(self.seconds);
+        writer.write_u8(self.minutes);
+        writer.write_u8(self.hours);
+        writer.write_u8(self.days_low);
+        writer.write_u8(self.days_high);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.seconds = reader.read_u8()?;
+        self.minutes = reader.read_u8()?;
+        self.hours = reader.read_u8()?;
+        self.days_low = reader.read_u8()?;
+        self.days_high = reader.read_u8()? & 0b1100_0001;
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RtcRegisterKind {
     /// Seconds register.
@@ -128,3 +181,21 @@ pub enum RtcRegisterKind {
     /// Days register (high bit) and Control.
     DaysHigh,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advance_keeps_sub_second_progress() {
+        let mut rtc = Rtc::from_registers(RtcRegisters::default());
+        rtc.advance(Duration::from_secs(3 * 24 * 60 * 60) + Duration::from_millis(600));
+        rtc.advance(Duration::from_millis(600));
+        assert_eq!(rtc.registers.days(), 3);
+        assert_eq!(rtc.registers.read(RtcRegisterKind::Seconds), 1);
+
+        rtc.registers.write(RtcRegisterKind::DaysHigh, 1 << 6);
+        rtc.advance(Duration::from_secs(10));
+        assert_eq!(rtc.registers.read(RtcRegisterKind::Seconds), 1);
+    }
+}
@@ -1,6 +1,7 @@
 mod memory_backend;
 mod save;
 mod save_backend;
+mod save_location;
 
 #[cfg(feature = "native")]
 mod native_file_backend;
@@ -11,3 +12,4 @@ pub use native_file_backend::NativeFileBackend;
 pub use memory_backend::MemoryBackend;
 pub use save::Save;
 pub use save_backend::{SaveBackend, SaveBackendKind};
+pub use save_location::SaveLocation;
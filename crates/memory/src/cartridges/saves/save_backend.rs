@@ -1,7 +1,7 @@
 use crate::cartridges::{
-    CartridgeHeader,
+    CartridgeError, CartridgeHeader,
     cartridge_mbc_info::CartridgeMbcInfo,
-    saves::{MemoryBackend, NativeFileBackend, save::Save},
+    saves::{MemoryBackend, NativeFileBackend, SaveLocation, save::Save},
 };
 
 pub trait SaveBackend {
@@ -15,20 +15,34 @@ pub enum SaveBackendKind {
 }
 
 impl SaveBackendKind {
-    pub fn new(cartridge_header: &CartridgeHeader, mbc_info: &CartridgeMbcInfo) -> Self {
-        if !mbc_info.includes_battery {
-            Self::Memory(MemoryBackend)
-        } else if cfg!(feature = "native") {
+    pub fn new(
+        cartridge_header: &CartridgeHeader,
+        mbc_info: &CartridgeMbcInfo,
+        location: &SaveLocation,
+    ) -> Result<Self, CartridgeError> {
+        let save_dir = match location {
+            SaveLocation::Directory(save_dir) if mbc_info.includes_battery => save_dir,
+            _ => return Ok(Self::Memory(MemoryBackend)),
+        };
+        if cfg!(feature = "native") {
+            let path = save_dir.join(format!("{}.sav", cartridge_header.title));
             #[cfg(feature = "trace")]
-            tracing::info!("Saving to {}", cartridge_header.title,);
-            let path = format!("out/saves/{}.sav", cartridge_header.title);
-            Self::NativeFile(NativeFileBackend::new(path).unwrap())
+            tracing::info!("Saving to {}", path.display());
+            let backend = NativeFileBackend::new(path).map_err(CartridgeError::Save)?;
+            Ok(Self::NativeFile(backend))
         } else {
-            panic!("Battery backed RAM is not supported on this platform");
+            Err(CartridgeError::SavesUnsupported)
         }
     }
 }
 
+impl SaveBackendKind {
+    /// Whether saves outlive the emulator, i.e. the cartridge has a battery.
+    pub fn is_persistent(&self) -> bool {
+        matches!(self, Self::NativeFile(_))
+    }
+}
+
 impl SaveBackend for SaveBackendKind {
     fn read(&mut self) -> Save {
         match self {
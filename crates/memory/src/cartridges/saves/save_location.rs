@@ -0,0 +1,20 @@
+use std::path::PathBuf;
+
+/// Where battery backed cartridges keep their saves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SaveLocation {
+    /// One `{title}.sav` file per cartridge in the directory.
+    Directory(PathBuf),
+    /// Nothing is read or written, every run starts from a blank save.
+    Memory,
+}
+
+impl SaveLocation {
+    pub const DEFAULT_DIR: &str = "out/saves";
+}
+
+impl Default for SaveLocation {
+    fn default() -> Self {
+        Self::Directory(PathBuf::from(Self::DEFAULT_DIR))
+    }
+}
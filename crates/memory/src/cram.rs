@@ -1,3 +1,5 @@
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
+
 use crate::Bus;
 
 #[derive(Default, Debug, Clone, Copy)]
@@ -63,6 +65,16 @@ impl Cram {
     }
 }
 
+impl Snapshot for Cram {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_bytes(&self.data);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        reader.read_bytes_into(&mut self.data)
+    }
+}
+
 impl Default for Cram {
     fn default() -> Self {
         Self::new()
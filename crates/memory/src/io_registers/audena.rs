@@ -59,8 +59,7 @@ impl Audena {
 
         let audena = bus.read(IOType::AUDENA.address());
         let new_audena = audena & !channel.audena_bit();
-        bus.io_registers
-            .write_unchecked(IOType::AUDENA.address(), new_audena);
+        bus.write_io_unchecked(IOType::AUDENA.address(), new_audena);
     }
 
     pub(crate) fn on_aud_1_env_write(bus: &mut Bus, value: u8) {
@@ -83,8 +82,7 @@ impl Audena {
 
         let audena = bus.read(IOType::AUDENA.address());
         let new_audena = audena & !AudioChannel::Ch3.audena_bit();
-        bus.io_registers
-            .write_unchecked(IOType::AUDENA.address(), new_audena);
+        bus.write_io_unchecked(IOType::AUDENA.address(), new_audena);
     }
 
     pub(crate) fn on_aud_1_high_write(bus: &mut Bus, value: u8) {
@@ -92,8 +90,7 @@ impl Audena {
         if aud1high.trigger_enabled() {
             let audena = bus.read(IOType::AUDENA.address());
             let new_audena = audena | AudioChannel::Ch1.audena_bit();
-            bus.io_registers
-                .write_unchecked(IOType::AUDENA.address(), new_audena);
+            bus.write_io_unchecked(IOType::AUDENA.address(), new_audena);
         }
     }
 
@@ -102,8 +99,7 @@ impl Audena {
         if aud2high.trigger_enabled() {
             let audena = bus.read(IOType::AUDENA.address());
             let new_audena = audena | AudioChannel::Ch2.audena_bit();
-            bus.io_registers
-                .write_unchecked(IOType::AUDENA.address(), new_audena);
+            bus.write_io_unchecked(IOType::AUDENA.address(), new_audena);
         }
     }
 
@@ -112,8 +108,7 @@ impl Audena {
         if aud3high.trigger_enabled() {
             let audena = bus.read(IOType::AUDENA.address());
             let new_audena = audena | AudioChannel::Ch3.audena_bit();
-            bus.io_registers
-                .write_unchecked(IOType::AUDENA.address(), new_audena);
+            bus.write_io_unchecked(IOType::AUDENA.address(), new_audena);
         }
     }
 
@@ -122,8 +117,7 @@ impl Audena {
         if aud4go.trigger_enabled() {
             let audena = bus.read(IOType::AUDENA.address());
             let new_audena = audena | AudioChannel::Ch4.audena_bit();
-            bus.io_registers
-                .write_unchecked(IOType::AUDENA.address(), new_audena);
+            bus.write_io_unchecked(IOType::AUDENA.address(), new_audena);
         }
     }
 
@@ -99,6 +99,16 @@ impl Aud3Volume {
         }
     }
 
+    /// Inverse of [`Aud3Volume::from_u8`].
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            Self::Mute => 0b00,
+            Self::Full => 0b01,
+            Self::Half => 0b10,
+            Self::Quarter => 0b11,
+        }
+    }
+
     pub fn as_shift(&self) -> u8 {
         match self {
             Self::Mute => 7,
@@ -1,4 +1,4 @@
-use crate::{Bus, IOType, cram::CramSpecification};
+use crate::{Bus, IOBus, IOType, cram::CramSpecification};
 
 pub struct BCPSRegister {
     specification: CramSpecification,
@@ -18,7 +18,7 @@ impl BCPSRegister {
 
         if bcps.auto_increment() {
             let new_bcps = bcps.value().wrapping_add(1);
-            bus.io_registers.write(IOType::BCPS.address(), new_bcps);
+            bus.write(IOType::BCPS.address(), new_bcps);
         }
     }
 }
@@ -48,7 +48,7 @@ impl OCPSRegister {
 
         if ocps.auto_increment() {
             let new_ocps = ocps.value().wrapping_add(1);
-            bus.io_registers.write(IOType::OCPS.address(), new_ocps);
+            bus.write(IOType::OCPS.address(), new_ocps);
         }
     }
 }
@@ -70,8 +70,8 @@ impl BCPDRegister {
             .write_unhooked(IOType::BCPD.address(), data);
     }
 
-    pub(crate) fn bcpd_reader(bus: &mut Bus, value: u8) -> u8 {
-        let stat = super::Stat::from_bus(bus);
+    pub(crate) fn bcpd_reader(io_bus: &IOBus, value: u8) -> u8 {
+        let stat = super::Stat::new(io_bus.read(IOType::STAT.address()));
         let mode = stat.mode();
         if mode == 3 { 0xFF } else { value }
     }
@@ -87,8 +87,8 @@ impl OCPDRegister {
             .write_unhooked(IOType::OCPD.address(), data);
     }
 
-    pub(crate) fn ocpd_reader(bus: &mut Bus, value: u8) -> u8 {
-        let stat = super::Stat::from_bus(bus);
+    pub(crate) fn ocpd_reader(io_bus: &IOBus, value: u8) -> u8 {
+        let stat = super::Stat::new(io_bus.read(IOType::STAT.address()));
         let mode = stat.mode();
         if mode == 3 { 0xFF } else { value }
     }
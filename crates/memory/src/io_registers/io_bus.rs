@@ -1,7 +1,23 @@
-use crate::{IOType, io_registers::io_register::IORegister, memory::Memory};
+use std::collections::VecDeque;
 
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
+
+use crate::{
+    IOType,
+    io_registers::io_register::{BusHook, IORegister, SharedHook},
+    memory::Memory,
+};
+
+/// IO registers, with the hooks other parts of the machine observe them through.
+///
+/// Writes queue the hooks of the register they land on, [`crate::Bus::write`] runs the bus
+/// hooks and [`crate::Bus::run_hooks`] the others.
 pub struct IOBus {
     data: Vec<IORegister>,
+    /// Writes whose bus hooks have yet to run, see [`crate::Bus::write`].
+    pending_bus_hooks: VecDeque<(usize, u8)>,
+    /// Writes whose hooks have yet to run, see [`crate::Bus::run_hooks`].
+    pending_hooks: VecDeque<(usize, u8)>,
 }
 
 impl IOBus {
@@ -13,11 +29,15 @@ impl IOBus {
             .map(|_| IORegister::new())
             .collect::<Vec<_>>();
 
-        Self { data }
+        Self {
+            data,
+            pending_bus_hooks: VecDeque::new(),
+            pending_hooks: VecDeque::new(),
+        }
     }
 
     pub fn read(&self, address: u16) -> u8 {
-        self.data[Self::virtual_address(address)].read()
+        self.data[Self::virtual_address(address)].read(self)
     }
 
     pub fn write(&mut self, address: u16, value: u8) {
@@ -28,9 +48,13 @@ impl IOBus {
             address,
             value
         );
-        self.data[Self::virtual_address(address)].write(value);
+        let index = Self::virtual_address(address);
+        if let Some(value) = self.data[index].write(value) {
+            self.on_write(index, value);
+        }
     }
 
+    /// Bypasses the transformer, hooks are still queued.
     pub fn write_unchecked(&mut self, address: u16, value: u8) {
         #[cfg(feature = "trace")]
         tracing::trace!(
@@ -39,7 +63,9 @@ impl IOBus {
             address,
             value
         );
-        self.data[Self::virtual_address(address)].write_unchecked(value);
+        let index = Self::virtual_address(address);
+        self.data[index].write_unhooked(value);
+        self.on_write(index, value);
     }
 
     pub fn write_unhooked(&mut self, address: u16, value: u8) {
@@ -53,24 +79,54 @@ impl IOBus {
         self.data[Self::virtual_address(address)].write_unhooked(value);
     }
 
+    fn on_write(&mut self, index: usize, value: u8) {
+        let register = &self.data[index];
+        if !register.bus_hooks().is_empty() {
+            self.pending_bus_hooks.push_back((index, value));
+        }
+        if !register.hooks().is_empty() {
+            self.pending_hooks.push_back((index, value));
+        }
+    }
+
+    /// Next write whose bus hooks have yet to run, as register index and written value.
+    pub(crate) fn pop_pending_bus_hooks(&mut self) -> Option<(usize, u8)> {
+        self.pending_bus_hooks.pop_front()
+    }
+
+    pub(crate) fn bus_hook(&self, index: usize, position: usize) -> Option<BusHook> {
+        self.data[index].bus_hooks().get(position).copied()
+    }
+
+    /// Next write whose hooks have yet to run, as register index and written value.
+    pub(crate) fn pop_pending_hooks(&mut self) -> Option<(usize, u8)> {
+        self.pending_hooks.pop_front()
+    }
+
+    pub(crate) fn hook(&self, index: usize, position: usize) -> Option<SharedHook> {
+        self.data[index].hooks().get(position).cloned()
+    }
+
     pub fn add_transformer<F>(&mut self, io: IOType, transformer: F) -> &mut Self
     where
-        F: Fn((u8, u8)) -> Option<u8> + 'static,
+        F: Fn((u8, u8)) -> Option<u8> + Send + Sync + 'static,
     {
         self.data[Self::virtual_address(io.address())].add_transformer(transformer);
         self
     }
 
+    /// Hooks receive the emulator and the written value, before [`crate::Bus::write_hooked`]
+    /// returns. Writes made straight on the bus run them on the next [`crate::Bus::run_hooks`].
     pub fn add_hook<F>(&mut self, io: IOType, hook: F)
     where
-        F: Fn(u8) + 'static,
+        F: Fn(&mut yagber_app::Emulator, u8) + Send + Sync + 'static,
     {
         self.data[Self::virtual_address(io.address())].add_hook(hook);
     }
 
     pub fn add_reader<F>(&mut self, io: IOType, reader: F) -> &mut Self
     where
-        F: Fn(u8) -> u8 + 'static,
+        F: Fn(&IOBus, u8) -> u8 + Send + Sync + 'static,
     {
         self.data[Self::virtual_address(io.address())].add_reader(reader);
         self
@@ -78,7 +134,7 @@ impl IOBus {
 
     pub fn with_transformer<F>(&mut self, io: IOType, transformer: F) -> &mut Self
     where
-        F: Fn((u8, u8)) -> Option<u8> + 'static,
+        F: Fn((u8, u8)) -> Option<u8> + Send + Sync + 'static,
     {
         self.add_transformer(io, transformer);
         self
@@ -86,15 +142,21 @@ impl IOBus {
 
     pub fn with_hook<F>(&mut self, io: IOType, hook: F) -> &mut Self
     where
-        F: Fn(u8) + 'static,
+        F: Fn(&mut yagber_app::Emulator, u8) + Send + Sync + 'static,
     {
         self.add_hook(io, hook);
         self
     }
 
+    /// Bus hooks run as part of the write, before it returns.
+    pub(crate) fn with_bus_hook(&mut self, io: IOType, hook: BusHook) -> &mut Self {
+        self.data[Self::virtual_address(io.address())].add_bus_hook(hook);
+        self
+    }
+
     pub fn with_reader<F>(&mut self, io: IOType, reader: F) -> &mut Self
     where
-        F: Fn(u8) -> u8 + 'static,
+        F: Fn(&IOBus, u8) -> u8 + Send + Sync + 'static,
     {
         self.add_reader(io, reader);
         self
@@ -105,6 +167,24 @@ impl IOBus {
     }
 }
 
+/// Only the raw register values are stored.
+/// Values are restored unhooked, the components observing them restore their own state.
+impl Snapshot for IOBus {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        let values = self.data.iter().map(IORegister::value).collect::<Vec<_>>();
+        writer.write_bytes(&values);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        let mut values = [0u8; Self::IO_REGISTERS_SIZE];
+        reader.read_bytes_into(&mut values)?;
+        for (register, value) in self.data.iter_mut().zip(values) {
+            register.write_unhooked(value);
+        }
+        Ok(())
+    }
+}
+
 impl Default for IOBus {
     fn default() -> Self {
         Self::new()
@@ -126,3 +206,59 @@ impl std::fmt::Debug for IOBus {
         f.debug_struct("IOBus").finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use yagber_app::Emulator;
+
+    use crate::{Bus, IOType};
+
+    fn emulator_logging_hooks() -> (Emulator, Arc<Mutex<Vec<u8>>>) {
+        let mut emulator = Emulator::new();
+        emulator.with_component(Bus::new());
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let (sb_log, sb_log2, sc_log) = (seen.clone(), seen.clone(), seen.clone());
+        emulator
+            .get_component_mut::<Bus>()
+            .unwrap()
+            .io_registers
+            .with_hook(IOType::SB, move |emulator, value| {
+                sb_log.lock().unwrap().push(value);
+                Bus::write_hooked(emulator, IOType::SC.address(), value + 1);
+            })
+            .with_hook(IOType::SB, move |_, value| {
+                sb_log2.lock().unwrap().push(value * 10);
+            })
+            .with_hook(IOType::SC, move |_, value| {
+                sc_log.lock().unwrap().push(value);
+            });
+        (emulator, seen)
+    }
+
+    #[test]
+    fn test_hooks_run_before_write_hooked_returns() {
+        let (mut emulator, seen) = emulator_logging_hooks();
+
+        Bus::write_hooked(&mut emulator, IOType::SB.address(), 1);
+
+        // The nested write runs its hooks before the remaining hooks of the outer one
+        assert_eq!(*seen.lock().unwrap(), [1, 2, 10]);
+    }
+
+    #[test]
+    fn test_straight_writes_run_their_hooks_on_run_hooks() {
+        let (mut emulator, seen) = emulator_logging_hooks();
+
+        let bus = emulator.get_component_mut::<Bus>().unwrap();
+        bus.io_registers.write(IOType::SC.address(), 5);
+        bus.write(IOType::SB.address(), 1);
+        assert!(seen.lock().unwrap().is_empty());
+
+        Bus::run_hooks(&mut emulator);
+        assert_eq!(*seen.lock().unwrap(), [5, 1, 2, 10]);
+        Bus::run_hooks(&mut emulator);
+        assert_eq!(seen.lock().unwrap().len(), 4);
+    }
+}
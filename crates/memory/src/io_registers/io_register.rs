@@ -1,83 +1,84 @@
-use crate::Register;
+use std::sync::Arc;
 
-type BoxedReader = Box<dyn Fn(u8) -> u8>;
-type BoxedTransformer = Box<dyn Fn((u8, u8)) -> Option<u8>>;
-type BoxedObserver = Box<dyn Fn(u8)>;
+use crate::{Bus, io_registers::IOBus};
+
+type BoxedReader = Box<dyn Fn(&IOBus, u8) -> u8 + Send + Sync>;
+type BoxedTransformer = Box<dyn Fn((u8, u8)) -> Option<u8> + Send + Sync>;
+/// Hooks keeping the bus consistent, they run as part of the write.
+pub(crate) type BusHook = fn(&mut Bus, u8);
+/// Hooks of other components, they run with the emulator at hand, see [`crate::Bus::run_hooks`].
+pub(crate) type SharedHook = Arc<dyn Fn(&mut yagber_app::Emulator, u8) + Send + Sync>;
 
 pub struct IORegister {
     value: u8,
     reader: BoxedReader,
     transformer: BoxedTransformer,
-    hooks: Vec<BoxedObserver>,
+    bus_hooks: Vec<BusHook>,
+    hooks: Vec<SharedHook>,
 }
 
 impl IORegister {
     pub fn new() -> Self {
         Self {
             value: 0,
-            reader: Box::new(|value| value),
+            reader: Box::new(|_, value| value),
             transformer: Box::new(|(_, value)| Some(value)),
+            bus_hooks: Vec::new(),
             hooks: Vec::new(),
         }
     }
 
     pub fn add_transformer<F>(&mut self, transformer: F)
     where
-        F: Fn((u8, u8)) -> Option<u8> + 'static,
+        F: Fn((u8, u8)) -> Option<u8> + Send + Sync + 'static,
     {
         self.transformer = Box::new(transformer);
     }
 
     pub fn add_hook<F>(&mut self, hook: F)
     where
-        F: Fn(u8) + 'static,
+        F: Fn(&mut yagber_app::Emulator, u8) + Send + Sync + 'static,
     {
-        self.hooks.push(Box::new(hook));
+        self.hooks.push(Arc::new(hook));
+    }
+
+    pub(crate) fn add_bus_hook(&mut self, hook: BusHook) {
+        self.bus_hooks.push(hook);
     }
 
     pub fn add_reader<F>(&mut self, reader: F)
     where
-        F: Fn(u8) -> u8 + 'static,
+        F: Fn(&IOBus, u8) -> u8 + Send + Sync + 'static,
     {
         self.reader = Box::new(reader);
     }
 
-    pub fn read(&self) -> u8 {
-        (self.reader)(self.value)
+    /// The stored value, bypassing the reader.
+    pub fn value(&self) -> u8 {
+        self.value
     }
 
-    pub fn write(&mut self, value: u8) {
-        let transformed_opt = (self.transformer)((self.value, value));
-        let Some(transformed) = transformed_opt else {
-            return;
-        };
-
-        self.value = transformed;
-
-        for hook in self.hooks.iter() {
-            hook(self.value);
-        }
+    pub fn read(&self, io_bus: &IOBus) -> u8 {
+        (self.reader)(io_bus, self.value)
     }
 
-    pub fn write_unchecked(&mut self, value: u8) {
-        self.value = value;
-        for hook in self.hooks.iter() {
-            hook(self.value);
-        }
+    /// Stores the transformed value, returns it unless the transformer discarded the write.
+    pub fn write(&mut self, value: u8) -> Option<u8> {
+        let transformed = (self.transformer)((self.value, value))?;
+        self.value = transformed;
+        Some(transformed)
     }
 
     pub fn write_unhooked(&mut self, value: u8) {
         self.value = value;
     }
-}
 
-impl Register for IORegister {
-    fn read(&self) -> u8 {
-        self.read()
+    pub(crate) fn bus_hooks(&self) -> &[BusHook] {
+        &self.bus_hooks
     }
 
-    fn write(&mut self, value: u8) {
-        self.write(value);
+    pub(crate) fn hooks(&self) -> &[SharedHook] {
+        &self.hooks
     }
 }
 
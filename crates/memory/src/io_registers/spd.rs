@@ -37,9 +37,29 @@ impl Spd {
         let speed_mode = spd.speed_mode();
         emulator.set_speed_mode(speed_mode);
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use yagber_app::{Emulator, ResetKind, SpeedMode};
+
+    use super::*;
+
+    #[test]
+    fn test_soft_reset_drops_back_to_single_speed() {
+        let mut emulator = Emulator::new()
+            .with_plugin(crate::MemoryPlugin::default())
+            .build()
+            .unwrap();
+        let bus = emulator.get_component_mut::<Bus>().unwrap();
+        bus.write_io_unchecked(IOType::SPD.address(), 0x81);
+        Bus::run_hooks(&mut emulator);
+        assert_eq!(emulator.speed_mode(), SpeedMode::Double);
 
-    pub(crate) fn emu_spd_hook(emulator: &mut yagber_app::Emulator) -> impl Fn(u8) + use<> {
-        let emulator_ptr = emulator as *mut yagber_app::Emulator;
-        move |value| Spd::on_spd_write(unsafe { &mut *emulator_ptr }, value)
+        emulator.reset(ResetKind::Soft);
+        assert_eq!(emulator.speed_mode(), SpeedMode::Single);
+        let spd = Spd::from_bus(emulator.get_component::<Bus>().unwrap());
+        assert_eq!(spd.speed_mode(), SpeedMode::Single);
+        assert!(!spd.speed_switch_armed());
     }
 }
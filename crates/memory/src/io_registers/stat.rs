@@ -1,4 +1,4 @@
-use yagber_app::{EdgeDetector, EdgeMode};
+use yagber_app::{EdgeDetector, EdgeMode, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 
 use crate::{Bus, IOType, InterruptType};
 
@@ -65,8 +65,7 @@ impl Stat {
         let stat = bus.read(IOType::STAT.address());
         let new_stat = (stat & !0x04) | bit_2;
         if new_stat != stat {
-            bus.io_registers
-                .write_unchecked(IOType::STAT.address(), new_stat);
+            bus.write_io_unchecked(IOType::STAT.address(), new_stat);
         }
     }
 }
@@ -102,6 +101,18 @@ impl StatInterruptDetector {
     }
 }
 
+impl Snapshot for StatInterruptDetector {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_bool(self.should_trigger_interrupt);
+        writer.write(&self.edge_detector);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.should_trigger_interrupt = reader.read_bool()?;
+        reader.read(&mut self.edge_detector)
+    }
+}
+
 impl Default for StatInterruptDetector {
     fn default() -> Self {
         Self::new()
@@ -5,31 +5,80 @@ mod cram;
 mod interrupt;
 mod io_registers;
 mod memory;
+mod memory_access;
+mod model;
 mod oam;
 mod ram;
 mod register;
 mod vram;
 mod wram;
 
+use std::path::PathBuf;
+
+pub use boot_rom::BootRom;
 pub use bus::Bus;
+pub use cartridges::{
+    Cartridge, CartridgeError, CartridgeHeader, CartridgeMbcInfo, Clock, ClockKind, EmulatedClock,
+    ManualClock, MbcType, SaveLocation, WallClock,
+};
 pub use interrupt::InterruptType;
 pub use io_registers::*;
 pub use memory::Memory;
+pub use memory_access::{AccessKind, MemoryAccess};
+pub use model::Model;
 pub use register::{ByteRegister, Register};
 
 pub struct MemoryPlugin {
     memory_bus: Option<Bus>,
+    rom: Option<Vec<u8>>,
+    clock: ClockKind,
+    saves: SaveLocation,
+    boot_rom: Option<Vec<u8>>,
+    model: Model,
 }
 
 impl MemoryPlugin {
     pub fn new() -> Self {
         Self {
             memory_bus: Some(Bus::new()),
+            rom: None,
+            clock: ClockKind::default(),
+            saves: SaveLocation::default(),
+            boot_rom: None,
+            model: Model::default(),
         }
     }
 
     pub fn with_cartridge(mut self, data: &[u8]) -> Self {
-        self.memory_bus.as_mut().unwrap().load_rom(data);
+        self.rom = Some(data.to_vec());
+        self
+    }
+
+    /// Clock used by the cartridge RTC and save timestamps, the wall clock by default.
+    pub fn with_clock(mut self, clock: impl Into<ClockKind>) -> Self {
+        self.clock = clock.into();
+        self
+    }
+
+    /// Where battery backed saves are kept, the `out/saves` directory by default.
+    pub fn with_saves(mut self, saves: SaveLocation) -> Self {
+        self.saves = saves;
+        self
+    }
+
+    pub fn with_save_dir(self, save_dir: impl Into<PathBuf>) -> Self {
+        self.with_saves(SaveLocation::Directory(save_dir.into()))
+    }
+
+    /// Replaces the bundled boot ROM, `data` must be a [`BootRom::SIZE`] bytes CGB boot ROM
+    /// or the emulator fails to build.
+    pub fn with_boot_rom(mut self, data: &[u8]) -> Self {
+        self.boot_rom = Some(data.to_vec());
+        self
+    }
+
+    pub fn with_model(mut self, model: Model) -> Self {
+        self.model = model;
         self
     }
 }
@@ -41,68 +90,83 @@ impl Default for MemoryPlugin {
 }
 
 impl yagber_app::Plugin for MemoryPlugin {
-    fn init(mut self, emulator: &mut yagber_app::Emulator) {
-        let memory_bus = std::mem::take(&mut self.memory_bus).unwrap();
+    fn init(mut self, emulator: &mut yagber_app::Emulator) -> Result<(), yagber_app::PluginError> {
+        let mut memory_bus = std::mem::take(&mut self.memory_bus).unwrap();
+        if let Some(rom) = &self.rom {
+            memory_bus
+                .load_rom(rom, self.clock, &self.saves)
+                .map_err(|error| {
+                    yagber_app::PluginError::init::<Self>(format!(
+                        "cannot load the cartridge: {error}"
+                    ))
+                })?;
+        }
+        if let Some(boot_rom) = self.boot_rom.take() {
+            if boot_rom.len() != BootRom::SIZE {
+                return Err(yagber_app::PluginError::init::<Self>(format!(
+                    "the boot ROM is {} bytes long, a CGB boot ROM is {} bytes",
+                    boot_rom.len(),
+                    BootRom::SIZE
+                )));
+            }
+            memory_bus.load_boot_rom(BootRom::from_bytes(&boot_rom));
+        }
+        memory_bus.set_model(self.model);
         let stat_interrupt_detector = io_registers::StatInterruptDetector::new();
+        emulator.set_content_id(memory_bus.content_id());
 
         emulator
             .with_component(memory_bus)
             .with_component(stat_interrupt_detector)
-            .on_tcycle(Bus::on_tcycle);
-
-        let stat_ly_hook = emulator.attach_component(io_registers::Stat::on_ly_write);
-        let stat_lyc_hook = emulator.attach_component(io_registers::Stat::on_lyc_write);
-        let stat_stat_hook =
-            emulator.attach_components2(io_registers::StatInterruptDetector::on_stat_write);
-        let bcps_bcpd_hook = emulator.attach_component(io_registers::BCPSRegister::on_bcpd_write);
-        let ocps_ocpd_hook = emulator.attach_component(io_registers::OCPSRegister::on_ocpd_write);
-        let bcpd_bcps_hook = emulator.attach_component(io_registers::BCPDRegister::on_bcps_write);
-        let ocpd_ocps_hook = emulator.attach_component(io_registers::OCPDRegister::on_ocps_write);
-        let bcpd_reader = emulator.attach_component(io_registers::BCPDRegister::bcpd_reader);
-        let ocpd_reader = emulator.attach_component(io_registers::OCPDRegister::ocpd_reader);
-        let vram_vbk_hook = emulator.attach_component(vram::Vram::on_vbk_write);
-        let wram_svbk_hook = emulator.attach_component(wram::Wram::on_svbk_write);
-        let oam_stat_hook = emulator.attach_component(oam::Oam::on_stat_write);
-        let vram_stat_hook = emulator.attach_component(vram::Vram::on_stat_write);
-        let aud_1_env_hook = emulator.attach_component(io_registers::Audena::on_aud_1_env_write);
-        let aud_2_env_hook = emulator.attach_component(io_registers::Audena::on_aud_2_env_write);
-        let aud_4_env_hook = emulator.attach_component(io_registers::Audena::on_aud_4_env_write);
-        let aud_3_ena_hook = emulator.attach_component(io_registers::Audena::on_aud_3_ena_write);
-        let emu_spd_hook = Spd::emu_spd_hook(emulator);
-        let aud_1_high_hook = emulator.attach_component(io_registers::Audena::on_aud_1_high_write);
-        let aud_2_high_hook = emulator.attach_component(io_registers::Audena::on_aud_2_high_write);
-        let aud_3_high_hook = emulator.attach_component(io_registers::Audena::on_aud_3_high_write);
-        let aud_4_go_hook = emulator.attach_component(io_registers::Audena::on_aud_4_go_write);
+            .with_snapshot::<Bus>("bus")
+            .with_snapshot::<io_registers::StatInterruptDetector>("stat_interrupt_detector")
+            .on_event(Bus::on_reset)
+            .add_periodic_task(
+                "cartridge_tick",
+                yagber_app::Delay::Dots(Bus::CARTRIDGE_TICK_DOTS),
+                Bus::on_cartridge_tick,
+            );
+
+        let stat_stat_hook = yagber_app::Emulator::attach_components2(
+            io_registers::StatInterruptDetector::on_stat_write,
+        );
 
         emulator
             .get_component_mut::<Bus>()
             .expect("Bus component missing")
             .io_registers
-            .with_hook(IOType::LY, stat_ly_hook)
-            .with_hook(IOType::LYC, stat_lyc_hook)
+            .with_bus_hook(IOType::LY, io_registers::Stat::on_ly_write)
+            .with_bus_hook(IOType::LYC, io_registers::Stat::on_lyc_write)
             .with_transformer(IOType::STAT, io_registers::Stat::stat_transformer)
             .with_hook(IOType::STAT, stat_stat_hook)
-            .with_hook(IOType::BCPD, bcps_bcpd_hook)
-            .with_hook(IOType::OCPD, ocps_ocpd_hook)
-            .with_hook(IOType::BCPS, bcpd_bcps_hook)
-            .with_hook(IOType::OCPS, ocpd_ocps_hook)
-            .with_reader(IOType::BCPS, bcpd_reader)
-            .with_reader(IOType::OCPD, ocpd_reader)
-            .with_hook(IOType::VBK, vram_vbk_hook)
-            .with_hook(IOType::SVBK, wram_svbk_hook)
-            .with_hook(IOType::STAT, oam_stat_hook)
-            .with_hook(IOType::STAT, vram_stat_hook)
+            .with_bus_hook(IOType::BCPD, io_registers::BCPSRegister::on_bcpd_write)
+            .with_bus_hook(IOType::OCPD, io_registers::OCPSRegister::on_ocpd_write)
+            .with_bus_hook(IOType::BCPS, io_registers::BCPDRegister::on_bcps_write)
+            .with_bus_hook(IOType::OCPS, io_registers::OCPDRegister::on_ocps_write)
+            .with_reader(IOType::BCPS, io_registers::BCPDRegister::bcpd_reader)
+            .with_reader(IOType::OCPD, io_registers::OCPDRegister::ocpd_reader)
+            .with_bus_hook(IOType::VBK, vram::Vram::on_vbk_write)
+            .with_bus_hook(IOType::SVBK, wram::Wram::on_svbk_write)
+            .with_bus_hook(IOType::STAT, oam::Oam::on_stat_write)
+            .with_bus_hook(IOType::STAT, vram::Vram::on_stat_write)
             .with_transformer(IOType::VBK, io_registers::Vbk::vbk_transformer)
             .with_transformer(IOType::AUDENA, io_registers::Audena::audena_transformer)
-            .with_hook(IOType::AUD1ENV, aud_1_env_hook)
-            .with_hook(IOType::AUD2ENV, aud_2_env_hook)
-            .with_hook(IOType::AUD4ENV, aud_4_env_hook)
-            .with_hook(IOType::AUD3ENA, aud_3_ena_hook)
+            .with_bus_hook(IOType::AUD1ENV, io_registers::Audena::on_aud_1_env_write)
+            .with_bus_hook(IOType::AUD2ENV, io_registers::Audena::on_aud_2_env_write)
+            .with_bus_hook(IOType::AUD4ENV, io_registers::Audena::on_aud_4_env_write)
+            .with_bus_hook(IOType::AUD3ENA, io_registers::Audena::on_aud_3_ena_write)
             .with_transformer(IOType::SPD, io_registers::Spd::spd_transformer)
-            .with_hook(IOType::SPD, emu_spd_hook)
-            .with_hook(IOType::AUD1HIGH, aud_1_high_hook)
-            .with_hook(IOType::AUD2HIGH, aud_2_high_hook)
-            .with_hook(IOType::AUD3HIGH, aud_3_high_hook)
-            .with_hook(IOType::AUD4GO, aud_4_go_hook);
+            .with_hook(IOType::SPD, Spd::on_spd_write)
+            .with_bus_hook(IOType::AUD1HIGH, io_registers::Audena::on_aud_1_high_write)
+            .with_bus_hook(IOType::AUD2HIGH, io_registers::Audena::on_aud_2_high_write)
+            .with_bus_hook(IOType::AUD3HIGH, io_registers::Audena::on_aud_3_high_write)
+            .with_bus_hook(IOType::AUD4GO, io_registers::Audena::on_aud_4_go_write);
+        Ok(())
+    }
+
+    fn dependencies(&self) -> yagber_app::PluginDependencies {
+        yagber_app::PluginDependencies::new()
+            .provides::<Bus>()
+            .provides::<io_registers::StatInterruptDetector>()
     }
 }
@@ -0,0 +1,15 @@
+/// A data read or write made through the bus, by the CPU or a DMA transfer.
+///
+/// Opcode and operand fetches are not data accesses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryAccess {
+    pub address: u16,
+    pub value: u8,
+    pub kind: AccessKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
@@ -0,0 +1,58 @@
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
+
+/// Hardware the cartridge runs on.
+///
+/// Only the CGB is emulated, [`Model::Dmg`] runs cartridges in its DMG compatibility mode.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Model {
+    #[default]
+    Cgb,
+    Dmg,
+}
+
+impl Model {
+    /// The boot ROM picks the compatibility mode from the header CGB flag,
+    /// hiding the flag makes it treat every cartridge as a DMG one.
+    pub(crate) fn boot_cgb_flag(self, flag: u8) -> u8 {
+        match self {
+            Self::Cgb => flag,
+            Self::Dmg => 0x00,
+        }
+    }
+}
+
+/// The model comes from the emulator configuration, states taken on another one are
+/// rejected rather than run in the wrong mode.
+impl Snapshot for Model {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_u8(*self as u8);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        let model = reader.read_u8()?;
+        if model != *self as u8 {
+            return Err(SnapshotError::InvalidData(format!(
+                "the state was taken on another model than {self:?}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects_states_from_another_model() {
+        let mut writer = SnapshotWriter::new();
+        writer.write(&Model::Dmg);
+        let snapshot = writer.into_bytes();
+
+        assert!(SnapshotReader::new(&snapshot).read(&mut Model::Dmg).is_ok());
+        assert!(matches!(
+            SnapshotReader::new(&snapshot).read(&mut Model::Cgb),
+            Err(SnapshotError::InvalidData(_))
+        ));
+    }
+}
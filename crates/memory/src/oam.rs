@@ -1,9 +1,13 @@
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
+
 use crate::{Bus, Memory, ram::Ram};
 
 #[derive(Debug)]
 pub struct Oam {
     ram: Ram,
     accessible: bool,
+    /// An OAM DMA transfer owns the OAM bus.
+    dma_active: bool,
 }
 
 impl Oam {
@@ -14,11 +18,12 @@ impl Oam {
         Self {
             ram: Ram::new(Self::SIZE, Self::OFFSET),
             accessible: true,
+            dma_active: false,
         }
     }
 
     pub fn read(&self, address: u16) -> u8 {
-        if self.accessible {
+        if self.accessible && !self.dma_active {
             self.ram.read(address)
         } else {
             0xFF
@@ -26,15 +31,25 @@ impl Oam {
     }
 
     pub fn write(&mut self, address: u16, value: u8) {
-        if self.accessible {
+        if self.accessible && !self.dma_active {
             self.ram.write(address, value);
         }
     }
 
+    /// Writes as the OAM DMA does, regardless of who else owns the OAM bus.
+    pub fn write_dma(&mut self, address: u16, value: u8) {
+        self.ram.write(address, value);
+    }
+
     pub fn set_accessible(&mut self, accessible: bool) {
         self.accessible = accessible;
     }
 
+    /// Reads return 0xFF and writes are ignored while an OAM DMA transfer runs.
+    pub fn set_dma_active(&mut self, dma_active: bool) {
+        self.dma_active = dma_active;
+    }
+
     pub(crate) fn on_stat_write(bus: &mut Bus, value: u8) {
         let stat = super::Stat::new(value);
         let mode = stat.mode();
@@ -60,6 +75,21 @@ impl Memory for Oam {
     }
 }
 
+impl Snapshot for Oam {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.ram);
+        writer.write_bool(self.accessible);
+        writer.write_bool(self.dma_active);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        reader.read(&mut self.ram)?;
+        self.accessible = reader.read_bool()?;
+        self.dma_active = reader.read_bool()?;
+        Ok(())
+    }
+}
+
 impl Default for Oam {
     fn default() -> Self {
         Self::new()
@@ -1,3 +1,5 @@
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
+
 use crate::memory::Memory;
 
 #[derive(Debug, Default, Clone)]
@@ -57,3 +59,29 @@ impl Memory for Ram {
         self.write_usize(address as usize, value);
     }
 }
+
+/// Stores the bytes followed by a bitmap of the initialized cells.
+impl Snapshot for Ram {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_bytes(&self.to_vec());
+        let mut initialized = vec![0u8; self.data.len().div_ceil(8)];
+        for (index, value) in self.data.iter().enumerate() {
+            if value.is_some() {
+                initialized[index / 8] |= 1 << (index % 8);
+            }
+        }
+        writer.write_bytes(&initialized);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        let mut bytes = vec![0u8; self.data.len()];
+        let mut initialized = vec![0u8; self.data.len().div_ceil(8)];
+        reader.read_bytes_into(&mut bytes)?;
+        reader.read_bytes_into(&mut initialized)?;
+        for (index, value) in bytes.into_iter().enumerate() {
+            let is_initialized = initialized[index / 8] & (1 << (index % 8)) != 0;
+            self.data[index] = is_initialized.then_some(value);
+        }
+        Ok(())
+    }
+}
@@ -1,3 +1,5 @@
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
+
 use crate::{Bus, Memory, TileMapArea, ram::Ram};
 
 #[derive(Debug)]
@@ -96,6 +98,25 @@ impl Memory for Vram {
     }
 }
 
+impl Snapshot for Vram {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        for ram in &self.ram {
+            writer.write(ram);
+        }
+        writer.write_u8(self.current_bank as u8);
+        writer.write_bool(self.accessible);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        for ram in &mut self.ram {
+            reader.read(ram)?;
+        }
+        self.current_bank = (reader.read_u8()? & 0x01) as usize;
+        self.accessible = reader.read_bool()?;
+        Ok(())
+    }
+}
+
 impl Default for Vram {
     fn default() -> Self {
         Self::new()
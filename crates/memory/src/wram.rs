@@ -1,3 +1,5 @@
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
+
 use crate::{Bus, Memory, ram::Ram};
 
 #[derive(Debug)]
@@ -57,6 +59,11 @@ impl Wram {
         self.current_bank = bank;
     }
 
+    /// Bank mapped at 0xD000..=0xDFFF.
+    pub fn switchable_bank(&self) -> usize {
+        self.switchable_bank_idx()
+    }
+
     fn switchable_bank_idx(&self) -> usize {
         if self.current_bank == 0 {
             1
@@ -81,6 +88,23 @@ impl Memory for Wram {
     }
 }
 
+impl Snapshot for Wram {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        for ram in &self.ram {
+            writer.write(ram);
+        }
+        writer.write_u8(self.current_bank as u8);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        for ram in &mut self.ram {
+            reader.read(ram)?;
+        }
+        self.current_bank = (reader.read_u8()? & 0x07) as usize;
+        Ok(())
+    }
+}
+
 impl Default for Wram {
     fn default() -> Self {
         Self::new()
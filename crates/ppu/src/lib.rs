@@ -8,9 +8,28 @@ pub use ppu_mode::PpuMode;
 pub struct PpuPlugin;
 
 impl yagber_app::Plugin for PpuPlugin {
-    fn init(self, emulator: &mut yagber_app::Emulator) {
+    fn init(self, emulator: &mut yagber_app::Emulator) -> Result<(), yagber_app::PluginError> {
+        let task = emulator.add_scheduled_task("ppu", Ppu::on_scheduled);
+        let mut ppu = Ppu::new();
+        ppu.set_task(task);
+        let dots = ppu.dots_until_event();
         emulator
-            .with_component(ppu::Ppu::new())
-            .on_dot_cycle(ppu::Ppu::on_dot_cycle);
+            .with_component(ppu)
+            .with_snapshot::<Ppu>("ppu")
+            .on_event(Ppu::on_reset);
+        emulator.schedule(task, yagber_app::Delay::Dots(dots));
+
+        emulator
+            .get_component_mut::<yagber_memory::Bus>()
+            .expect("Bus component missing")
+            .io_registers
+            .with_hook(yagber_memory::IOType::LCDC, Ppu::on_lcdc_write);
+        Ok(())
+    }
+
+    fn dependencies(&self) -> yagber_app::PluginDependencies {
+        yagber_app::PluginDependencies::new()
+            .provides::<Ppu>()
+            .requires::<yagber_memory::Bus>()
     }
 }
@@ -23,6 +23,11 @@ impl Object {
         Self::new(bytes[1], bytes[0], bytes[2], bytes[3])
     }
 
+    /// Inverse of [`Object::from_bytes`].
+    pub fn to_bytes(self) -> [u8; 4] {
+        [self.y, self.x, self.tile_index, self.attr.value()]
+    }
+
     pub fn x(&self) -> u8 {
         self.x
     }
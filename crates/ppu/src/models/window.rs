@@ -1,3 +1,5 @@
+use yagber_app::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
+
 #[derive(Debug, Clone, Copy)]
 pub struct WindowScanLine {
     last_y: Option<u8>,
@@ -18,12 +20,25 @@ impl WindowScanLine {
     }
 
     pub fn get_and_update(&mut self, y: u8) -> u8 {
-        if let Some(last_y) = self.last_y {
-            if last_y != y {
-                self.scan_line += 1;
-            }
+        if let Some(last_y) = self.last_y
+            && last_y != y
+        {
+            self.scan_line += 1;
         }
         self.last_y = Some(y);
         self.scan_line
     }
 }
+
+impl Snapshot for WindowScanLine {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_option_u8(self.last_y);
+        writer.write_u8(self.scan_line);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.last_y = reader.read_option_u8()?;
+        self.scan_line = reader.read_u8()?;
+        Ok(())
+    }
+}
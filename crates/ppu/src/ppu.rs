@@ -1,3 +1,4 @@
+use yagber_app::{Delay, ScheduledTask, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 use yagber_memory::{
     Bus, IOType, LcdcRegister, OpriRegister, SysRegister, TileFetcherMode, TileSize,
 };
@@ -14,6 +15,9 @@ pub struct Ppu {
     frame_buffer: [[u8; 4]; Self::FRAME_BUFFER_SIZE],
     objects: Vec<Object>,
     window_scan_line: WindowScanLine,
+    /// Dot cycle the PPU caught up to.
+    synced_at: u64,
+    task: Option<ScheduledTask>,
 }
 
 impl Ppu {
@@ -29,20 +33,97 @@ impl Ppu {
             frame_buffer: [[0; 4]; Self::FRAME_BUFFER_SIZE],
             objects: Vec::new(),
             window_scan_line: WindowScanLine::new(),
+            synced_at: 0,
+            task: None,
         }
     }
 
-    pub fn on_dot_cycle(emulator: &mut yagber_app::Emulator) {
+    pub(crate) fn set_task(&mut self, task: ScheduledTask) {
+        self.task = Some(task);
+    }
+
+    /// Runs on the dots where the PPU does something visible: mode and scan line changes, and
+    /// every pixel of the pixel transfer. Dots in between are skipped at once.
+    pub(crate) fn on_scheduled(emulator: &mut yagber_app::Emulator) {
+        let now = emulator.get_cycles();
         let (bus, ppu) = emulator
             .get_components_mut2::<Bus, Ppu>()
             .expect("Bus and/or PPU component missing");
+        let task = ppu.task.expect("PPU task not registered");
 
-        if !Ppu::enabled(bus) {
+        // STOP turns the LCD off along with the system clock, check every dot whether it is back
+        if !Ppu::enabled(bus) || bus.stopped() {
+            ppu.synced_at = now;
+            emulator.schedule(task, Delay::Dots(1));
             return;
         }
 
         // Step the PPU even if there's no display, so that the scan line index is updated and interrupt is requested if necessary.
+        ppu.skip(now - ppu.synced_at - 1);
+        ppu.synced_at = now;
         ppu.step(bus);
+        let dots = ppu.dots_until_event();
+
+        let changed_scan_line = ppu.just_changed_scan_line();
+        let entered_vblank = ppu.just_entered_mode(PpuMode::VBlank);
+        // Mode changes are the last thing a step writes, their STAT hooks run right after
+        Bus::run_hooks(emulator);
+        if changed_scan_line {
+            emulator.reached_boundary(yagber_app::StepBoundary::Scanline);
+        }
+        if entered_vblank {
+            emulator.emit(yagber_app::FrameCompletedEvent);
+            emulator.reached_boundary(yagber_app::StepBoundary::Frame);
+        }
+        emulator.schedule(task, Delay::Dots(dots));
+    }
+
+    /// Soft resets cancel the pending PPU run, the PPU catches up on the next dot.
+    pub(crate) fn on_reset(emulator: &mut yagber_app::Emulator, event: &yagber_app::ResetEvent) {
+        if *event == yagber_app::ResetEvent::Finished(yagber_app::ResetKind::Soft) {
+            let task = emulator
+                .get_component::<Ppu>()
+                .and_then(|ppu| ppu.task)
+                .expect("PPU task not registered");
+            emulator.schedule(task, Delay::Dots(1));
+        }
+    }
+
+    /// The LCD freezes once turned off, the dots it ran before the write are caught up first
+    /// and the PPU checks every dot for it to be turned back on.
+    pub(crate) fn on_lcdc_write(emulator: &mut yagber_app::Emulator, _value: u8) {
+        let now = emulator.get_cycles();
+        let (bus, ppu) = emulator
+            .get_components_mut2::<Bus, Ppu>()
+            .expect("Bus and/or PPU component missing");
+        if Ppu::enabled(bus) || ppu.synced_at >= now {
+            return;
+        }
+        ppu.skip(now - ppu.synced_at);
+        ppu.synced_at = now;
+        let task = ppu.task.expect("PPU task not registered");
+        emulator.schedule(task, Delay::Dots(1));
+    }
+
+    /// Dots until the next one the PPU has to step on.
+    pub fn dots_until_event(&self) -> u64 {
+        let x = self.x as u64;
+        match self.y {
+            0..=143 => match x {
+                0..80 => 80 - x,
+                // Pixels are output until x 240
+                80..239 => 1,
+                239..252 => 252 - x,
+                _ => 456 - x,
+            },
+            _ => 456 - x,
+        }
+    }
+
+    /// Advances by `dots` that neither change mode nor output a pixel.
+    fn skip(&mut self, dots: u64) {
+        debug_assert!(dots < self.dots_until_event(), "Skipped over a PPU event");
+        self.x += dots as u16;
     }
 
     pub fn step(&mut self, bus: &mut Bus) {
@@ -353,8 +434,7 @@ impl Ppu {
         let stat = bus.io_registers.read(IOType::STAT.address());
         let new_stat = (stat & !0x03) | mode.to_u8();
         if new_stat != stat {
-            bus.io_registers
-                .write_unchecked(IOType::STAT.address(), new_stat);
+            bus.write_io_unchecked(IOType::STAT.address(), new_stat);
         }
     }
 
@@ -388,6 +468,45 @@ impl Default for Ppu {
 
 impl yagber_app::Component for Ppu {}
 
+impl Snapshot for Ppu {
+    const VERSION: u16 = 2;
+
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_u16(self.x);
+        writer.write_u8(self.y);
+        writer.write_u64(self.synced_at);
+        writer.write_bytes(self.frame_buffer.as_flattened());
+        let objects = self
+            .objects
+            .iter()
+            .flat_map(|object| object.to_bytes())
+            .collect::<Vec<_>>();
+        writer.write_bytes(&objects);
+        writer.write(&self.window_scan_line);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.x = reader.read_u16()?;
+        self.y = reader.read_u8()?;
+        if self.x >= 456 || self.y > 153 {
+            return Err(SnapshotError::InvalidData(format!(
+                "PPU position ({}, {}) out of range",
+                self.x, self.y
+            )));
+        }
+        self.synced_at = reader.read_u64()?;
+        reader.read_bytes_into(self.frame_buffer.as_flattened_mut())?;
+        let objects = reader.read_bytes()?;
+        if !objects.len().is_multiple_of(4) {
+            return Err(SnapshotError::InvalidData(
+                "PPU object list is not a multiple of 4 bytes".to_string(),
+            ));
+        }
+        self.objects = objects.chunks_exact(4).map(Object::from_bytes).collect();
+        reader.read(&mut self.window_scan_line)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -472,4 +591,56 @@ mod test {
             yagber_memory::InterruptType::VBlank.bit()
         ));
     }
+
+    #[test]
+    fn scheduled_ppu_matches_stepping_every_dot() {
+        let mut emulator = yagber_app::Emulator::new()
+            .with_plugin(yagber_memory::MemoryPlugin::default())
+            .with_plugin(crate::PpuPlugin)
+            .build()
+            .unwrap();
+        Bus::write_hooked(&mut emulator, IOType::LCDC.address(), 0x80);
+
+        let mut bus = Bus::new();
+        bus.write(IOType::LCDC.address(), 0x80);
+        let mut ppu = Ppu::new();
+
+        for dot in 0..Ppu::DOTS_PER_FRAME * 2 {
+            emulator.step();
+            ppu.step(&mut bus);
+            let scheduled_bus = emulator.get_component::<Bus>().unwrap();
+            // Only the plugin registers the LYC coincidence hook, STAT is compared on its mode
+            for (io, mask) in [(IOType::LY, 0xFF), (IOType::STAT, 0x03), (IOType::IF, 0xFF)] {
+                assert_eq!(
+                    scheduled_bus.io_registers.read(io.address()) & mask,
+                    bus.io_registers.read(io.address()) & mask,
+                    "{io:?} at dot {dot}"
+                );
+            }
+        }
+        assert_eq!(
+            emulator.get_component::<Ppu>().unwrap().frame_buffer(),
+            ppu.frame_buffer()
+        );
+    }
+
+    #[test]
+    fn keeps_running_after_a_soft_reset() {
+        let mut emulator = yagber_app::Emulator::new()
+            .with_plugin(yagber_memory::MemoryPlugin::default())
+            .with_plugin(crate::PpuPlugin)
+            .build()
+            .unwrap();
+        Bus::write_hooked(&mut emulator, IOType::LCDC.address(), 0x80);
+        for _ in 0..1000 {
+            emulator.step();
+        }
+
+        emulator.reset(yagber_app::ResetKind::Soft);
+        for _ in 0..456 * 10 {
+            emulator.step();
+        }
+        let bus = emulator.get_component::<Bus>().unwrap();
+        assert_eq!(bus.io_registers.read(IOType::LY.address()), 12);
+    }
 }
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use yagber_disasm::BankedAddress;
+
+/// Every call stack seen so far, sharing their common prefixes.
+///
+/// Node 0 is the root, standing for the code running outside of any tracked call.
+#[derive(Debug)]
+pub(crate) struct CallTree {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Node {
+    /// Entry point of the function, `None` for the root.
+    pub(crate) function: Option<BankedAddress>,
+    pub(crate) parent: usize,
+    children: HashMap<BankedAddress, usize>,
+    /// M-cycles spent with this exact stack.
+    pub(crate) cycles: u64,
+    /// Times this stack was entered.
+    pub(crate) calls: u64,
+}
+
+impl CallTree {
+    pub(crate) const ROOT: usize = 0;
+
+    pub(crate) fn new() -> Self {
+        Self {
+            nodes: vec![Node {
+                function: None,
+                parent: Self::ROOT,
+                children: HashMap::new(),
+                cycles: 0,
+                calls: 0,
+            }],
+        }
+    }
+
+    /// The node for `function` called from `parent`, counting the call.
+    pub(crate) fn enter(&mut self, parent: usize, function: BankedAddress) -> usize {
+        let next = self.nodes.len();
+        let index = *self.nodes[parent].children.entry(function).or_insert(next);
+        if index == next {
+            self.nodes.push(Node {
+                function: Some(function),
+                parent,
+                children: HashMap::new(),
+                cycles: 0,
+                calls: 0,
+            });
+        }
+        self.nodes[index].calls += 1;
+        index
+    }
+
+    pub(crate) fn add_cycles(&mut self, node: usize, cycles: u64) {
+        self.nodes[node].cycles += cycles;
+    }
+
+    pub(crate) fn nodes(&self) -> impl Iterator<Item = (usize, &Node)> {
+        self.nodes.iter().enumerate()
+    }
+
+    /// Functions from the outermost call to the node, the root excluded.
+    pub(crate) fn stack(&self, mut node: usize) -> Vec<BankedAddress> {
+        let mut stack = Vec::new();
+        while let Some(function) = self.nodes[node].function {
+            stack.push(function);
+            node = self.nodes[node].parent;
+        }
+        stack.reverse();
+        stack
+    }
+}
@@ -0,0 +1,24 @@
+use yagber_disasm::BankedAddress;
+
+/// M-cycles spent in a function, see [`crate::Profiler::functions`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FunctionProfile {
+    /// Entry point, `None` for the code running outside of any call.
+    pub function: Option<BankedAddress>,
+    /// Cycles spent in the function and the functions it calls.
+    pub inclusive: u64,
+    /// Cycles spent in the function itself.
+    pub exclusive: u64,
+    pub calls: u64,
+}
+
+impl FunctionProfile {
+    pub(crate) fn new(function: Option<BankedAddress>) -> Self {
+        Self {
+            function,
+            inclusive: 0,
+            exclusive: 0,
+            calls: 0,
+        }
+    }
+}
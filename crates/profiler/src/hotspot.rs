@@ -0,0 +1,8 @@
+use yagber_disasm::BankedAddress;
+
+/// M-cycles spent on one instruction, see [`crate::Profiler::hotspots`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hotspot {
+    pub location: BankedAddress,
+    pub cycles: u64,
+}
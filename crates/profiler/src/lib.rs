@@ -0,0 +1,91 @@
+mod call_tree;
+mod function_profile;
+mod hotspot;
+mod profile_failed_event;
+mod profiler;
+
+use std::path::PathBuf;
+
+use yagber_disasm::SymbolTable;
+
+pub use function_profile::FunctionProfile;
+pub use hotspot::Hotspot;
+pub use profile_failed_event::ProfileFailedEvent;
+pub use profiler::Profiler;
+
+use crate::profiler::ProfileOutput;
+
+/// Adds a [`Profiler`], attributing M-cycles to instructions and call stacks.
+pub struct ProfilerPlugin {
+    symbols: SymbolTable,
+    output: ProfileOutput,
+}
+
+impl ProfilerPlugin {
+    /// Rows of each table of the report.
+    pub const DEFAULT_TOP: usize = 20;
+
+    pub fn new() -> Self {
+        Self {
+            symbols: SymbolTable::new(),
+            output: ProfileOutput {
+                top: Self::DEFAULT_TOP,
+                ..ProfileOutput::default()
+            },
+        }
+    }
+
+    /// Names functions and instructions after the labels of an RGBDS symbol file.
+    pub fn with_symbols(mut self, symbols: SymbolTable) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Writes [`Profiler::report`] to `path` once the emulator exits.
+    pub fn with_report(mut self, path: impl Into<PathBuf>) -> Self {
+        self.output.report = Some(path.into());
+        self
+    }
+
+    /// Rows of each table of the report.
+    pub fn with_top(mut self, top: usize) -> Self {
+        self.output.top = top;
+        self
+    }
+
+    /// Writes [`Profiler::write_folded_stacks`] to `path` once the emulator exits.
+    pub fn with_folded_stacks(mut self, path: impl Into<PathBuf>) -> Self {
+        self.output.folded_stacks = Some(path.into());
+        self
+    }
+}
+
+impl Default for ProfilerPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl yagber_app::Plugin for ProfilerPlugin {
+    fn init(self, emulator: &mut yagber_app::Emulator) -> Result<(), yagber_app::PluginError> {
+        let mut profiler = Profiler::new(self.symbols, self.output);
+        if let (Some(cpu), Some(bus)) = (
+            emulator.get_component::<yagber_cpu::Cpu>(),
+            emulator.get_component::<yagber_memory::Bus>(),
+        ) {
+            profiler.begin(cpu, bus);
+        }
+        emulator
+            .with_component(profiler)
+            .on_mcycle(Profiler::on_mcycle)
+            .on_event(Profiler::on_state_transition);
+        Ok(())
+    }
+
+    fn dependencies(&self) -> yagber_app::PluginDependencies {
+        yagber_app::PluginDependencies::new()
+            .provides::<Profiler>()
+            .requires::<yagber_cpu::Cpu>()
+            .requires::<yagber_memory::Bus>()
+    }
+}
@@ -0,0 +1,21 @@
+use std::path::PathBuf;
+
+/// Writing the profile report or the folded stacks failed.
+#[derive(Debug)]
+pub struct ProfileFailedEvent {
+    pub path: PathBuf,
+    pub error: std::io::Error,
+}
+
+impl std::fmt::Display for ProfileFailedEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "cannot write the profile {}: {}",
+            self.path.display(),
+            self.error
+        )
+    }
+}
+
+impl yagber_app::Event for ProfileFailedEvent {}
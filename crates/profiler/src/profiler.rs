@@ -0,0 +1,366 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::Write,
+    path::PathBuf,
+};
+
+use yagber_app::{Emulator, EmulatorState, StateTransitionEvent};
+use yagber_cpu::Cpu;
+use yagber_disasm::{BankedAddress, SymbolTable};
+use yagber_memory::Bus;
+
+use crate::{FunctionProfile, Hotspot, ProfileFailedEvent, call_tree::CallTree};
+
+/// Attributes every M-cycle to the instruction running, and to the stack of calls it
+/// runs in.
+///
+/// Calls are `CALL`, `RST` and interrupt dispatches, a call returns once the stack
+/// pointer moves above the return address it pushed. Cycles spent halted count towards
+/// the instruction after the `HALT`. The report and folded stacks files, when set, are
+/// written when the emulator exits, a failure emits a [`ProfileFailedEvent`].
+#[derive(Debug)]
+pub struct Profiler {
+    symbols: SymbolTable,
+    output: ProfileOutput,
+    instructions: HashMap<BankedAddress, u64>,
+    tree: CallTree,
+    /// Tree node of each active call, with the stack pointer right after it pushed
+    /// its return address.
+    stack: Vec<(usize, u16)>,
+    /// Instruction in progress, with its opcode and the stack pointer before it.
+    current: Option<(BankedAddress, u8, u16)>,
+    /// M-cycles of the instruction in progress.
+    cycles: u64,
+    total: u64,
+}
+
+impl Profiler {
+    pub(crate) fn new(symbols: SymbolTable, output: ProfileOutput) -> Self {
+        Self {
+            symbols,
+            output,
+            instructions: HashMap::new(),
+            tree: CallTree::new(),
+            stack: Vec::new(),
+            current: None,
+            cycles: 0,
+            total: 0,
+        }
+    }
+
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    /// M-cycles profiled so far.
+    pub fn total_cycles(&self) -> u64 {
+        self.total
+    }
+
+    /// The `count` instructions that took the most M-cycles, most first.
+    pub fn hotspots(&self, count: usize) -> Vec<Hotspot> {
+        let mut hotspots = self
+            .instructions
+            .iter()
+            .map(|(location, cycles)| Hotspot {
+                location: *location,
+                cycles: *cycles,
+            })
+            .collect::<Vec<_>>();
+        hotspots.sort_by(|a, b| b.cycles.cmp(&a.cycles).then(a.location.cmp(&b.location)));
+        hotspots.truncate(count);
+        hotspots
+    }
+
+    /// Every function called, by inclusive M-cycles, most first. `None` is the code
+    /// running outside of any call, such as the main loop.
+    pub fn functions(&self) -> Vec<FunctionProfile> {
+        let mut functions = HashMap::<Option<BankedAddress>, FunctionProfile>::new();
+        for (index, node) in self.tree.nodes() {
+            let profile = functions
+                .entry(node.function)
+                .or_insert_with(|| FunctionProfile::new(node.function));
+            profile.exclusive += node.cycles;
+            profile.calls += node.calls;
+
+            // Recursive calls count once towards the inclusive cycles
+            let mut seen = HashSet::new();
+            let stack = self.tree.stack(index);
+            for function in stack.into_iter().map(Some).chain([None]) {
+                if seen.insert(function) {
+                    functions
+                        .entry(function)
+                        .or_insert_with(|| FunctionProfile::new(function))
+                        .inclusive += node.cycles;
+                }
+            }
+        }
+        let mut functions = functions.into_values().collect::<Vec<_>>();
+        functions.sort_by(|a, b| {
+            (b.inclusive, b.exclusive)
+                .cmp(&(a.inclusive, a.exclusive))
+                .then(a.function.cmp(&b.function))
+        });
+        functions
+    }
+
+    /// Name of a location, from the symbol file when it has one.
+    pub fn name(&self, function: Option<BankedAddress>) -> String {
+        match function {
+            Some(location) => self.symbols.name(location),
+            None => "root".to_string(),
+        }
+    }
+
+    /// Writes one `root;caller;callee cycles` line per call stack, as read by
+    /// `flamegraph.pl` and inferno.
+    pub fn write_folded_stacks(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        let mut lines = self
+            .tree
+            .nodes()
+            .filter(|(_, node)| node.cycles > 0)
+            .map(|(index, node)| {
+                let names = std::iter::once(self.name(None))
+                    .chain(
+                        self.tree
+                            .stack(index)
+                            .into_iter()
+                            .map(|function| self.name(Some(function))),
+                    )
+                    .collect::<Vec<_>>();
+                (names.join(";"), node.cycles)
+            })
+            .collect::<Vec<_>>();
+        lines.sort();
+        for (stack, cycles) in lines {
+            writeln!(writer, "{stack} {cycles}")?;
+        }
+        Ok(())
+    }
+
+    /// Total, then the `top` hottest instructions and functions.
+    pub fn report(&self, top: usize) -> String {
+        let total = self.total.max(1) as f64;
+        let percent = |cycles: u64| cycles as f64 * 100.0 / total;
+        let mut report = format!("Total: {} M-cycles\n\nHottest instructions\n", self.total);
+        report.push_str(&format!("{:>12} {:>7}  location\n", "cycles", "%"));
+        for hotspot in self.hotspots(top) {
+            let label = match self.symbols.containing(hotspot.location) {
+                Some(_) => format!(" {}", self.symbols.name(hotspot.location)),
+                None => String::new(),
+            };
+            report.push_str(&format!(
+                "{:>12} {:>6.2}%  {}{label}\n",
+                hotspot.cycles,
+                percent(hotspot.cycles),
+                hotspot.location,
+            ));
+        }
+
+        report.push_str("\nFunctions\n");
+        report.push_str(&format!(
+            "{:>12} {:>7} {:>12} {:>7} {:>9}  function\n",
+            "inclusive", "%", "exclusive", "%", "calls"
+        ));
+        for function in self.functions().into_iter().take(top) {
+            report.push_str(&format!(
+                "{:>12} {:>6.2}% {:>12} {:>6.2}% {:>9}  {}\n",
+                function.inclusive,
+                percent(function.inclusive),
+                function.exclusive,
+                percent(function.exclusive),
+                function.calls,
+                self.name(function.function),
+            ));
+        }
+        report
+    }
+
+    /// Writes the files while every component is still alive.
+    pub(crate) fn on_state_transition(emulator: &mut Emulator, event: &StateTransitionEvent) {
+        if event.to != EmulatorState::Ending {
+            return;
+        }
+        let Some(profiler) = emulator.get_component::<Self>() else {
+            return;
+        };
+        for failure in profiler.write_output() {
+            emulator.emit(failure);
+        }
+    }
+
+    fn write_output(&self) -> Vec<ProfileFailedEvent> {
+        let mut failures = Vec::new();
+        if let Some(path) = &self.output.report
+            && let Err(error) = std::fs::write(path, self.report(self.output.top))
+        {
+            failures.push(ProfileFailedEvent {
+                path: path.clone(),
+                error,
+            });
+        }
+        if let Some(path) = &self.output.folded_stacks {
+            let result = std::fs::File::create(path).and_then(|file| {
+                let mut writer = std::io::BufWriter::new(file);
+                self.write_folded_stacks(&mut writer)?;
+                writer.flush()
+            });
+            if let Err(error) = result {
+                failures.push(ProfileFailedEvent {
+                    path: path.clone(),
+                    error,
+                });
+            }
+        }
+        failures
+    }
+
+    pub(crate) fn on_mcycle(emulator: &mut Emulator) {
+        let (profiler, cpu, bus) = emulator
+            .get_components_mut3::<Self, Cpu, Bus>()
+            .expect("Profiler, Cpu and/or Bus component missing");
+        profiler.cycles += 1;
+        if cpu.at_instruction_boundary() {
+            profiler.instruction_done(cpu, bus);
+        }
+    }
+
+    fn instruction_done(&mut self, cpu: &Cpu, bus: &Bus) {
+        self.tree.add_cycles(self.node(), self.cycles);
+        self.total += self.cycles;
+        let previous = self.current;
+        if let Some((location, _, _)) = previous {
+            *self.instructions.entry(location).or_default() += self.cycles;
+        }
+        self.cycles = 0;
+
+        let (pc, sp) = (cpu.pc(), cpu.sp());
+        while self
+            .stack
+            .last()
+            .is_some_and(|(_, frame_sp)| sp > *frame_sp)
+        {
+            self.stack.pop();
+        }
+        if let Some((from, opcode, previous_sp)) = previous
+            && sp == previous_sp.wrapping_sub(2)
+        {
+            let pushed = u16::from_le_bytes([bus.read(sp), bus.read(sp.wrapping_add(1))]);
+            let called = match opcode {
+                // CALL a16, CALL cc, a16
+                0xCD | 0xC4 | 0xCC | 0xD4 | 0xDC => pushed == from.address.wrapping_add(3),
+                // RST
+                0xC7 | 0xCF | 0xD7 | 0xDF | 0xE7 | 0xEF | 0xF7 | 0xFF => {
+                    pushed == from.address.wrapping_add(1)
+                }
+                _ => false,
+            };
+            let interrupted =
+                pushed == from.address && matches!(pc, 0x40 | 0x48 | 0x50 | 0x58 | 0x60);
+            if called || interrupted {
+                let function = BankedAddress::new(bus.bank(pc), pc);
+                let node = self.tree.enter(self.node(), function);
+                self.stack.push((node, sp));
+            }
+        }
+        self.begin(cpu, bus);
+    }
+
+    /// Starts attributing cycles to the instruction at the program counter.
+    pub(crate) fn begin(&mut self, cpu: &Cpu, bus: &Bus) {
+        let (pc, sp) = (cpu.pc(), cpu.sp());
+        self.current = Some((BankedAddress::new(bus.bank(pc), pc), bus.read(pc), sp));
+    }
+
+    /// Tree node of the innermost active call.
+    fn node(&self) -> usize {
+        self.stack.last().map_or(CallTree::ROOT, |(node, _)| *node)
+    }
+}
+
+/// Files written once profiling ends.
+#[derive(Debug, Default)]
+pub(crate) struct ProfileOutput {
+    pub(crate) report: Option<PathBuf>,
+    pub(crate) folded_stacks: Option<PathBuf>,
+    pub(crate) top: usize,
+}
+
+impl yagber_app::Component for Profiler {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProfilerPlugin;
+
+    /// Runs the CGB boot ROM up to `0085`, right after its `CALL $0275` returns.
+    #[test]
+    fn attributes_cycles_to_calls() {
+        let mut symbols = SymbolTable::new();
+        symbols.insert(BankedAddress::new(0, 0x0275), "ClearVram");
+        let mut emulator = Emulator::new()
+            .with_plugin(yagber_memory::MemoryPlugin::default())
+            .with_plugin(yagber_cpu::CpuPlugin)
+            .with_plugin(ProfilerPlugin::new().with_symbols(symbols))
+            .build()
+            .unwrap();
+        while {
+            let cpu = emulator.get_component::<Cpu>().unwrap();
+            cpu.pc() != 0x0085 || !cpu.at_instruction_boundary()
+        } {
+            emulator.step();
+        }
+
+        let profiler = emulator.get_component::<Profiler>().unwrap();
+        let functions = profiler.functions();
+        assert_eq!(functions[0].function, None);
+        assert_eq!(functions[0].inclusive, profiler.total_cycles());
+        let callee = functions
+            .iter()
+            .find(|function| function.function == Some(BankedAddress::new(0, 0x0275)))
+            .unwrap();
+        assert_eq!(callee.calls, 1);
+        assert!(callee.exclusive > 0 && callee.inclusive >= callee.exclusive);
+        assert_eq!(
+            functions[0].exclusive + functions[1..].iter().map(|f| f.exclusive).sum::<u64>(),
+            profiler.total_cycles()
+        );
+
+        let hotspots = profiler.hotspots(3);
+        assert!(
+            hotspots
+                .windows(2)
+                .all(|pair| pair[0].cycles >= pair[1].cycles)
+        );
+
+        let mut folded = Vec::new();
+        profiler.write_folded_stacks(&mut folded).unwrap();
+        let folded = String::from_utf8(folded).unwrap();
+        assert!(
+            folded
+                .lines()
+                .any(|line| line.starts_with("root;ClearVram "))
+        );
+        assert!(profiler.report(5).contains("ClearVram"));
+    }
+
+    #[test]
+    fn reports_the_files_it_cannot_write() {
+        let path = std::env::temp_dir().join("yagber-missing-directory/profile.txt");
+        let mut emulator = Emulator::new()
+            .with_plugin(yagber_memory::MemoryPlugin::default())
+            .with_plugin(yagber_cpu::CpuPlugin)
+            .with_plugin(ProfilerPlugin::new().with_report(&path))
+            .build()
+            .unwrap();
+        let failures = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = failures.clone();
+        emulator.on_event(move |_, event: &ProfileFailedEvent| {
+            seen.lock().unwrap().push(event.path.clone());
+        });
+        emulator.step();
+        emulator.exit();
+
+        assert_eq!(*failures.lock().unwrap(), [path]);
+    }
+}
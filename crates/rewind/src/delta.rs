@@ -0,0 +1,115 @@
+//! Delta compression of snapshots against a keyframe.
+//!
+//! The target is XORed with the keyframe, so unchanged bytes become zero,
+//! and the result is stored as alternating runs of zeros and literal bytes:
+//! `len, (zero_run, literal_len, literal bytes)*`, all lengths as LEB128 varints.
+
+/// Zero runs shorter than this are kept inside literals, a token costs at least two bytes.
+const MIN_ZERO_RUN: usize = 4;
+
+pub fn encode(keyframe: &[u8], target: &[u8]) -> Vec<u8> {
+    let xor = |index: usize| target[index] ^ keyframe.get(index).copied().unwrap_or(0);
+    // Counts zeros starting at `start`, up to `limit`.
+    let zeros_from = |start: usize, limit: usize| {
+        (start..target.len())
+            .take(limit)
+            .take_while(|&i| xor(i) == 0)
+            .count()
+    };
+
+    let mut delta = Vec::new();
+    write_varint(&mut delta, target.len());
+
+    let mut index = 0;
+    while index < target.len() {
+        let zero_run = zeros_from(index, usize::MAX);
+        index += zero_run;
+
+        let literal_start = index;
+        while index < target.len() {
+            let zeros = zeros_from(index, MIN_ZERO_RUN);
+            if zeros == MIN_ZERO_RUN || index + zeros == target.len() {
+                break;
+            }
+            index += zeros.max(1);
+        }
+
+        write_varint(&mut delta, zero_run);
+        write_varint(&mut delta, index - literal_start);
+        delta.extend((literal_start..index).map(xor));
+    }
+
+    delta
+}
+
+pub fn decode(keyframe: &[u8], delta: &[u8]) -> Vec<u8> {
+    let mut position = 0;
+    let len = read_varint(delta, &mut position);
+    let mut target = (0..len)
+        .map(|index| keyframe.get(index).copied().unwrap_or(0))
+        .collect::<Vec<_>>();
+
+    let mut index = 0;
+    while position < delta.len() {
+        index += read_varint(delta, &mut position);
+        let literal_len = read_varint(delta, &mut position);
+        for byte in &delta[position..position + literal_len] {
+            target[index] ^= byte;
+            index += 1;
+        }
+        position += literal_len;
+    }
+
+    target
+}
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+fn read_varint(buffer: &[u8], position: &mut usize) -> usize {
+    let mut value = 0;
+    let mut shift = 0;
+    loop {
+        let byte = buffer[*position];
+        *position += 1;
+        value |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return value;
+        }
+        shift += 7;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let keyframe = (0..1000).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let mut target = keyframe.clone();
+        target[3] = 0xAA;
+        target[500..520].fill(0x55);
+        target[999] = 0;
+
+        let delta = encode(&keyframe, &target);
+        assert!(delta.len() < 64);
+        assert_eq!(decode(&keyframe, &delta), target);
+    }
+
+    #[test]
+    fn test_round_trip_different_lengths() {
+        let keyframe = vec![1, 2, 3, 4, 5, 6];
+        let longer = vec![1, 2, 3, 4, 5, 6, 7, 8, 0, 0];
+        let shorter = vec![1, 9, 3];
+
+        assert_eq!(decode(&keyframe, &encode(&keyframe, &longer)), longer);
+        assert_eq!(decode(&keyframe, &encode(&keyframe, &shorter)), shorter);
+        assert_eq!(decode(&keyframe, &encode(&keyframe, &keyframe)), keyframe);
+    }
+}
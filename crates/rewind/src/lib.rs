@@ -0,0 +1,70 @@
+mod delta;
+mod rewind;
+mod rewind_buffer;
+
+pub use rewind::Rewind;
+pub use rewind_buffer::RewindBuffer;
+
+/// Captures a snapshot every few frames so the emulator can be rewound
+/// with [`yagber_app::EmulationControlEvent::Rewind`].
+pub struct RewindPlugin {
+    frames_per_snapshot: u32,
+    keyframe_interval: usize,
+    memory_budget: usize,
+}
+
+impl RewindPlugin {
+    const DEFAULT_FRAMES_PER_SNAPSHOT: u32 = 2;
+    const DEFAULT_KEYFRAME_INTERVAL: usize = 30;
+    const DEFAULT_MEMORY_BUDGET: usize = 64 * 1024 * 1024;
+
+    pub fn new() -> Self {
+        Self {
+            frames_per_snapshot: Self::DEFAULT_FRAMES_PER_SNAPSHOT,
+            keyframe_interval: Self::DEFAULT_KEYFRAME_INTERVAL,
+            memory_budget: Self::DEFAULT_MEMORY_BUDGET,
+        }
+    }
+
+    /// Number of frames between two snapshots, also the distance covered by a single rewind step.
+    pub fn with_frames_per_snapshot(mut self, frames: u32) -> Self {
+        self.frames_per_snapshot = frames.max(1);
+        self
+    }
+
+    /// Number of snapshots stored per full keyframe, the others are stored as deltas.
+    pub fn with_keyframe_interval(mut self, snapshots: usize) -> Self {
+        self.keyframe_interval = snapshots.max(1);
+        self
+    }
+
+    /// Maximum number of bytes held by the rewind buffer.
+    pub fn with_memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = bytes;
+        self
+    }
+}
+
+impl Default for RewindPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl yagber_app::Plugin for RewindPlugin {
+    fn init(self, emulator: &mut yagber_app::Emulator) -> Result<(), yagber_app::PluginError> {
+        let buffer = RewindBuffer::new(self.memory_budget, self.keyframe_interval);
+        emulator
+            .with_component(Rewind::new(buffer, self.frames_per_snapshot))
+            .on_event(Rewind::on_frame_completed)
+            .on_fixed_step(Rewind::on_fixed_step)
+            .on_event(Rewind::on_reset);
+        Ok(())
+    }
+
+    fn dependencies(&self) -> yagber_app::PluginDependencies {
+        yagber_app::PluginDependencies::new()
+            .provides::<Rewind>()
+            .requires::<yagber_ppu::Ppu>()
+    }
+}
@@ -0,0 +1,133 @@
+use crate::RewindBuffer;
+
+pub struct Rewind {
+    buffer: RewindBuffer,
+    frames_per_snapshot: u32,
+    frames_since_snapshot: u32,
+}
+
+impl Rewind {
+    pub fn new(buffer: RewindBuffer, frames_per_snapshot: u32) -> Self {
+        Self {
+            buffer,
+            frames_per_snapshot,
+            frames_since_snapshot: 0,
+        }
+    }
+
+    pub fn buffer(&self) -> &RewindBuffer {
+        &self.buffer
+    }
+
+    /// History from before a reset is dropped, rewinding does not cross resets.
+    pub(crate) fn on_reset(emulator: &mut yagber_app::Emulator, event: &yagber_app::ResetEvent) {
+        if let yagber_app::ResetEvent::Finished(_) = event {
+            let rewind = emulator
+                .get_component_mut::<Self>()
+                .expect("Rewind component missing");
+            rewind.buffer.clear();
+            rewind.frames_since_snapshot = 0;
+        }
+    }
+
+    /// Snapshots are taken on completed frames, when the PPU enters VBlank,
+    /// so every rewind step lands on a complete frame.
+    /// Frames reported by the rewind itself are not captured again.
+    pub(crate) fn on_frame_completed(
+        emulator: &mut yagber_app::Emulator,
+        _event: &yagber_app::FrameCompletedEvent,
+    ) {
+        if emulator.is_rewinding() {
+            return;
+        }
+
+        let rewind = emulator
+            .get_component_mut::<Self>()
+            .expect("Rewind component missing");
+        rewind.frames_since_snapshot += 1;
+        if rewind.frames_since_snapshot < rewind.frames_per_snapshot {
+            return;
+        }
+        rewind.frames_since_snapshot = 0;
+
+        let state = emulator.save_state();
+        emulator
+            .get_component_mut::<Self>()
+            .expect("Rewind component missing")
+            .buffer
+            .push(state);
+    }
+
+    /// Restores one snapshot per fixed step while rewinding.
+    /// Snapshots are taken on VBlank, so a frame is reported as completed after each restore.
+    pub(crate) fn on_fixed_step(emulator: &mut yagber_app::Emulator) {
+        if !emulator.is_rewinding() {
+            return;
+        }
+
+        let rewind = emulator
+            .get_component_mut::<Self>()
+            .expect("Rewind component missing");
+        rewind.frames_since_snapshot = 0;
+        let Some(state) = rewind.buffer.pop() else {
+            return;
+        };
+
+        // The emulator is rolled back on error, older snapshots are dropped along with the
+        // failed one since they were taken by the same components.
+        if let Err(_error) = emulator.load_state(&state) {
+            #[cfg(feature = "trace")]
+            tracing::error!("Failed to restore rewind snapshot: {_error}");
+            emulator
+                .get_component_mut::<Self>()
+                .expect("Rewind component missing")
+                .buffer
+                .clear();
+            return;
+        }
+        emulator.emit(yagber_app::FrameCompletedEvent);
+    }
+}
+
+impl yagber_app::Component for Rewind {}
+
+#[cfg(test)]
+mod tests {
+    use yagber_app::{EmulationControlEvent, Emulator, FrameCompletedEvent};
+
+    use super::*;
+
+    fn emulator() -> Emulator {
+        let mut emulator = Emulator::new();
+        emulator
+            .with_component(Rewind::new(RewindBuffer::new(usize::MAX, 4), 2))
+            .on_event(Rewind::on_frame_completed)
+            .on_fixed_step(Rewind::on_fixed_step);
+        emulator
+    }
+
+    fn snapshots(emulator: &Emulator) -> usize {
+        emulator.get_component::<Rewind>().unwrap().buffer().len()
+    }
+
+    #[test]
+    fn test_captures_every_few_frames() {
+        let mut emulator = emulator();
+        for _ in 0..5 {
+            emulator.emit(FrameCompletedEvent);
+            emulator.flush_events();
+        }
+        assert_eq!(snapshots(&emulator), 2);
+    }
+
+    #[test]
+    fn test_drops_history_that_cannot_be_restored() {
+        let mut emulator = emulator();
+        let rewind = emulator.get_component_mut::<Rewind>().unwrap();
+        rewind.buffer.push(b"not a state".to_vec());
+        emulator.handle_control_event(EmulationControlEvent::Rewind(true));
+
+        Rewind::on_fixed_step(&mut emulator);
+        assert_eq!(snapshots(&emulator), 0);
+    }
+}
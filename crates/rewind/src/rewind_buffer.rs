@@ -0,0 +1,134 @@
+use std::collections::VecDeque;
+
+use crate::delta;
+
+/// A keyframe and the snapshots taken after it, stored as deltas against it.
+struct Group {
+    keyframe: Vec<u8>,
+    deltas: Vec<Vec<u8>>,
+}
+
+impl Group {
+    fn size(&self) -> usize {
+        self.keyframe.len() + self.deltas.iter().map(Vec::len).sum::<usize>()
+    }
+}
+
+/// Bounded ring buffer of emulator snapshots.
+///
+/// When the memory budget is exceeded the oldest group is dropped,
+/// the newest group is always kept so rewinding has somewhere to go.
+pub struct RewindBuffer {
+    groups: VecDeque<Group>,
+    memory_budget: usize,
+    keyframe_interval: usize,
+    size: usize,
+}
+
+impl RewindBuffer {
+    pub fn new(memory_budget: usize, keyframe_interval: usize) -> Self {
+        Self {
+            groups: VecDeque::new(),
+            memory_budget,
+            keyframe_interval: keyframe_interval.max(1),
+            size: 0,
+        }
+    }
+
+    pub fn push(&mut self, state: Vec<u8>) {
+        match self.groups.back_mut() {
+            Some(group) if group.deltas.len() + 1 < self.keyframe_interval => {
+                let delta = delta::encode(&group.keyframe, &state);
+                self.size += delta.len();
+                group.deltas.push(delta);
+            }
+            _ => {
+                self.size += state.len();
+                self.groups.push_back(Group {
+                    keyframe: state,
+                    deltas: Vec::new(),
+                });
+            }
+        }
+
+        while self.size > self.memory_budget && self.groups.len() > 1 {
+            let group = self.groups.pop_front().expect("Group count checked above");
+            self.size -= group.size();
+        }
+    }
+
+    /// Removes and returns the newest snapshot.
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        let group = self.groups.back_mut()?;
+        if let Some(delta) = group.deltas.pop() {
+            self.size -= delta.len();
+            return Some(delta::decode(&group.keyframe, &delta));
+        }
+
+        let group = self.groups.pop_back()?;
+        self.size -= group.keyframe.len();
+        Some(group.keyframe)
+    }
+
+    /// Number of snapshots held.
+    pub fn len(&self) -> usize {
+        self.groups.iter().map(|group| group.deltas.len() + 1).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Number of bytes held.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn clear(&mut self) {
+        self.groups.clear();
+        self.size = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(value: u8) -> Vec<u8> {
+        let mut state = vec![0; 256];
+        state[value as usize] = value;
+        state
+    }
+
+    #[test]
+    fn test_pop_returns_newest_first() {
+        let mut buffer = RewindBuffer::new(usize::MAX, 3);
+        for value in 1..=7 {
+            buffer.push(state(value));
+        }
+        assert_eq!(buffer.len(), 7);
+
+        for value in (1..=7).rev() {
+            assert_eq!(buffer.pop(), Some(state(value)));
+        }
+        assert_eq!(buffer.pop(), None);
+        assert_eq!(buffer.size(), 0);
+    }
+
+    #[test]
+    fn test_evicts_oldest_group_over_budget() {
+        // Each keyframe is 256 bytes, only two groups fit.
+        let mut buffer = RewindBuffer::new(600, 2);
+        for value in 1..=6 {
+            buffer.push(state(value));
+        }
+        assert!(buffer.size() <= 600);
+        assert_eq!(buffer.len(), 4);
+
+        let mut oldest = None;
+        while let Some(state) = buffer.pop() {
+            oldest = Some(state);
+        }
+        assert_eq!(oldest, Some(state(3)));
+    }
+}
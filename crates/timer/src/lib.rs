@@ -5,17 +5,28 @@ pub use timer::Timer;
 pub struct TimerPlugin;
 
 impl yagber_app::Plugin for TimerPlugin {
-    fn init(self, emulator: &mut yagber_app::Emulator) {
+    fn init(self, emulator: &mut yagber_app::Emulator) -> Result<(), yagber_app::PluginError> {
+        let task = emulator.add_scheduled_task("timer", Timer::on_scheduled);
+        let mut timer = Timer::new();
+        timer.set_task(task);
         emulator
-            .with_component(Timer::new())
-            .on_mcycle(Timer::on_mcycle);
-
-        let timer_div_hook = emulator.attach_component(timer::Timer::on_div_write);
+            .with_component(timer)
+            .with_snapshot::<Timer>("timer")
+            .on_event(Timer::on_reset);
+        emulator.schedule(task, yagber_app::Delay::MCycles(1));
 
         emulator
             .get_component_mut::<yagber_memory::Bus>()
             .expect("Bus component missing")
             .io_registers
-            .add_hook(yagber_memory::IOType::DIV, timer_div_hook);
+            .with_hook(yagber_memory::IOType::DIV, Timer::on_div_write)
+            .with_hook(yagber_memory::IOType::TAC, Timer::on_tac_write);
+        Ok(())
+    }
+
+    fn dependencies(&self) -> yagber_app::PluginDependencies {
+        yagber_app::PluginDependencies::new()
+            .provides::<Timer>()
+            .requires::<yagber_memory::Bus>()
     }
 }
@@ -1,5 +1,8 @@
-use yagber_app::{EdgeDetector, EdgeMode};
-use yagber_memory::{Bus, IOType, InterruptType};
+use yagber_app::{
+    Delay, EdgeDetector, EdgeMode, ScheduledTask, Snapshot, SnapshotError, SnapshotReader,
+    SnapshotWriter, SpeedMode,
+};
+use yagber_memory::{Bus, IOType, InterruptType, Spd, TacRegister};
 
 /// System Counter is a 16.777216 MHz clock.
 /// Incremented every M-Cycle.
@@ -18,11 +21,32 @@ impl SystemCounter {
         }
     }
 
+    const MASK: u16 = (1 << 14) - 1;
+
     pub fn tick(&mut self) {
-        self.m_cycles += 1;
-        if self.m_cycles >= (1 << 14) {
-            self.m_cycles = 0;
+        self.skip(1);
+    }
+
+    /// Advances by `m_cycles` during which TIMA does not increment.
+    fn skip(&mut self, m_cycles: u16) {
+        self.m_cycles = self.m_cycles.wrapping_add(m_cycles) & Self::MASK;
+    }
+
+    /// Syncs the edge detector with the counter, as ticking without an edge would.
+    fn skip_edge(&mut self, tac_clock: yagber_memory::TacClock) {
+        let bit_value = (self.m_cycles & tac_clock.div_mask()) != 0;
+        self.tac_edge_detector.set_value(bit_value);
+    }
+
+    /// M-cycles until the next falling edge of the TAC selected bit, or a single one if the
+    /// detector is out of sync with the counter after a TAC or DIV write.
+    fn m_cycles_until_edge(&self, tac_clock: yagber_memory::TacClock) -> u16 {
+        let bit_value = (self.m_cycles & tac_clock.div_mask()) != 0;
+        if self.tac_edge_detector.value() != bit_value {
+            return 1;
         }
+        let period = tac_clock.div_mask() << 1;
+        period - (self.m_cycles & (period - 1))
     }
 
     /// Div is the visible part of the system counter.
@@ -55,16 +79,41 @@ impl SystemCounter {
     }
 }
 
+impl Snapshot for SystemCounter {
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write_u16(self.m_cycles);
+        writer.write(&self.tac_edge_detector);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.m_cycles = reader.read_u16()? & Self::MASK;
+        reader.read(&mut self.tac_edge_detector)
+    }
+}
+
 impl Default for SystemCounter {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// DIV, TIMA and the DIV-APU clock.
+///
+/// The timer only runs on M-cycles where something visible happens: DIV changing, TIMA
+/// incrementing or reloading. M-cycles in between are skipped at once, DIV and TAC writes
+/// catch the timer up before they apply.
 #[derive(Debug, Default, Clone, Copy)]
 pub struct Timer {
     system_counter: SystemCounter,
     tima_overflow: bool,
+    /// TAC the timer counts with, writes apply once the timer caught up.
+    tac: u8,
+    /// M-cycle the timer caught up to.
+    synced_at: u64,
+    /// M-cycle at which DIV-APU steps, set when its DIV bit falls.
+    div_apu_due: Option<u64>,
+    task: Option<ScheduledTask>,
+    div_apu_task: Option<ScheduledTask>,
 }
 
 impl Timer {
@@ -72,24 +121,142 @@ impl Timer {
         Self {
             system_counter: SystemCounter::new(),
             tima_overflow: false,
+            tac: 0,
+            synced_at: 0,
+            div_apu_due: None,
+            task: None,
+            div_apu_task: None,
+        }
+    }
+
+    pub(crate) fn set_task(&mut self, task: ScheduledTask) {
+        self.task = Some(task);
+    }
+
+    /// Task the timer schedules on every DIV-APU clock, when DIV bit 4 (bit 5 in double speed)
+    /// falls.
+    pub fn set_div_apu_task(&mut self, task: ScheduledTask) {
+        self.div_apu_task = Some(task);
+    }
+
+    pub(crate) fn on_scheduled(emulator: &mut yagber_app::Emulator) {
+        let now = emulator.completed_mcycles();
+        let (timer, bus) = emulator
+            .get_components_mut2::<Timer, Bus>()
+            .expect("Timer and/or Bus component missing");
+
+        // DIV and TIMA do not count while the system clock is stopped
+        if bus.stopped() {
+            timer.synced_at = now;
+        } else {
+            timer.sync(bus, now);
+        }
+        Self::schedule(emulator);
+    }
+
+    /// Soft resets cancel the pending timer run, the timer keeps counting from where it is.
+    pub(crate) fn on_reset(emulator: &mut yagber_app::Emulator, event: &yagber_app::ResetEvent) {
+        if *event == yagber_app::ResetEvent::Finished(yagber_app::ResetKind::Soft) {
+            Self::schedule(emulator);
+        }
+    }
+
+    pub(crate) fn on_div_write(emulator: &mut yagber_app::Emulator, _value: u8) {
+        let now = emulator.completed_mcycles();
+        let (timer, bus) = emulator
+            .get_components_mut2::<Timer, Bus>()
+            .expect("Timer and/or Bus component missing");
+        timer.sync(bus, now);
+        let div_apu_mask = Self::div_apu_mask(bus);
+        if timer.system_counter.div() & div_apu_mask != 0 {
+            timer.div_apu_due = Some(now + 1);
         }
+        timer.reset();
+        timer.write_div(bus, 0);
+        Self::schedule(emulator);
     }
 
-    pub fn on_mcycle(emulator: &mut yagber_app::Emulator) {
+    pub(crate) fn on_tac_write(emulator: &mut yagber_app::Emulator, value: u8) {
+        let now = emulator.completed_mcycles();
         let (timer, bus) = emulator
             .get_components_mut2::<Timer, Bus>()
             .expect("Timer and/or Bus component missing");
+        timer.sync(bus, now);
+        timer.tac = value;
+        Self::schedule(emulator);
+    }
 
-        timer.tick(bus);
+    /// Schedules the next M-cycle the timer has to run on, and DIV-APU if its clock ticked.
+    pub(crate) fn schedule(emulator: &mut yagber_app::Emulator) {
+        let now = emulator.get_mcycles();
+        let (timer, bus) = emulator
+            .get_components_mut2::<Timer, Bus>()
+            .expect("Timer and/or Bus component missing");
+        // Stopped, the timer checks every M-cycle whether the clock is back
+        let next = if bus.stopped() {
+            timer.synced_at + 1
+        } else {
+            timer.synced_at + timer.m_cycles_until_event() as u64
+        };
+        let div_apu = timer.div_apu_due.take().zip(timer.div_apu_task);
+        let task = timer.task.expect("Timer task not registered");
+
+        emulator.schedule(task, Delay::MCycles(next - now));
+        if let Some((due, div_apu_task)) = div_apu {
+            emulator.schedule(div_apu_task, Delay::MCycles(due - now));
+        }
+    }
+
+    /// Runs every M-cycle up to and including `m_cycle`.
+    fn sync(&mut self, bus: &mut Bus, m_cycle: u64) {
+        while self.synced_at < m_cycle {
+            let m_cycles = self
+                .m_cycles_until_event()
+                .min((m_cycle - self.synced_at).min(u16::MAX as u64) as u16);
+            self.skip(m_cycles - 1);
+            self.synced_at += m_cycles as u64;
+            self.tick(bus);
+        }
+    }
+
+    /// M-cycles until the next one changing DIV, TIMA or IF.
+    fn m_cycles_until_event(&self) -> u16 {
+        if self.tima_overflow {
+            return 1;
+        }
+        let div_change = 64 - (self.system_counter.m_cycles & 63);
+        let tac = TacRegister::new(self.tac);
+        if tac.enabled() {
+            div_change.min(self.system_counter.m_cycles_until_edge(tac.clock_select()))
+        } else {
+            div_change
+        }
+    }
+
+    /// Advances by `m_cycles` that change neither DIV nor TIMA.
+    fn skip(&mut self, m_cycles: u16) {
+        if m_cycles == 0 {
+            return;
+        }
+        self.system_counter.skip(m_cycles);
+        let tac = TacRegister::new(self.tac);
+        if tac.enabled() {
+            self.system_counter.skip_edge(tac.clock_select());
+        }
     }
 
     /// Tick the timer.
     /// Represents a single M-Cycle.
     /// Meant to be called after executing the instruction.
     pub fn tick(&mut self, bus: &mut Bus) {
+        let div_apu_mask = Self::div_apu_mask(bus);
+        let old_div = self.system_counter.div();
         self.system_counter.tick();
         let div = self.system_counter.div();
         self.write_div(bus, div);
+        if old_div & div_apu_mask != 0 && div & div_apu_mask == 0 {
+            self.div_apu_due = Some(self.synced_at + 1);
+        }
 
         let tima = self.read_tima(bus);
         if self.tima_overflow {
@@ -99,7 +266,7 @@ impl Timer {
             self.tima_overflow = false;
         }
 
-        let tac = yagber_memory::TacRegister::from_bus(bus);
+        let tac = TacRegister::new(self.tac);
         let tac_clock = tac.clock_select();
         if tac.enabled() && self.system_counter.tima_should_increment(tac_clock) {
             let tima = tima.checked_add(1).unwrap_or_else(|| {
@@ -111,6 +278,14 @@ impl Timer {
         }
     }
 
+    /// DIV-APU runs at 512Hz in both speeds.
+    fn div_apu_mask(bus: &Bus) -> u8 {
+        match Spd::from_bus(bus).speed_mode() {
+            SpeedMode::Single => 1 << 4,
+            SpeedMode::Double => 1 << 5,
+        }
+    }
+
     fn read_tima(&self, bus: &Bus) -> u8 {
         bus.io_registers.read(IOType::TIMA.address())
     }
@@ -120,7 +295,7 @@ impl Timer {
     }
 
     fn write_tima(&mut self, bus: &mut Bus, value: u8) {
-        bus.io_registers.write(IOType::TIMA.address(), value);
+        bus.write(IOType::TIMA.address(), value);
     }
 
     fn write_div(&mut self, bus: &mut Bus, value: u8) {
@@ -136,19 +311,16 @@ impl Timer {
         self.tima_overflow = false;
     }
 
-    pub(crate) fn on_div_write(&mut self, _value: u8) {
-        self.reset();
-    }
-
     #[cfg(test)]
     pub(crate) fn from_cycles(cycles: u16, tac: u8) -> Self {
-        let tac_clock = yagber_memory::TacRegister::new(tac).clock_select();
+        let tac_clock = TacRegister::new(tac).clock_select();
         let mut system_counter = SystemCounter::from_cycles(cycles);
         // Tick the system counter to set the edge detector to the correct value
         system_counter.tima_should_increment(tac_clock);
         Self {
             system_counter,
-            tima_overflow: false,
+            tac,
+            ..Self::new()
         }
     }
 
@@ -160,6 +332,25 @@ impl Timer {
 
 impl yagber_app::Component for Timer {}
 
+impl Snapshot for Timer {
+    const VERSION: u16 = 2;
+
+    fn save(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.system_counter);
+        writer.write_bool(self.tima_overflow);
+        writer.write_u8(self.tac);
+        writer.write_u64(self.synced_at);
+    }
+
+    fn load(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        reader.read(&mut self.system_counter)?;
+        self.tima_overflow = reader.read_bool()?;
+        self.tac = reader.read_u8()?;
+        self.synced_at = reader.read_u64()?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -170,10 +361,10 @@ mod tests {
         let mut timer = Timer::from_cycles(0x2B, tac);
         let mut bus = Bus::default();
 
-        bus.io_registers.write(IOType::TIMA.address(), 0xFE);
-        bus.io_registers.write(IOType::TMA.address(), 0x23);
-        bus.io_registers.write(IOType::TAC.address(), tac);
-        bus.io_registers.write(IOType::IF.address(), 0xE0); // IF
+        bus.write(IOType::TIMA.address(), 0xFE);
+        bus.write(IOType::TMA.address(), 0x23);
+        bus.write(IOType::TAC.address(), tac);
+        bus.write(IOType::IF.address(), 0xE0); // IF
 
         assert_eq!(timer.cycles(), 0x2B);
         assert_eq!(timer.read_tima(&bus), 0xFE);
@@ -226,4 +417,35 @@ mod tests {
         assert_eq!(bus.io_registers.read(IOType::IF.address()), 0xE4);
         assert_eq!(timer.read_tma(&bus), 0x23)
     }
+
+    #[test]
+    fn skipping_quiet_mcycles_matches_ticking_every_mcycle() {
+        for tac in [0xF8, 0xFC, 0xFD, 0xFE, 0xFF] {
+            let mut ticked = Timer::from_cycles(0x3FF0, tac);
+            let mut synced = Timer::from_cycles(0x3FF0, tac);
+            let mut ticked_bus = Bus::default();
+            let mut synced_bus = Bus::default();
+            for bus in [&mut ticked_bus, &mut synced_bus] {
+                bus.write(IOType::TIMA.address(), 0xF0);
+                bus.write(IOType::TMA.address(), 0x80);
+                bus.write(IOType::TAC.address(), tac);
+            }
+
+            for m_cycle in 1..=5000 {
+                ticked.tick(&mut ticked_bus);
+                // Catch up at irregular intervals
+                if m_cycle % 7 == 0 || m_cycle % 97 == 0 {
+                    synced.sync(&mut synced_bus, m_cycle);
+                    assert_eq!(synced.cycles(), ticked.cycles(), "TAC {tac:02X}");
+                    for io in [IOType::DIV, IOType::TIMA, IOType::IF] {
+                        assert_eq!(
+                            synced_bus.io_registers.read(io.address()),
+                            ticked_bus.io_registers.read(io.address()),
+                            "{io:?} with TAC {tac:02X} at M-cycle {m_cycle}"
+                        );
+                    }
+                }
+            }
+        }
+    }
 }
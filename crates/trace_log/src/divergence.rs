@@ -0,0 +1,105 @@
+use std::io::BufRead;
+
+/// First line where two trace logs differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// Line number, counting from 1.
+    pub line: usize,
+    /// Last line both logs agree on, the instruction that ran differently.
+    pub previous: Option<String>,
+    /// `None` when the log ends before the reference.
+    pub actual: Option<String>,
+    /// `None` when the reference ends before the log.
+    pub expected: Option<String>,
+}
+
+impl Divergence {
+    /// Names of the `NAME:VALUE` fields that differ, such as `F` or `PC`.
+    pub fn fields(&self) -> Vec<&str> {
+        let (Some(actual), Some(expected)) = (&self.actual, &self.expected) else {
+            return Vec::new();
+        };
+        let mut expected = expected.split_whitespace();
+        actual
+            .split_whitespace()
+            .zip(expected.by_ref())
+            .filter(|(actual, expected)| actual != expected)
+            .map(|(actual, _)| actual.split_once(':').map_or(actual, |(name, _)| name))
+            .collect()
+    }
+}
+
+impl std::fmt::Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fields = self.fields();
+        if fields.is_empty() {
+            writeln!(f, "line {} differs", self.line)?;
+        } else {
+            writeln!(f, "line {} differs in {}", self.line, fields.join(", "))?;
+        }
+        if let Some(previous) = &self.previous {
+            writeln!(f, "  after:    {previous}")?;
+        }
+        let or_end = |line: &Option<String>| line.clone().unwrap_or_else(|| "end of log".into());
+        writeln!(f, "  expected: {}", or_end(&self.expected))?;
+        write!(f, "  actual:   {}", or_end(&self.actual))
+    }
+}
+
+/// Compares a trace log with a reference log line by line, ignoring line endings and
+/// surrounding whitespace.
+pub fn first_divergence(
+    actual: impl BufRead,
+    reference: impl BufRead,
+) -> std::io::Result<Option<Divergence>> {
+    let mut actual = actual.lines();
+    let mut reference = reference.lines();
+    let mut previous = None;
+    let mut line = 0;
+    loop {
+        line += 1;
+        let actual = actual.next().transpose()?;
+        let expected = reference.next().transpose()?;
+        let trim = |text: Option<String>| text.map(|text| text.trim().to_string());
+        let (actual, expected) = (trim(actual), trim(expected));
+        match (actual, expected) {
+            (None, None) => return Ok(None),
+            (Some(actual), Some(expected)) if actual == expected => previous = Some(actual),
+            (actual, expected) => {
+                return Ok(Some(Divergence {
+                    line,
+                    previous,
+                    actual,
+                    expected,
+                }));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_the_first_different_line() {
+        let reference = "A:01 F:B0 PC:0100\nA:01 F:B0 PC:0101\nA:02 F:00 PC:0103\n";
+        let actual = "A:01 F:B0 PC:0100\r\nA:01 F:B0 PC:0101\r\nA:02 F:80 PC:0103\r\n";
+        let divergence = first_divergence(actual.as_bytes(), reference.as_bytes())
+            .unwrap()
+            .unwrap();
+        assert_eq!(divergence.line, 3);
+        assert_eq!(divergence.previous.as_deref(), Some("A:01 F:B0 PC:0101"));
+        assert_eq!(divergence.fields(), ["F"]);
+
+        let divergence = first_divergence(&actual.as_bytes()[..38], reference.as_bytes())
+            .unwrap()
+            .unwrap();
+        assert_eq!((divergence.line, divergence.actual), (3, None));
+
+        assert_eq!(
+            first_divergence(reference.as_bytes(), reference.as_bytes()).unwrap(),
+            None
+        );
+    }
+}
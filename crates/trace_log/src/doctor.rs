@@ -0,0 +1,42 @@
+use yagber_app::Emulator;
+use yagber_cpu::Cpu;
+use yagber_memory::{Bus, IOType};
+
+/// The IO registers the DMG boot ROM leaves behind, DIV and DMA aside since writing them
+/// resets the divider and starts a transfer.
+const POST_BOOT_IO: [(IOType, u8); 8] = [
+    (IOType::TAC, 0xF8),
+    (IOType::IF, 0xE1),
+    (IOType::AUDENA, 0xF1),
+    (IOType::AUDVOL, 0x77),
+    (IOType::AUDTERM, 0xF3),
+    (IOType::LCDC, 0x91),
+    (IOType::BGP, 0xFC),
+    // Unmaps the boot ROM
+    (IOType::BANK, 0x01),
+];
+
+/// Skips the boot ROM, starting at `0100` with the DMG post-boot state Gameboy Doctor
+/// expects, and makes LY read `$90` as its reference logs do.
+pub(crate) fn load_post_boot_state(emulator: &mut Emulator) {
+    // The hooks keep the timer and the PPU in step with their registers
+    for (io, value) in POST_BOOT_IO {
+        Bus::write_hooked(emulator, io.address(), value);
+    }
+    emulator
+        .get_component_mut::<Bus>()
+        .expect("Bus component missing")
+        .io_registers
+        .with_reader(IOType::LY, |_, _| 0x90);
+
+    let cpu = emulator
+        .get_component_mut::<Cpu>()
+        .expect("Cpu component missing");
+    let registers = cpu.registers_mut();
+    registers.set_af(0x01B0);
+    registers.set_bc(0x0013);
+    registers.set_de(0x00D8);
+    registers.set_hl(0x014D);
+    cpu.set_sp(0xFFFE);
+    cpu.set_pc(0x0100);
+}
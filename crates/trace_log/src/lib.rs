@@ -0,0 +1,84 @@
+mod divergence;
+mod doctor;
+mod trace_log;
+mod trace_log_failed_event;
+
+use std::{fs::File, io::BufWriter, io::Write, path::Path};
+
+pub use divergence::{Divergence, first_divergence};
+pub use trace_log::TraceLog;
+pub use trace_log_failed_event::TraceLogFailedEvent;
+
+/// Adds a [`TraceLog`], logging every instruction in the Gameboy Doctor format.
+///
+/// Gameboy Doctor's reference logs start at `0100` with the registers the DMG boot ROM
+/// leaves, and read `$90` from LY, see [`TraceLogPlugin::with_doctor_mode`] to match them.
+/// A failed write emits a [`TraceLogFailedEvent`].
+pub struct TraceLogPlugin {
+    writer: Box<dyn Write + Send>,
+    after_boot: bool,
+    doctor: bool,
+}
+
+impl TraceLogPlugin {
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            writer: Box::new(writer),
+            after_boot: false,
+            doctor: false,
+        }
+    }
+
+    /// Logs to a new file, replacing any existing one.
+    pub fn create(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+
+    /// Starts logging once the boot ROM unmaps itself, at `0100`.
+    pub fn with_after_boot(mut self) -> Self {
+        self.after_boot = true;
+        self
+    }
+
+    /// Skips the boot ROM, starting at `0100` with the DMG post-boot registers, and makes
+    /// LY read `$90`, so the log lines up with Gameboy Doctor's reference logs.
+    pub fn with_doctor_mode(mut self) -> Self {
+        self.after_boot = true;
+        self.doctor = true;
+        self
+    }
+}
+
+impl yagber_app::Plugin for TraceLogPlugin {
+    fn init(self, emulator: &mut yagber_app::Emulator) -> Result<(), yagber_app::PluginError> {
+        if self.doctor {
+            doctor::load_post_boot_state(emulator);
+        }
+        let mut log = TraceLog::new(self.writer, self.after_boot);
+        // The first instruction starts before any M-cycle runs
+        if let (Some(cpu), Some(bus)) = (
+            emulator.get_component::<yagber_cpu::Cpu>(),
+            emulator.get_component::<yagber_memory::Bus>(),
+        ) {
+            log.record(cpu, bus);
+        }
+        emulator
+            .with_component(log)
+            .on_mcycle(TraceLog::on_mcycle)
+            .on_event(TraceLog::on_state_transition);
+        Ok(())
+    }
+
+    fn dependencies(&self) -> yagber_app::PluginDependencies {
+        let dependencies = yagber_app::PluginDependencies::new()
+            .provides::<TraceLog>()
+            .requires::<yagber_cpu::Cpu>()
+            .requires::<yagber_memory::Bus>();
+        // The post-boot registers go through the hooks of every other plugin
+        if self.doctor {
+            dependencies.last()
+        } else {
+            dependencies
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use std::io::Write;
+
+use yagber_app::{Emulator, EmulatorState, StateTransitionEvent};
+use yagber_cpu::Cpu;
+use yagber_memory::Bus;
+
+use crate::TraceLogFailedEvent;
+
+/// Writes the CPU state before every instruction, one Gameboy Doctor line each.
+///
+/// A line reads `A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100 PCMEM:00,C3,13,02`,
+/// PCMEM being the four bytes at the program counter. Halted cycles are not logged.
+/// A failed write emits a [`TraceLogFailedEvent`] once, the log stops there.
+pub struct TraceLog {
+    writer: Option<Box<dyn Write + Send>>,
+    after_boot: bool,
+    lines: u64,
+    error: Option<std::io::Error>,
+}
+
+impl TraceLog {
+    pub(crate) fn new(writer: Box<dyn Write + Send>, after_boot: bool) -> Self {
+        Self {
+            writer: Some(writer),
+            after_boot,
+            lines: 0,
+            error: None,
+        }
+    }
+
+    /// The Gameboy Doctor line for the current CPU state.
+    pub fn line(cpu: &Cpu, bus: &Bus) -> String {
+        let registers = cpu.registers();
+        let pc = cpu.pc();
+        let pcmem = bus.read(pc);
+        format!(
+            "A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} SP:{:04X} PC:{pc:04X} PCMEM:{pcmem:02X},{:02X},{:02X},{:02X}",
+            registers.a(),
+            registers.f(),
+            registers.b(),
+            registers.c(),
+            registers.d(),
+            registers.e(),
+            registers.h(),
+            registers.l(),
+            cpu.sp(),
+            bus.read(pc.wrapping_add(1)),
+            bus.read(pc.wrapping_add(2)),
+            bus.read(pc.wrapping_add(3)),
+        )
+    }
+
+    /// Lines written so far.
+    pub fn lines(&self) -> u64 {
+        self.lines
+    }
+
+    /// A failed write drops the writer, the error waits for the next M-cycle to be emitted.
+    pub(crate) fn record(&mut self, cpu: &Cpu, bus: &Bus) {
+        if self.after_boot && bus.booting() {
+            return;
+        }
+        let Some(writer) = &mut self.writer else {
+            return;
+        };
+        if let Err(error) = writeln!(writer, "{}", Self::line(cpu, bus)) {
+            self.fail(error);
+            return;
+        }
+        self.lines += 1;
+    }
+
+    fn flush(&mut self) {
+        if let Some(writer) = &mut self.writer
+            && let Err(error) = writer.flush()
+        {
+            self.fail(error);
+        }
+    }
+
+    fn fail(&mut self, error: std::io::Error) {
+        self.writer = None;
+        self.error = Some(error);
+    }
+
+    fn report_error(emulator: &mut Emulator) {
+        if let Some(error) = emulator
+            .get_component_mut::<Self>()
+            .and_then(|log| log.error.take())
+        {
+            emulator.emit(TraceLogFailedEvent { error });
+        }
+    }
+
+    pub(crate) fn on_mcycle(emulator: &mut Emulator) {
+        let (log, cpu, bus) = emulator
+            .get_components_mut3::<Self, Cpu, Bus>()
+            .expect("TraceLog, Cpu and/or Bus component missing");
+        if cpu.at_instruction_boundary() {
+            log.record(cpu, bus);
+        }
+        Self::report_error(emulator);
+    }
+
+    /// Flushes whenever the emulator stops running, so the log can be read while paused.
+    pub(crate) fn on_state_transition(emulator: &mut Emulator, event: &StateTransitionEvent) {
+        if event.to != EmulatorState::Running
+            && let Some(log) = emulator.get_component_mut::<Self>()
+        {
+            log.flush();
+            Self::report_error(emulator);
+        }
+    }
+}
+
+impl Drop for TraceLog {
+    fn drop(&mut self) {
+        // Nobody is left to hear about a failure, the emulator is going away
+        self.flush();
+    }
+}
+
+impl yagber_app::Component for TraceLog {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::TraceLogPlugin;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(bytes)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// The CGB boot ROM starts with `0000 LD SP,$FFFE`, `0003 LD A,$02`, `0005 JP $007C`.
+    #[test]
+    fn logs_one_line_per_instruction() {
+        let buffer = SharedBuffer::default();
+        let mut emulator = Emulator::new()
+            .with_plugin(yagber_memory::MemoryPlugin::default())
+            .with_plugin(yagber_cpu::CpuPlugin)
+            .with_plugin(TraceLogPlugin::new(buffer.clone()))
+            .build()
+            .unwrap();
+        for _ in 0..100 {
+            emulator.step();
+        }
+
+        let log = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines = log.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[..3],
+            [
+                "A:00 F:00 B:00 C:00 D:00 E:00 H:00 L:00 SP:0000 PC:0000 PCMEM:31,FE,FF,3E",
+                "A:00 F:00 B:00 C:00 D:00 E:00 H:00 L:00 SP:FFFE PC:0003 PCMEM:3E,02,C3,7C",
+                "A:02 F:00 B:00 C:00 D:00 E:00 H:00 L:00 SP:FFFE PC:0005 PCMEM:C3,7C,00,D3",
+            ]
+        );
+        assert_eq!(
+            emulator.get_component::<TraceLog>().unwrap().lines(),
+            lines.len() as u64
+        );
+    }
+
+    /// `0100 NOP`, `0101 JP $0150`, `0150 LDH A,[LY]`, `0152 LD B,A`, `0153 JR -2`
+    #[test]
+    fn doctor_mode_matches_the_reference_logs() {
+        let mut rom = vec![0; 0x8000];
+        rom[0x100..0x104].copy_from_slice(&[0x00, 0xC3, 0x50, 0x01]);
+        rom[0x150..0x155].copy_from_slice(&[0xF0, 0x44, 0x47, 0x18, 0xFE]);
+        let buffer = SharedBuffer::default();
+        let mut emulator = Emulator::new()
+            .with_plugin(
+                yagber_memory::MemoryPlugin::default()
+                    .with_cartridge(&rom)
+                    .with_saves(yagber_memory::SaveLocation::Memory),
+            )
+            .with_plugin(yagber_cpu::CpuPlugin)
+            .with_plugin(TraceLogPlugin::new(buffer.clone()).with_doctor_mode())
+            .build()
+            .unwrap();
+        for _ in 0..100 {
+            emulator.step();
+        }
+
+        let log = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines = log.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[..6],
+            [
+                "A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100 PCMEM:00,C3,50,01",
+                "A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0101 PCMEM:C3,50,01,00",
+                "A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0150 PCMEM:F0,44,47,18",
+                "A:90 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0152 PCMEM:47,18,FE,00",
+                "A:90 F:B0 B:90 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0153 PCMEM:18,FE,00,00",
+                "A:90 F:B0 B:90 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0153 PCMEM:18,FE,00,00",
+            ]
+        );
+    }
+
+    struct FailingWriter;
+
+    impl Write for FailingWriter {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("disk full"))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn reports_a_failed_write_once() {
+        let mut emulator = Emulator::new()
+            .with_plugin(yagber_memory::MemoryPlugin::default())
+            .with_plugin(yagber_cpu::CpuPlugin)
+            .with_plugin(TraceLogPlugin::new(FailingWriter))
+            .build()
+            .unwrap();
+        let failures = Arc::new(Mutex::new(Vec::new()));
+        let seen = failures.clone();
+        emulator.on_event(move |_, event: &TraceLogFailedEvent| {
+            seen.lock().unwrap().push(event.to_string());
+        });
+        for _ in 0..100 {
+            emulator.step();
+        }
+
+        assert_eq!(
+            *failures.lock().unwrap(),
+            ["stopping the trace log: disk full"]
+        );
+    }
+}
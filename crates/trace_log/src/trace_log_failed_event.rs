@@ -0,0 +1,13 @@
+/// Writing the trace log failed, nothing more is logged.
+#[derive(Debug)]
+pub struct TraceLogFailedEvent {
+    pub error: std::io::Error,
+}
+
+impl std::fmt::Display for TraceLogFailedEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "stopping the trace log: {}", self.error)
+    }
+}
+
+impl yagber_app::Event for TraceLogFailedEvent {}
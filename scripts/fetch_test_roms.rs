@@ -32,6 +32,7 @@ fn main() {
     fetch_mooneye_test_roms();
     fetch_dmg_acid2_test_roms();
     fetch_cgb_acid2_test_roms();
+    fetch_sm83_tests();
 }
 
 fn fetch_blargg_test_roms() {
@@ -232,6 +233,33 @@ fn fetch_cgb_acid2_test_roms() {
     );
 }
 
+fn fetch_sm83_tests() {
+    let repo = "https://github.com/SingleStepTests/sm83";
+    let path = format!("{}{}", TEST_ROMS_PATH, "sm83/");
+    if Path::new(&path).exists() {
+        println!("SM83 tests already exist at {}, SKIPPING", path);
+        return;
+    }
+
+    println!("Cloning {} into {}", repo, path);
+    Command::new("git")
+        .arg("clone")
+        .arg("--depth")
+        .arg("1")
+        .arg(repo)
+        .arg(&path)
+        .status()
+        .expect("Failed to clone repository");
+
+    let commit_hash = get_commit_hash(&path);
+    write_metadata(&path, "sm83", &commit_hash);
+
+    println!(
+        "SM83 tests (version {}) fetched successfully to {}",
+        commit_hash, path
+    );
+}
+
 fn get_commit_hash(path: &str) -> String {
     let git_rev_output = Command::new("git")
         .arg("-C")
@@ -0,0 +1,167 @@
+use std::{path::PathBuf, process::ExitCode};
+
+#[derive(Debug)]
+pub enum CliError {
+    /// A file given on the command line cannot be read.
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// An output file cannot be written.
+    Write {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// The ROM cannot be emulated.
+    Cartridge {
+        path: PathBuf,
+        source: yagber_memory::CartridgeError,
+    },
+    /// The config file cannot be read or holds invalid settings.
+    Config {
+        path: PathBuf,
+        source: yagber_config::ConfigError,
+    },
+    /// The symbol file is not an RGBDS `.sym` file.
+    Symbols {
+        path: PathBuf,
+        source: yagber_disasm::ParseError,
+    },
+    /// The code/data log cannot be read or belongs to another cartridge.
+    Cdl {
+        path: PathBuf,
+        source: yagber_cdl::CdlError,
+    },
+    /// The boot ROM is not a CGB boot ROM.
+    BootRomSize { path: PathBuf, size: usize },
+    /// The screenshot cannot be encoded.
+    Screenshot {
+        path: PathBuf,
+        source: image::ImageError,
+    },
+    /// The GDB stub cannot listen on its port.
+    Gdb { port: u16, source: std::io::Error },
+    /// A trace log differs from its reference.
+    TraceMismatch { line: usize },
+    /// The plugins do not fit together, or one cannot use its input data.
+    Plugins(yagber_app::PluginError),
+}
+
+impl CliError {
+    /// Exit codes follow the BSD `sysexits.h` conventions, clap exits with 2 on usage errors.
+    pub fn exit_code(&self) -> ExitCode {
+        const EX_DATAERR: u8 = 65;
+        const EX_NOINPUT: u8 = 66;
+        const EX_UNAVAILABLE: u8 = 69;
+        const EX_SOFTWARE: u8 = 70;
+        const EX_CANTCREAT: u8 = 73;
+        const EX_CONFIG: u8 = 78;
+
+        ExitCode::from(match self {
+            // Like `cmp` and `diff`
+            Self::TraceMismatch { .. } => 1,
+            Self::Read { .. } => EX_NOINPUT,
+            Self::Cartridge { .. } | Self::Symbols { .. } | Self::BootRomSize { .. } => EX_DATAERR,
+            Self::Write { .. } | Self::Screenshot { .. } => EX_CANTCREAT,
+            Self::Gdb { .. } => EX_UNAVAILABLE,
+            Self::Plugins(yagber_app::PluginError::Init { .. }) => EX_DATAERR,
+            Self::Plugins(_) => EX_SOFTWARE,
+            Self::Config {
+                source: yagber_config::ConfigError::Io(_),
+                ..
+            } => EX_NOINPUT,
+            Self::Config { .. } => EX_CONFIG,
+            Self::Cdl {
+                source: yagber_cdl::CdlError::Io(_),
+                ..
+            } => EX_NOINPUT,
+            Self::Cdl { .. } => EX_DATAERR,
+        })
+    }
+}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Read { path, source } => write!(f, "cannot read {}: {source}", path.display()),
+            Self::Write { path, source } => {
+                write!(f, "cannot write {}: {source}", path.display())
+            }
+            Self::Cartridge { path, source } => {
+                write!(f, "cannot load {}: {source}", path.display())
+            }
+            Self::Config { path, source } => {
+                write!(
+                    f,
+                    "cannot load the config file {}: {source}",
+                    path.display()
+                )
+            }
+            Self::Symbols { path, source } => {
+                write!(
+                    f,
+                    "cannot load the symbol file {}: {source}",
+                    path.display()
+                )
+            }
+            Self::Cdl { path, source } => {
+                write!(
+                    f,
+                    "cannot load the code/data log {}: {source}",
+                    path.display()
+                )
+            }
+            Self::BootRomSize { path, size } => write!(
+                f,
+                "{} is {size} bytes long, a CGB boot ROM is {} bytes",
+                path.display(),
+                yagber_memory::BootRom::SIZE
+            ),
+            Self::Screenshot { path, source } => {
+                write!(f, "cannot save the screenshot {}: {source}", path.display())
+            }
+            Self::Gdb { port, source } => {
+                write!(f, "cannot listen for GDB clients on port {port}: {source}")
+            }
+            Self::TraceMismatch { line } => write!(f, "trace logs differ from line {line}"),
+            Self::Plugins(error) => write!(f, "failed to initialize the emulator: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl From<yagber_app::PluginError> for CliError {
+    fn from(error: yagber_app::PluginError) -> Self {
+        Self::Plugins(error)
+    }
+}
+
+/// Reads a file named on the command line.
+pub fn read_file(path: &std::path::Path) -> Result<Vec<u8>, CliError> {
+    std::fs::read(path).map_err(|source| CliError::Read {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Reads the RGBDS symbol file given on the command line, or the one next to the ROM
+/// when there is one.
+pub fn read_symbols(
+    path: Option<&std::path::Path>,
+    rom: &std::path::Path,
+) -> Result<yagber_disasm::SymbolTable, CliError> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => {
+            let path = rom.with_extension("sym");
+            if !path.is_file() {
+                return Ok(yagber_disasm::SymbolTable::new());
+            }
+            path
+        }
+    };
+    let text = String::from_utf8_lossy(&read_file(&path)?).into_owned();
+    text.parse()
+        .map_err(|source| CliError::Symbols { path, source })
+}
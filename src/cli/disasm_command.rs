@@ -0,0 +1,56 @@
+use std::{io::Write, path::PathBuf};
+
+use yagber_cdl::CodeDataLog;
+use yagber_disasm::{BankedAddress, Disassembler};
+
+use crate::cli::{
+    CliError,
+    cli_error::{read_file, read_symbols},
+};
+
+#[derive(Debug, clap::Args)]
+pub struct DisasmCommand {
+    /// ROM file to disassemble.
+    rom: PathBuf,
+    /// RGBDS symbol file labeling the output, defaults to the ROM with a `.sym`
+    /// extension when there is one.
+    #[arg(long, value_name = "PATH")]
+    sym: Option<PathBuf>,
+    /// Extra location to start from, `BB:AAAA` in hexadecimal. Repeatable.
+    #[arg(long, value_name = "LOCATION")]
+    entry: Vec<BankedAddress>,
+    /// Code/data log whose executed opcodes are used as extra entry points.
+    #[arg(long, value_name = "PATH")]
+    cdl: Option<PathBuf>,
+}
+
+impl DisasmCommand {
+    pub fn run(self) -> Result<(), CliError> {
+        let rom = read_file(&self.rom)?;
+        let symbols = read_symbols(self.sym.as_deref(), &self.rom)?;
+        let mut entries = self.entry;
+        if let Some(path) = &self.cdl {
+            let mut log = CodeDataLog::for_rom(&rom);
+            let source = match std::fs::read(path) {
+                Ok(bytes) => log.merge_bytes(&bytes).err(),
+                Err(error) => Some(error.into()),
+            };
+            if let Some(source) = source {
+                return Err(CliError::Cdl {
+                    path: path.clone(),
+                    source,
+                });
+            }
+            entries.extend(log.code_entries());
+        }
+        let disassembly = entries
+            .into_iter()
+            .fold(Disassembler::new(&rom), Disassembler::with_entry)
+            .run();
+
+        let mut stdout = std::io::stdout().lock();
+        // A closed stdout, e.g. piped into `head`, is not worth failing over.
+        let _ = write!(stdout, "{}", disassembly.listing(&symbols)).and_then(|()| stdout.flush());
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+use std::path::PathBuf;
+
+use yagber::EmulatorBuilder;
+use yagber_config::Config;
+
+use crate::cli::{
+    CliError,
+    cli_error::{read_file, read_symbols},
+};
+
+/// Options shared by the commands that emulate a ROM.
+#[derive(Debug, clap::Args)]
+pub struct EmulatorArgs {
+    /// ROM file to load.
+    rom: PathBuf,
+    /// Hardware to emulate, `dmg` runs the cartridge in the CGB's DMG compatibility mode.
+    #[arg(long, value_enum, default_value_t = ModelArg::Cgb)]
+    model: ModelArg,
+    /// CGB boot ROM to use instead of the bundled one.
+    #[arg(long, value_name = "PATH")]
+    boot_rom: Option<PathBuf>,
+    /// Directory battery backed saves are kept in, `out/saves` unless the config file sets one.
+    #[arg(long, value_name = "DIR")]
+    save_dir: Option<PathBuf>,
+    /// Config file to use instead of `$XDG_CONFIG_HOME/yagber/config.toml`.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Tracing filter directives, such as `yagber_cpu=debug`, needs the `trace` feature.
+    #[arg(long, value_name = "FILTER")]
+    trace: Option<String>,
+    /// File every instruction is logged to, in the Gameboy Doctor format.
+    #[arg(long, value_name = "PATH")]
+    trace_log: Option<PathBuf>,
+    /// Starts the trace log once the boot ROM is done, at 0100.
+    #[arg(long, requires = "trace_log")]
+    trace_log_after_boot: bool,
+    /// Skips the boot ROM and logs from 0100 with the DMG post-boot registers and LY reading
+    /// $90, like Gameboy Doctor's reference logs. Only meant for test ROMs.
+    #[arg(long, requires = "trace_log")]
+    trace_log_doctor: bool,
+    /// File the profile report is written to on exit, with the hottest instructions and
+    /// the cycles spent in each function.
+    #[arg(long, value_name = "PATH")]
+    profile: Option<PathBuf>,
+    /// File the profiled call stacks are written to on exit, in the folded format read by
+    /// flamegraph tools.
+    #[arg(long, value_name = "PATH")]
+    profile_folded: Option<PathBuf>,
+    /// Rows of each table of the profile report.
+    #[arg(long, value_name = "N", default_value_t = yagber_profiler::ProfilerPlugin::DEFAULT_TOP)]
+    profile_top: usize,
+    /// Code/data log tagging the cartridge bytes executed and read, in the FCEUX `.cdl`
+    /// layout. An existing log is added to and saved back on exit.
+    #[arg(long, value_name = "PATH")]
+    cdl: Option<PathBuf>,
+    /// RGBDS symbol file naming profiled code, defaults to the ROM with a `.sym` extension
+    /// when there is one.
+    #[arg(long, value_name = "PATH")]
+    sym: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum ModelArg {
+    Cgb,
+    Dmg,
+}
+
+impl From<ModelArg> for yagber_memory::Model {
+    fn from(model: ModelArg) -> Self {
+        match model {
+            ModelArg::Cgb => Self::Cgb,
+            ModelArg::Dmg => Self::Dmg,
+        }
+    }
+}
+
+impl EmulatorArgs {
+    /// Checks the ROM and boot ROM can be used, then hands them to `builder` along with
+    /// the config file settings for the ROM.
+    pub fn configure(&self, builder: EmulatorBuilder) -> Result<EmulatorBuilder, CliError> {
+        let rom = read_file(&self.rom)?;
+        let (header, mbc_info) =
+            yagber_memory::Cartridge::inspect(&rom).map_err(|source| CliError::Cartridge {
+                path: self.rom.clone(),
+                source,
+            })?;
+        let settings = self
+            .config()?
+            .settings_for(&header.title, u16::from_be_bytes(header.global_checksum));
+
+        let save_dir = self
+            .save_dir
+            .clone()
+            .or_else(|| settings.saves.directory.clone())
+            .unwrap_or_else(|| PathBuf::from(yagber_memory::SaveLocation::DEFAULT_DIR));
+        if mbc_info.includes_battery {
+            std::fs::create_dir_all(&save_dir).map_err(|source| CliError::Write {
+                path: save_dir.clone(),
+                source,
+            })?;
+        }
+
+        let mut builder = builder
+            .with_settings(&settings)
+            .with_rom(&rom)
+            .with_save_dir(save_dir)
+            .with_model(self.model.into());
+        if let Some(path) = &self.boot_rom {
+            let boot_rom = read_file(path)?;
+            if boot_rom.len() != yagber_memory::BootRom::SIZE {
+                return Err(CliError::BootRomSize {
+                    path: path.clone(),
+                    size: boot_rom.len(),
+                });
+            }
+            builder = builder.with_boot_rom(&boot_rom);
+        }
+
+        if let Some(filter) = &self.trace {
+            if cfg!(feature = "trace") {
+                builder = builder.with_log(yagber_log::LogPlugin::default().with_filter(filter));
+            } else {
+                eprintln!("yagber: ignoring --trace, built without the `trace` feature");
+            }
+        }
+        if let Some(path) = &self.trace_log {
+            let trace_log = yagber_trace_log::TraceLogPlugin::create(path).map_err(|source| {
+                CliError::Write {
+                    path: path.clone(),
+                    source,
+                }
+            })?;
+            builder = builder.with_trace_log(if self.trace_log_doctor {
+                trace_log.with_doctor_mode()
+            } else if self.trace_log_after_boot {
+                trace_log.with_after_boot()
+            } else {
+                trace_log
+            });
+        }
+        if let Some(path) = &self.cdl {
+            let cdl = yagber_cdl::CdlPlugin::for_rom(&rom)
+                .with_file(path)
+                .map_err(|source| CliError::Cdl {
+                    path: path.clone(),
+                    source,
+                })?;
+            builder = builder.with_cdl(cdl);
+        }
+        if self.profile.is_some() || self.profile_folded.is_some() {
+            builder = builder.with_profiler(self.profiler()?);
+        }
+        Ok(builder)
+    }
+
+    fn profiler(&self) -> Result<yagber_profiler::ProfilerPlugin, CliError> {
+        let mut profiler = yagber_profiler::ProfilerPlugin::new()
+            .with_symbols(read_symbols(self.sym.as_deref(), &self.rom)?)
+            .with_top(self.profile_top);
+        if let Some(path) = &self.profile {
+            profiler = profiler.with_report(path);
+        }
+        if let Some(path) = &self.profile_folded {
+            profiler = profiler.with_folded_stacks(path);
+        }
+        Ok(profiler)
+    }
+
+    /// The file given with `--config` must exist, the default one is optional.
+    fn config(&self) -> Result<Config, CliError> {
+        let result = match &self.config {
+            Some(path) => Config::load(path).map_err(|source| (path.clone(), source)),
+            None => match Config::default_path() {
+                Some(path) => Config::load_if_exists(&path).map_err(|source| (path, source)),
+                None => Ok(Config::default()),
+            },
+        };
+        result.map_err(|(path, source)| CliError::Config { path, source })
+    }
+}
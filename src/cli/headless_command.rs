@@ -0,0 +1,74 @@
+use std::{io::Write, path::PathBuf};
+
+use yagber_app::HeadlessRunner;
+
+use crate::cli::{
+    CliError, EmulatorArgs, report_cdl_failure, report_lockup, report_profile_failure,
+    report_trace_log_failure,
+};
+
+#[derive(Debug, clap::Args)]
+pub struct HeadlessCommand {
+    #[command(flatten)]
+    emulator: EmulatorArgs,
+    /// Frames to run before stopping.
+    #[arg(long, default_value_t = 600)]
+    frames: u64,
+    /// Image file the last frame is saved to, the format follows the extension.
+    #[arg(long, value_name = "PATH")]
+    screenshot: Option<PathBuf>,
+    /// File the serial output is written to, stdout otherwise.
+    #[arg(long, value_name = "PATH")]
+    serial_out: Option<PathBuf>,
+}
+
+impl HeadlessCommand {
+    pub fn run(self) -> Result<(), CliError> {
+        let mut emulator = self
+            .emulator
+            .configure(yagber::EmulatorBuilder::headless())?
+            .build()?;
+        emulator.on_event(report_lockup);
+        emulator.on_event(report_trace_log_failure);
+        emulator.on_event(report_profile_failure);
+        emulator.on_event(report_cdl_failure);
+
+        let mut runner = HeadlessRunner::new(emulator).for_frames(self.frames);
+        runner.run_until_stop();
+        // Lets the profiler and the code/data log write their files and the trace log flush,
+        // reporting failures
+        runner.emulator_mut().exit();
+
+        if let Some(path) = &self.screenshot {
+            let ppu = runner
+                .emulator()
+                .get_component::<yagber_ppu::Ppu>()
+                .expect("Ppu component missing");
+            image::save_buffer(
+                path,
+                ppu.frame_buffer().as_flattened(),
+                yagber_display::Display::WIDTH,
+                yagber_display::Display::HEIGHT,
+                image::ExtendedColorType::Rgba8,
+            )
+            .map_err(|source| CliError::Screenshot {
+                path: path.clone(),
+                source,
+            })?;
+        }
+
+        let serial = runner.serial_output();
+        match &self.serial_out {
+            Some(path) => std::fs::write(path, serial).map_err(|source| CliError::Write {
+                path: path.clone(),
+                source,
+            }),
+            None => {
+                let mut stdout = std::io::stdout();
+                // A closed stdout, e.g. piped into `head`, is not worth failing over.
+                let _ = stdout.write_all(serial).and_then(|()| stdout.flush());
+                Ok(())
+            }
+        }
+    }
+}
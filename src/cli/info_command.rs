@@ -0,0 +1,123 @@
+use std::path::PathBuf;
+
+use yagber_memory::{Cartridge, CartridgeHeader, CartridgeMbcInfo};
+
+use crate::cli::{CliError, cli_error::read_file};
+
+#[derive(Debug, clap::Args)]
+pub struct InfoCommand {
+    /// ROM file to inspect.
+    rom: PathBuf,
+}
+
+impl InfoCommand {
+    pub fn run(self) -> Result<(), CliError> {
+        let rom = read_file(&self.rom)?;
+        let cartridge_error = |source| CliError::Cartridge {
+            path: self.rom.clone(),
+            source,
+        };
+        let header = CartridgeHeader::new(&rom).map_err(cartridge_error)?;
+        let mbc_info = CartridgeMbcInfo::new(&header).map_err(cartridge_error)?;
+
+        print_header(&rom, &header);
+        print_mbc_info(&mbc_info);
+        match Cartridge::inspect(&rom) {
+            Ok(_) => println!("{:<16} yes", "Supported:"),
+            Err(error) => println!("{:<16} no, {error}", "Supported:"),
+        }
+        Ok(())
+    }
+}
+
+fn print_header(rom: &[u8], header: &CartridgeHeader) {
+    let cgb_support = match header.cgb_flag {
+        0xC0 => "CGB only",
+        0x80 => "CGB enhanced",
+        _ => "DMG",
+    };
+    let destination = match header.destination_code {
+        0x00 => "Japan",
+        _ => "overseas",
+    };
+    let computed_checksum = CartridgeHeader::computed_checksum(rom);
+    let checksum_status = if computed_checksum == header.checksum {
+        "ok".to_string()
+    } else {
+        format!("mismatch, computed {computed_checksum:#04X}")
+    };
+
+    println!("{:<16} {}", "Title:", header.title);
+    println!(
+        "{:<16} {:#04X} ({cgb_support})",
+        "CGB flag:", header.cgb_flag
+    );
+    println!("{:<16} {:#04X}", "SGB flag:", header.sgb_flag);
+    println!("{:<16} {:#04X}", "Type:", header.type_code);
+    println!(
+        "{:<16} {:#04X} ({destination})",
+        "Destination:", header.destination_code
+    );
+    // 0x33 points to the two character code that replaced the old one byte code.
+    if header.old_license_code == 0x33 {
+        println!(
+            "{:<16} {:?}",
+            "Licensee:",
+            String::from_utf8_lossy(&header.licence_code)
+        );
+    } else {
+        println!("{:<16} {:#04X}", "Licensee:", header.old_license_code);
+    }
+    println!("{:<16} {}", "Version:", header.mask_rom_version);
+    println!(
+        "{:<16} {:#04X} ({checksum_status})",
+        "Header checksum:", header.checksum
+    );
+    println!(
+        "{:<16} {:#06X}",
+        "Global checksum:",
+        u16::from_be_bytes(header.global_checksum)
+    );
+    println!("{:<16} {:#018x}", "Content id:", header.content_id());
+    println!("{:<16} {} bytes", "File size:", rom.len());
+}
+
+fn print_mbc_info(mbc_info: &CartridgeMbcInfo) {
+    let features = [
+        (mbc_info.includes_ram, "RAM"),
+        (mbc_info.includes_battery, "battery"),
+        (mbc_info.includes_timer, "timer"),
+        (mbc_info.includes_rumble, "rumble"),
+    ]
+    .into_iter()
+    .filter_map(|(included, name)| included.then_some(name))
+    .collect::<Vec<_>>();
+
+    println!("{:<16} {:?}", "MBC:", mbc_info.mbc_type);
+    println!(
+        "{:<16} {}, {} banks",
+        "ROM size:",
+        format_size(mbc_info.rom_size),
+        mbc_info.rom_bank_count
+    );
+    println!(
+        "{:<16} {}, {} banks",
+        "RAM size:",
+        format_size(mbc_info.ram_size),
+        mbc_info.ram_bank_count
+    );
+    if features.is_empty() {
+        println!("{:<16} none", "Features:");
+    } else {
+        println!("{:<16} {}", "Features:", features.join(", "));
+    }
+}
+
+/// MBC2 RAM is 512 bytes, every other size is a whole number of KiB.
+fn format_size(bytes: usize) -> String {
+    if bytes.is_multiple_of(1024) {
+        format!("{} KiB", bytes / 1024)
+    } else {
+        format!("{bytes} B")
+    }
+}
@@ -0,0 +1,25 @@
+use yagber_cpu::CpuLockedEvent;
+use yagber_disasm::{BankedAddress, DecodedInstruction};
+use yagber_memory::Bus;
+
+/// Prints why the CPU locked up on stderr, along with the instructions that led there.
+pub fn report_lockup(emulator: &mut yagber_app::Emulator, event: &CpuLockedEvent) {
+    eprintln!("{event}");
+    eprintln!("last instructions:");
+    let bus = emulator
+        .get_component::<Bus>()
+        .expect("Bus component missing");
+    for executed in &event.history {
+        let location = BankedAddress::new(executed.bank, executed.address);
+        // Bytes the CPU never read, such as the one STOP skips, come from the bus
+        let instruction = DecodedInstruction::decode(location, |address| {
+            let offset = address.wrapping_sub(executed.address);
+            executed
+                .bytes()
+                .get(usize::from(offset))
+                .copied()
+                .unwrap_or_else(|| bus.read(address))
+        });
+        eprintln!("  {location}  {instruction}");
+    }
+}
@@ -0,0 +1,119 @@
+mod cli_error;
+mod disasm_command;
+mod emulator_args;
+mod headless_command;
+mod info_command;
+mod lockup_report;
+mod output_failures;
+mod run_command;
+mod serial_target;
+mod trace_diff_command;
+
+pub use cli_error::CliError;
+pub use disasm_command::DisasmCommand;
+pub use emulator_args::EmulatorArgs;
+pub use headless_command::HeadlessCommand;
+pub use info_command::InfoCommand;
+pub use lockup_report::report_lockup;
+pub use output_failures::{report_cdl_failure, report_profile_failure, report_trace_log_failure};
+pub use run_command::RunCommand;
+pub use serial_target::SerialTarget;
+pub use trace_diff_command::TraceDiffCommand;
+
+#[derive(Debug, clap::Parser)]
+#[command(name = "yagber", version, about = "Yet another Game Boy emulator")]
+pub struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum Command {
+    /// Plays a ROM in a window.
+    Run(RunCommand),
+    /// Prints the cartridge header and memory bank controller of a ROM.
+    Info(InfoCommand),
+    /// Runs a ROM without a window, then dumps the screen and serial output.
+    Headless(HeadlessCommand),
+    /// Disassembles the code reached from the entry points of a ROM.
+    Disasm(DisasmCommand),
+    /// Compares a trace log with a reference log and reports the first different line.
+    TraceDiff(TraceDiffCommand),
+}
+
+impl Cli {
+    pub fn run(self) -> Result<(), CliError> {
+        match self.command {
+            Command::Run(command) => command.run(),
+            Command::Info(command) => command.run(),
+            Command::Headless(command) => command.run(),
+            Command::Disasm(command) => command.run(),
+            Command::TraceDiff(command) => command.run(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{CommandFactory, Parser};
+
+    use super::*;
+
+    #[test]
+    fn cli_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn parses_run_options() {
+        let cli = Cli::try_parse_from([
+            "yagber",
+            "run",
+            "game.gb",
+            "--scale",
+            "3",
+            "--model",
+            "dmg",
+            "--no-audio",
+            "--serial",
+            "none",
+            "--config",
+            "yagber.toml",
+            "--break",
+            "03:4A10",
+            "--break",
+            "0150",
+            "--gdb",
+            "--trace-log",
+            "trace.log",
+            "--trace-log-after-boot",
+            "--trace-log-doctor",
+            "--profile",
+            "profile.txt",
+            "--profile-folded",
+            "profile.folded",
+            "--sym",
+            "game.sym",
+            "--cdl",
+            "game.cdl",
+        ])
+        .unwrap();
+        assert!(matches!(cli.command, Command::Run(_)));
+    }
+
+    #[test]
+    fn parses_disasm_options() {
+        let cli = Cli::try_parse_from([
+            "yagber", "disasm", "game.gb", "--sym", "game.sym", "--entry", "03:4A10", "--cdl",
+            "game.cdl",
+        ])
+        .unwrap();
+        assert!(matches!(cli.command, Command::Disasm(_)));
+        assert!(Cli::try_parse_from(["yagber", "disasm", "game.gb", "--entry", "x"]).is_err());
+    }
+
+    #[test]
+    fn rejects_a_zero_scale() {
+        assert!(Cli::try_parse_from(["yagber", "run", "game.gb", "--scale", "0"]).is_err());
+    }
+}
@@ -0,0 +1,18 @@
+use yagber_cdl::CdlFailedEvent;
+use yagber_profiler::ProfileFailedEvent;
+use yagber_trace_log::TraceLogFailedEvent;
+
+/// Prints on stderr why the trace log stopped.
+pub fn report_trace_log_failure(_: &mut yagber_app::Emulator, event: &TraceLogFailedEvent) {
+    eprintln!("yagber: {event}");
+}
+
+/// Prints on stderr which profile file could not be written.
+pub fn report_profile_failure(_: &mut yagber_app::Emulator, event: &ProfileFailedEvent) {
+    eprintln!("yagber: {event}");
+}
+
+/// Prints on stderr why the code/data log could not be saved.
+pub fn report_cdl_failure(_: &mut yagber_app::Emulator, event: &CdlFailedEvent) {
+    eprintln!("yagber: {event}");
+}
@@ -0,0 +1,74 @@
+use std::net::Ipv4Addr;
+
+use yagber_debugger::{Breakpoint, CodeLocation, DebuggerPlugin};
+use yagber_gdb::GdbPlugin;
+
+use crate::cli::{
+    CliError, EmulatorArgs, SerialTarget, report_cdl_failure, report_lockup,
+    report_profile_failure, report_trace_log_failure,
+};
+
+#[derive(Debug, clap::Args)]
+pub struct RunCommand {
+    #[command(flatten)]
+    emulator: EmulatorArgs,
+    /// Window size as a multiple of the 160x144 screen, overrides the config file.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..=16))]
+    scale: Option<u32>,
+    /// Runs without sound output.
+    #[arg(long)]
+    no_audio: bool,
+    /// Serial port output: `none`, `stdout` or a file path.
+    #[arg(long, value_name = "TARGET", default_value = "stdout")]
+    serial: SerialTarget,
+    /// Reads debugger commands from stdin, type `help` for the list.
+    #[arg(long)]
+    debug: bool,
+    /// Stops at `AAAA` or `BB:AAAA` in hexadecimal, can be repeated, implies `--debug`.
+    #[arg(long = "break", value_name = "LOCATION")]
+    breakpoints: Vec<CodeLocation>,
+    /// Waits for GDB clients on a localhost port, `--gdb=PORT` to pick another than 2345.
+    #[arg(
+        long,
+        value_name = "PORT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "2345"
+    )]
+    gdb: Option<u16>,
+}
+
+impl RunCommand {
+    pub fn run(self) -> Result<(), CliError> {
+        let mut builder = self
+            .emulator
+            .configure(yagber::EmulatorBuilder::windowed())?
+            .with_link_cable(self.serial.plugin());
+        if let Some(scale) = self.scale {
+            builder = builder.with_scale(scale);
+        }
+        if self.no_audio {
+            builder = builder.with_audio(false);
+        }
+        if self.debug || !self.breakpoints.is_empty() {
+            let debugger = self.breakpoints.into_iter().map(Breakpoint::new).fold(
+                DebuggerPlugin::new().with_repl(),
+                DebuggerPlugin::with_breakpoint,
+            );
+            builder = builder.with_debugger(debugger);
+        }
+        if let Some(port) = self.gdb {
+            let gdb = GdbPlugin::bind((Ipv4Addr::LOCALHOST, port))
+                .map_err(|source| CliError::Gdb { port, source })?;
+            builder = builder.with_gdb(gdb);
+        }
+        let mut emulator = builder.build()?;
+        emulator.on_event(report_lockup);
+        emulator.on_event(report_trace_log_failure);
+        emulator.on_event(report_profile_failure);
+        emulator.on_event(report_cdl_failure);
+
+        emulator.run::<yagber_display::WinitRunner>()?;
+        Ok(())
+    }
+}
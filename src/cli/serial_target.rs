@@ -0,0 +1,33 @@
+use std::{path::PathBuf, str::FromStr};
+
+/// Where bytes sent through the serial port go while playing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SerialTarget {
+    None,
+    Stdout,
+    File(PathBuf),
+}
+
+impl SerialTarget {
+    pub fn plugin(&self) -> yagber_link_cable::LinkCablePlugin {
+        let plugin = yagber_link_cable::LinkCablePlugin::default();
+        match self {
+            Self::None => plugin,
+            Self::Stdout => plugin.with_serial_output_stdout(),
+            Self::File(path) => plugin.with_serial_output_file(&path.to_string_lossy()),
+        }
+    }
+}
+
+/// `none` and `stdout` are keywords, anything else is a file path.
+impl FromStr for SerialTarget {
+    type Err = std::convert::Infallible;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(match value {
+            "none" => Self::None,
+            "stdout" => Self::Stdout,
+            path => Self::File(PathBuf::from(path)),
+        })
+    }
+}
@@ -0,0 +1,43 @@
+use std::{io::BufReader, path::PathBuf};
+
+use crate::cli::CliError;
+
+#[derive(Debug, clap::Args)]
+pub struct TraceDiffCommand {
+    /// Log written with `--trace-log`.
+    log: PathBuf,
+    /// Reference log, such as one from Gameboy Doctor.
+    reference: PathBuf,
+}
+
+impl TraceDiffCommand {
+    pub fn run(self) -> Result<(), CliError> {
+        let open = |path: &PathBuf| {
+            std::fs::File::open(path)
+                .map(BufReader::new)
+                .map_err(|source| CliError::Read {
+                    path: path.clone(),
+                    source,
+                })
+        };
+        let divergence =
+            yagber_trace_log::first_divergence(open(&self.log)?, open(&self.reference)?).map_err(
+                |source| CliError::Read {
+                    path: self.log.clone(),
+                    source,
+                },
+            )?;
+        match divergence {
+            Some(divergence) => {
+                println!("{divergence}");
+                Err(CliError::TraceMismatch {
+                    line: divergence.line,
+                })
+            }
+            None => {
+                println!("logs match");
+                Ok(())
+            }
+        }
+    }
+}
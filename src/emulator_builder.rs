@@ -0,0 +1,341 @@
+use std::{path::PathBuf, time::Duration};
+
+use yagber_app::{Emulator, PluginError};
+use yagber_cdl::CdlPlugin;
+use yagber_config::Settings;
+use yagber_cpal::CpalPlugin;
+use yagber_debugger::DebuggerPlugin;
+use yagber_display::DisplayPlugin;
+use yagber_gdb::GdbPlugin;
+use yagber_input::{InputPlugin, KeyBindings};
+use yagber_link_cable::LinkCablePlugin;
+use yagber_log::LogPlugin;
+use yagber_memory::{ClockKind, EmulatedClock, MemoryPlugin, Model, SaveLocation};
+use yagber_profiler::ProfilerPlugin;
+use yagber_trace_log::TraceLogPlugin;
+
+/// Assembles the plugins of a complete emulator.
+///
+/// Start from the preset matching the host, then swap subsystems in or out.
+/// The CPU, PPU, APU, DMA and timer are always registered.
+pub struct EmulatorBuilder {
+    memory: MemoryPlugin,
+    log: Option<LogPlugin>,
+    display: Option<DisplayPlugin>,
+    audio: Option<CpalPlugin>,
+    input: Option<InputPlugin>,
+    link_cable: Option<LinkCablePlugin>,
+    debugger: Option<DebuggerPlugin>,
+    gdb: Option<GdbPlugin>,
+    trace_log: Option<TraceLogPlugin>,
+    profiler: Option<ProfilerPlugin>,
+    cdl: Option<CdlPlugin>,
+    rewind: bool,
+}
+
+impl EmulatorBuilder {
+    fn new() -> Self {
+        Self {
+            memory: MemoryPlugin::default(),
+            log: cfg!(feature = "trace").then(LogPlugin::default),
+            display: None,
+            audio: None,
+            input: Some(InputPlugin::default()),
+            link_cable: Some(LinkCablePlugin::default()),
+            debugger: None,
+            gdb: None,
+            trace_log: None,
+            profiler: None,
+            cdl: None,
+            rewind: false,
+        }
+    }
+
+    /// Plays in a window with sound, serial output goes to stdout.
+    pub fn windowed() -> Self {
+        Self {
+            display: Some(DisplayPlugin::default()),
+            audio: Some(CpalPlugin::default()),
+            link_cable: Some(LinkCablePlugin::default().with_serial_output_stdout()),
+            rewind: true,
+            ..Self::new()
+        }
+    }
+
+    /// No window and no sound, for tools and benchmarks.
+    pub fn headless() -> Self {
+        Self::new()
+    }
+
+    /// Headless, with logging and a reproducible run: saves stay in memory and the
+    /// cartridge clock follows emulated time from the last save.
+    pub fn test() -> Self {
+        Self {
+            memory: MemoryPlugin::default()
+                .with_saves(SaveLocation::Memory)
+                .with_clock(EmulatedClock::from_save()),
+            log: Some(LogPlugin::default()),
+            ..Self::new()
+        }
+    }
+
+    pub fn with_rom(mut self, rom: &[u8]) -> Self {
+        self.memory = self.memory.with_cartridge(rom);
+        self
+    }
+
+    /// See [`MemoryPlugin::with_boot_rom`].
+    pub fn with_boot_rom(mut self, boot_rom: &[u8]) -> Self {
+        self.memory = self.memory.with_boot_rom(boot_rom);
+        self
+    }
+
+    pub fn with_model(mut self, model: Model) -> Self {
+        self.memory = self.memory.with_model(model);
+        self
+    }
+
+    pub fn with_saves(mut self, saves: SaveLocation) -> Self {
+        self.memory = self.memory.with_saves(saves);
+        self
+    }
+
+    pub fn with_save_dir(self, save_dir: impl Into<PathBuf>) -> Self {
+        self.with_saves(SaveLocation::Directory(save_dir.into()))
+    }
+
+    pub fn with_clock(mut self, clock: impl Into<ClockKind>) -> Self {
+        self.memory = self.memory.with_clock(clock);
+        self
+    }
+
+    pub fn with_log(mut self, log: LogPlugin) -> Self {
+        self.log = Some(log);
+        self
+    }
+
+    pub fn without_log(mut self) -> Self {
+        self.log = None;
+        self
+    }
+
+    /// Window scale, only used by the windowed preset.
+    pub fn with_scale(mut self, scale: u32) -> Self {
+        self.display = self.display.map(|display| display.with_scale(scale));
+        self
+    }
+
+    /// Whether sound is played through the default output device.
+    pub fn with_audio(mut self, enabled: bool) -> Self {
+        self.audio = enabled.then(|| self.audio.unwrap_or_default());
+        self
+    }
+
+    /// See [`CpalPlugin::with_volume`], only used when audio is enabled.
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.audio = self.audio.map(|audio| audio.with_volume(volume));
+        self
+    }
+
+    /// See [`CpalPlugin::with_max_latency`], only used when audio is enabled.
+    pub fn with_max_latency(mut self, max_latency: Duration) -> Self {
+        self.audio = self.audio.map(|audio| audio.with_max_latency(max_latency));
+        self
+    }
+
+    /// Whether the joypad is connected, the window needs it to forward key presses.
+    pub fn with_input(mut self, enabled: bool) -> Self {
+        self.input = enabled.then(|| self.input.unwrap_or_default());
+        self
+    }
+
+    /// Only used when the joypad is connected.
+    pub fn with_key_bindings(mut self, bindings: KeyBindings) -> Self {
+        self.input = self.input.map(|input| input.with_bindings(bindings));
+        self
+    }
+
+    /// Applies settings from the config file.
+    ///
+    /// Settings never turn on a subsystem the preset leaves out: a scale without a
+    /// window is ignored, and `audio.enabled` can only mute.
+    pub fn with_settings(mut self, settings: &Settings) -> Self {
+        if let Some(scale) = settings.display.scale {
+            self = self.with_scale(scale);
+        }
+        if settings.audio.enabled == Some(false) {
+            self = self.with_audio(false);
+        }
+        if let Some(volume) = settings.audio.volume {
+            self = self.with_volume(volume);
+        }
+        if let Some(latency_ms) = settings.audio.latency_ms {
+            self = self.with_max_latency(Duration::from_millis(latency_ms));
+        }
+        if let Some(directory) = &settings.saves.directory {
+            self = self.with_save_dir(directory);
+        }
+        let bindings = settings.input.apply(KeyBindings::default());
+        self.with_key_bindings(bindings)
+    }
+
+    pub fn with_link_cable(mut self, link_cable: LinkCablePlugin) -> Self {
+        self.link_cable = Some(link_cable);
+        self
+    }
+
+    pub fn without_link_cable(mut self) -> Self {
+        self.link_cable = None;
+        self
+    }
+
+    pub fn with_debugger(mut self, debugger: DebuggerPlugin) -> Self {
+        self.debugger = Some(debugger);
+        self
+    }
+
+    /// Serves GDB clients, adds a debugger unless one was set already.
+    pub fn with_gdb(mut self, gdb: GdbPlugin) -> Self {
+        self.debugger.get_or_insert_default();
+        self.gdb = Some(gdb);
+        self
+    }
+
+    pub fn with_trace_log(mut self, trace_log: TraceLogPlugin) -> Self {
+        self.trace_log = Some(trace_log);
+        self
+    }
+
+    pub fn with_profiler(mut self, profiler: ProfilerPlugin) -> Self {
+        self.profiler = Some(profiler);
+        self
+    }
+
+    pub fn with_cdl(mut self, cdl: CdlPlugin) -> Self {
+        self.cdl = Some(cdl);
+        self
+    }
+
+    pub fn with_rewind(mut self, enabled: bool) -> Self {
+        self.rewind = enabled;
+        self
+    }
+
+    /// Registers the plugins without building, so callers can add their own first.
+    pub fn into_emulator(self) -> Emulator {
+        let mut emulator = Emulator::new();
+        if let Some(log) = self.log {
+            emulator = emulator.with_plugin(log);
+        }
+
+        emulator = emulator
+            .with_plugin(self.memory)
+            .with_plugin(yagber_cpu::CpuPlugin)
+            .with_plugin(yagber_ppu::PpuPlugin)
+            .with_plugin(yagber_apu::ApuPlugin)
+            .with_plugin(yagber_dma::DmaPlugin)
+            .with_plugin(yagber_timer::TimerPlugin);
+
+        if let Some(display) = self.display {
+            emulator = emulator.with_plugin(display);
+        }
+        if let Some(audio) = self.audio {
+            emulator = emulator.with_plugin(audio);
+        }
+        if let Some(input) = self.input {
+            emulator = emulator.with_plugin(input);
+        }
+        if let Some(link_cable) = self.link_cable {
+            emulator = emulator.with_plugin(link_cable);
+        }
+        if let Some(debugger) = self.debugger {
+            emulator = emulator.with_plugin(debugger);
+        }
+        if let Some(gdb) = self.gdb {
+            emulator = emulator.with_plugin(gdb);
+        }
+        if let Some(trace_log) = self.trace_log {
+            emulator = emulator.with_plugin(trace_log);
+        }
+        if let Some(profiler) = self.profiler {
+            emulator = emulator.with_plugin(profiler);
+        }
+        if let Some(cdl) = self.cdl {
+            emulator = emulator.with_plugin(cdl);
+        }
+        if self.rewind {
+            emulator = emulator.with_plugin(yagber_rewind::RewindPlugin::default());
+        }
+        emulator
+    }
+
+    pub fn build(self) -> Result<Emulator, PluginError> {
+        self.into_emulator().build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_preset_builds_without_a_rom() {
+        let emulator = EmulatorBuilder::headless().build().unwrap();
+        assert!(emulator.has_component::<yagber_input::InputEventQueue>());
+        assert!(emulator.has_component::<yagber_link_cable::LinkCable>());
+    }
+
+    #[test]
+    fn subsystems_can_be_left_out() {
+        let emulator = EmulatorBuilder::test()
+            .with_input(false)
+            .without_link_cable()
+            .build()
+            .unwrap();
+        assert!(!emulator.has_component::<yagber_input::InputEventQueue>());
+        assert!(!emulator.has_component::<yagber_link_cable::LinkCable>());
+    }
+
+    #[test]
+    fn window_requires_input() {
+        let error = EmulatorBuilder::windowed()
+            .with_audio(false)
+            .with_input(false)
+            .build()
+            .err()
+            .unwrap();
+        assert!(matches!(error, PluginError::MissingComponent { .. }));
+    }
+
+    #[test]
+    fn test_step_until_due_matches_stepping_every_dot() {
+        let dots = 3 * yagber_app::Emulator::DOTS_PER_FRAME;
+        let mut stepped = EmulatorBuilder::test().build().unwrap();
+        for _ in 0..dots {
+            stepped.step();
+        }
+        let mut batched = EmulatorBuilder::test().build().unwrap();
+        let mut ran = 0;
+        while ran < dots {
+            ran += batched.step_until_due(dots - ran);
+        }
+        assert_eq!(batched.save_state(), stepped.save_state());
+    }
+
+    #[test]
+    fn invalid_data_fails_the_build() {
+        let error = EmulatorBuilder::test()
+            .with_boot_rom(&[0; 0x100])
+            .build()
+            .err()
+            .unwrap();
+        assert!(matches!(error, PluginError::Init { .. }));
+
+        let error = EmulatorBuilder::test()
+            .with_rom(&[0; 0x10])
+            .build()
+            .err()
+            .unwrap();
+        assert!(matches!(error, PluginError::Init { .. }));
+    }
+}
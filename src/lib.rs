@@ -1,7 +1,16 @@
+mod emulator_builder;
+
 pub use yagber_app as app;
+pub use yagber_cdl as cdl;
 pub use yagber_cpu as cpu;
+pub use yagber_debugger as debugger;
+pub use yagber_disasm as disasm;
+pub use yagber_gdb as gdb;
 pub use yagber_log as log;
 pub use yagber_memory as ram;
 pub use yagber_ppu as ppu;
+pub use yagber_profiler as profiler;
+pub use yagber_trace_log as trace_log;
 
+pub use emulator_builder::EmulatorBuilder;
 pub use yagber_app::Emulator;
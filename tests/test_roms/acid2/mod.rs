@@ -1,36 +1,44 @@
+use yagber::app::HeadlessRunner;
+
 use crate::utils::{TestError, run_boot};
 
 mod cgb_acid2;
 mod dmg_acid2;
 
-const WAIT_CYCLES: u32 = 15 * yagber_ppu::Ppu::DOTS_PER_FRAME;
+const WAIT_FRAMES: u64 = 15;
 
 pub fn run_emulator(
     rom: &[u8],
     out_log_path: &str,
     expected_screen_path: &str,
-) -> <Acid2TestRunner as yagber_app::Runner>::Result {
+) -> Result<(), (TestError, Vec<u8>)> {
     let expected_screen = ExpectedScreen::from_file(expected_screen_path);
 
-    // Order matters, some plugins depend on others
-    let mut emulator = yagber::Emulator::new();
-    emulator.with_component(expected_screen);
-    emulator
-        // Log must be first
-        .with_plugin(yagber_log::LogPlugin::default())
-        // Memory must be second
-        .with_plugin(yagber_memory::MemoryPlugin::default().with_cartridge(rom))
-        .with_plugin(yagber_cpu::CpuPlugin)
-        .with_plugin(yagber_ppu::PpuPlugin)
-        .with_plugin(yagber_dma::DmaPlugin)
-        .with_plugin(
-            yagber_link_cable::LinkCablePlugin::default()
-                .with_serial_output_buffer()
-                .with_serial_output_file(out_log_path),
+    let emulator = yagber::EmulatorBuilder::test()
+        .with_rom(rom)
+        .with_link_cable(
+            yagber_link_cable::LinkCablePlugin::default().with_serial_output_file(out_log_path),
         )
-        // Timer must be last
-        .with_plugin(yagber_timer::TimerPlugin)
-        .run::<Acid2TestRunner>()
+        .build()
+        .expect("Failed to build emulator");
+
+    let emulator = run_boot(emulator).map_err(|error| (error, Vec::new()))?;
+    let mut runner = HeadlessRunner::new(emulator).for_frames(WAIT_FRAMES);
+    runner.run_until_stop();
+
+    let output_screen = runner
+        .emulator()
+        .get_component::<yagber_ppu::Ppu>()
+        .expect("Display not found")
+        .frame_buffer()
+        .as_flattened()
+        .to_vec();
+
+    if output_screen == expected_screen.screen() {
+        Ok(())
+    } else {
+        Err((TestError::Failed, output_screen))
+    }
 }
 
 pub struct ExpectedScreen {
@@ -50,64 +58,6 @@ impl ExpectedScreen {
     }
 }
 
-impl yagber_app::Component for ExpectedScreen {}
-
-pub struct Acid2TestRunner {
-    emulator: yagber::Emulator,
-    expected_screen: Vec<u8>,
-}
-
-impl Acid2TestRunner {
-    pub fn new(emulator: yagber::Emulator) -> Self {
-        let expected_screen = emulator
-            .get_component::<ExpectedScreen>()
-            .expect("ExpectedScreen not found")
-            .screen()
-            .to_vec();
-        Self {
-            emulator,
-            expected_screen,
-        }
-    }
-
-    fn run_until_result(&mut self) -> <Acid2TestRunner as yagber_app::Runner>::Result {
-        let boot_res = run_boot(&mut self.emulator);
-        if let Err(result) = boot_res {
-            return Err((result, Vec::new()));
-        }
-
-        for _ in 0..WAIT_CYCLES {
-            self.emulator.step();
-        }
-
-        let output_screen = self
-            .emulator
-            .get_component::<yagber_ppu::Ppu>()
-            .expect("Display not found")
-            .frame_buffer()
-            .as_flattened()
-            .to_vec();
-
-        if output_screen == self.expected_screen {
-            Ok(())
-        } else {
-            Err((TestError::Failed, output_screen))
-        }
-    }
-}
-
-impl yagber_app::Runner for Acid2TestRunner {
-    type Result = Result<(), (TestError, Vec<u8>)>;
-
-    fn new(emulator: yagber::Emulator) -> Self {
-        Self::new(emulator)
-    }
-
-    fn run(mut self) -> Self::Result {
-        self.run_until_result()
-    }
-}
-
 pub fn save_screen(screen: &[u8], path: &str) {
     use image::codecs::png::PngEncoder;
     let mut png_bytes = Vec::new();
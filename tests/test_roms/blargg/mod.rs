@@ -1,69 +1,28 @@
 mod cpu_instrs;
 mod halt_bug;
 
+use yagber::app::{HeadlessRunner, StopReason};
+
 use crate::utils::{MAX_FRAMES, TestError};
 
-pub fn run_emulator(
-    rom: &[u8],
-    out_log_path: &str,
-) -> <BlarggTestRunner as yagber_app::Runner>::Result {
-    // Order matters, some plugins depend on others
-    yagber::Emulator::new()
-        // Log must be first
-        .with_plugin(yagber_log::LogPlugin::default())
-        // Memory must be second
-        .with_plugin(yagber_memory::MemoryPlugin::default().with_cartridge(rom))
-        .with_plugin(yagber_cpu::CpuPlugin)
-        .with_plugin(yagber_ppu::PpuPlugin)
-        .with_plugin(yagber_dma::DmaPlugin)
-        .with_plugin(
-            yagber_link_cable::LinkCablePlugin::default()
-                .with_serial_output_buffer()
-                .with_serial_output_file(out_log_path),
+pub fn run_emulator(rom: &[u8], out_log_path: &str) -> Result<(), (TestError, String)> {
+    let emulator = yagber::EmulatorBuilder::test()
+        .with_rom(rom)
+        .with_link_cable(
+            yagber_link_cable::LinkCablePlugin::default().with_serial_output_file(out_log_path),
         )
-        // Timer must be last
-        .with_plugin(yagber_timer::TimerPlugin)
-        .run::<BlarggTestRunner>()
-}
-
-pub struct BlarggTestRunner {
-    emulator: yagber::Emulator,
-}
-
-impl BlarggTestRunner {
-    pub fn new(emulator: yagber::Emulator) -> Self {
-        Self { emulator }
-    }
-
-    /// Run the emulator for a specified number of cycles
-    /// and check the output buffer for "Passed" or "Failed"
-    fn run_until_result(&mut self) -> <BlarggTestRunner as yagber_app::Runner>::Result {
-        for _ in 0..MAX_FRAMES {
-            self.emulator.step();
-            let Some(buf) = yagber_link_cable::LinkCable::output_buffer_for(&mut self.emulator)
-            else {
-                panic!("No output buffer");
-            };
-
-            let buf = String::from_utf8_lossy(buf);
-            if buf.contains("Passed") {
-                return Ok(());
-            } else if buf.contains("Failed") {
-                return Err((TestError::Failed, buf.to_string()));
-            }
-        }
-        Err((TestError::TimedOut, String::new()))
-    }
-}
-
-impl yagber::app::Runner for BlarggTestRunner {
-    type Result = Result<(), (TestError, String)>;
-
-    fn new(emulator: yagber::Emulator) -> Self {
-        Self::new(emulator)
-    }
-
-    fn run(mut self) -> Self::Result {
-        self.run_until_result()
+        .build()
+        .expect("Failed to build emulator");
+
+    let mut runner = HeadlessRunner::new(emulator)
+        .for_frames(MAX_FRAMES)
+        .until_serial("Passed")
+        .until_serial("Failed");
+    let reason = runner.run_until_stop();
+    let output = String::from_utf8_lossy(runner.serial_output()).to_string();
+    match reason {
+        StopReason::Condition { index: 0, .. } => Ok(()),
+        StopReason::Condition { .. } => Err((TestError::Failed, output)),
+        _ => Err((TestError::TimedOut, output)),
     }
 }
@@ -1 +1,14 @@
+mod add_sp_e_timing;
+mod call_cc_timing;
+mod call_cc_timing2;
+mod call_timing;
+mod call_timing2;
 mod instr;
+mod jp_cc_timing;
+mod jp_timing;
+mod ld_hl_sp_e_timing;
+mod pop_timing;
+mod push_timing;
+mod ret_cc_timing;
+mod ret_timing;
+mod rst_timing;
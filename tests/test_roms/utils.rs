@@ -1,4 +1,6 @@
-pub const MAX_FRAMES: u32 = 60 * 60 * yagber_ppu::Ppu::DOTS_PER_FRAME; // frames
+use yagber::app::{HeadlessRunner, StopReason};
+
+pub const MAX_FRAMES: u64 = 60 * 60;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum TestError {
@@ -6,20 +8,21 @@ pub enum TestError {
     TimedOut,
 }
 
-pub fn run_boot(emulator: &mut yagber::Emulator) -> Result<u8, TestError> {
-    // Run the boot sequence
-    for _ in 0..MAX_FRAMES {
-        let memory_bus = emulator
-            .get_component::<yagber_memory::Bus>()
-            .expect("Memory bus not found");
-        let boot_reg = memory_bus
-            .io_registers
-            .read(yagber_memory::IOType::BANK.address());
-        if boot_reg == 0x11 {
-            return Ok(boot_reg);
-        }
-
-        emulator.step();
+/// Runs the boot sequence, handing the emulator back once the boot ROM is unmapped.
+pub fn run_boot(emulator: yagber::Emulator) -> Result<yagber::Emulator, TestError> {
+    let mut runner = HeadlessRunner::new(emulator)
+        .for_frames(MAX_FRAMES)
+        .until_fn("boot rom unmapped", |emulator| {
+            let memory_bus = emulator
+                .get_component::<yagber_memory::Bus>()
+                .expect("Memory bus not found");
+            memory_bus
+                .io_registers
+                .read(yagber_memory::IOType::BANK.address())
+                == 0x11
+        });
+    match runner.run_until_stop() {
+        StopReason::Condition { .. } => Ok(runner.into_emulator()),
+        _ => Err(TestError::TimedOut),
     }
-    Err(TestError::TimedOut)
 }